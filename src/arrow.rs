@@ -0,0 +1,1049 @@
+// Copyright 2015 The GeoRust Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Apache Arrow conversion
+//!
+//! Available with the `arrow` feature. `write` converts a `FeatureCollection` to an Arrow
+//! `RecordBatch` with one row per feature: a [GeoArrow](https://geoarrow.org) geometry column,
+//! followed by a column for each property. `read` converts such a batch back.
+//!
+//! Geometries are encoded as ISO WKB (`geoarrow.wkb`) by default, which allows any mix of
+//! geometry types, or with the native GeoArrow encodings (`geoarrow.point` and so on) and
+//! separated coordinates. The native encodings need all geometries to share a type, though a
+//! mix of single and multi geometries of the same kind is stored as the multi type. Features
+//! without a geometry get a null geometry.
+//!
+//! Property columns are inferred from the properties, in order of first appearance: booleans,
+//! integers, floating-point numbers and strings get typed columns, and any other or mixed
+//! values are stored as JSON text, in columns of the `arrow.json` extension type. Columns of
+//! null values only have the `Null` type. Arrow has no equivalent of `Feature::id`, which is
+//! not stored.
+//!
+//! The schema carries the [GeoParquet](https://geoparquet.org) `geo` metadata describing the
+//! geometry column, so that a Parquet writer produces a GeoParquet file from the batch. When
+//! reading, the geometry column is found through that metadata, or as the first column with a
+//! GeoArrow extension type. All other columns become properties, with null values kept.
+//!
+//! The `OGC:CRS84` CRS is the default of both GeoJSON and GeoArrow: a collection without a
+//! `crs` is written with it, and it is read back as no `crs`.
+//!
+//! # Example
+//!
+//! ```
+//! extern crate arrow_array;
+//! extern crate geojson;
+//!
+//! use geojson::{Feature, FeatureCollection, Value};
+//! use geojson::arrow::ArrowOptions;
+//!
+//! # fn main() {
+//! let fc = FeatureCollection::builder()
+//!     .feature(Feature::builder()
+//!         .geometry(Value::Point(vec![1.0, 2.0]))
+//!         .property("name", "a")
+//!         .build())
+//!     .build();
+//!
+//! let batch = geojson::arrow::write(&fc, &ArrowOptions::default()).unwrap();
+//! assert_eq!(batch.num_rows(), 1);
+//! assert_eq!(batch.schema().field(1).name(), "name");
+//!
+//! let read = geojson::arrow::read(&batch).unwrap();
+//! assert_eq!(read.features, fc.features);
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow_array::cast::AsArray;
+use arrow_array::types::{Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type,
+                         UInt16Type, UInt32Type, UInt64Type, UInt8Type};
+use arrow_array::{Array, ArrayRef, BinaryArray, BooleanArray, Float64Array, GenericStringArray,
+                  Int64Array, ListArray, NullArray, OffsetSizeTrait, RecordBatch, StringArray,
+                  StructArray};
+use arrow_buffer::{NullBuffer, OffsetBuffer};
+use arrow_schema::{DataType, Field, Fields, Schema};
+use json::{JsonObject, JsonValue};
+use serde_json;
+use wkb::ByteOrder;
+
+use {Crs, CrsEquivalence, CrsId, Error, Feature, FeatureCollection, Geometry, Position, Value,
+     util};
+
+
+const EXTENSION_NAME: &'static str = "ARROW:extension:name";
+
+const EXTENSION_METADATA: &'static str = "ARROW:extension:metadata";
+
+const JSON_EXTENSION: &'static str = "arrow.json";
+
+const GEOARROW_PREFIX: &'static str = "geoarrow.";
+
+const GEOPARQUET_VERSION: &'static str = "1.1.0";
+
+const COORDINATE_NAMES: [&'static str; 4] = ["x", "y", "z", "m"];
+
+/// How geometries are encoded in a GeoArrow column
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GeometryEncoding {
+    /// ISO WKB, in a binary column
+    Wkb,
+
+    /// Nested lists of coordinate structs, for collections of a single geometry type
+    Native,
+}
+
+/// Options for converting to Arrow
+#[derive(Clone, Debug, PartialEq)]
+pub struct ArrowOptions {
+    pub geometry_column: String,
+    pub encoding: GeometryEncoding,
+}
+
+impl Default for ArrowOptions {
+    fn default() -> Self {
+        ArrowOptions {
+            geometry_column: String::from("geometry"),
+            encoding: GeometryEncoding::Wkb,
+        }
+    }
+}
+
+fn malformed(message: &str) -> Error {
+    Error::MalformedArrow(String::from(message))
+}
+
+fn unsupported(message: &str) -> Error {
+    Error::ArrowUnsupported(String::from(message))
+}
+
+fn crs84() -> CrsId {
+    CrsId::new("OGC", "CRS84")
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match *value {
+        Value::Point(..) => "Point",
+        Value::MultiPoint(..) => "MultiPoint",
+        Value::LineString(..) => "LineString",
+        Value::MultiLineString(..) => "MultiLineString",
+        Value::Polygon(..) => "Polygon",
+        Value::MultiPolygon(..) => "MultiPolygon",
+        Value::GeometryCollection(..) => "GeometryCollection",
+    }
+}
+
+
+// Writing
+
+/// Native geometry types, without their multi variants
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kind {
+    Point,
+    LineString,
+    Polygon,
+}
+
+impl Kind {
+    fn of(value: &Value) -> Result<(Kind, bool), Error> {
+        Ok(match *value {
+            Value::Point(..) => (Kind::Point, false),
+            Value::MultiPoint(..) => (Kind::Point, true),
+            Value::LineString(..) => (Kind::LineString, false),
+            Value::MultiLineString(..) => (Kind::LineString, true),
+            Value::Polygon(..) => (Kind::Polygon, false),
+            Value::MultiPolygon(..) => (Kind::Polygon, true),
+            Value::GeometryCollection(..) => {
+                return Err(unsupported("GeometryCollection needs the WKB encoding"))
+            }
+        })
+    }
+
+    /// Returns the encoding name, the GeoJSON type and the names of the nested list fields.
+    fn layout(self, multi: bool) -> (&'static str, &'static str, &'static [&'static str]) {
+        match (self, multi) {
+            (Kind::Point, false) => ("point", "Point", &[]),
+            (Kind::LineString, false) => ("linestring", "LineString", &["vertices"]),
+            (Kind::Polygon, false) => ("polygon", "Polygon", &["rings", "vertices"]),
+            (Kind::Point, true) => ("multipoint", "MultiPoint", &["points"]),
+            (Kind::LineString, true) => {
+                ("multilinestring", "MultiLineString", &["linestrings", "vertices"])
+            }
+            (Kind::Polygon, true) => {
+                ("multipolygon", "MultiPolygon", &["polygons", "rings", "vertices"])
+            }
+        }
+    }
+}
+
+/// Coordinates of a geometry, nested as in a native column
+enum Coordinates<'a> {
+    Position(&'a Position),
+    List(Vec<Coordinates<'a>>),
+}
+
+/// Coordinates of a line string or ring, leaving out positions with fewer than two ordinates
+fn line_coordinates<'a>(line: &'a [Position]) -> Coordinates<'a> {
+    Coordinates::List(line.iter()
+        .filter(|position| position.len() >= 2)
+        .map(Coordinates::Position)
+        .collect())
+}
+
+fn polygon_coordinates<'a>(polygon: &'a [Vec<Position>]) -> Coordinates<'a> {
+    Coordinates::List(polygon.iter().map(|ring| line_coordinates(ring)).collect())
+}
+
+/// Returns the coordinates of a value, wrapping single geometries if `multi`.
+fn coordinates<'a>(value: &'a Value, multi: bool) -> Coordinates<'a> {
+    let single = match *value {
+        Value::Point(ref position) => Coordinates::Position(position),
+        Value::LineString(ref line) => line_coordinates(line),
+        Value::Polygon(ref polygon) => polygon_coordinates(polygon),
+        Value::MultiPoint(ref positions) => return line_coordinates(positions),
+        Value::MultiLineString(ref lines) => {
+            return Coordinates::List(lines.iter().map(|line| line_coordinates(line)).collect())
+        }
+        Value::MultiPolygon(ref polygons) => {
+            return Coordinates::List(polygons.iter()
+                .map(|polygon| polygon_coordinates(polygon))
+                .collect())
+        }
+        Value::GeometryCollection(..) => Coordinates::List(vec![]),
+    };
+    if multi {
+        Coordinates::List(vec![single])
+    } else {
+        single
+    }
+}
+
+fn null_buffer<T>(items: &[Option<T>]) -> Option<NullBuffer> {
+    if items.iter().all(Option::is_some) {
+        return None;
+    }
+    Some(NullBuffer::from(items.iter().map(Option::is_some).collect::<Vec<bool>>()))
+}
+
+/// Builds an array of `items`, nested in a list for each of `list_names`.
+fn build_array(items: Vec<Option<Coordinates>>,
+               list_names: &[&str],
+               dimensions: usize)
+               -> ArrayRef {
+    let nulls = null_buffer(&items);
+    match list_names.split_first() {
+        None => {
+            let fields: Fields = COORDINATE_NAMES[..dimensions]
+                .iter()
+                .map(|name| Field::new(*name, DataType::Float64, false))
+                .collect();
+            let arrays = (0..dimensions)
+                .map(|i| {
+                    let ordinates: Vec<f64> = items.iter()
+                        .map(|item| match *item {
+                            // Empty points have NaN coordinates
+                            Some(Coordinates::Position(position)) => {
+                                position.get(i).cloned().unwrap_or(::std::f64::NAN)
+                            }
+                            _ => 0.0,
+                        })
+                        .collect();
+                    Arc::new(Float64Array::from(ordinates)) as ArrayRef
+                })
+                .collect();
+            Arc::new(StructArray::new(fields, arrays, nulls))
+        }
+        Some((name, list_names)) => {
+            let mut lengths = Vec::with_capacity(items.len());
+            let mut children = vec![];
+            for item in items {
+                match item {
+                    Some(Coordinates::List(list)) => {
+                        lengths.push(list.len());
+                        children.extend(list.into_iter().map(Some));
+                    }
+                    _ => lengths.push(0),
+                }
+            }
+            let values = build_array(children, list_names, dimensions);
+            let field = Field::new(*name, values.data_type().clone(), false);
+            Arc::new(ListArray::new(Arc::new(field),
+                                    OffsetBuffer::from_lengths(lengths),
+                                    values,
+                                    nulls))
+        }
+    }
+}
+
+/// Encodes the values natively, returning the column, its encoding and its geometry types.
+fn encode_native(values: &[Option<&Value>])
+                 -> Result<(ArrayRef, &'static str, Vec<String>), Error> {
+    let mut kind = None;
+    let mut multi = false;
+    let mut dimensions = 4;
+    for value in values.iter().filter_map(|value| *value) {
+        let (value_kind, value_multi) = try!(Kind::of(value));
+        match kind {
+            Some(kind) if kind != value_kind => {
+                return Err(unsupported(&format!("mixed {:?} and {:?} geometries need the WKB \
+                                                 encoding",
+                                                kind,
+                                                value_kind)))
+            }
+            _ => kind = Some(value_kind),
+        }
+        multi |= value_multi;
+        dimensions = ::std::cmp::min(dimensions, util::coordinate_dimensions(value));
+    }
+    let kind = kind.unwrap_or(Kind::Point);
+    if values.iter().all(Option::is_none) {
+        dimensions = 2;
+    }
+
+    let (encoding, geometry_type, list_names) = kind.layout(multi);
+    let items = values.iter().map(|value| value.map(|value| coordinates(value, multi))).collect();
+    let geometry_types = match (values.iter().any(Option::is_some), dimensions) {
+        (false, _) => vec![],
+        (true, 2) => vec![String::from(geometry_type)],
+        (true, _) => vec![format!("{} Z", geometry_type)],
+    };
+    Ok((build_array(items, list_names, dimensions), encoding, geometry_types))
+}
+
+/// Encodes the values as WKB, returning the column and its geometry types.
+fn encode_wkb(values: &[Option<&Value>]) -> (ArrayRef, Vec<String>) {
+    let array: BinaryArray = values.iter()
+        .map(|value| value.map(|value| value.to_wkb(ByteOrder::LittleEndian)))
+        .collect();
+    let mut geometry_types: Vec<String> = values.iter()
+        .filter_map(|value| *value)
+        .map(|value| match util::coordinate_dimensions(value) {
+            2 => String::from(type_name(value)),
+            _ => format!("{} Z", type_name(value)),
+        })
+        .collect();
+    geometry_types.sort();
+    geometry_types.dedup();
+    (Arc::new(array), geometry_types)
+}
+
+/// Returns the GeoArrow extension metadata for a CRS.
+fn geoarrow_metadata(crs: Option<&Crs>) -> String {
+    let id = match crs {
+        Some(crs) => crs.id(),
+        None => Some(crs84()),
+    };
+    match id {
+        Some(id) => {
+            json!({"crs": format!("{}:{}", id.authority, id.code), "crs_type": "authority_code"})
+                .to_string()
+        }
+        None => String::from("{}"),
+    }
+}
+
+/// Returns the GeoParquet metadata for a CRS, or `None` for the default CRS84.
+fn geoparquet_crs(crs: Option<&Crs>) -> Option<JsonValue> {
+    let id = match crs {
+        Some(crs) => crs.id(),
+        None => return None,
+    };
+    match id {
+        // GeoParquet coordinates are always longitude first
+        Some(ref id) if id.equivalence(&crs84()) != CrsEquivalence::Different => None,
+        Some(id) => {
+            let code = match id.code.parse::<u64>() {
+                Ok(code) => JsonValue::from(code),
+                Err(..) => JsonValue::from(id.code.clone()),
+            };
+            Some(json!({"id": {"authority": id.authority, "code": code}}))
+        }
+        None => Some(JsonValue::Null),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ColumnType {
+    Boolean,
+    Int64,
+    Float64,
+    Utf8,
+    Json,
+}
+
+impl ColumnType {
+    fn of(value: &JsonValue) -> Option<ColumnType> {
+        Some(match *value {
+            JsonValue::Null => return None,
+            JsonValue::Bool(..) => ColumnType::Boolean,
+            JsonValue::Number(ref n) if n.is_i64() => ColumnType::Int64,
+            JsonValue::Number(..) => ColumnType::Float64,
+            JsonValue::String(..) => ColumnType::Utf8,
+            JsonValue::Array(..) | JsonValue::Object(..) => ColumnType::Json,
+        })
+    }
+
+    fn merge(self, other: ColumnType) -> ColumnType {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (ColumnType::Int64, ColumnType::Float64) |
+            (ColumnType::Float64, ColumnType::Int64) => ColumnType::Float64,
+            _ => ColumnType::Json,
+        }
+    }
+}
+
+/// Returns the name and type of each property, in order of first appearance.
+fn infer_columns(fc: &FeatureCollection) -> Vec<(String, Option<ColumnType>)> {
+    let mut columns: Vec<(String, Option<ColumnType>)> = vec![];
+    let mut indexes = HashMap::new();
+    for properties in fc.features.iter().filter_map(|f| f.properties.as_ref()) {
+        for (name, value) in properties {
+            let index = *indexes.entry(name.clone()).or_insert_with(|| {
+                columns.push((name.clone(), None));
+                columns.len() - 1
+            });
+            let column_type = &mut columns[index].1;
+            *column_type = match (*column_type, ColumnType::of(value)) {
+                (current, None) => current,
+                (None, new) => new,
+                (Some(current), Some(new)) => Some(current.merge(new)),
+            };
+        }
+    }
+    columns
+}
+
+fn build_column(fc: &FeatureCollection,
+                name: &str,
+                column_type: Option<ColumnType>)
+                -> (Field, ArrayRef) {
+    let values: Vec<Option<&JsonValue>> = fc.features
+        .iter()
+        .map(|f| f.properties.as_ref().and_then(|p| p.get(name)).filter(|v| !v.is_null()))
+        .collect();
+    let field = |data_type| Field::new(name, data_type, true);
+    match column_type {
+        None => (field(DataType::Null), Arc::new(NullArray::new(values.len()))),
+        Some(ColumnType::Boolean) => {
+            let array: BooleanArray = values.iter().map(|v| v.and_then(|v| v.as_bool())).collect();
+            (field(DataType::Boolean), Arc::new(array))
+        }
+        Some(ColumnType::Int64) => {
+            let array: Int64Array = values.iter().map(|v| v.and_then(|v| v.as_i64())).collect();
+            (field(DataType::Int64), Arc::new(array))
+        }
+        Some(ColumnType::Float64) => {
+            let array: Float64Array = values.iter().map(|v| v.and_then(|v| v.as_f64())).collect();
+            (field(DataType::Float64), Arc::new(array))
+        }
+        Some(ColumnType::Utf8) => {
+            let array: StringArray = values.iter().map(|v| v.and_then(|v| v.as_str())).collect();
+            (field(DataType::Utf8), Arc::new(array))
+        }
+        Some(ColumnType::Json) => {
+            let array: StringArray = values.iter().map(|v| v.map(|v| v.to_string())).collect();
+            let mut metadata = HashMap::new();
+            metadata.insert(String::from(EXTENSION_NAME), String::from(JSON_EXTENSION));
+            metadata.insert(String::from(EXTENSION_METADATA), String::new());
+            (field(DataType::Utf8).with_metadata(metadata), Arc::new(array))
+        }
+    }
+}
+
+/// Converts `fc` to an Arrow `RecordBatch`, with a GeoArrow geometry column.
+///
+/// Fails if the geometries can't be stored with the chosen encoding, or if a property has
+/// the name of the geometry column.
+pub fn write(fc: &FeatureCollection, options: &ArrowOptions) -> Result<RecordBatch, Error> {
+    let values: Vec<Option<&Value>> = fc.features
+        .iter()
+        .map(|feature| feature.geometry.as_ref().map(|geometry| &geometry.value))
+        .collect();
+    let (geometry, encoding, geometry_types) = match options.encoding {
+        GeometryEncoding::Wkb => {
+            let (geometry, geometry_types) = encode_wkb(&values);
+            (geometry, "wkb", geometry_types)
+        }
+        GeometryEncoding::Native => try!(encode_native(&values)),
+    };
+
+    let mut metadata = HashMap::new();
+    metadata.insert(String::from(EXTENSION_NAME),
+                    format!("{}{}", GEOARROW_PREFIX, encoding));
+    metadata.insert(String::from(EXTENSION_METADATA),
+                    geoarrow_metadata(fc.crs.as_ref()));
+    let mut fields = vec![Field::new(options.geometry_column.clone(),
+                                     geometry.data_type().clone(),
+                                     true)
+                              .with_metadata(metadata)];
+    let mut columns = vec![geometry];
+    for (name, column_type) in infer_columns(fc) {
+        if name == options.geometry_column {
+            return Err(unsupported(&format!("property '{}' has the name of the geometry \
+                                             column",
+                                            name)));
+        }
+        let (field, column) = build_column(fc, &name, column_type);
+        fields.push(field);
+        columns.push(column);
+    }
+
+    let mut column = json!({
+        "encoding": if encoding == "wkb" { "WKB" } else { encoding },
+        "geometry_types": geometry_types,
+    });
+    if let Some(crs) = geoparquet_crs(fc.crs.as_ref()) {
+        column["crs"] = crs;
+    }
+    let mut columns_metadata = JsonObject::new();
+    columns_metadata.insert(options.geometry_column.clone(), column);
+    let geo = json!({
+        "version": GEOPARQUET_VERSION,
+        "primary_column": options.geometry_column,
+        "columns": columns_metadata,
+    });
+    let mut metadata = HashMap::new();
+    metadata.insert(String::from("geo"), geo.to_string());
+
+    let schema = Schema::new_with_metadata(fields, metadata);
+    RecordBatch::try_new(Arc::new(schema), columns).map_err(|e| malformed(&e.to_string()))
+}
+
+
+// Reading
+
+/// Reads every position of a coordinate array, of structs or of fixed-size lists.
+fn read_positions(array: &dyn Array) -> Result<Vec<Position>, Error> {
+    let doubles = |array: &ArrayRef| {
+        array.as_primitive_opt::<Float64Type>()
+            .cloned()
+            .ok_or_else(|| malformed("expected double coordinates"))
+    };
+    if let Some(coordinates) = array.as_struct_opt() {
+        let mut columns = vec![];
+        for name in &COORDINATE_NAMES {
+            // M values are only kept along with Z values
+            match coordinates.column_by_name(name) {
+                Some(column) if *name != "m" || columns.len() == 3 => {
+                    columns.push(try!(doubles(column)))
+                }
+                None if columns.len() < 2 => {
+                    return Err(malformed(&format!("missing '{}' coordinates", name)))
+                }
+                _ => {}
+            }
+        }
+        Ok((0..coordinates.len())
+            .map(|i| columns.iter().map(|column| column.value(i)).collect())
+            .collect())
+    } else if let Some(coordinates) = array.as_fixed_size_list_opt() {
+        let dimensions = coordinates.value_length() as usize;
+        if dimensions < 2 {
+            return Err(malformed("expected at least two coordinates"));
+        }
+        let values = try!(doubles(coordinates.values()));
+        Ok((0..coordinates.len())
+            .map(|i| {
+                let at = coordinates.value_offset(i) as usize;
+                (at..at + dimensions).map(|j| values.value(j)).collect()
+            })
+            .collect())
+    } else {
+        Err(malformed("expected an array of coordinates"))
+    }
+}
+
+/// Reads every list of a list array, reading their values with `read_values`.
+fn read_lists<T, F>(array: &dyn Array, read_values: F) -> Result<Vec<Vec<T>>, Error>
+    where T: Clone,
+          F: Fn(&dyn Array) -> Result<Vec<T>, Error>
+{
+    let lists = try!(array.as_list_opt::<i32>().ok_or_else(|| malformed("expected a list array")));
+    let values = try!(read_values(lists.values().as_ref()));
+    lists.value_offsets()
+        .windows(2)
+        .map(|offsets| {
+            values.get(offsets[0] as usize..offsets[1] as usize)
+                .map(|values| values.to_vec())
+                .ok_or_else(|| malformed("list offsets out of bounds"))
+        })
+        .collect()
+}
+
+fn read_wkb(array: &dyn Array) -> Result<Vec<Value>, Error> {
+    let wkb: Vec<&[u8]> = if let Some(array) = array.as_binary_opt::<i32>() {
+        (0..array.len()).map(|i| array.value(i)).collect()
+    } else if let Some(array) = array.as_binary_opt::<i64>() {
+        (0..array.len()).map(|i| array.value(i)).collect()
+    } else {
+        return Err(malformed("expected a binary geometry column"));
+    };
+    wkb.into_iter()
+        .enumerate()
+        .map(|(i, wkb)| {
+            // Null rows may hold anything
+            if array.is_null(i) {
+                Ok(Value::GeometryCollection(vec![]))
+            } else {
+                Value::from_wkb(wkb)
+            }
+        })
+        .collect()
+}
+
+fn read_geometries(array: &dyn Array, encoding: &str) -> Result<Vec<Option<Geometry>>, Error> {
+    let values = match encoding {
+        "wkb" => try!(read_wkb(array)),
+        "point" => {
+            try!(read_positions(array))
+                .into_iter()
+                .map(|position| if position.iter().all(|x| x.is_nan()) {
+                    Value::Point(vec![])
+                } else {
+                    Value::Point(position)
+                })
+                .collect()
+        }
+        "linestring" => {
+            try!(read_lists(array, read_positions)).into_iter().map(Value::LineString).collect()
+        }
+        "multipoint" => {
+            try!(read_lists(array, read_positions)).into_iter().map(Value::MultiPoint).collect()
+        }
+        "polygon" => {
+            try!(read_lists(array, |rings| read_lists(rings, read_positions)))
+                .into_iter()
+                .map(Value::Polygon)
+                .collect()
+        }
+        "multilinestring" => {
+            try!(read_lists(array, |lines| read_lists(lines, read_positions)))
+                .into_iter()
+                .map(Value::MultiLineString)
+                .collect()
+        }
+        "multipolygon" => {
+            try!(read_lists(array, |polygons| {
+                    read_lists(polygons, |rings| read_lists(rings, read_positions))
+                }))
+                .into_iter()
+                .map(Value::MultiPolygon)
+                .collect()
+        }
+        _ => return Err(malformed(&format!("unsupported geometry encoding '{}'", encoding))),
+    };
+    Ok(values.into_iter()
+        .enumerate()
+        .map(|(i, value)| if array.is_null(i) {
+            None
+        } else {
+            Some(Geometry::new(value))
+        })
+        .collect())
+}
+
+/// Reads the CRS of GeoArrow or GeoParquet metadata, as an identifier or PROJJSON.
+fn read_crs(crs: &JsonValue) -> Option<CrsId> {
+    match *crs {
+        JsonValue::String(ref crs) => crs.parse().ok(),
+        JsonValue::Object(ref projjson) => {
+            let id = match projjson.get("id") {
+                Some(id) => id,
+                None => return None,
+            };
+            let code = match id["code"] {
+                JsonValue::String(ref code) => code.clone(),
+                ref code => code.to_string(),
+            };
+            id["authority"].as_str().map(|authority| CrsId::new(authority, &code))
+        }
+        _ => None,
+    }
+}
+
+fn read_values<F>(array: &dyn Array, value: F) -> Vec<JsonValue>
+    where F: Fn(usize) -> JsonValue
+{
+    (0..array.len())
+        .map(|i| if array.is_null(i) {
+            JsonValue::Null
+        } else {
+            value(i)
+        })
+        .collect()
+}
+
+fn read_strings<O: OffsetSizeTrait>(field: &Field,
+                                    array: &GenericStringArray<O>)
+                                    -> Result<Vec<JsonValue>, Error> {
+    if field.extension_type_name() != Some(JSON_EXTENSION) {
+        return Ok(read_values(array, |i| JsonValue::from(array.value(i))));
+    }
+    (0..array.len())
+        .map(|i| if array.is_null(i) {
+            Ok(JsonValue::Null)
+        } else {
+            serde_json::from_str(array.value(i)).map_err(|_| {
+                malformed(&format!("invalid JSON in column '{}'", field.name()))
+            })
+        })
+        .collect()
+}
+
+fn read_column(field: &Field, array: &dyn Array) -> Result<Vec<JsonValue>, Error> {
+    Ok(match *array.data_type() {
+        DataType::Null => read_values(array, |_| JsonValue::Null),
+        DataType::Boolean => {
+            let array = array.as_boolean();
+            read_values(array, |i| JsonValue::from(array.value(i)))
+        }
+        DataType::Int8 => {
+            let array = array.as_primitive::<Int8Type>();
+            read_values(array, |i| JsonValue::from(array.value(i)))
+        }
+        DataType::Int16 => {
+            let array = array.as_primitive::<Int16Type>();
+            read_values(array, |i| JsonValue::from(array.value(i)))
+        }
+        DataType::Int32 => {
+            let array = array.as_primitive::<Int32Type>();
+            read_values(array, |i| JsonValue::from(array.value(i)))
+        }
+        DataType::Int64 => {
+            let array = array.as_primitive::<Int64Type>();
+            read_values(array, |i| JsonValue::from(array.value(i)))
+        }
+        DataType::UInt8 => {
+            let array = array.as_primitive::<UInt8Type>();
+            read_values(array, |i| JsonValue::from(array.value(i)))
+        }
+        DataType::UInt16 => {
+            let array = array.as_primitive::<UInt16Type>();
+            read_values(array, |i| JsonValue::from(array.value(i)))
+        }
+        DataType::UInt32 => {
+            let array = array.as_primitive::<UInt32Type>();
+            read_values(array, |i| JsonValue::from(array.value(i)))
+        }
+        DataType::UInt64 => {
+            let array = array.as_primitive::<UInt64Type>();
+            read_values(array, |i| JsonValue::from(array.value(i)))
+        }
+        DataType::Float32 => {
+            let array = array.as_primitive::<Float32Type>();
+            read_values(array, |i| JsonValue::from(array.value(i)))
+        }
+        DataType::Float64 => {
+            let array = array.as_primitive::<Float64Type>();
+            read_values(array, |i| JsonValue::from(array.value(i)))
+        }
+        DataType::Utf8 => try!(read_strings(field, array.as_string::<i32>())),
+        DataType::LargeUtf8 => try!(read_strings(field, array.as_string::<i64>())),
+        ref data_type => {
+            return Err(malformed(&format!("unsupported type {} of column '{}'",
+                                          data_type,
+                                          field.name())))
+        }
+    })
+}
+
+/// Converts an Arrow `RecordBatch` with a GeoArrow or GeoParquet geometry column to a
+/// `FeatureCollection`.
+pub fn read(batch: &RecordBatch) -> Result<FeatureCollection, Error> {
+    let schema = batch.schema();
+    let geo = schema.metadata()
+        .get("geo")
+        .and_then(|geo| serde_json::from_str::<JsonValue>(geo).ok())
+        .unwrap_or(JsonValue::Null);
+    let index = geo["primary_column"]
+        .as_str()
+        .and_then(|name| schema.index_of(name).ok())
+        .or_else(|| {
+            schema.fields().iter().position(|field| {
+                field.extension_type_name().map_or(false, |n| n.starts_with(GEOARROW_PREFIX))
+            })
+        });
+    let index = try!(index.ok_or_else(|| malformed("missing a geometry column")));
+    let field = schema.field(index);
+    let column = &geo["columns"][field.name()];
+
+    let encoding = match field.extension_type_name() {
+        Some(name) if name.starts_with(GEOARROW_PREFIX) => {
+            String::from(&name[GEOARROW_PREFIX.len()..])
+        }
+        _ => column["encoding"].as_str().unwrap_or("WKB").to_lowercase(),
+    };
+    let geometries = try!(read_geometries(batch.column(index).as_ref(), &encoding));
+
+    let extension_metadata = field.metadata()
+        .get(EXTENSION_METADATA)
+        .and_then(|metadata| serde_json::from_str::<JsonValue>(metadata).ok())
+        .unwrap_or(JsonValue::Null);
+    let crs = match extension_metadata.get("crs") {
+        Some(crs) => read_crs(crs),
+        None => column.get("crs").and_then(read_crs),
+    };
+
+    let mut properties = vec![JsonObject::new(); batch.num_rows()];
+    for (i, field) in schema.fields().iter().enumerate().filter(|&(i, _)| i != index) {
+        let values = try!(read_column(field, batch.column(i).as_ref()));
+        for (properties, value) in properties.iter_mut().zip(values) {
+            properties.insert(field.name().clone(), value);
+        }
+    }
+
+    Ok(FeatureCollection {
+        bbox: None,
+        crs: crs.filter(|crs| crs.equivalence(&crs84()) != CrsEquivalence::Identical)
+            .map(Crs::from),
+        features: geometries.into_iter()
+            .zip(properties)
+            .map(|(geometry, properties)| {
+                Feature {
+                    bbox: None,
+                    crs: None,
+                    geometry: geometry,
+                    id: None,
+                    properties: Some(properties).filter(|p| !p.is_empty()),
+                }
+            })
+            .collect(),
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use arrow_array::{Array, ArrayRef, FixedSizeListArray, Float64Array, Int32Array,
+                      RecordBatch};
+    use arrow_schema::{DataType, Field, Schema};
+    use serde_json;
+
+    use {Crs, Error, Feature, FeatureCollection, Geometry, Value};
+    use json::JsonValue;
+    use super::{ArrowOptions, GeometryEncoding, read, write};
+
+    fn native() -> ArrowOptions {
+        ArrowOptions { encoding: GeometryEncoding::Native, ..ArrowOptions::default() }
+    }
+
+    fn geo_metadata(batch: &RecordBatch) -> JsonValue {
+        serde_json::from_str(&batch.schema().metadata()["geo"]).unwrap()
+    }
+
+    #[test]
+    fn wkb_and_properties() {
+        let fc = FeatureCollection::builder()
+            .crs(Crs::Named { name: String::from("EPSG:3857") })
+            .feature(Feature::builder()
+                .geometry(Value::Point(vec![1.0, 2.0]))
+                .id(7)
+                .property("name", "a")
+                .property("count", 1)
+                .property("ratio", 1)
+                .property("flag", true)
+                .property("tags", json!(["x", 1]))
+                .property("empty", JsonValue::Null)
+                .build())
+            .feature(Feature::builder()
+                .geometry(Value::LineString(vec![vec![0.0, 0.0, 1.0], vec![1.0, 1.0, 2.0]]))
+                .property("ratio", 0.5)
+                .property("mixed", 1)
+                .build())
+            .feature(Feature::builder()
+                .property("mixed", "one")
+                .build())
+            .build();
+        let batch = write(&fc, &ArrowOptions::default()).unwrap();
+
+        let schema = batch.schema();
+        let types: Vec<(&str, &DataType)> =
+            schema.fields().iter().map(|f| (f.name() as &str, f.data_type())).collect();
+        assert_eq!(types,
+                   vec![("geometry", &DataType::Binary),
+                        ("count", &DataType::Int64),
+                        ("empty", &DataType::Null),
+                        ("flag", &DataType::Boolean),
+                        ("name", &DataType::Utf8),
+                        ("ratio", &DataType::Float64),
+                        ("tags", &DataType::Utf8),
+                        ("mixed", &DataType::Utf8)]);
+        assert_eq!(schema.field(0).extension_type_name(), Some("geoarrow.wkb"));
+        assert_eq!(schema.field(0).metadata()["ARROW:extension:metadata"],
+                   r#"{"crs":"EPSG:3857","crs_type":"authority_code"}"#);
+        assert_eq!(schema.field(6).extension_type_name(), Some("arrow.json"));
+        assert_eq!(geo_metadata(&batch),
+                   json!({
+                       "version": "1.1.0",
+                       "primary_column": "geometry",
+                       "columns": {
+                           "geometry": {
+                               "encoding": "WKB",
+                               "geometry_types": ["LineString Z", "Point"],
+                               "crs": {"id": {"authority": "EPSG", "code": 3857}},
+                           },
+                       },
+                   }));
+
+        let read_back = read(&batch).unwrap();
+        assert_eq!(read_back.crs,
+                   Some(Crs::Named { name: String::from("urn:ogc:def:crs:EPSG::3857") }));
+        assert_eq!(read_back.features[0].id, None);
+        assert_eq!(read_back.features[0].geometry, fc.features[0].geometry);
+        assert_eq!(read_back.features[1].geometry, fc.features[1].geometry);
+        assert_eq!(read_back.features[2].geometry, None);
+        assert_eq!(read_back.features[0].properties.as_ref().map(|p| json!(p)),
+                   Some(json!({
+                       "count": 1,
+                       "empty": null,
+                       "flag": true,
+                       "mixed": null,
+                       "name": "a",
+                       "ratio": 1.0,
+                       "tags": ["x", 1],
+                   })));
+        // JSON columns keep the type of each value
+        assert_eq!(read_back.features[1].properties.as_ref().map(|p| p["mixed"].clone()),
+                   Some(json!(1)));
+        assert_eq!(read_back.features[2].properties.as_ref().map(|p| p["mixed"].clone()),
+                   Some(json!("one")));
+    }
+
+    #[test]
+    fn native_geometries() {
+        let square = vec![vec![0.0, 0.0], vec![1.0, 0.0], vec![1.0, 1.0], vec![0.0, 0.0]];
+        let cases = vec![
+            (vec![Value::Point(vec![1.0, 2.0]), Value::Point(vec![3.0, 4.0])], "geoarrow.point"),
+            (vec![Value::Point(vec![1.0, 2.0]), Value::Point(vec![])], "geoarrow.point"),
+            (vec![Value::LineString(vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]])],
+             "geoarrow.linestring"),
+            (vec![Value::Polygon(vec![square.clone(), square.clone()])], "geoarrow.polygon"),
+            (vec![Value::MultiPoint(vec![vec![1.0, 2.0], vec![3.0, 4.0]]),
+                  Value::MultiPoint(vec![])],
+             "geoarrow.multipoint"),
+            (vec![Value::MultiLineString(vec![vec![vec![1.0, 2.0, 3.0, 4.0]]])],
+             "geoarrow.multilinestring"),
+            (vec![Value::MultiPolygon(vec![vec![square.clone()], vec![square.clone()]])],
+             "geoarrow.multipolygon"),
+        ];
+        for (values, extension_name) in cases {
+            let mut builder = FeatureCollection::builder();
+            for value in &values {
+                builder = builder.feature(Feature::builder().geometry(value.clone()).build());
+            }
+            let fc = builder.feature(Feature::builder().build()).build();
+            let batch = write(&fc, &native()).unwrap();
+            assert_eq!(batch.schema().field(0).extension_type_name(), Some(extension_name));
+            assert_eq!(read(&batch).unwrap(), fc);
+        }
+
+        // Empty positions of lines are left out
+        let fc = FeatureCollection::builder()
+            .feature(Feature::builder()
+                .geometry(Value::LineString(vec![vec![0.0, 0.0], vec![], vec![1.0, 1.0]]))
+                .build())
+            .build();
+        let batch = write(&fc, &native()).unwrap();
+        assert_eq!(read(&batch).unwrap().features[0].geometry,
+                   Some(Geometry::new(Value::LineString(vec![vec![0.0, 0.0], vec![1.0, 1.0]]))));
+    }
+
+    #[test]
+    fn native_promotion() {
+        let fc = FeatureCollection::builder()
+            .feature(Feature::builder()
+                .geometry(Value::LineString(vec![vec![0.0, 0.0, 1.0], vec![1.0, 1.0, 2.0]]))
+                .build())
+            .feature(Feature::builder()
+                .geometry(Value::MultiLineString(vec![vec![vec![2.0, 2.0], vec![3.0, 3.0]]]))
+                .build())
+            .build();
+        let batch = write(&fc, &native()).unwrap();
+        assert_eq!(geo_metadata(&batch)["columns"]["geometry"]["encoding"],
+                   json!("multilinestring"));
+        let read_back = read(&batch).unwrap();
+        assert_eq!(read_back.features[0].geometry.as_ref().map(|g| g.value.clone()),
+                   Some(Value::MultiLineString(vec![vec![vec![0.0, 0.0], vec![1.0, 1.0]]])));
+        assert_eq!(read_back.features[1].geometry, fc.features[1].geometry);
+    }
+
+    #[test]
+    fn unsupported() {
+        let mixed = FeatureCollection::builder()
+            .feature(Feature::builder().geometry(Value::Point(vec![0.0, 0.0])).build())
+            .feature(Feature::builder()
+                .geometry(Value::LineString(vec![vec![0.0, 0.0], vec![1.0, 1.0]]))
+                .build())
+            .build();
+        assert_eq!(write(&mixed, &native()).unwrap_err(),
+                   Error::ArrowUnsupported(String::from("mixed Point and LineString geometries \
+                                                         need the WKB encoding")));
+        assert!(write(&mixed, &ArrowOptions::default()).is_ok());
+
+        let collection = FeatureCollection::builder()
+            .feature(Feature::builder().geometry(Value::GeometryCollection(vec![])).build())
+            .build();
+        assert!(write(&collection, &native()).is_err());
+
+        let clash = FeatureCollection::builder()
+            .feature(Feature::builder().property("geometry", 1).build())
+            .build();
+        assert!(write(&clash, &ArrowOptions::default()).is_err());
+    }
+
+    #[test]
+    fn interleaved_geoparquet() {
+        // Interleaved points, described only by GeoParquet metadata
+        let coordinates = Float64Array::from(vec![1.0, 2.0, 3.0, 4.0]);
+        let item = Arc::new(Field::new("xy", DataType::Float64, false));
+        let points = FixedSizeListArray::new(item, 2, Arc::new(coordinates), None);
+        let mut metadata = HashMap::new();
+        metadata.insert(String::from("geo"),
+                        json!({
+                            "version": "1.1.0",
+                            "primary_column": "geom",
+                            "columns": {
+                                "geom": {
+                                    "encoding": "point",
+                                    "geometry_types": ["Point"],
+                                    "crs": {"id": {"authority": "EPSG", "code": 4326}},
+                                },
+                            },
+                        })
+                        .to_string());
+        let schema = Schema::new_with_metadata(vec![Field::new("count", DataType::Int32, true),
+                                                    Field::new("geom",
+                                                               points.data_type().clone(),
+                                                               true)],
+                                               metadata);
+        let columns = vec![Arc::new(Int32Array::from(vec![Some(5), None])) as ArrayRef,
+                           Arc::new(points) as ArrayRef];
+        let batch = RecordBatch::try_new(Arc::new(schema), columns).unwrap();
+
+        let read_back = read(&batch).unwrap();
+        assert_eq!(read_back.crs,
+                   Some(Crs::Named { name: String::from("urn:ogc:def:crs:EPSG::4326") }));
+        assert_eq!(read_back.features[1].geometry.as_ref().map(|g| g.value.clone()),
+                   Some(Value::Point(vec![3.0, 4.0])));
+        assert_eq!(read_back.features[0].properties.as_ref().map(|p| json!(p)),
+                   Some(json!({"count": 5})));
+        assert_eq!(read_back.features[1].properties.as_ref().map(|p| json!(p)),
+                   Some(json!({"count": null})));
+    }
+}
@@ -12,6 +12,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::fmt;
+use std::str::FromStr;
+
 use json::{Serialize, Deserialize, Serializer, Deserializer, JsonObject};
 use serde_json;
 
@@ -37,6 +40,180 @@ pub enum Crs {
     Linked { href: String, type_: Option<String> },
 }
 
+impl Crs {
+    /// Parses the name of a named CRS into a structured identifier.
+    ///
+    /// Returns `None` for linked CRSs and for names that are not in one of the forms
+    /// understood by `CrsId::from_str`.
+    pub fn id(&self) -> Option<CrsId> {
+        match *self {
+            Crs::Named { ref name } => name.parse().ok(),
+            Crs::Linked { .. } => None,
+        }
+    }
+}
+
+impl From<CrsId> for Crs {
+    fn from(id: CrsId) -> Crs {
+        Crs::Named { name: id.to_string() }
+    }
+}
+
+/// Structured CRS identifier
+///
+/// Parsed from the name of a `Crs::Named`. The following forms are understood:
+///
+/// * `EPSG:4326`
+/// * `urn:ogc:def:crs:EPSG::4326` and `urn:ogc:def:crs:OGC:1.3:CRS84`
+/// * `http://www.opengis.net/def/crs/EPSG/0/4326`
+///
+/// Authorities are normalized to upper case. An empty version, or the version `0` used by
+/// the OGC HTTP URIs, is stored as `None`.
+///
+/// # Examples
+///
+/// ```
+/// use geojson::{CrsId, CrsEquivalence};
+///
+/// let epsg = "EPSG:4326".parse::<CrsId>().unwrap();
+/// let urn = "urn:ogc:def:crs:EPSG::4326".parse::<CrsId>().unwrap();
+/// let crs84 = "urn:ogc:def:crs:OGC:1.3:CRS84".parse::<CrsId>().unwrap();
+///
+/// assert_eq!(epsg.equivalence(&urn), CrsEquivalence::Identical);
+/// assert_eq!(epsg.equivalence(&crs84), CrsEquivalence::AxisOrderSwapped);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CrsId {
+    pub authority: String,
+    pub version: Option<String>,
+    pub code: String,
+}
+
+/// How two CRS identifiers relate to one another
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CrsEquivalence {
+    /// Both identifiers denote the same CRS
+    Identical,
+
+    /// Both identifiers denote the same datum and units, but with the first two axes in the
+    /// opposite order (e.g. `EPSG:4326` is latitude/longitude while `CRS84` is
+    /// longitude/latitude)
+    AxisOrderSwapped,
+
+    /// The identifiers are unrelated, as far as this crate knows
+    Different,
+}
+
+/// Pairs of OGC longitude-first CRSs and their EPSG latitude-first counterparts
+const AXIS_SWAPPED_PAIRS: &'static [(&'static str, &'static str)] = &[("CRS84", "4326"),
+                                                                      ("CRS83", "4269"),
+                                                                      ("CRS27", "4267")];
+
+impl CrsId {
+    /// Returns a new unversioned `CrsId`.
+    pub fn new(authority: &str, code: &str) -> Self {
+        CrsId {
+            authority: authority.to_uppercase(),
+            version: None,
+            code: String::from(code),
+        }
+    }
+
+    /// Returns how `self` relates to `other`. Versions are not taken into account.
+    pub fn equivalence(&self, other: &CrsId) -> CrsEquivalence {
+        if self.authority == other.authority && self.code.eq_ignore_ascii_case(&other.code) {
+            return CrsEquivalence::Identical;
+        }
+        let swapped = AXIS_SWAPPED_PAIRS.iter().any(|&(ogc, epsg)| {
+            (self.is("OGC", ogc) && other.is("EPSG", epsg)) ||
+            (self.is("EPSG", epsg) && other.is("OGC", ogc))
+        });
+        if swapped {
+            CrsEquivalence::AxisOrderSwapped
+        } else {
+            CrsEquivalence::Different
+        }
+    }
+
+    /// Returns `true` if `self` and `other` denote the same CRS, regardless of axis order.
+    pub fn is_equivalent(&self, other: &CrsId) -> bool {
+        self.equivalence(other) != CrsEquivalence::Different
+    }
+
+    /// Returns `true` if `self` denotes a CRS whose first axis is latitude or northing
+    /// rather than the longitude or easting GeoJSON positions expect.
+    pub fn is_latitude_first(&self) -> bool {
+        AXIS_SWAPPED_PAIRS.iter().any(|&(_, epsg)| self.is("EPSG", epsg))
+    }
+
+    fn is(&self, authority: &str, code: &str) -> bool {
+        self.authority == authority && self.code.eq_ignore_ascii_case(code)
+    }
+}
+
+impl FromStr for CrsId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::CrsInvalidName(String::from(s));
+
+        let lower = s.to_lowercase();
+        let (authority, version, code) = if lower.starts_with("urn:ogc:def:crs:") {
+            // urn:ogc:def:crs:{authority}:{version}:{code}
+            let parts = s["urn:ogc:def:crs:".len()..].split(':').collect::<Vec<_>>();
+            if parts.len() != 3 {
+                return Err(invalid());
+            }
+            (parts[0], parts[1], parts[2])
+        } else if lower.starts_with("http://www.opengis.net/def/crs/") {
+            // http://www.opengis.net/def/crs/{authority}/{version}/{code}
+            let parts = s["http://www.opengis.net/def/crs/".len()..]
+                .split('/')
+                .collect::<Vec<_>>();
+            if parts.len() != 3 {
+                return Err(invalid());
+            }
+            (parts[0], parts[1], parts[2])
+        } else {
+            // {authority}:{code}
+            let parts = s.split(':').collect::<Vec<_>>();
+            if parts.len() != 2 {
+                return Err(invalid());
+            }
+            (parts[0], "", parts[1])
+        };
+
+        if authority.is_empty() || code.is_empty() {
+            return Err(invalid());
+        }
+
+        // The WMS 1.3 spelling of CRS84 is CRS:84
+        if authority.eq_ignore_ascii_case("CRS") {
+            return Ok(CrsId::new("OGC", &format!("CRS{}", code)));
+        }
+
+        Ok(CrsId {
+            authority: authority.to_uppercase(),
+            version: match version {
+                "" | "0" => None,
+                v => Some(String::from(v)),
+            },
+            code: String::from(code),
+        })
+    }
+}
+
+impl fmt::Display for CrsId {
+    /// Formats the identifier as an OGC URN, e.g. `urn:ogc:def:crs:EPSG::4326`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f,
+               "urn:ogc:def:crs:{}:{}:{}",
+               self.authority,
+               self.version.as_ref().map(|v| v as &str).unwrap_or(""),
+               self.code)
+    }
+}
+
 impl<'a> From<&'a Crs> for JsonObject {
     fn from(crs: &'a Crs) -> JsonObject {
         let mut crs_map = JsonObject::new();
@@ -118,3 +295,59 @@ impl<'de> Deserialize<'de> for Crs {
         Crs::from_object(&val).map_err(|e| D::Error::custom(e.description()))
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use {Crs, CrsEquivalence, CrsId, Error};
+
+    #[test]
+    fn parse_crs_names() {
+        let expected = CrsId::new("EPSG", "4326");
+        assert_eq!("EPSG:4326".parse::<CrsId>(), Ok(expected.clone()));
+        assert_eq!("epsg:4326".parse::<CrsId>(), Ok(expected.clone()));
+        assert_eq!("urn:ogc:def:crs:EPSG::4326".parse::<CrsId>(), Ok(expected.clone()));
+        assert_eq!("http://www.opengis.net/def/crs/EPSG/0/4326".parse::<CrsId>(),
+                   Ok(expected));
+
+        assert_eq!("urn:ogc:def:crs:OGC:1.3:CRS84".parse::<CrsId>(),
+                   Ok(CrsId {
+                       authority: String::from("OGC"),
+                       version: Some(String::from("1.3")),
+                       code: String::from("CRS84"),
+                   }));
+        assert_eq!("CRS:84".parse::<CrsId>(), Ok(CrsId::new("OGC", "CRS84")));
+
+        assert_eq!("4326".parse::<CrsId>(),
+                   Err(Error::CrsInvalidName(String::from("4326"))));
+        assert!("urn:ogc:def:crs:EPSG:4326".parse::<CrsId>().is_err());
+    }
+
+    #[test]
+    fn crs_id_round_trip() {
+        let crs = Crs::Named { name: String::from("urn:ogc:def:crs:OGC:1.3:CRS84") };
+        let id = crs.id().unwrap();
+        assert_eq!(Crs::from(id), crs);
+
+        let linked = Crs::Linked {
+            href: String::from("http://example.com/crs/42"),
+            type_: None,
+        };
+        assert_eq!(linked.id(), None);
+    }
+
+    #[test]
+    fn crs_equivalence() {
+        let epsg_4326 = CrsId::new("EPSG", "4326");
+        let crs84 = CrsId::new("OGC", "CRS84");
+        let web_mercator = CrsId::new("EPSG", "3857");
+
+        assert_eq!(epsg_4326.equivalence(&epsg_4326), CrsEquivalence::Identical);
+        assert_eq!(epsg_4326.equivalence(&crs84), CrsEquivalence::AxisOrderSwapped);
+        assert_eq!(crs84.equivalence(&epsg_4326), CrsEquivalence::AxisOrderSwapped);
+        assert_eq!(epsg_4326.equivalence(&web_mercator), CrsEquivalence::Different);
+        assert!(crs84.is_equivalent(&epsg_4326));
+        assert!(epsg_4326.is_latitude_first());
+        assert!(!crs84.is_latitude_first());
+    }
+}
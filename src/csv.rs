@@ -0,0 +1,504 @@
+// Copyright 2015 The GeoRust Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! CSV reading and writing
+//!
+//! Each record of a CSV file with a header row becomes a feature. Its geometry is read from
+//! latitude and longitude columns or from a WKT column, and its other cells become properties.
+//! A property column whose non-empty cells are all numbers becomes numeric, one whose cells are
+//! all `true` or `false` becomes boolean, and any other column holds strings; empty cells
+//! become `null`. Numbers with leading zeros, such as postal codes, are kept as strings.
+//!
+//! Quoted fields, doubled quotes and line breaks within quoted fields are handled as described
+//! in [RFC 4180](https://tools.ietf.org/html/rfc4180).
+
+use std::collections::HashSet;
+
+use json::{JsonObject, JsonValue};
+use serde_json;
+
+use {Error, Feature, FeatureCollection, Geometry, Value};
+
+
+/// Columns holding the geometry of each feature
+#[derive(Clone, Debug, PartialEq)]
+pub enum GeometryColumns {
+    /// Points as latitude and longitude columns. Other geometries are kept as WKT in the `wkt`
+    /// column if one is named, which is read for records without a latitude and longitude.
+    LatLon {
+        latitude: String,
+        longitude: String,
+        wkt: Option<String>,
+    },
+    /// Geometries as WKT in the named column
+    Wkt(String),
+}
+
+impl GeometryColumns {
+    fn names(&self) -> Vec<&str> {
+        match *self {
+            GeometryColumns::LatLon { ref latitude, ref longitude, ref wkt } => {
+                let mut names = vec![latitude as &str, longitude as &str];
+                names.extend(wkt.as_ref().map(|wkt| wkt as &str));
+                names
+            }
+            GeometryColumns::Wkt(ref wkt) => vec![wkt as &str],
+        }
+    }
+}
+
+/// Options for reading and writing CSV
+#[derive(Clone, Debug, PartialEq)]
+pub struct CsvOptions {
+    pub geometry: GeometryColumns,
+    pub delimiter: char,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        CsvOptions {
+            geometry: GeometryColumns::LatLon {
+                latitude: String::from("latitude"),
+                longitude: String::from("longitude"),
+                wkt: Some(String::from("wkt")),
+            },
+            delimiter: ',',
+        }
+    }
+}
+
+fn malformed(line: usize, message: &str) -> Error {
+    Error::MalformedCsv(format!("line {}: {}", line, message))
+}
+
+/// Splits CSV text into records, each with the line it starts on.
+fn parse_records(csv: &str, delimiter: char) -> Result<Vec<(usize, Vec<String>)>, Error> {
+    let csv = csv.trim_start_matches('\u{feff}');
+    let mut records = vec![];
+    let mut chars = csv.chars().peekable();
+    let mut line = 1;
+    while chars.peek().is_some() {
+        let start_line = line;
+        let mut record = vec![];
+        let mut field = String::new();
+        let mut quoted = false;
+        loop {
+            match chars.next() {
+                Some('"') if field.is_empty() && !quoted => {
+                    quoted = true;
+                    loop {
+                        match chars.next() {
+                            Some('"') if chars.peek() == Some(&'"') => {
+                                chars.next();
+                                field.push('"');
+                            }
+                            Some('"') => break,
+                            Some(c) => {
+                                if c == '\n' {
+                                    line += 1;
+                                }
+                                field.push(c);
+                            }
+                            None => {
+                                return Err(malformed(start_line, "unterminated quoted field"));
+                            }
+                        }
+                    }
+                    match chars.peek() {
+                        Some(&c) if c != delimiter && c != '\r' && c != '\n' => {
+                            return Err(malformed(line, "unexpected character after quote"));
+                        }
+                        _ => {}
+                    }
+                }
+                Some(c) if c == delimiter => {
+                    record.push(field);
+                    field = String::new();
+                    quoted = false;
+                }
+                Some('\r') if chars.peek() == Some(&'\n') => {}
+                Some('\n') | None => {
+                    line += 1;
+                    break;
+                }
+                Some(c) => field.push(c),
+            }
+        }
+        record.push(field);
+        // Skip blank lines
+        if record.len() > 1 || !record[0].is_empty() || quoted {
+            records.push((start_line, record));
+        }
+    }
+    Ok(records)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ColumnType {
+    Number,
+    Bool,
+    String,
+}
+
+fn parse_number(cell: &str) -> Option<JsonValue> {
+    if !cell.chars().all(|c| c.is_ascii_digit() || "+-.eE".contains(c)) {
+        return None;
+    }
+    let digits = cell.trim_start_matches('-');
+    let bytes = digits.as_bytes();
+    if bytes.len() > 1 && bytes[0] == b'0' && bytes[1].is_ascii_digit() {
+        return None;
+    }
+    if let Ok(n) = cell.parse::<i64>() {
+        return Some(JsonValue::from(n));
+    }
+    cell.parse::<f64>()
+        .ok()
+        .and_then(serde_json::Number::from_f64)
+        .map(JsonValue::Number)
+}
+
+fn parse_bool(cell: &str) -> Option<bool> {
+    if cell.eq_ignore_ascii_case("true") {
+        Some(true)
+    } else if cell.eq_ignore_ascii_case("false") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+fn column_type<'a, I: Iterator<Item = &'a str>>(cells: I) -> ColumnType {
+    let mut column_type = None;
+    for cell in cells.filter(|cell| !cell.is_empty()) {
+        let cell_type = if parse_number(cell).is_some() {
+            ColumnType::Number
+        } else if parse_bool(cell).is_some() {
+            ColumnType::Bool
+        } else {
+            return ColumnType::String;
+        };
+        if column_type.map_or(false, |t| t != cell_type) {
+            return ColumnType::String;
+        }
+        column_type = Some(cell_type);
+    }
+    column_type.unwrap_or(ColumnType::String)
+}
+
+fn parse_coordinate(cell: &str, line: usize, name: &str) -> Result<f64, Error> {
+    match cell.trim().parse::<f64>() {
+        Ok(n) if n.is_finite() => Ok(n),
+        _ => Err(malformed(line, &format!("invalid {} '{}'", name, cell))),
+    }
+}
+
+/// Reads features from CSV text with a header row.
+///
+/// # Examples
+///
+/// ```
+/// use geojson::Value;
+/// use geojson::csv::CsvOptions;
+///
+/// let csv = "name,latitude,longitude,visitors\n\
+///            Eiffel Tower,48.8584,2.2945,7000000\n\
+///            \"Louvre, Paris\",48.8606,2.3376,\n";
+/// let fc = geojson::csv::read(csv, &CsvOptions::default()).unwrap();
+///
+/// assert_eq!(fc.features[1].geometry.as_ref().unwrap().value,
+///            Value::Point(vec![2.3376, 48.8606]));
+/// assert_eq!(fc.features[1].property::<String>("name").unwrap(), "Louvre, Paris");
+/// assert_eq!(fc.features[0].property::<u64>("visitors"), Some(7000000));
+/// assert_eq!(fc.features[1].property::<u64>("visitors"), None);
+/// ```
+pub fn read(csv: &str, options: &CsvOptions) -> Result<FeatureCollection, Error> {
+    let mut records = try!(parse_records(csv, options.delimiter)).into_iter();
+    let (_, header) = match records.next() {
+        Some(header) => header,
+        None => return Err(malformed(1, "missing header")),
+    };
+    let records = records.collect::<Vec<_>>();
+    let mut seen = HashSet::new();
+    for name in &header {
+        if !seen.insert(name) {
+            return Err(malformed(1, &format!("duplicate column '{}'", name)));
+        }
+    }
+    for &(line, ref record) in &records {
+        if record.len() != header.len() {
+            return Err(malformed(line,
+                                 &format!("expected {} fields, found {}",
+                                          header.len(),
+                                          record.len())));
+        }
+    }
+
+    let column = |name: &str| header.iter().position(|column| column == name);
+    let required = |name: &str| match column(name) {
+        Some(index) => Ok(index),
+        None => Err(malformed(1, &format!("missing column '{}'", name))),
+    };
+    let (lat_lon, wkt) = match options.geometry {
+        GeometryColumns::LatLon { ref latitude, ref longitude, ref wkt } => {
+            (Some((try!(required(latitude)), try!(required(longitude)))),
+             wkt.as_ref().and_then(|wkt| column(wkt)))
+        }
+        GeometryColumns::Wkt(ref wkt) => (None, Some(try!(required(wkt)))),
+    };
+    let geometry_names = options.geometry.names();
+    let properties = header.iter()
+        .enumerate()
+        .filter(|&(_, name)| !geometry_names.contains(&(name as &str)))
+        .map(|(i, name)| {
+            let column_type = column_type(records.iter().map(|r| &r.1[i] as &str));
+            (i, name, column_type)
+        })
+        .collect::<Vec<_>>();
+
+    let mut features = vec![];
+    for (line, record) in records {
+        let mut value = None;
+        if let Some((latitude, longitude)) = lat_lon {
+            let (lat, lon) = (record[latitude].trim(), record[longitude].trim());
+            if !lat.is_empty() || !lon.is_empty() {
+                let lat = try!(parse_coordinate(lat, line, "latitude"));
+                let lon = try!(parse_coordinate(lon, line, "longitude"));
+                value = Some(Value::Point(vec![lon, lat]));
+            }
+        }
+        if let Some(wkt) = wkt {
+            if value.is_none() && !record[wkt].trim().is_empty() {
+                match Value::from_wkt(&record[wkt]) {
+                    Ok(wkt) => value = Some(wkt),
+                    Err(e) => return Err(malformed(line, &format!("invalid WKT: {}", e))),
+                }
+            }
+        }
+
+        let mut object = JsonObject::new();
+        for &(i, name, column_type) in &properties {
+            let cell = &record[i];
+            let property = match column_type {
+                _ if cell.is_empty() => JsonValue::Null,
+                ColumnType::Number => parse_number(cell).unwrap(),
+                ColumnType::Bool => JsonValue::Bool(parse_bool(cell).unwrap()),
+                ColumnType::String => JsonValue::String(cell.clone()),
+            };
+            object.insert(name.clone(), property);
+        }
+        features.push(Feature {
+            bbox: None,
+            crs: None,
+            geometry: value.map(Geometry::new),
+            id: None,
+            properties: Some(object),
+        });
+    }
+
+    Ok(FeatureCollection {
+        bbox: None,
+        crs: None,
+        features: features,
+    })
+}
+
+/// Writes the features of `fc` as CSV text with a header row.
+///
+/// Geometry columns come first, followed by a column for each property in order of first
+/// appearance. Arrays and objects are written as JSON, and `null` as an empty cell. With
+/// `GeometryColumns::LatLon`, geometries other than points are written as WKT if a `wkt`
+/// column is named, and left out otherwise.
+pub fn write(fc: &FeatureCollection, options: &CsvOptions) -> String {
+    let geometry_names = options.geometry.names();
+    let mut names: Vec<&str> = vec![];
+    let mut seen = HashSet::new();
+    for properties in fc.features.iter().filter_map(|f| f.properties.as_ref()) {
+        for name in properties.keys() {
+            if !geometry_names.contains(&(name as &str)) && seen.insert(name) {
+                names.push(name);
+            }
+        }
+    }
+
+    let mut csv = String::new();
+    let header = geometry_names.iter().chain(names.iter()).map(|name| String::from(*name));
+    write_record(&mut csv, &header.collect::<Vec<_>>(), options.delimiter);
+    for feature in &fc.features {
+        let value = feature.geometry.as_ref().map(|geometry| &geometry.value);
+        let mut record = match options.geometry {
+            GeometryColumns::LatLon { ref wkt, .. } => {
+                let mut record = match value {
+                    Some(&Value::Point(ref position)) if position.len() >= 2 => {
+                        vec![position[1].to_string(), position[0].to_string()]
+                    }
+                    _ => vec![String::new(), String::new()],
+                };
+                if wkt.is_some() {
+                    record.push(match value {
+                        Some(&Value::Point(..)) | None => String::new(),
+                        Some(value) => value.to_wkt(),
+                    });
+                }
+                record
+            }
+            GeometryColumns::Wkt(..) => vec![value.map(Value::to_wkt).unwrap_or_default()],
+        };
+        for name in &names {
+            let property = feature.properties.as_ref().and_then(|p| p.get(*name));
+            record.push(match property {
+                None | Some(&JsonValue::Null) => String::new(),
+                Some(&JsonValue::String(ref s)) => s.clone(),
+                Some(property) => serde_json::to_string(property).unwrap(),
+            });
+        }
+        write_record(&mut csv, &record, options.delimiter);
+    }
+    csv
+}
+
+fn write_record(csv: &mut String, fields: &[String], delimiter: char) {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            csv.push(delimiter);
+        }
+        // A record of a single empty field is quoted to tell it from a blank line
+        let needs_quotes = fields.len() == 1 && field.is_empty() ||
+                           field.chars().any(|c| c == delimiter || "\"\r\n".contains(c));
+        if needs_quotes {
+            csv.push('"');
+            csv.push_str(&field.replace('"', "\"\""));
+            csv.push('"');
+        } else {
+            csv.push_str(field);
+        }
+    }
+    csv.push_str("\r\n");
+}
+
+
+#[cfg(test)]
+mod tests {
+    use {Error, Feature, FeatureCollection, Value};
+    use super::{CsvOptions, GeometryColumns, parse_records, read, write};
+
+    fn wkt_options() -> CsvOptions {
+        CsvOptions {
+            geometry: GeometryColumns::Wkt(String::from("WKT")),
+            delimiter: ';',
+        }
+    }
+
+    #[test]
+    fn records() {
+        let records = parse_records("a,\"b \"\"c\"\"\",\"d\r\ne\"\r\n\n1,,\n", ',').unwrap();
+        assert_eq!(records,
+                   vec![(1, vec![String::from("a"), String::from("b \"c\""),
+                                 String::from("d\r\ne")]),
+                        (4, vec![String::from("1"), String::new(), String::new()])]);
+
+        match parse_records("a,\"b\n", ',') {
+            Err(Error::MalformedCsv(e)) => assert_eq!(e, "line 1: unterminated quoted field"),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(parse_records("a,\"b\"c\n", ',').is_err());
+    }
+
+    #[test]
+    fn property_types() {
+        let csv = "latitude,longitude,count,ratio,flag,zip,mixed,empty\n\
+                   1,2,10,0.5,true,01234,1,\n\
+                   3,4,,2,FALSE,99999,yes,\n";
+        let fc = read(csv, &CsvOptions::default()).unwrap();
+        let properties = fc.features
+            .iter()
+            .map(|f| ::serde_json::to_value(f.properties.as_ref().unwrap()).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(properties,
+                   vec![json!({"count": 10, "ratio": 0.5, "flag": true, "zip": "01234",
+                               "mixed": "1", "empty": null}),
+                        json!({"count": null, "ratio": 2, "flag": false, "zip": "99999",
+                               "mixed": "yes", "empty": null})]);
+    }
+
+    #[test]
+    fn lat_lon_round_trip() {
+        let fc = FeatureCollection::builder()
+            .feature(Feature::builder()
+                .geometry(Value::Point(vec![2.2945, 48.8584]))
+                .property("name", "Eiffel Tower")
+                .property("height", 300)
+                .build())
+            .feature(Feature::builder()
+                .geometry(Value::LineString(vec![vec![1.0, 2.0], vec![3.0, 4.0]]))
+                .property("name", "Line, \"quoted\"")
+                .property("tags", json!(["a", "b"]))
+                .build())
+            .feature(Feature::builder().property("name", "Nowhere").build())
+            .build();
+        let csv = write(&fc, &CsvOptions::default());
+        assert_eq!(csv,
+                   "latitude,longitude,wkt,height,name,tags\r\n\
+                    48.8584,2.2945,,300,Eiffel Tower,\r\n\
+                    ,,\"LINESTRING (1 2, 3 4)\",,\"Line, \"\"quoted\"\"\",\
+                    \"[\"\"a\"\",\"\"b\"\"]\"\r\n\
+                    ,,,,Nowhere,\r\n");
+
+        let read_back = read(&csv, &CsvOptions::default()).unwrap();
+        let geometries = read_back.features
+            .iter()
+            .map(|f| f.geometry.as_ref().map(|g| g.value.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(geometries,
+                   fc.features.iter().map(|f| f.geometry.as_ref().map(|g| g.value.clone()))
+                       .collect::<Vec<_>>());
+        assert_eq!(read_back.features[0].property::<i64>("height"), Some(300));
+        assert_eq!(read_back.features[1].property::<String>("name").unwrap(), "Line, \"quoted\"");
+        assert_eq!(read_back.features[1].property::<String>("tags").unwrap(), "[\"a\",\"b\"]");
+    }
+
+    #[test]
+    fn wkt_round_trip() {
+        let fc = FeatureCollection::builder()
+            .feature(Feature::builder()
+                .geometry(Value::Polygon(vec![vec![vec![0.0, 0.0], vec![1.0, 0.0],
+                                                   vec![1.0, 1.0], vec![0.0, 0.0]]]))
+                .property("area", 0.5)
+                .build())
+            .feature(Feature::builder()
+                .geometry(Value::Point(vec![1.0, 2.0, 3.0]))
+                .property("area", 0)
+                .build())
+            .build();
+        let csv = write(&fc, &wkt_options());
+        assert_eq!(csv,
+                   "WKT;area\r\nPOLYGON ((0 0, 1 0, 1 1, 0 0));0.5\r\nPOINT Z (1 2 3);0\r\n");
+        let read_back = read(&csv, &wkt_options()).unwrap();
+        assert_eq!(read_back, fc);
+    }
+
+    #[test]
+    fn malformed() {
+        let parse = |csv: &str| read(csv, &CsvOptions::default());
+        let error = |message: &str| Err(Error::MalformedCsv(String::from(message)));
+        assert_eq!(parse(""), error("line 1: missing header"));
+        assert_eq!(parse("name\nx\n"), error("line 1: missing column 'latitude'"));
+        assert_eq!(parse("latitude,longitude,a,a\n"), error("line 1: duplicate column 'a'"));
+        assert_eq!(parse("latitude,longitude\n1,2\n3\n"),
+                   error("line 3: expected 2 fields, found 1"));
+        assert_eq!(parse("latitude,longitude\n1,north\n"),
+                   error("line 2: invalid longitude 'north'"));
+        assert!(parse("latitude,longitude,wkt\n,,POINT (1\n").is_err());
+    }
+}
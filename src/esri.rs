@@ -0,0 +1,706 @@
+// Copyright 2015 The GeoRust Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Esri JSON conversion
+//!
+//! Geometries and feature sets of the [ArcGIS REST API]
+//! (https://developers.arcgis.com/documentation/common-data-types/geometry-objects.htm) are
+//! converted to and from `Geometry` with `Geometry::from_esri` and `Geometry::to_esri`, and
+//! to and from `FeatureCollection` with `read` and `write`.
+//!
+//! Esri polygons are a flat list of rings, where clockwise rings are outer rings and
+//! counter-clockwise rings are holes. When reading, each hole is assigned to the smallest outer
+//! ring containing it, and rings are rewound to the counter-clockwise outer rings and clockwise
+//! holes of RFC 7946. When writing, rings are rewound to the Esri orientation.
+//!
+//! As with WKT, `M` values are only kept for geometries that also have `Z` values. A
+//! `spatialReference` `wkid` (or `latestWkid`, when present) becomes a `Crs::Named` in the
+//! `EPSG` authority, or the `ESRI` authority for codes from 100000 up.
+
+use json::{JsonObject, JsonValue};
+use rings;
+use serde_json;
+
+use {Crs, CrsId, Error, Feature, FeatureCollection, Geometry, LineStringType, Position,
+     Value, util};
+
+
+fn malformed(message: &str) -> Error {
+    Error::MalformedEsriJson(String::from(message))
+}
+
+
+// Reading
+
+fn read_f64(value: &JsonValue, name: &str) -> Result<f64, Error> {
+    match value.as_f64() {
+        Some(n) => Ok(n),
+        None => Err(malformed(&format!("expected a number for '{}'", name))),
+    }
+}
+
+fn read_position(value: &JsonValue, has_z: bool, has_m: bool) -> Result<Position, Error> {
+    let array = match value.as_array() {
+        Some(array) if array.len() >= 2 => array,
+        _ => return Err(malformed("expected an array of at least two coordinates")),
+    };
+    let dimensions = if has_m && !has_z { 2 } else { 4 };
+    array.iter().take(dimensions).map(|ordinate| read_f64(ordinate, "coordinates")).collect()
+}
+
+fn read_array<'a>(object: &'a JsonObject, key: &str) -> Result<&'a Vec<JsonValue>, Error> {
+    match object.get(key).and_then(JsonValue::as_array) {
+        Some(array) => Ok(array),
+        None => Err(malformed(&format!("expected an array for '{}'", key))),
+    }
+}
+
+fn read_lines(object: &JsonObject, key: &str) -> Result<Vec<LineStringType>, Error> {
+    let has_z = object.get("hasZ").and_then(JsonValue::as_bool).unwrap_or(false);
+    let has_m = object.get("hasM").and_then(JsonValue::as_bool).unwrap_or(false);
+    let mut lines = vec![];
+    for line in try!(read_array(object, key)) {
+        let line = match line.as_array() {
+            Some(line) => line,
+            None => return Err(malformed(&format!("expected arrays in '{}'", key))),
+        };
+        let line = try!(line.iter().map(|p| read_position(p, has_z, has_m)).collect());
+        lines.push(line);
+    }
+    Ok(lines)
+}
+
+/// Reads an Esri geometry, returning `None` for empty geometries.
+fn read_value(object: &JsonObject) -> Result<Option<Value>, Error> {
+    if let Some(x) = object.get("x") {
+        if x.is_null() || x.as_str() == Some("NaN") {
+            return Ok(None);
+        }
+        let mut position = vec![try!(read_f64(x, "x")),
+                                try!(read_f64(try!(get_required(object, "y")), "y"))];
+        if let Some(z) = object.get("z").filter(|z| !z.is_null()) {
+            position.push(try!(read_f64(z, "z")));
+            if let Some(m) = object.get("m").filter(|m| !m.is_null()) {
+                position.push(try!(read_f64(m, "m")));
+            }
+        }
+        return Ok(Some(Value::Point(position)));
+    }
+    if object.contains_key("points") {
+        let has_z = object.get("hasZ").and_then(JsonValue::as_bool).unwrap_or(false);
+        let has_m = object.get("hasM").and_then(JsonValue::as_bool).unwrap_or(false);
+        let points = try!(try!(read_array(object, "points"))
+            .iter()
+            .map(|p| read_position(p, has_z, has_m))
+            .collect::<Result<Vec<_>, _>>());
+        return Ok(if points.is_empty() { None } else { Some(Value::MultiPoint(points)) });
+    }
+    if object.contains_key("paths") {
+        let mut paths = try!(read_lines(object, "paths"));
+        return Ok(match paths.len() {
+            0 => None,
+            1 => Some(Value::LineString(paths.remove(0))),
+            _ => Some(Value::MultiLineString(paths)),
+        });
+    }
+    if object.contains_key("rings") {
+        let rings = try!(read_lines(object, "rings"));
+        if rings.iter().any(|ring| ring.is_empty()) {
+            return Err(malformed("empty ring"));
+        }
+        let mut polygons = rings::group_clockwise(rings);
+        return Ok(match polygons.len() {
+            0 => None,
+            1 => Some(Value::Polygon(polygons.remove(0))),
+            _ => Some(Value::MultiPolygon(polygons)),
+        });
+    }
+    if let Some(xmin) = object.get("xmin") {
+        if xmin.is_null() || xmin.as_str() == Some("NaN") {
+            return Ok(None);
+        }
+        let mut bounds = vec![];
+        for key in &["xmin", "ymin", "xmax", "ymax"] {
+            bounds.push(try!(read_f64(try!(get_required(object, key)), key)));
+        }
+        let (x0, y0, x1, y1) = (bounds[0], bounds[1], bounds[2], bounds[3]);
+        return Ok(Some(Value::Polygon(vec![vec![vec![x0, y0], vec![x1, y0], vec![x1, y1],
+                                                vec![x0, y1], vec![x0, y0]]])));
+    }
+    if object.contains_key("curvePaths") || object.contains_key("curveRings") {
+        return Err(malformed("curves are not supported"));
+    }
+    Err(malformed("unknown geometry type"))
+}
+
+fn get_required<'a>(object: &'a JsonObject, key: &str) -> Result<&'a JsonValue, Error> {
+    match object.get(key) {
+        Some(value) => Ok(value),
+        None => Err(malformed(&format!("missing '{}'", key))),
+    }
+}
+
+fn read_spatial_reference(object: &JsonObject) -> Option<Crs> {
+    let reference = match object.get("spatialReference").and_then(JsonValue::as_object) {
+        Some(reference) => reference,
+        None => return None,
+    };
+    let wkid = reference.get("latestWkid")
+        .and_then(JsonValue::as_u64)
+        .or_else(|| reference.get("wkid").and_then(JsonValue::as_u64));
+    wkid.map(|wkid| {
+        let authority = if wkid >= 100000 { "ESRI" } else { "EPSG" };
+        Crs::from(CrsId::new(authority, &wkid.to_string()))
+    })
+}
+
+
+// Writing
+
+fn write_spatial_reference(crs: &Crs) -> Option<JsonValue> {
+    let id = match crs.id() {
+        Some(id) => id,
+        None => return None,
+    };
+    let wkid = match (&id.authority as &str, &id.code as &str) {
+        ("OGC", "CRS84") => Some(4326),
+        ("EPSG", code) | ("ESRI", code) => code.parse::<u64>().ok(),
+        _ => None,
+    };
+    wkid.map(|wkid| json!({ "wkid": wkid }))
+}
+
+fn write_positions(positions: &[Position], dimensions: usize) -> JsonValue {
+    JsonValue::Array(positions.iter()
+        .map(|position| json!(position.iter().take(dimensions).collect::<Vec<_>>()))
+        .collect())
+}
+
+fn write_lines(lines: &[&LineStringType], dimensions: usize) -> JsonValue {
+    JsonValue::Array(lines.iter().map(|line| write_positions(line, dimensions)).collect())
+}
+
+/// Returns the Esri geometry type that holds `value`.
+fn geometry_type(value: &Value) -> Result<&'static str, Error> {
+    Ok(match *value {
+        Value::Point(..) => "esriGeometryPoint",
+        Value::MultiPoint(..) => "esriGeometryMultipoint",
+        Value::LineString(..) |
+        Value::MultiLineString(..) => "esriGeometryPolyline",
+        Value::Polygon(..) |
+        Value::MultiPolygon(..) => "esriGeometryPolygon",
+        Value::GeometryCollection(..) => {
+            return Err(Error::EsriUnsupportedGeometry(String::from("GeometryCollection")))
+        }
+    })
+}
+
+fn write_value(value: &Value, object: &mut JsonObject) -> Result<(), Error> {
+    try!(geometry_type(value));
+    let dimensions = util::coordinate_dimensions(value);
+    let is_point = match *value {
+        Value::Point(..) => true,
+        _ => false,
+    };
+    if dimensions >= 3 && !is_point {
+        object.insert(String::from("hasZ"), JsonValue::Bool(true));
+        if dimensions == 4 {
+            object.insert(String::from("hasM"), JsonValue::Bool(true));
+        }
+    }
+    match *value {
+        // Empty points are written as in ArcGIS, with a null `x`
+        Value::Point(ref position) if position.len() < 2 => {
+            object.insert(String::from("x"), JsonValue::Null);
+        }
+        Value::Point(ref position) => {
+            for (key, ordinate) in ["x", "y", "z", "m"].iter().zip(position) {
+                object.insert(String::from(*key), json!(ordinate));
+            }
+        }
+        Value::MultiPoint(ref positions) => {
+            object.insert(String::from("points"), write_positions(positions, dimensions));
+        }
+        Value::LineString(ref line) => {
+            object.insert(String::from("paths"), write_lines(&[line], dimensions));
+        }
+        Value::MultiLineString(ref lines) => {
+            let lines = lines.iter().collect::<Vec<_>>();
+            object.insert(String::from("paths"), write_lines(&lines, dimensions));
+        }
+        Value::Polygon(..) |
+        Value::MultiPolygon(..) => {
+            let polygons = match *value {
+                Value::Polygon(ref polygon) => vec![polygon.clone()],
+                Value::MultiPolygon(ref polygons) => polygons.clone(),
+                _ => unreachable!(),
+            };
+            let rings = polygons.iter()
+                .flat_map(|polygon| {
+                    polygon.iter().enumerate().map(|(i, ring)| rings::wound(ring, i > 0))
+                })
+                .collect::<Vec<_>>();
+            let rings = rings.iter().collect::<Vec<_>>();
+            object.insert(String::from("rings"), write_lines(&rings, dimensions));
+        }
+        Value::GeometryCollection(..) => unreachable!(),
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum FieldType {
+    SmallInteger,
+    Integer,
+    Double,
+    String,
+}
+
+impl FieldType {
+    fn of(value: &JsonValue) -> Option<FieldType> {
+        Some(match *value {
+            JsonValue::Null => return None,
+            JsonValue::Bool(..) => FieldType::SmallInteger,
+            JsonValue::Number(ref n) => {
+                match n.as_i64() {
+                    Some(n) if n >= i32::min_value() as i64 && n <= i32::max_value() as i64 => {
+                        FieldType::Integer
+                    }
+                    _ => FieldType::Double,
+                }
+            }
+            _ => FieldType::String,
+        })
+    }
+
+    fn merge(self, other: FieldType) -> FieldType {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (FieldType::Integer, FieldType::Double) |
+            (FieldType::Double, FieldType::Integer) => FieldType::Double,
+            _ => FieldType::String,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match *self {
+            FieldType::SmallInteger => "esriFieldTypeSmallInteger",
+            FieldType::Integer => "esriFieldTypeInteger",
+            FieldType::Double => "esriFieldTypeDouble",
+            FieldType::String => "esriFieldTypeString",
+        }
+    }
+
+    fn convert(&self, value: &JsonValue) -> JsonValue {
+        match (*self, value) {
+            (_, &JsonValue::Null) => JsonValue::Null,
+            (FieldType::SmallInteger, &JsonValue::Bool(b)) => json!(b as i32),
+            (FieldType::String, &JsonValue::String(..)) => value.clone(),
+            (FieldType::String, value) => JsonValue::String(serde_json::to_string(value).unwrap()),
+            (_, value) => value.clone(),
+        }
+    }
+}
+
+const OBJECT_ID_FIELD: &'static str = "OBJECTID";
+
+impl Geometry {
+    /// Converts an Esri JSON geometry: a point, multipoint, polyline, polygon or envelope.
+    ///
+    /// Envelopes become polygons. A `spatialReference` sets `crs`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[macro_use]
+    /// extern crate serde_json;
+    /// extern crate geojson;
+    ///
+    /// use geojson::{Geometry, Value};
+    ///
+    /// # fn main() {
+    /// let esri = json!({
+    ///     "rings": [[[0.0, 0.0], [0.0, 4.0], [4.0, 4.0], [4.0, 0.0], [0.0, 0.0]],
+    ///               [[1.0, 1.0], [2.0, 1.0], [2.0, 2.0], [1.0, 1.0]]],
+    ///     "spatialReference": {"wkid": 4326}
+    /// });
+    /// let geometry = Geometry::from_esri(esri.as_object().unwrap()).unwrap();
+    /// assert_eq!(geometry.value,
+    ///            Value::Polygon(vec![vec![vec![0.0, 0.0], vec![4.0, 0.0], vec![4.0, 4.0],
+    ///                                     vec![0.0, 4.0], vec![0.0, 0.0]],
+    ///                                vec![vec![1.0, 1.0], vec![2.0, 2.0], vec![2.0, 1.0],
+    ///                                     vec![1.0, 1.0]]]));
+    /// assert_eq!(geometry.to_esri().unwrap(), *esri.as_object().unwrap());
+    /// # }
+    /// ```
+    pub fn from_esri(object: &JsonObject) -> Result<Geometry, Error> {
+        match try!(read_value(object)) {
+            Some(value) => {
+                Ok(Geometry {
+                    bbox: None,
+                    value: value,
+                    crs: read_spatial_reference(object),
+                })
+            }
+            None => Err(malformed("empty geometry")),
+        }
+    }
+
+    /// Converts the geometry to Esri JSON, with a `spatialReference` if `crs` has an `EPSG` or
+    /// `ESRI` code.
+    ///
+    /// Geometry collections have no Esri JSON equivalent, and fail with
+    /// `Error::EsriUnsupportedGeometry`.
+    pub fn to_esri(&self) -> Result<JsonObject, Error> {
+        let mut object = JsonObject::new();
+        try!(write_value(&self.value, &mut object));
+        if let Some(reference) = self.crs.as_ref().and_then(write_spatial_reference) {
+            object.insert(String::from("spatialReference"), reference);
+        }
+        Ok(object)
+    }
+}
+
+/// Converts an Esri JSON feature set.
+///
+/// Feature attributes become properties, except for the object id field, named by
+/// `objectIdFieldName` or of type `esriFieldTypeOID`, which becomes the feature id. The
+/// feature set's `spatialReference` sets `crs`.
+pub fn read(object: &JsonObject) -> Result<FeatureCollection, Error> {
+    let object_id = object.get("objectIdFieldName")
+        .and_then(JsonValue::as_str)
+        .or_else(|| {
+            object.get("fields")
+                .and_then(JsonValue::as_array)
+                .and_then(|fields| {
+                    fields.iter()
+                        .find(|f| f["type"] == JsonValue::from("esriFieldTypeOID"))
+                        .and_then(|f| f["name"].as_str())
+                })
+        });
+
+    let mut features = vec![];
+    for feature in try!(read_array(object, "features")) {
+        let feature = match feature.as_object() {
+            Some(feature) => feature,
+            None => return Err(malformed("expected an object in 'features'")),
+        };
+        let geometry = match feature.get("geometry") {
+            Some(&JsonValue::Object(ref geometry)) => {
+                try!(read_value(geometry)).map(|value| {
+                    Geometry {
+                        bbox: None,
+                        value: value,
+                        crs: read_spatial_reference(geometry),
+                    }
+                })
+            }
+            None | Some(&JsonValue::Null) => None,
+            Some(..) => return Err(malformed("expected an object for 'geometry'")),
+        };
+        let mut properties = match feature.get("attributes") {
+            Some(&JsonValue::Object(ref attributes)) => attributes.clone(),
+            None | Some(&JsonValue::Null) => JsonObject::new(),
+            Some(..) => return Err(malformed("expected an object for 'attributes'")),
+        };
+        let id = object_id.and_then(|name| properties.remove(name));
+        features.push(Feature {
+            bbox: None,
+            crs: None,
+            geometry: geometry,
+            id: id,
+            properties: Some(properties),
+        });
+    }
+
+    Ok(FeatureCollection {
+        bbox: None,
+        crs: read_spatial_reference(object),
+        features: features,
+    })
+}
+
+/// Converts the features of `fc` to an Esri JSON feature set.
+///
+/// Fields are inferred from the properties. Booleans become `esriFieldTypeSmallInteger`
+/// values of 0 or 1, and arrays, objects and fields of mixed types become strings. If every
+/// feature has an integer id and no property is named `OBJECTID`, the ids are written to
+/// an `OBJECTID` object id field; otherwise ids are left out.
+///
+/// Point and multipoint features are written together as multipoints. Other mixes of
+/// geometry types, and geometry collections, fail with `Error::EsriUnsupportedGeometry`.
+///
+/// # Examples
+///
+/// ```
+/// #[macro_use]
+/// extern crate serde_json;
+/// extern crate geojson;
+///
+/// use geojson::{Feature, FeatureCollection, Value};
+///
+/// # fn main() {
+/// let fc = FeatureCollection::builder()
+///     .feature(Feature::builder()
+///         .geometry(Value::Point(vec![-117.2, 34.05]))
+///         .id(1)
+///         .property("name", "Redlands")
+///         .build())
+///     .build();
+///
+/// let esri = geojson::esri::write(&fc).unwrap();
+/// assert_eq!(serde_json::to_value(&esri).unwrap(), json!({
+///     "geometryType": "esriGeometryPoint",
+///     "objectIdFieldName": "OBJECTID",
+///     "fields": [{"name": "OBJECTID", "type": "esriFieldTypeOID"},
+///                {"name": "name", "type": "esriFieldTypeString"}],
+///     "features": [{"attributes": {"OBJECTID": 1, "name": "Redlands"},
+///                   "geometry": {"x": -117.2, "y": 34.05}}]
+/// }));
+/// assert_eq!(geojson::esri::read(&esri).unwrap(), fc);
+/// # }
+/// ```
+pub fn write(fc: &FeatureCollection) -> Result<JsonObject, Error> {
+    let mut geometry_types = vec![];
+    for geometry in fc.features.iter().filter_map(|f| f.geometry.as_ref()) {
+        let geometry_type = try!(geometry_type(&geometry.value));
+        if !geometry_types.contains(&geometry_type) {
+            geometry_types.push(geometry_type);
+        }
+    }
+    geometry_types.sort();
+    let geometry_type = match &geometry_types[..] {
+        [] => None,
+        [geometry_type] => Some(geometry_type),
+        ["esriGeometryMultipoint", "esriGeometryPoint"] => Some(&"esriGeometryMultipoint"),
+        _ => {
+            let message = format!("mixed geometry types {}", geometry_types.join(", "));
+            return Err(Error::EsriUnsupportedGeometry(message));
+        }
+    };
+
+    let mut fields: Vec<(&str, Option<FieldType>)> = vec![];
+    for properties in fc.features.iter().filter_map(|f| f.properties.as_ref()) {
+        for (name, value) in properties {
+            let index = match fields.iter().position(|f| f.0 == name) {
+                Some(index) => index,
+                None => {
+                    fields.push((name, None));
+                    fields.len() - 1
+                }
+            };
+            let field_type = &mut fields[index].1;
+            *field_type = match (*field_type, FieldType::of(value)) {
+                (Some(a), Some(b)) => Some(a.merge(b)),
+                (a, b) => a.or(b),
+            };
+        }
+    }
+    let fields = fields.into_iter()
+        .map(|(name, field_type)| (name, field_type.unwrap_or(FieldType::String)))
+        .collect::<Vec<_>>();
+    let has_object_ids = !fc.features.is_empty() &&
+                         fc.features.iter().all(|f| {
+        f.id.as_ref().map_or(false, |id| id.is_i64() || id.is_u64())
+    }) && !fields.iter().any(|f| f.0 == OBJECT_ID_FIELD);
+
+    let mut features = vec![];
+    for feature in &fc.features {
+        let mut attributes = JsonObject::new();
+        if has_object_ids {
+            attributes.insert(String::from(OBJECT_ID_FIELD), feature.id.clone().unwrap());
+        }
+        for &(name, field_type) in &fields {
+            let value = feature.properties.as_ref().and_then(|p| p.get(name));
+            attributes.insert(String::from(name),
+                              value.map_or(JsonValue::Null, |v| field_type.convert(v)));
+        }
+        let mut object = JsonObject::new();
+        object.insert(String::from("attributes"), JsonValue::Object(attributes));
+        if let Some(ref geometry) = feature.geometry {
+            let mut value = geometry.value.clone();
+            if geometry_type == Some(&"esriGeometryMultipoint") {
+                if let Value::Point(position) = value {
+                    value = Value::MultiPoint(vec![position]);
+                }
+            }
+            let mut esri = JsonObject::new();
+            try!(write_value(&value, &mut esri));
+            object.insert(String::from("geometry"), JsonValue::Object(esri));
+        }
+        features.push(JsonValue::Object(object));
+    }
+
+    let mut object = JsonObject::new();
+    if let Some(geometry_type) = geometry_type {
+        object.insert(String::from("geometryType"), json!(geometry_type));
+    }
+    if let Some(reference) = fc.crs.as_ref().and_then(write_spatial_reference) {
+        object.insert(String::from("spatialReference"), reference);
+    }
+    let mut field_list = vec![];
+    if has_object_ids {
+        object.insert(String::from("objectIdFieldName"), json!(OBJECT_ID_FIELD));
+        field_list.push(json!({ "name": OBJECT_ID_FIELD, "type": "esriFieldTypeOID" }));
+    }
+    for (name, field_type) in fields {
+        field_list.push(json!({ "name": name, "type": field_type.name() }));
+    }
+    object.insert(String::from("fields"), JsonValue::Array(field_list));
+    object.insert(String::from("features"), JsonValue::Array(features));
+    Ok(object)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use {Crs, Error, Feature, FeatureCollection, Geometry, Value};
+    use super::{read, write};
+
+    #[test]
+    fn geometries() {
+        let cases = vec![
+            (json!({"x": 1.0, "y": 2.0, "z": 3.0, "m": 4.0}),
+             Value::Point(vec![1.0, 2.0, 3.0, 4.0])),
+            (json!({"points": [[1.0, 2.0], [3.0, 4.0]]}),
+             Value::MultiPoint(vec![vec![1.0, 2.0], vec![3.0, 4.0]])),
+            (json!({"hasZ": true, "paths": [[[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]]}),
+             Value::LineString(vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]])),
+            (json!({"paths": [[[1.0, 2.0], [3.0, 4.0]], [[5.0, 6.0], [7.0, 8.0]]]}),
+             Value::MultiLineString(vec![vec![vec![1.0, 2.0], vec![3.0, 4.0]],
+                                         vec![vec![5.0, 6.0], vec![7.0, 8.0]]])),
+            (json!({"rings": [[[0.0, 0.0], [0.0, 4.0], [4.0, 4.0], [4.0, 0.0], [0.0, 0.0]],
+                              [[1.0, 1.0], [2.0, 1.0], [2.0, 2.0], [1.0, 2.0], [1.0, 1.0]]]}),
+             Value::Polygon(vec![vec![vec![0.0, 0.0],
+                                      vec![4.0, 0.0],
+                                      vec![4.0, 4.0],
+                                      vec![0.0, 4.0],
+                                      vec![0.0, 0.0]],
+                                 vec![vec![1.0, 1.0],
+                                      vec![1.0, 2.0],
+                                      vec![2.0, 2.0],
+                                      vec![2.0, 1.0],
+                                      vec![1.0, 1.0]]])),
+        ];
+        for (esri, value) in cases {
+            let geometry = Geometry::from_esri(esri.as_object().unwrap()).unwrap();
+            assert_eq!(geometry.value, value);
+            assert_eq!(geometry.to_esri().unwrap(), *esri.as_object().unwrap());
+        }
+
+        let parse = |esri: ::serde_json::Value| Geometry::from_esri(esri.as_object().unwrap());
+        let envelope = parse(json!({"xmin": 0, "ymin": 0, "xmax": 1, "ymax": 1})).unwrap();
+        assert_eq!(envelope.value,
+                   Value::Polygon(vec![vec![vec![0.0, 0.0],
+                                            vec![1.0, 0.0],
+                                            vec![1.0, 1.0],
+                                            vec![0.0, 1.0],
+                                            vec![0.0, 0.0]]]));
+        // M values without Z values are dropped
+        let measured = parse(json!({"hasM": true, "points": [[1, 2, 9]]})).unwrap();
+        assert_eq!(measured.value, Value::MultiPoint(vec![vec![1.0, 2.0]]));
+        let projected = parse(json!({"x": 1, "y": 2,
+                                    "spatialReference": {"wkid": 102100, "latestWkid": 3857}}))
+            .unwrap();
+        assert_eq!(projected.crs,
+                   Some(Crs::Named { name: String::from("urn:ogc:def:crs:EPSG::3857") }));
+        assert_eq!(parse(json!({"x": null, "y": null})),
+                   Err(Error::MalformedEsriJson(String::from("empty geometry"))));
+        assert_eq!(Geometry::new(Value::Point(vec![])).to_esri().unwrap(),
+                   *json!({"x": null}).as_object().unwrap());
+        assert!(parse(json!({"curveRings": []})).is_err());
+        assert!(parse(json!({"paths": [[[1]]]})).is_err());
+        assert!(parse(json!({"rings": [[]]})).is_err());
+        assert!(parse(json!({"type": "Point"})).is_err());
+    }
+
+    #[test]
+    fn feature_sets() {
+        let esri = json!({
+            "objectIdFieldName": "FID",
+            "geometryType": "esriGeometryPolyline",
+            "spatialReference": {"wkid": 4326},
+            "fields": [{"name": "FID", "type": "esriFieldTypeOID"},
+                       {"name": "name", "type": "esriFieldTypeString"}],
+            "features": [
+                {"attributes": {"FID": 10, "name": "Main St"},
+                 "geometry": {"paths": [[[0, 0], [1, 1]]]}},
+                {"attributes": {"FID": 11, "name": null}, "geometry": null}
+            ]
+        });
+        let fc = read(esri.as_object().unwrap()).unwrap();
+        assert_eq!(fc.crs,
+                   Some(Crs::Named { name: String::from("urn:ogc:def:crs:EPSG::4326") }));
+        assert_eq!(fc.features[0].id, Some(json!(10)));
+        assert_eq!(fc.features[0].property::<String>("name").unwrap(), "Main St");
+        assert_eq!(fc.features[1].geometry, None);
+
+        let written = ::serde_json::to_value(write(&fc).unwrap()).unwrap();
+        assert_eq!(written,
+                   json!({
+                       "geometryType": "esriGeometryPolyline",
+                       "spatialReference": {"wkid": 4326},
+                       "objectIdFieldName": "OBJECTID",
+                       "fields": [{"name": "OBJECTID", "type": "esriFieldTypeOID"},
+                                  {"name": "name", "type": "esriFieldTypeString"}],
+                       "features": [
+                           {"attributes": {"OBJECTID": 10, "name": "Main St"},
+                            "geometry": {"paths": [[[0.0, 0.0], [1.0, 1.0]]]}},
+                           {"attributes": {"OBJECTID": 11, "name": null}}
+                       ]
+                   }));
+    }
+
+    #[test]
+    fn field_types() {
+        let fc = FeatureCollection::builder()
+            .feature(Feature::builder()
+                .geometry(Value::Point(vec![0.0, 0.0]))
+                .id("a")
+                .property("flag", true)
+                .property("count", 1)
+                .property("big", 1u64 << 40)
+                .property("mixed", 1)
+                .property("tags", json!(["x"]))
+                .build())
+            .feature(Feature::builder()
+                .geometry(Value::MultiPoint(vec![vec![1.0, 1.0]]))
+                .property("count", 2.5)
+                .property("mixed", "one")
+                .build())
+            .build();
+        let written = ::serde_json::to_value(write(&fc).unwrap()).unwrap();
+        assert_eq!(written["geometryType"], json!("esriGeometryMultipoint"));
+        assert_eq!(written["fields"],
+                   json!([{"name": "big", "type": "esriFieldTypeDouble"},
+                          {"name": "count", "type": "esriFieldTypeDouble"},
+                          {"name": "flag", "type": "esriFieldTypeSmallInteger"},
+                          {"name": "mixed", "type": "esriFieldTypeString"},
+                          {"name": "tags", "type": "esriFieldTypeString"}]));
+        assert_eq!(written["features"][0]["attributes"],
+                   json!({"big": 1u64 << 40, "count": 1, "flag": 1, "mixed": "1",
+                          "tags": "[\"x\"]"}));
+        assert_eq!(written["features"][0]["geometry"], json!({"points": [[0.0, 0.0]]}));
+
+        let mixed = FeatureCollection::builder()
+            .feature(Feature::builder().geometry(Value::Point(vec![0.0, 0.0])).build())
+            .feature(Feature::builder()
+                .geometry(Value::LineString(vec![vec![0.0, 0.0], vec![1.0, 1.0]]))
+                .build())
+            .build();
+        assert_eq!(write(&mixed),
+                   Err(Error::EsriUnsupportedGeometry(String::from(
+                       "mixed geometry types esriGeometryPoint, esriGeometryPolyline"))));
+    }
+}
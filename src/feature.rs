@@ -12,6 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use serde::de::DeserializeOwned;
+
 use json::{Serialize, Deserialize, Serializer, Deserializer, JsonValue, JsonObject};
 use serde_json;
 use {Bbox, Crs, Error, FromObject, Geometry, util};
@@ -30,6 +32,141 @@ pub struct Feature {
     pub properties: Option<JsonObject>,
 }
 
+impl Feature {
+    /// Returns a `FeatureBuilder` for a feature with no members set.
+    pub fn builder() -> FeatureBuilder {
+        FeatureBuilder::new()
+    }
+
+    /// Returns the property `key` deserialized as a `T`.
+    ///
+    /// Returns `None` if the property is missing or cannot be deserialized as a `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geojson::Feature;
+    ///
+    /// let feature = Feature::builder().property("population", 42).build();
+    ///
+    /// assert_eq!(feature.property::<u64>("population"), Some(42));
+    /// assert_eq!(feature.property::<String>("population"), None);
+    /// assert_eq!(feature.property::<u64>("area"), None);
+    /// ```
+    pub fn property<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        self.properties
+            .as_ref()
+            .and_then(|properties| properties.get(key))
+            .and_then(|value| serde_json::from_value(value.clone()).ok())
+    }
+
+    /// Sets the property `key` to `value`, creating the `properties` member if it is `None`.
+    pub fn set_property<K: Into<String>, V: Into<JsonValue>>(&mut self, key: K, value: V) {
+        if self.properties.is_none() {
+            self.properties = Some(JsonObject::new());
+        }
+        if let Some(ref mut properties) = self.properties {
+            properties.insert(key.into(), value.into());
+        }
+    }
+
+    /// Removes the property `key`, returning its value if it was present.
+    pub fn remove_property(&mut self, key: &str) -> Option<JsonValue> {
+        self.properties.as_mut().and_then(|properties| properties.remove(key))
+    }
+
+    /// Returns `true` if the feature has a property `key`, even if its value is `null`.
+    pub fn contains_property(&self, key: &str) -> bool {
+        self.properties.as_ref().map_or(false, |properties| properties.contains_key(key))
+    }
+
+    /// Swaps the first two ordinates of every position in the geometry and of the `bbox`,
+    /// if any.
+    pub fn swap_axes(&mut self) {
+        if let Some(ref mut geometry) = self.geometry {
+            geometry.swap_axes();
+        }
+        if let Some(ref mut bbox) = self.bbox {
+            util::swap_bbox_axes(bbox);
+        }
+    }
+}
+
+/// Builder for `Feature`s
+///
+/// # Examples
+///
+/// ```
+/// use geojson::{Feature, Value};
+///
+/// let feature = Feature::builder()
+///     .geometry(Value::Point(vec![-120.66029, 35.2812]))
+///     .id("firestone")
+///     .property("name", "Firestone Grill")
+///     .build();
+///
+/// assert_eq!(feature.property::<String>("name"), Some(String::from("Firestone Grill")));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct FeatureBuilder {
+    feature: Feature,
+}
+
+impl FeatureBuilder {
+    pub fn new() -> Self {
+        FeatureBuilder::default()
+    }
+
+    pub fn bbox(mut self, bbox: Bbox) -> Self {
+        self.feature.bbox = Some(bbox);
+        self
+    }
+
+    pub fn crs(mut self, crs: Crs) -> Self {
+        self.feature.crs = Some(crs);
+        self
+    }
+
+    /// Sets the geometry, which can be given either as a `Geometry` or as a bare `Value`.
+    pub fn geometry<G: Into<Geometry>>(mut self, geometry: G) -> Self {
+        self.feature.geometry = Some(geometry.into());
+        self
+    }
+
+    pub fn id<V: Into<JsonValue>>(mut self, id: V) -> Self {
+        self.feature.id = Some(id.into());
+        self
+    }
+
+    /// Replaces all properties with `properties`.
+    pub fn properties(mut self, properties: JsonObject) -> Self {
+        self.feature.properties = Some(properties);
+        self
+    }
+
+    /// Adds a single property, creating the `properties` member if needed.
+    pub fn property<K: Into<String>, V: Into<JsonValue>>(mut self, key: K, value: V) -> Self {
+        self.feature.set_property(key, value);
+        self
+    }
+
+    pub fn build(self) -> Feature {
+        self.feature
+    }
+}
+
+impl Default for Feature {
+    fn default() -> Self {
+        Feature {
+            bbox: None,
+            crs: None,
+            geometry: None,
+            id: None,
+            properties: None,
+        }
+    }
+}
+
 impl<'a> From<&'a Feature> for JsonObject {
     fn from(feature: &'a Feature) -> JsonObject {
         let mut map = JsonObject::new();
@@ -156,6 +293,50 @@ mod tests {
         assert!(feature.geometry.is_none());
     }
 
+    #[test]
+    fn build_feature() {
+        let built = Feature::builder()
+            .geometry(Value::Point(vec![1.1, 2.1]))
+            .properties(::json::JsonObject::new())
+            .build();
+        assert_eq!(built, feature());
+
+        let built = Feature::builder()
+            .id(7)
+            .property("name", "x")
+            .property("visible", true)
+            .bbox(vec![1.1, 2.1, 1.1, 2.1])
+            .build();
+        assert_eq!(built.id, Some(json!(7)));
+        assert_eq!(built.bbox, Some(vec![1.1, 2.1, 1.1, 2.1]));
+        assert_eq!(built.geometry, None);
+        assert_eq!(built.properties, Some(json!({"name": "x", "visible": true})
+            .as_object()
+            .unwrap()
+            .clone()));
+    }
+
+    #[test]
+    fn feature_properties() {
+        let mut feature = Feature::builder().build();
+        assert!(!feature.contains_property("name"));
+        assert_eq!(feature.remove_property("name"), None);
+
+        feature.set_property("name", "Firestone Grill");
+        feature.set_property("rating", 4.5);
+        feature.set_property("tags", vec!["bbq", "tri-tip"]);
+        assert!(feature.contains_property("name"));
+        assert_eq!(feature.property::<String>("name"),
+                   Some(String::from("Firestone Grill")));
+        assert_eq!(feature.property::<f64>("rating"), Some(4.5));
+        assert_eq!(feature.property::<Vec<String>>("tags"),
+                   Some(vec![String::from("bbq"), String::from("tri-tip")]));
+        assert_eq!(feature.property::<bool>("rating"), None);
+
+        assert_eq!(feature.remove_property("rating"), Some(json!(4.5)));
+        assert!(!feature.contains_property("rating"));
+    }
+
     #[test]
     fn feature_json_invalid_geometry() {
         let geojson_str = r#"{"geometry":3.14,"properties":{},"type":"Feature"}"#;
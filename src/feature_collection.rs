@@ -54,6 +54,80 @@ pub struct FeatureCollection {
     pub features: Vec<Feature>,
 }
 
+impl FeatureCollection {
+    /// Returns a `FeatureCollectionBuilder` for an empty collection.
+    pub fn builder() -> FeatureCollectionBuilder {
+        FeatureCollectionBuilder::new()
+    }
+
+    /// Swaps the first two ordinates of every position in every feature and of every `bbox`.
+    pub fn swap_axes(&mut self) {
+        for feature in &mut self.features {
+            feature.swap_axes();
+        }
+        if let Some(ref mut bbox) = self.bbox {
+            util::swap_bbox_axes(bbox);
+        }
+    }
+}
+
+/// Builder for `FeatureCollection`s
+///
+/// # Examples
+///
+/// ```
+/// use geojson::{Feature, FeatureCollection, Value};
+///
+/// let feature_collection = FeatureCollection::builder()
+///     .feature(Feature::builder().geometry(Value::Point(vec![1.0, 2.0])).build())
+///     .features(vec![Feature::builder().build(), Feature::builder().build()])
+///     .build();
+///
+/// assert_eq!(feature_collection.features.len(), 3);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct FeatureCollectionBuilder {
+    bbox: Option<Bbox>,
+    crs: Option<Crs>,
+    features: Vec<Feature>,
+}
+
+impl FeatureCollectionBuilder {
+    pub fn new() -> Self {
+        FeatureCollectionBuilder::default()
+    }
+
+    pub fn bbox(mut self, bbox: Bbox) -> Self {
+        self.bbox = Some(bbox);
+        self
+    }
+
+    pub fn crs(mut self, crs: Crs) -> Self {
+        self.crs = Some(crs);
+        self
+    }
+
+    /// Appends a single feature.
+    pub fn feature(mut self, feature: Feature) -> Self {
+        self.features.push(feature);
+        self
+    }
+
+    /// Appends every feature of `features`.
+    pub fn features<I: IntoIterator<Item = Feature>>(mut self, features: I) -> Self {
+        self.features.extend(features);
+        self
+    }
+
+    pub fn build(self) -> FeatureCollection {
+        FeatureCollection {
+            bbox: self.bbox,
+            crs: self.crs,
+            features: self.features,
+        }
+    }
+}
+
 impl<'a> From<&'a FeatureCollection> for JsonObject {
     fn from(fc: &'a FeatureCollection) -> JsonObject {
         let mut map = JsonObject::new();
@@ -0,0 +1,1596 @@
+// Copyright 2015 The GeoRust Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! FlatGeobuf reading and writing
+//!
+//! [FlatGeobuf](https://flatgeobuf.org) stores features as FlatBuffers, preceded by a header
+//! describing their property columns and by a packed Hilbert R-tree over their bounding boxes,
+//! which lets a reader fetch only the features intersecting a bounding box.
+//!
+//! When writing, the property columns are inferred from the features' properties: booleans,
+//! integers, floating-point numbers and strings get typed columns, and any other or mixed
+//! values are stored as JSON. Features are stored in Hilbert order, so reading them back may
+//! not preserve their original order. FlatGeobuf has no equivalent of `Feature::id`, which is
+//! not stored. Positions keep their first three ordinates.
+
+use std::cmp;
+use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+
+use json::{JsonObject, JsonValue};
+use serde_json;
+
+use {Bbox, Crs, CrsId, Error, Feature, FeatureCollection, Geometry, LineStringType, Position,
+     Value};
+
+
+/// Default number of children of each node of the spatial index
+pub const DEFAULT_NODE_SIZE: u16 = 16;
+
+const MAGIC: [u8; 8] = [0x66, 0x67, 0x62, 0x03, 0x66, 0x67, 0x62, 0x00];
+
+const NODE_ITEM_SIZE: u64 = 40;
+
+const HILBERT_MAX: f64 = 65535.0;
+
+/// Maximum number of geometry tables decoded per byte of a feature
+const MAX_GEOMETRIES_PER_BYTE: usize = 1;
+
+/// Deepest nesting of geometry parts accepted when reading
+const MAX_DEPTH: usize = 64;
+
+fn malformed(message: &str) -> Error {
+    Error::MalformedFlatGeobuf(String::from(message))
+}
+
+mod geometry_type {
+    pub const UNKNOWN: u8 = 0;
+    pub const POINT: u8 = 1;
+    pub const LINE_STRING: u8 = 2;
+    pub const POLYGON: u8 = 3;
+    pub const MULTI_POINT: u8 = 4;
+    pub const MULTI_LINE_STRING: u8 = 5;
+    pub const MULTI_POLYGON: u8 = 6;
+    pub const GEOMETRY_COLLECTION: u8 = 7;
+}
+
+mod column_type {
+    pub const BYTE: u8 = 0;
+    pub const UBYTE: u8 = 1;
+    pub const BOOL: u8 = 2;
+    pub const SHORT: u8 = 3;
+    pub const USHORT: u8 = 4;
+    pub const INT: u8 = 5;
+    pub const UINT: u8 = 6;
+    pub const LONG: u8 = 7;
+    pub const ULONG: u8 = 8;
+    pub const FLOAT: u8 = 9;
+    pub const DOUBLE: u8 = 10;
+    pub const STRING: u8 = 11;
+    pub const JSON: u8 = 12;
+    pub const DATE_TIME: u8 = 13;
+    pub const BINARY: u8 = 14;
+}
+
+
+// FlatBuffers
+
+/// A table under construction, as `(slot, field)` pairs
+struct Table(Vec<(usize, Field)>);
+
+enum Field {
+    UByte(u8),
+    Bool(bool),
+    UShort(u16),
+    Int(i32),
+    ULong(u64),
+    String(String),
+    UBytes(Vec<u8>),
+    UInts(Vec<u32>),
+    Doubles(Vec<f64>),
+    Table(Table),
+    Tables(Vec<Table>),
+}
+
+impl Field {
+    fn inline_size(&self) -> usize {
+        match *self {
+            Field::UByte(..) | Field::Bool(..) => 1,
+            Field::UShort(..) => 2,
+            Field::ULong(..) => 8,
+            _ => 4,
+        }
+    }
+}
+
+/// Serializes `root` as a size-prefixed FlatBuffer.
+fn finish(root: &Table) -> Vec<u8> {
+    let mut buf = vec![0; 4];
+    let pos = write_table(&mut buf, root);
+    patch_offset(&mut buf, 0, pos);
+    let mut prefixed = Vec::with_capacity(buf.len() + 4);
+    prefixed.extend_from_slice(&(buf.len() as u32).to_le_bytes());
+    prefixed.extend(buf);
+    prefixed
+}
+
+fn pad_to(buf: &mut Vec<u8>, align: usize) {
+    while buf.len() % align != 0 {
+        buf.push(0);
+    }
+}
+
+fn patch_offset(buf: &mut [u8], at: usize, target: usize) {
+    let offset = (target - at) as u32;
+    buf[at..at + 4].copy_from_slice(&offset.to_le_bytes());
+}
+
+/// Writes a table after its vtable, followed by the objects it refers to, returning its
+/// position.
+fn write_table(buf: &mut Vec<u8>, table: &Table) -> usize {
+    // Inline fields follow the offset to the vtable, from the largest to the smallest
+    let mut fields = table.0.iter().collect::<Vec<_>>();
+    fields.sort_by_key(|&&(_, ref field)| cmp::Reverse(field.inline_size()));
+    let slots = table.0.iter().map(|&(slot, _)| slot + 1).max().unwrap_or(0);
+    let mut offsets = vec![0usize; slots];
+    let mut size = 4;
+    for &&(slot, ref field) in &fields {
+        let field_size = field.inline_size();
+        size = (size + field_size - 1) / field_size * field_size;
+        offsets[slot] = size;
+        size += field_size;
+    }
+
+    pad_to(buf, 2);
+    let vtable = buf.len();
+    buf.extend_from_slice(&((4 + 2 * slots) as u16).to_le_bytes());
+    buf.extend_from_slice(&(size as u16).to_le_bytes());
+    for &offset in &offsets {
+        buf.extend_from_slice(&(offset as u16).to_le_bytes());
+    }
+
+    pad_to(buf, 8);
+    let table_pos = buf.len();
+    buf.resize(table_pos + size, 0);
+    buf[table_pos..table_pos + 4].copy_from_slice(&((table_pos - vtable) as i32).to_le_bytes());
+
+    let mut children = vec![];
+    for &(slot, ref field) in &table.0 {
+        let at = table_pos + offsets[slot];
+        match *field {
+            Field::UByte(v) => buf[at] = v,
+            Field::Bool(v) => buf[at] = v as u8,
+            Field::UShort(v) => buf[at..at + 2].copy_from_slice(&v.to_le_bytes()),
+            Field::Int(v) => buf[at..at + 4].copy_from_slice(&v.to_le_bytes()),
+            Field::ULong(v) => buf[at..at + 8].copy_from_slice(&v.to_le_bytes()),
+            _ => children.push((at, field)),
+        }
+    }
+    for (at, field) in children {
+        let pos = write_object(buf, field);
+        patch_offset(buf, at, pos);
+    }
+    table_pos
+}
+
+/// Writes the length of a vector, aligning its elements to `align`.
+fn write_vector_length(buf: &mut Vec<u8>, len: usize, align: usize) -> usize {
+    while (buf.len() + 4) % align != 0 {
+        buf.push(0);
+    }
+    let pos = buf.len();
+    buf.extend_from_slice(&(len as u32).to_le_bytes());
+    pos
+}
+
+fn write_object(buf: &mut Vec<u8>, field: &Field) -> usize {
+    match *field {
+        Field::String(ref s) => {
+            let pos = write_vector_length(buf, s.len(), 4);
+            buf.extend_from_slice(s.as_bytes());
+            buf.push(0);
+            pos
+        }
+        Field::UBytes(ref bytes) => {
+            let pos = write_vector_length(buf, bytes.len(), 4);
+            buf.extend_from_slice(bytes);
+            pos
+        }
+        Field::UInts(ref values) => {
+            let pos = write_vector_length(buf, values.len(), 4);
+            for value in values {
+                buf.extend_from_slice(&value.to_le_bytes());
+            }
+            pos
+        }
+        Field::Doubles(ref values) => {
+            let pos = write_vector_length(buf, values.len(), 8);
+            for value in values {
+                buf.extend_from_slice(&value.to_bits().to_le_bytes());
+            }
+            pos
+        }
+        Field::Table(ref table) => write_table(buf, table),
+        Field::Tables(ref tables) => {
+            let pos = write_vector_length(buf, tables.len(), 4);
+            let start = buf.len();
+            buf.resize(start + 4 * tables.len(), 0);
+            for (i, table) in tables.iter().enumerate() {
+                let table_pos = write_table(buf, table);
+                patch_offset(buf, start + 4 * i, table_pos);
+            }
+            pos
+        }
+        _ => unreachable!(),
+    }
+}
+
+fn read_bytes(buf: &[u8], at: usize, len: usize) -> Result<&[u8], Error> {
+    match at.checked_add(len) {
+        Some(end) if end <= buf.len() => Ok(&buf[at..end]),
+        _ => Err(malformed("offset out of bounds")),
+    }
+}
+
+fn read_u16(buf: &[u8], at: usize) -> Result<u16, Error> {
+    let bytes = try!(read_bytes(buf, at, 2));
+    Ok(bytes[0] as u16 | (bytes[1] as u16) << 8)
+}
+
+fn read_u32(buf: &[u8], at: usize) -> Result<u32, Error> {
+    let bytes = try!(read_bytes(buf, at, 4));
+    Ok(bytes.iter().rev().fold(0, |value, &byte| (value << 8) | byte as u32))
+}
+
+fn read_u64(buf: &[u8], at: usize) -> Result<u64, Error> {
+    let bytes = try!(read_bytes(buf, at, 8));
+    Ok(bytes.iter().rev().fold(0, |value, &byte| (value << 8) | byte as u64))
+}
+
+/// A table of a FlatBuffer being read
+#[derive(Clone, Copy)]
+struct TableRef<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    vtable: usize,
+    vtable_len: usize,
+}
+
+impl<'a> TableRef<'a> {
+    fn root(buf: &'a [u8]) -> Result<Self, Error> {
+        let pos = try!(read_u32(buf, 0)) as usize;
+        TableRef::new(buf, pos)
+    }
+
+    fn new(buf: &'a [u8], pos: usize) -> Result<Self, Error> {
+        let soffset = try!(read_u32(buf, pos)) as i32 as i64;
+        let vtable = pos as i64 - soffset;
+        if vtable < 0 {
+            return Err(malformed("vtable out of bounds"));
+        }
+        let vtable = vtable as usize;
+        let vtable_len = try!(read_u16(buf, vtable)) as usize;
+        try!(read_bytes(buf, vtable, vtable_len));
+        Ok(TableRef {
+            buf: buf,
+            pos: pos,
+            vtable: vtable,
+            vtable_len: vtable_len,
+        })
+    }
+
+    /// Returns the position of a field, or `None` if it is absent.
+    fn field(&self, slot: usize) -> Result<Option<usize>, Error> {
+        let entry = 4 + 2 * slot;
+        if entry + 2 > self.vtable_len {
+            return Ok(None);
+        }
+        match try!(read_u16(self.buf, self.vtable + entry)) {
+            0 => Ok(None),
+            offset => Ok(Some(self.pos + offset as usize)),
+        }
+    }
+
+    fn u8(&self, slot: usize, default: u8) -> Result<u8, Error> {
+        match try!(self.field(slot)) {
+            Some(at) => Ok(try!(read_bytes(self.buf, at, 1))[0]),
+            None => Ok(default),
+        }
+    }
+
+    fn u16(&self, slot: usize, default: u16) -> Result<u16, Error> {
+        match try!(self.field(slot)) {
+            Some(at) => read_u16(self.buf, at),
+            None => Ok(default),
+        }
+    }
+
+    fn i32(&self, slot: usize) -> Result<Option<i32>, Error> {
+        match try!(self.field(slot)) {
+            Some(at) => Ok(Some(try!(read_u32(self.buf, at)) as i32)),
+            None => Ok(None),
+        }
+    }
+
+    fn u64(&self, slot: usize) -> Result<u64, Error> {
+        match try!(self.field(slot)) {
+            Some(at) => read_u64(self.buf, at),
+            None => Ok(0),
+        }
+    }
+
+    /// Follows the offset stored at `at`.
+    fn target(&self, at: usize) -> Result<usize, Error> {
+        let offset = try!(read_u32(self.buf, at)) as usize;
+        match at.checked_add(offset) {
+            Some(target) => Ok(target),
+            None => Err(malformed("offset out of bounds")),
+        }
+    }
+
+    /// Returns the bytes and length of a vector of elements of `size` bytes.
+    fn vector(&self, slot: usize, size: usize) -> Result<Option<(&'a [u8], usize)>, Error> {
+        let at = match try!(self.field(slot)) {
+            Some(at) => try!(self.target(at)),
+            None => return Ok(None),
+        };
+        let len = try!(read_u32(self.buf, at)) as usize;
+        let bytes = match len.checked_mul(size) {
+            Some(byte_len) => try!(read_bytes(self.buf, at + 4, byte_len)),
+            None => return Err(malformed("vector out of bounds")),
+        };
+        Ok(Some((bytes, len)))
+    }
+
+    fn bytes(&self, slot: usize) -> Result<Option<&'a [u8]>, Error> {
+        Ok(try!(self.vector(slot, 1)).map(|(bytes, _)| bytes))
+    }
+
+    fn string(&self, slot: usize) -> Result<Option<String>, Error> {
+        match try!(self.bytes(slot)) {
+            Some(bytes) => {
+                match String::from_utf8(bytes.to_vec()) {
+                    Ok(s) => Ok(Some(s)),
+                    Err(..) => Err(malformed("invalid UTF-8 string")),
+                }
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn u32s(&self, slot: usize) -> Result<Vec<u32>, Error> {
+        Ok(match try!(self.vector(slot, 4)) {
+            Some((bytes, _)) => {
+                bytes.chunks(4).map(|b| read_u32(b, 0).unwrap()).collect()
+            }
+            None => vec![],
+        })
+    }
+
+    fn doubles(&self, slot: usize) -> Result<Vec<f64>, Error> {
+        Ok(match try!(self.vector(slot, 8)) {
+            Some((bytes, _)) => {
+                bytes.chunks(8).map(|b| f64::from_bits(read_u64(b, 0).unwrap())).collect()
+            }
+            None => vec![],
+        })
+    }
+
+    fn table(&self, slot: usize) -> Result<Option<TableRef<'a>>, Error> {
+        match try!(self.field(slot)) {
+            Some(at) => Ok(Some(try!(TableRef::new(self.buf, try!(self.target(at)))))),
+            None => Ok(None),
+        }
+    }
+
+    fn tables(&self, slot: usize) -> Result<Vec<TableRef<'a>>, Error> {
+        let at = match try!(self.field(slot)) {
+            Some(at) => try!(self.target(at)),
+            None => return Ok(vec![]),
+        };
+        let len = try!(read_u32(self.buf, at)) as usize;
+        if len > self.buf.len() / 4 {
+            return Err(malformed("vector out of bounds"));
+        }
+        let mut tables = vec![];
+        for i in 0..len {
+            let element = at + 4 + 4 * i;
+            tables.push(try!(TableRef::new(self.buf, try!(self.target(element)))));
+        }
+        Ok(tables)
+    }
+}
+
+
+// Spatial index
+
+/// Bounding box of a feature or of a node of the index, with the offset of the feature or of
+/// the node's first child
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct NodeItem {
+    min_x: f64,
+    min_y: f64,
+    max_x: f64,
+    max_y: f64,
+    offset: u64,
+}
+
+impl NodeItem {
+    fn empty(offset: u64) -> Self {
+        NodeItem {
+            min_x: ::std::f64::INFINITY,
+            min_y: ::std::f64::INFINITY,
+            max_x: ::std::f64::NEG_INFINITY,
+            max_y: ::std::f64::NEG_INFINITY,
+            offset: offset,
+        }
+    }
+
+    fn bounds(value: &Value) -> Self {
+        let mut item = NodeItem::empty(0);
+        for position in value.coords().filter(|p| p.len() >= 2) {
+            item.min_x = item.min_x.min(position[0]);
+            item.min_y = item.min_y.min(position[1]);
+            item.max_x = item.max_x.max(position[0]);
+            item.max_y = item.max_y.max(position[1]);
+        }
+        item
+    }
+
+    fn expand(&mut self, other: &NodeItem) {
+        self.min_x = self.min_x.min(other.min_x);
+        self.min_y = self.min_y.min(other.min_y);
+        self.max_x = self.max_x.max(other.max_x);
+        self.max_y = self.max_y.max(other.max_y);
+    }
+
+    fn intersects(&self, bbox: &[f64; 4]) -> bool {
+        self.min_x <= bbox[2] && self.max_x >= bbox[0] && self.min_y <= bbox[3] &&
+        self.max_y >= bbox[1]
+    }
+
+    fn write(&self, buf: &mut Vec<u8>) {
+        for value in &[self.min_x, self.min_y, self.max_x, self.max_y] {
+            buf.extend_from_slice(&value.to_bits().to_le_bytes());
+        }
+        buf.extend_from_slice(&self.offset.to_le_bytes());
+    }
+
+    fn read(buf: &[u8]) -> Self {
+        let f = |i: usize| f64::from_bits(read_u64(buf, 8 * i).unwrap());
+        NodeItem {
+            min_x: f(0),
+            min_y: f(1),
+            max_x: f(2),
+            max_y: f(3),
+            offset: read_u64(buf, 32).unwrap(),
+        }
+    }
+}
+
+/// Returns the range of nodes of each level of the index, from the leaves up to the root.
+fn level_bounds(num_items: u64, node_size: u16) -> Result<Vec<Range<u64>>, Error> {
+    if node_size < 2 {
+        return Err(malformed("index node size below 2"));
+    }
+    let node_size = node_size as u64;
+    let overflow = || malformed("too many features");
+    let mut n = num_items;
+    let mut num_nodes = n;
+    let mut level_num_nodes = vec![n];
+    loop {
+        n = n / node_size + if n % node_size == 0 { 0 } else { 1 };
+        num_nodes = try!(num_nodes.checked_add(n).ok_or_else(&overflow));
+        level_num_nodes.push(n);
+        if n == 1 {
+            break;
+        }
+    }
+    try!(num_nodes.checked_mul(NODE_ITEM_SIZE).ok_or_else(&overflow));
+    let mut bounds = vec![];
+    let mut end = num_nodes;
+    for size in level_num_nodes {
+        bounds.push(end - size..end);
+        end -= size;
+    }
+    Ok(bounds)
+}
+
+/// Position of a point along a Hilbert curve over a 65536 by 65536 grid
+fn hilbert(x: u32, y: u32) -> u32 {
+    let mut a = x ^ y;
+    let mut b = 0xFFFF ^ a;
+    let mut c = 0xFFFF ^ (x | y);
+    let mut d = x & (y ^ 0xFFFF);
+
+    let mut aa = a | (b >> 1);
+    let mut bb = (a >> 1) ^ a;
+    let mut cc = ((c >> 1) ^ (b & (d >> 1))) ^ c;
+    let mut dd = ((a & (c >> 1)) ^ (d >> 1)) ^ d;
+
+    a = aa;
+    b = bb;
+    c = cc;
+    d = dd;
+    aa = (a & (a >> 2)) ^ (b & (b >> 2));
+    bb = (a & (b >> 2)) ^ (b & ((a ^ b) >> 2));
+    cc ^= (a & (c >> 2)) ^ (b & (d >> 2));
+    dd ^= (b & (c >> 2)) ^ ((a ^ b) & (d >> 2));
+
+    a = aa;
+    b = bb;
+    c = cc;
+    d = dd;
+    aa = (a & (a >> 4)) ^ (b & (b >> 4));
+    bb = (a & (b >> 4)) ^ (b & ((a ^ b) >> 4));
+    cc ^= (a & (c >> 4)) ^ (b & (d >> 4));
+    dd ^= (b & (c >> 4)) ^ ((a ^ b) & (d >> 4));
+
+    a = aa;
+    b = bb;
+    c = cc;
+    d = dd;
+    cc ^= (a & (c >> 8)) ^ (b & (d >> 8));
+    dd ^= (b & (c >> 8)) ^ ((a ^ b) & (d >> 8));
+
+    a = cc ^ (cc >> 1);
+    b = dd ^ (dd >> 1);
+
+    let spread = |mut i: u32| {
+        i = (i | (i << 8)) & 0x00FF00FF;
+        i = (i | (i << 4)) & 0x0F0F0F0F;
+        i = (i | (i << 2)) & 0x33333333;
+        (i | (i << 1)) & 0x55555555
+    };
+    let i0 = x ^ y;
+    let i1 = b | (0xFFFF ^ (i0 | a));
+    (spread(i1) << 1) | spread(i0)
+}
+
+fn hilbert_bbox(item: &NodeItem, extent: &NodeItem) -> u32 {
+    let scale = |value: f64, min: f64, max: f64| if max > min {
+        (HILBERT_MAX * (value - min) / (max - min)).floor() as u32
+    } else {
+        0
+    };
+    let x = scale((item.min_x + item.max_x) / 2.0, extent.min_x, extent.max_x);
+    let y = scale((item.min_y + item.max_y) / 2.0, extent.min_y, extent.max_y);
+    hilbert(x, y)
+}
+
+
+// Writing
+
+/// Writes a feature collection as a FlatGeobuf layer named `name`, with a spatial index.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::Cursor;
+/// use geojson::{Feature, FeatureCollection, Value};
+/// use geojson::flatgeobuf;
+///
+/// let fc = FeatureCollection::builder()
+///     .feature(Feature::builder()
+///         .geometry(Value::Point(vec![-120.66029, 35.2812]))
+///         .property("name", "Firestone Grill")
+///         .build())
+///     .feature(Feature::builder()
+///         .geometry(Value::Point(vec![2.3522, 48.8566]))
+///         .property("name", "Paris")
+///         .build())
+///     .build();
+///
+/// let mut file = Cursor::new(vec![]);
+/// flatgeobuf::write(&mut file, &fc, "places").unwrap();
+///
+/// let mut reader = flatgeobuf::Reader::new(file).unwrap();
+/// let features = reader.features_in_bbox(&[-125.0, 30.0, -115.0, 40.0]).unwrap()
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap();
+/// assert_eq!(features, vec![fc.features[0].clone()]);
+/// ```
+pub fn write<W: Write>(writer: &mut W, fc: &FeatureCollection, name: &str) -> Result<(), Error> {
+    let columns = infer_columns(fc);
+    let column_indexes = columns.iter()
+        .enumerate()
+        .map(|(i, &(ref name, column_type))| (name as &str, (i as u16, column_type)))
+        .collect::<HashMap<_, _>>();
+    let has_z = fc.coords().any(|position| position.len() >= 3);
+
+    let mut geometry_types = fc.features
+        .iter()
+        .filter_map(|feature| feature.geometry.as_ref())
+        .map(|geometry| geometry_type(&geometry.value));
+    let header_geometry_type = match geometry_types.next() {
+        Some(first) if geometry_types.all(|t| t == first) => first,
+        _ => geometry_type::UNKNOWN,
+    };
+
+    let mut items = fc.features
+        .iter()
+        .map(|feature| {
+            let item = match feature.geometry {
+                Some(ref geometry) => NodeItem::bounds(&geometry.value),
+                None => NodeItem::empty(0),
+            };
+            (item, encode_feature(feature, &column_indexes, has_z))
+        })
+        .collect::<Vec<_>>();
+
+    let mut extent = NodeItem::empty(0);
+    for &(ref item, _) in &items {
+        extent.expand(item);
+    }
+    items.sort_by_key(|&(ref item, _)| cmp::Reverse(hilbert_bbox(item, &extent)));
+    let mut offset = 0;
+    for &mut (ref mut item, ref encoded) in &mut items {
+        item.offset = offset;
+        offset += encoded.len() as u64;
+    }
+
+    let node_size = if items.is_empty() { 0 } else { DEFAULT_NODE_SIZE };
+    let mut header = vec![
+        (2, Field::UByte(header_geometry_type)),
+        (3, Field::Bool(has_z)),
+        (7, Field::Tables(columns.iter()
+            .map(|&(ref name, column_type)| {
+                Table(vec![(0, Field::String(name.clone())), (1, Field::UByte(column_type))])
+            })
+            .collect())),
+        (8, Field::ULong(items.len() as u64)),
+        (9, Field::UShort(node_size)),
+    ];
+    if !name.is_empty() {
+        header.push((0, Field::String(String::from(name))));
+    }
+    if extent.min_x <= extent.max_x {
+        header.push((1,
+                     Field::Doubles(vec![extent.min_x, extent.min_y, extent.max_x,
+                                         extent.max_y])));
+    }
+    if let Some(id) = fc.crs.as_ref().and_then(|crs| crs.id()) {
+        let mut crs = vec![(0, Field::String(id.authority.clone()))];
+        match id.code.parse::<i32>() {
+            Ok(code) => crs.push((1, Field::Int(code))),
+            Err(..) => crs.push((5, Field::String(id.code.clone()))),
+        }
+        header.push((10, Field::Table(Table(crs))));
+    }
+
+    try!(writer.write_all(&MAGIC));
+    try!(writer.write_all(&finish(&Table(header))));
+    if !items.is_empty() {
+        try!(writer.write_all(&build_index(&items, node_size)));
+    }
+    for (_, encoded) in items {
+        try!(writer.write_all(&encoded));
+    }
+    Ok(())
+}
+
+fn build_index(items: &[(NodeItem, Vec<u8>)], node_size: u16) -> Vec<u8> {
+    let level_bounds = level_bounds(items.len() as u64, node_size).unwrap();
+    let num_nodes = level_bounds[0].end as usize;
+    let mut nodes = vec![NodeItem::empty(0); num_nodes];
+    let leaves = level_bounds[0].start as usize;
+    for (i, &(ref item, _)) in items.iter().enumerate() {
+        nodes[leaves + i] = *item;
+    }
+    for level in 0..level_bounds.len() - 1 {
+        let children = level_bounds[level].clone();
+        let mut parent = level_bounds[level + 1].start as usize;
+        let mut child = children.start;
+        while child < children.end {
+            let mut node = NodeItem::empty(child);
+            let last = cmp::min(child + node_size as u64, children.end);
+            for i in child..last {
+                node.expand(&nodes[i as usize]);
+            }
+            nodes[parent] = node;
+            parent += 1;
+            child = last;
+        }
+    }
+    let mut buf = Vec::with_capacity(num_nodes * NODE_ITEM_SIZE as usize);
+    for node in &nodes {
+        node.write(&mut buf);
+    }
+    buf
+}
+
+fn property_column_type(value: &JsonValue) -> Option<u8> {
+    Some(match *value {
+        JsonValue::Null => return None,
+        JsonValue::Bool(..) => column_type::BOOL,
+        JsonValue::Number(ref n) if n.is_i64() => column_type::LONG,
+        JsonValue::Number(ref n) if n.is_u64() => column_type::ULONG,
+        JsonValue::Number(..) => column_type::DOUBLE,
+        JsonValue::String(..) => column_type::STRING,
+        JsonValue::Array(..) | JsonValue::Object(..) => column_type::JSON,
+    })
+}
+
+/// Returns the name and type of each property, in order of first appearance.
+fn infer_columns(fc: &FeatureCollection) -> Vec<(String, u8)> {
+    let mut columns: Vec<(String, Option<u8>)> = vec![];
+    let mut indexes = HashMap::new();
+    for properties in fc.features.iter().filter_map(|f| f.properties.as_ref()) {
+        for (name, value) in properties {
+            let index = *indexes.entry(name.clone()).or_insert_with(|| {
+                columns.push((name.clone(), None));
+                columns.len() - 1
+            });
+            let column_type = &mut columns[index].1;
+            *column_type = match (*column_type, property_column_type(value)) {
+                (current, None) => current,
+                (None, new) => new,
+                (Some(current), Some(new)) if current == new => Some(current),
+                (Some(column_type::DOUBLE), Some(column_type::LONG)) |
+                (Some(column_type::DOUBLE), Some(column_type::ULONG)) |
+                (Some(column_type::LONG), Some(column_type::DOUBLE)) |
+                (Some(column_type::ULONG), Some(column_type::DOUBLE)) => {
+                    Some(column_type::DOUBLE)
+                }
+                _ => Some(column_type::JSON),
+            };
+        }
+    }
+    columns.into_iter()
+        .map(|(name, column_type)| (name, column_type.unwrap_or(column_type::JSON)))
+        .collect()
+}
+
+fn encode_property(buf: &mut Vec<u8>, column_type: u8, value: &JsonValue) {
+    let write_string = |buf: &mut Vec<u8>, s: &str| {
+        buf.extend_from_slice(&(s.len() as u32).to_le_bytes());
+        buf.extend_from_slice(s.as_bytes());
+    };
+    match (column_type, value) {
+        (column_type::BOOL, &JsonValue::Bool(b)) => buf.push(b as u8),
+        (column_type::LONG, value) => {
+            buf.extend_from_slice(&value.as_i64().unwrap().to_le_bytes())
+        }
+        (column_type::ULONG, value) => {
+            buf.extend_from_slice(&value.as_u64().unwrap().to_le_bytes())
+        }
+        (column_type::DOUBLE, value) => {
+            buf.extend_from_slice(&value.as_f64().unwrap().to_bits().to_le_bytes())
+        }
+        (column_type::STRING, &JsonValue::String(ref s)) => write_string(buf, s),
+        (_, value) => write_string(buf, &serde_json::to_string(value).unwrap()),
+    }
+}
+
+fn geometry_type(value: &Value) -> u8 {
+    match *value {
+        Value::Point(..) => geometry_type::POINT,
+        Value::MultiPoint(..) => geometry_type::MULTI_POINT,
+        Value::LineString(..) => geometry_type::LINE_STRING,
+        Value::MultiLineString(..) => geometry_type::MULTI_LINE_STRING,
+        Value::Polygon(..) => geometry_type::POLYGON,
+        Value::MultiPolygon(..) => geometry_type::MULTI_POLYGON,
+        Value::GeometryCollection(..) => geometry_type::GEOMETRY_COLLECTION,
+    }
+}
+
+/// Whether `value` is a point without a position, which FlatGeobuf cannot represent
+fn is_empty_point(value: &Value) -> bool {
+    match *value {
+        Value::Point(ref position) => position.len() < 2,
+        _ => false,
+    }
+}
+
+fn encode_geometry(value: &Value, has_z: bool) -> Table {
+    let mut positions: Vec<&Position> = vec![];
+    let mut ends = vec![];
+    let mut parts = vec![];
+    match *value {
+        Value::Point(ref position) => positions.push(position),
+        Value::MultiPoint(ref line) |
+        Value::LineString(ref line) => positions.extend(line),
+        Value::MultiLineString(ref lines) |
+        Value::Polygon(ref lines) => {
+            for line in lines {
+                positions.extend(line.iter().filter(|position| position.len() >= 2));
+                ends.push(positions.len() as u32);
+            }
+            if lines.len() <= 1 {
+                ends.clear();
+            }
+        }
+        Value::MultiPolygon(ref polygons) => {
+            parts = polygons.iter()
+                .map(|polygon| encode_geometry(&Value::Polygon(polygon.clone()), has_z))
+                .collect();
+        }
+        Value::GeometryCollection(ref geometries) => {
+            parts = geometries.iter()
+                .filter(|geometry| !is_empty_point(&geometry.value))
+                .map(|geometry| encode_geometry(&geometry.value, has_z))
+                .collect();
+        }
+    }
+    positions.retain(|position| position.len() >= 2);
+    let xy = positions.iter().flat_map(|position| position[..2].to_vec()).collect::<Vec<_>>();
+    let z = positions.iter()
+        .map(|position| position.get(2).cloned().unwrap_or(::std::f64::NAN))
+        .collect::<Vec<_>>();
+
+    let mut table = vec![(6, Field::UByte(geometry_type(value)))];
+    if !ends.is_empty() {
+        table.push((0, Field::UInts(ends)));
+    }
+    if !xy.is_empty() {
+        table.push((1, Field::Doubles(xy)));
+        if has_z {
+            table.push((2, Field::Doubles(z)));
+        }
+    }
+    if !parts.is_empty() {
+        table.push((7, Field::Tables(parts)));
+    }
+    Table(table)
+}
+
+fn encode_feature(feature: &Feature,
+                  columns: &HashMap<&str, (u16, u8)>,
+                  has_z: bool)
+                  -> Vec<u8> {
+    let mut table = vec![];
+    // Features whose geometry is an empty point are written without geometry
+    if let Some(geometry) = feature.geometry.as_ref().filter(|g| !is_empty_point(&g.value)) {
+        table.push((0, Field::Table(encode_geometry(&geometry.value, has_z))));
+    }
+    let mut properties = vec![];
+    for (name, value) in feature.properties.iter().flat_map(|p| p.iter()) {
+        if value.is_null() {
+            continue;
+        }
+        let (index, column_type) = columns[name as &str];
+        properties.extend_from_slice(&index.to_le_bytes());
+        encode_property(&mut properties, column_type, value);
+    }
+    if !properties.is_empty() {
+        table.push((1, Field::UBytes(properties)));
+    }
+    finish(&Table(table))
+}
+
+
+// Reading
+
+struct Header {
+    name: Option<String>,
+    bbox: Option<Bbox>,
+    geometry_type: u8,
+    columns: Vec<(String, u8)>,
+    features_count: u64,
+    index_node_size: u16,
+    crs: Option<Crs>,
+}
+
+fn decode_columns(table: &TableRef, slot: usize) -> Result<Vec<(String, u8)>, Error> {
+    let mut columns = vec![];
+    for column in try!(table.tables(slot)) {
+        let name = match try!(column.string(0)) {
+            Some(name) => name,
+            None => return Err(malformed("column without a name")),
+        };
+        columns.push((name, try!(column.u8(1, column_type::BYTE))));
+    }
+    Ok(columns)
+}
+
+fn decode_header(buf: &[u8]) -> Result<Header, Error> {
+    let table = try!(TableRef::root(buf));
+    let envelope = try!(table.doubles(1));
+    let crs = match try!(table.table(10)) {
+        Some(crs) => {
+            let org = try!(crs.string(0)).unwrap_or_else(|| String::from("EPSG"));
+            let code = match (try!(crs.i32(1)), try!(crs.string(5))) {
+                (_, Some(code)) => Some(code),
+                (Some(code), None) if code != 0 => Some(code.to_string()),
+                _ => None,
+            };
+            code.map(|code| Crs::from(CrsId::new(&org, &code)))
+        }
+        None => None,
+    };
+    Ok(Header {
+        name: try!(table.string(0)),
+        bbox: if envelope.len() >= 4 { Some(envelope[..4].to_vec()) } else { None },
+        geometry_type: try!(table.u8(2, geometry_type::UNKNOWN)),
+        columns: try!(decode_columns(&table, 7)),
+        features_count: try!(table.u64(8)),
+        index_node_size: try!(table.u16(9, DEFAULT_NODE_SIZE)),
+        crs: crs,
+    })
+}
+
+fn decode_property(buf: &[u8], pos: &mut usize, column_type: u8) -> Result<JsonValue, Error> {
+    let fixed = |pos: &mut usize, len: usize| -> Result<u64, Error> {
+        let bytes = try!(read_bytes(buf, *pos, len));
+        *pos += len;
+        Ok(bytes.iter().rev().fold(0, |value, &byte| (value << 8) | byte as u64))
+    };
+    Ok(match column_type {
+        column_type::BYTE => json!(try!(fixed(pos, 1)) as u8 as i8),
+        column_type::UBYTE => json!(try!(fixed(pos, 1)) as u8),
+        column_type::BOOL => JsonValue::Bool(try!(fixed(pos, 1)) != 0),
+        column_type::SHORT => json!(try!(fixed(pos, 2)) as u16 as i16),
+        column_type::USHORT => json!(try!(fixed(pos, 2)) as u16),
+        column_type::INT => json!(try!(fixed(pos, 4)) as u32 as i32),
+        column_type::UINT => json!(try!(fixed(pos, 4)) as u32),
+        column_type::LONG => json!(try!(fixed(pos, 8)) as i64),
+        column_type::ULONG => json!(try!(fixed(pos, 8))),
+        column_type::FLOAT => json!(f32::from_bits(try!(fixed(pos, 4)) as u32) as f64),
+        column_type::DOUBLE => json!(f64::from_bits(try!(fixed(pos, 8)))),
+        column_type::STRING | column_type::JSON | column_type::DATE_TIME |
+        column_type::BINARY => {
+            let len = try!(fixed(pos, 4)) as usize;
+            let bytes = try!(read_bytes(buf, *pos, len));
+            *pos += len;
+            if column_type == column_type::BINARY {
+                return Ok(json!(bytes));
+            }
+            let s = match ::std::str::from_utf8(bytes) {
+                Ok(s) => s,
+                Err(..) => return Err(malformed("invalid UTF-8 string")),
+            };
+            if column_type == column_type::JSON {
+                match serde_json::from_str(s) {
+                    Ok(value) => value,
+                    Err(..) => return Err(malformed("invalid JSON property")),
+                }
+            } else {
+                JsonValue::String(String::from(s))
+            }
+        }
+        _ => return Err(malformed("unknown column type")),
+    })
+}
+
+fn decode_properties(buf: &[u8], columns: &[(String, u8)]) -> Result<JsonObject, Error> {
+    let mut properties = JsonObject::new();
+    let mut pos = 0;
+    while pos < buf.len() {
+        let index = try!(read_u16(buf, pos)) as usize;
+        pos += 2;
+        let &(ref name, column_type) = match columns.get(index) {
+            Some(column) => column,
+            None => return Err(malformed("column index out of range")),
+        };
+        let value = try!(decode_property(buf, &mut pos, column_type));
+        properties.insert(name.clone(), value);
+    }
+    Ok(properties)
+}
+
+/// Decodes geometry tables, within a budget of tables per feature and a limit on their nesting.
+struct GeometryDecoder {
+    budget: usize,
+    depth: usize,
+}
+
+impl GeometryDecoder {
+    fn positions(&self, table: &TableRef) -> Result<Vec<Position>, Error> {
+        let xy = try!(table.doubles(1));
+        let z = try!(table.doubles(2));
+        if xy.len() % 2 != 0 {
+            return Err(malformed("odd number of xy ordinates"));
+        }
+        if !z.is_empty() && z.len() * 2 != xy.len() {
+            return Err(malformed("mismatched z ordinates"));
+        }
+        Ok(xy.chunks(2)
+            .enumerate()
+            .map(|(i, xy)| {
+                let mut position = xy.to_vec();
+                if let Some(&z) = z.get(i) {
+                    if !z.is_nan() {
+                        position.push(z);
+                    }
+                }
+                position
+            })
+            .collect())
+    }
+
+    fn lines(&self, table: &TableRef) -> Result<Vec<LineStringType>, Error> {
+        let mut positions = try!(self.positions(table));
+        let ends = try!(table.u32s(0));
+        if ends.is_empty() {
+            return Ok(vec![positions]);
+        }
+        let mut lines = vec![];
+        let mut start = 0;
+        for end in ends {
+            let end = end as usize;
+            if end < start || end > positions.len() {
+                return Err(malformed("invalid part end"));
+            }
+            lines.push(positions[start..end].to_vec());
+            start = end;
+        }
+        positions.clear();
+        Ok(lines)
+    }
+
+    fn decode(&mut self, table: &TableRef, default_type: u8) -> Result<Value, Error> {
+        if self.budget == 0 {
+            return Err(malformed("too many geometries"));
+        }
+        if self.depth == MAX_DEPTH {
+            return Err(malformed("geometry parts nested too deeply"));
+        }
+        self.budget -= 1;
+        self.depth += 1;
+        let value = self.decode_inner(table, default_type);
+        self.depth -= 1;
+        value
+    }
+
+    fn decode_inner(&mut self, table: &TableRef, default_type: u8) -> Result<Value, Error> {
+        let geometry_type = match try!(table.u8(6, geometry_type::UNKNOWN)) {
+            geometry_type::UNKNOWN => default_type,
+            geometry_type => geometry_type,
+        };
+        Ok(match geometry_type {
+            geometry_type::POINT => {
+                match try!(self.positions(table)).into_iter().next() {
+                    Some(position) => Value::Point(position),
+                    None => return Err(malformed("empty point")),
+                }
+            }
+            geometry_type::MULTI_POINT => Value::MultiPoint(try!(self.positions(table))),
+            geometry_type::LINE_STRING => Value::LineString(try!(self.positions(table))),
+            geometry_type::MULTI_LINE_STRING => Value::MultiLineString(try!(self.lines(table))),
+            geometry_type::POLYGON => Value::Polygon(try!(self.lines(table))),
+            geometry_type::MULTI_POLYGON => {
+                let mut polygons = vec![];
+                for part in try!(table.tables(7)) {
+                    match try!(self.decode(&part, geometry_type::POLYGON)) {
+                        Value::Polygon(polygon) => polygons.push(polygon),
+                        _ => return Err(malformed("expected a polygon part")),
+                    }
+                }
+                Value::MultiPolygon(polygons)
+            }
+            geometry_type::GEOMETRY_COLLECTION => {
+                let mut geometries = vec![];
+                for part in try!(table.tables(7)) {
+                    geometries.push(Geometry::new(try!(self.decode(&part,
+                                                                   geometry_type::UNKNOWN))));
+                }
+                Value::GeometryCollection(geometries)
+            }
+            _ => return Err(Error::GeometryUnknownType),
+        })
+    }
+}
+
+/// Reads the features of a FlatGeobuf file.
+///
+/// The header is read when the reader is created; features are read on demand.
+pub struct Reader<R> {
+    reader: R,
+    header: Header,
+    index_start: u64,
+    features_start: u64,
+}
+
+impl<R: Read + Seek> Reader<R> {
+    /// Reads the header at the start of `reader`.
+    pub fn new(mut reader: R) -> Result<Self, Error> {
+        try!(reader.seek(SeekFrom::Start(0)));
+        let mut magic = [0; 8];
+        try!(reader.read_exact(&mut magic));
+        if magic[..4] != MAGIC[..4] || magic[4..7] != MAGIC[4..7] {
+            return Err(malformed("not a FlatGeobuf file version 3"));
+        }
+        let header_buf = match try!(read_size_prefixed(&mut reader)) {
+            Some(buf) => buf,
+            None => return Err(malformed("missing header")),
+        };
+        let header = try!(decode_header(&header_buf));
+
+        let index_start = 12 + header_buf.len() as u64;
+        let index_size = if header.index_node_size > 0 && header.features_count > 0 {
+            let bounds = try!(level_bounds(header.features_count, header.index_node_size));
+            bounds[0].end * NODE_ITEM_SIZE
+        } else {
+            0
+        };
+        Ok(Reader {
+            reader: reader,
+            header: header,
+            index_start: index_start,
+            features_start: index_start + index_size,
+        })
+    }
+
+    /// Returns the name of the layer.
+    pub fn name(&self) -> Option<&str> {
+        self.header.name.as_ref().map(|name| name as &str)
+    }
+
+    /// Returns the bounding box of all features, if known.
+    pub fn bbox(&self) -> Option<&Bbox> {
+        self.header.bbox.as_ref()
+    }
+
+    /// Returns the coordinate reference system of the features, if known.
+    pub fn crs(&self) -> Option<&Crs> {
+        self.header.crs.as_ref()
+    }
+
+    /// Returns the number of features, or zero if unknown.
+    pub fn features_count(&self) -> u64 {
+        self.header.features_count
+    }
+
+    /// Iterates over all features.
+    pub fn features<'a>(&'a mut self) -> Result<Features<'a, R>, Error> {
+        try!(self.reader.seek(SeekFrom::Start(self.features_start)));
+        Ok(Features {
+            reader: self,
+            offsets: None,
+            bbox: None,
+            read: 0,
+            done: false,
+        })
+    }
+
+    /// Iterates over the features whose bounding box intersects `bbox`, given as
+    /// `[west, south, east, north]`. Uses the spatial index if the file has one.
+    pub fn features_in_bbox<'a>(&'a mut self, bbox: &[f64]) -> Result<Features<'a, R>, Error> {
+        if bbox.len() < 4 {
+            return Err(Error::BboxExpectedNumericValues);
+        }
+        let bbox = [bbox[0], bbox[1], bbox[2], bbox[3]];
+        if self.header.index_node_size == 0 || self.header.features_count == 0 {
+            let mut features = try!(self.features());
+            features.bbox = Some(bbox);
+            return Ok(features);
+        }
+        let offsets = try!(self.search(&bbox));
+        Ok(Features {
+            reader: self,
+            offsets: Some(offsets.into_iter()),
+            bbox: None,
+            read: 0,
+            done: false,
+        })
+    }
+
+    fn read_nodes(&mut self, range: Range<u64>) -> Result<Vec<NodeItem>, Error> {
+        try!(self.reader.seek(SeekFrom::Start(self.index_start + range.start * NODE_ITEM_SIZE)));
+        let len = (range.end - range.start) * NODE_ITEM_SIZE;
+        let mut buf = vec![];
+        try!((&mut self.reader).take(len).read_to_end(&mut buf));
+        if (buf.len() as u64) < len {
+            return Err(malformed("truncated index"));
+        }
+        Ok(buf.chunks(NODE_ITEM_SIZE as usize).map(NodeItem::read).collect())
+    }
+
+    /// Returns the offsets of the features whose bounding boxes intersect `bbox`, in file order.
+    fn search(&mut self, bbox: &[f64; 4]) -> Result<Vec<u64>, Error> {
+        let node_size = self.header.index_node_size as u64;
+        let bounds = try!(level_bounds(self.header.features_count, self.header.index_node_size));
+        let mut queue = vec![(0, bounds.len() - 1)];
+        let mut offsets = vec![];
+        while let Some((first, level)) = queue.pop() {
+            let end = cmp::min(first + node_size, bounds[level].end);
+            if first < bounds[level].start || first >= end {
+                return Err(malformed("invalid index"));
+            }
+            for node in try!(self.read_nodes(first..end)) {
+                if !node.intersects(bbox) {
+                    continue;
+                }
+                if level == 0 {
+                    offsets.push(node.offset);
+                } else {
+                    queue.push((node.offset, level - 1));
+                }
+            }
+        }
+        offsets.sort();
+        offsets.dedup();
+        Ok(offsets)
+    }
+
+    fn read_feature(&mut self) -> Result<Option<Feature>, Error> {
+        let buf = match try!(read_size_prefixed(&mut self.reader)) {
+            Some(buf) => buf,
+            None => return Ok(None),
+        };
+        let table = try!(TableRef::root(&buf));
+        let columns = try!(decode_columns(&table, 2));
+        let columns = if columns.is_empty() { &self.header.columns } else { &columns };
+        let properties = match try!(table.bytes(1)) {
+            Some(bytes) if !bytes.is_empty() => Some(try!(decode_properties(bytes, columns))),
+            _ => None,
+        };
+        let mut decoder = GeometryDecoder {
+            budget: buf.len() * MAX_GEOMETRIES_PER_BYTE,
+            depth: 0,
+        };
+        let geometry = match try!(table.table(0)) {
+            Some(geometry) => {
+                Some(Geometry::new(try!(decoder.decode(&geometry, self.header.geometry_type))))
+            }
+            None => None,
+        };
+        Ok(Some(Feature {
+            bbox: None,
+            crs: None,
+            geometry: geometry,
+            id: None,
+            properties: properties,
+        }))
+    }
+}
+
+/// Reads a little-endian `u32` size followed by that many bytes, or `None` at the end of the
+/// input.
+fn read_size_prefixed<R: Read>(reader: &mut R) -> Result<Option<Vec<u8>>, Error> {
+    let mut size = vec![];
+    try!(reader.take(4).read_to_end(&mut size));
+    match size.len() {
+        0 => return Ok(None),
+        4 => {}
+        _ => return Err(malformed("truncated size")),
+    }
+    let size = try!(read_u32(&size, 0)) as u64;
+    let mut buf = vec![];
+    try!(reader.take(size).read_to_end(&mut buf));
+    if (buf.len() as u64) < size {
+        return Err(malformed("truncated table"));
+    }
+    Ok(Some(buf))
+}
+
+/// Iterator over the features of a `Reader`
+pub struct Features<'a, R: 'a> {
+    reader: &'a mut Reader<R>,
+    /// Offsets of the features to read, if selected by the spatial index
+    offsets: Option<::std::vec::IntoIter<u64>>,
+    /// Bounding box to filter features by, if not selected by the spatial index
+    bbox: Option<[f64; 4]>,
+    read: u64,
+    done: bool,
+}
+
+impl<'a, R: Read + Seek> Features<'a, R> {
+    fn next_feature(&mut self) -> Result<Option<Feature>, Error> {
+        loop {
+            if let Some(ref mut offsets) = self.offsets {
+                match offsets.next() {
+                    Some(offset) => {
+                        let start = self.reader.features_start;
+                        match start.checked_add(offset) {
+                            Some(pos) => try!(self.reader.reader.seek(SeekFrom::Start(pos))),
+                            None => return Err(malformed("feature offset out of range")),
+                        };
+                    }
+                    None => return Ok(None),
+                }
+            } else if self.reader.header.features_count > 0 &&
+                      self.read == self.reader.header.features_count {
+                return Ok(None);
+            }
+            self.read += 1;
+
+            let feature = match try!(self.reader.read_feature()) {
+                Some(feature) => feature,
+                None if self.offsets.is_none() && self.reader.header.features_count == 0 => {
+                    return Ok(None)
+                }
+                None => return Err(malformed("missing feature")),
+            };
+            if let Some(ref bbox) = self.bbox {
+                let intersects = feature.geometry
+                    .as_ref()
+                    .map(|geometry| NodeItem::bounds(&geometry.value).intersects(bbox))
+                    .unwrap_or(false);
+                if !intersects {
+                    continue;
+                }
+            }
+            return Ok(Some(feature));
+        }
+    }
+}
+
+impl<'a, R: Read + Seek> Iterator for Features<'a, R> {
+    type Item = Result<Feature, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.next_feature() {
+            Ok(Some(feature)) => Some(Ok(feature)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use {Crs, Error, Feature, FeatureCollection, Geometry, Value};
+    use super::{GeometryDecoder, MAX_DEPTH, NodeItem, Reader, TableRef, geometry_type, hilbert,
+                level_bounds, write};
+
+    fn read_all(bytes: Vec<u8>) -> Result<Vec<Feature>, Error> {
+        let mut reader = try!(Reader::new(Cursor::new(bytes)));
+        let features = try!(reader.features());
+        features.collect()
+    }
+
+    fn sorted_by_name(mut features: Vec<Feature>) -> Vec<Feature> {
+        features.sort_by_key(|feature| feature.property::<String>("name"));
+        features
+    }
+
+    fn grid(n: usize) -> FeatureCollection {
+        let mut builder = FeatureCollection::builder();
+        for i in 0..n {
+            let (x, y) = ((i % 10) as f64, (i / 10) as f64);
+            builder = builder.feature(Feature::builder()
+                .geometry(Value::Point(vec![x, y]))
+                .property("name", format!("{:03}", i))
+                .property("x", x as i64)
+                .build());
+        }
+        builder.build()
+    }
+
+    #[test]
+    fn index_layout() {
+        assert_eq!(level_bounds(1, 16).unwrap(), vec![1..2, 0..1]);
+        assert_eq!(level_bounds(17, 16).unwrap(), vec![3..20, 1..3, 0..1]);
+        assert!(level_bounds(::std::u64::MAX, 16).is_err());
+        assert_eq!(hilbert(0, 0), 0);
+        assert!(hilbert(0xFFFF, 0) > hilbert(0, 0xFFFF));
+    }
+
+    #[test]
+    fn round_trip() {
+        let polygon = vec![vec![vec![0.0, 0.0], vec![1.0, 0.0], vec![1.0, 1.0], vec![0.0, 0.0]],
+                           vec![vec![0.2, 0.2], vec![0.2, 0.4], vec![0.4, 0.4], vec![0.2, 0.2]]];
+        let values = vec![
+            Value::Point(vec![1.0, 2.0]),
+            Value::MultiPoint(vec![vec![1.0, 2.0], vec![3.0, 4.0]]),
+            Value::LineString(vec![vec![1.0, 2.0], vec![3.0, 4.0]]),
+            Value::MultiLineString(vec![vec![vec![1.0, 2.0], vec![3.0, 4.0]],
+                                        vec![vec![5.0, 6.0], vec![7.0, 8.0]]]),
+            Value::Polygon(polygon.clone()),
+            Value::MultiPolygon(vec![polygon.clone(), vec![polygon[0].clone()]]),
+            Value::GeometryCollection(vec![Geometry::new(Value::Point(vec![1.0, 2.0])),
+                                           Geometry::new(Value::Polygon(polygon.clone()))]),
+        ];
+        let mut builder = FeatureCollection::builder();
+        for (i, value) in values.into_iter().enumerate() {
+            builder = builder.feature(Feature::builder()
+                .geometry(value)
+                .property("name", format!("{}", i))
+                .property("bool", i % 2 == 0)
+                .property("number", if i % 2 == 0 { json!(i) } else { json!(i as f64 + 0.5) })
+                .property("big", ::std::u64::MAX)
+                .property("mixed", if i % 2 == 0 { json!("text") } else { json!(i) })
+                .property("nested", json!({"values": [i]}))
+                .build());
+        }
+        builder = builder.feature(Feature::builder()
+            .property("name", "9 without geometry")
+            .property("number", json!(null))
+            .build());
+        let fc = builder.crs(Crs::Named { name: String::from("urn:ogc:def:crs:EPSG::3857") })
+            .build();
+
+        let mut file = Cursor::new(vec![]);
+        write(&mut file, &fc, "layer").unwrap();
+        let bytes = file.into_inner();
+        {
+            let reader = Reader::new(Cursor::new(bytes.clone())).unwrap();
+            assert_eq!(reader.name(), Some("layer"));
+            assert_eq!(reader.features_count(), 8);
+            assert_eq!(reader.crs(), fc.crs.as_ref());
+            assert_eq!(reader.bbox(), Some(&vec![0.0, 0.0, 7.0, 8.0]));
+        }
+
+        let mut expected = fc.features.clone();
+        for (i, feature) in expected.iter_mut().enumerate() {
+            let properties = feature.properties.as_mut().unwrap();
+            if i % 2 == 0 && i < 7 {
+                // Stored in a column of floating-point numbers
+                properties.insert(String::from("number"), json!(i as f64));
+            }
+            properties.remove("number").map(|number| if !number.is_null() {
+                properties.insert(String::from("number"), number);
+            });
+        }
+        assert_eq!(sorted_by_name(read_all(bytes).unwrap()), expected);
+    }
+
+    #[test]
+    fn three_dimensions() {
+        let fc = FeatureCollection::builder()
+            .feature(Feature::builder()
+                .geometry(Value::LineString(vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]]))
+                .build())
+            .feature(Feature::builder().geometry(Value::Point(vec![1.0, 2.0])).build())
+            .build();
+        let mut file = Cursor::new(vec![]);
+        write(&mut file, &fc, "").unwrap();
+        let mut features = read_all(file.into_inner()).unwrap();
+        features.sort_by_key(|feature| match feature.geometry.as_ref().unwrap().value {
+            Value::Point(..) => 1,
+            _ => 0,
+        });
+        assert_eq!(features, fc.features);
+    }
+
+    #[test]
+    fn empty_points() {
+        let fc = FeatureCollection::builder()
+            .feature(Feature::builder().geometry(Value::Point(vec![])).id(1).build())
+            .feature(Feature::builder()
+                .geometry(Value::GeometryCollection(vec![
+                    Geometry::new(Value::Point(vec![])),
+                    Geometry::new(Value::Point(vec![1.0, 2.0])),
+                ]))
+                .id(2)
+                .build())
+            .build();
+        let mut file = Cursor::new(vec![]);
+        write(&mut file, &fc, "").unwrap();
+        let mut features = read_all(file.into_inner()).unwrap();
+        features.sort_by_key(|feature| feature.id.as_ref().and_then(|id| id.as_u64()));
+        assert_eq!(features[0].geometry, None);
+        assert_eq!(features[1].geometry,
+                   Some(Geometry::new(Value::GeometryCollection(vec![
+                       Geometry::new(Value::Point(vec![1.0, 2.0])),
+                   ]))));
+    }
+
+    #[test]
+    fn bbox_queries() {
+        let fc = grid(100);
+        let mut file = Cursor::new(vec![]);
+        write(&mut file, &fc, "grid").unwrap();
+        let mut reader = Reader::new(file).unwrap();
+
+        let names = |features: Vec<Feature>| {
+            let mut names = features.iter()
+                .map(|feature| feature.property::<String>("name").unwrap())
+                .collect::<Vec<_>>();
+            names.sort();
+            names
+        };
+        let found = reader.features_in_bbox(&[2.5, 3.5, 4.5, 4.5])
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(names(found), vec!["043", "044"]);
+
+        let found = reader.features_in_bbox(&[-1.0, -1.0, 100.0, 100.0])
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(found.len(), 100);
+
+        let found = reader.features_in_bbox(&[20.0, 20.0, 30.0, 30.0])
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert!(found.is_empty());
+
+        // Every query agrees with a scan of all features
+        let all = reader.features().unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+        for &bbox in &[[0.0, 0.0, 0.0, 0.0], [1.5, 0.0, 7.0, 2.0], [9.0, 9.0, 9.5, 9.5]] {
+            let scanned = all.iter()
+                .filter(|f| NodeItem::bounds(&f.geometry.as_ref().unwrap().value).intersects(&bbox))
+                .cloned()
+                .collect::<Vec<_>>();
+            let queried = reader.features_in_bbox(&bbox)
+                .unwrap()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+            assert_eq!(names(queried), names(scanned));
+        }
+    }
+
+    #[test]
+    fn empty_collection() {
+        let mut file = Cursor::new(vec![]);
+        write(&mut file, &FeatureCollection::builder().build(), "").unwrap();
+        let mut reader = Reader::new(file).unwrap();
+        assert_eq!(reader.bbox(), None);
+        assert_eq!(reader.features_in_bbox(&[0.0, 0.0, 1.0, 1.0]).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn malformed_input() {
+        let mut file = Cursor::new(vec![]);
+        write(&mut file, &grid(20), "grid").unwrap();
+        let bytes = file.into_inner();
+        for len in 0..bytes.len() {
+            let result = Reader::new(Cursor::new(bytes[..len].to_vec())).and_then(|mut reader| {
+                let found = try!(reader.features_in_bbox(&[0.0, 0.0, 9.0, 9.0]));
+                found.collect::<Result<Vec<_>, _>>()
+            });
+            match result {
+                Err(Error::MalformedFlatGeobuf(..)) |
+                Err(Error::Io(..)) => {}
+                other => panic!("unexpected result for length {}: {:?}", len, other),
+            }
+        }
+        // Flipping any single byte must not panic
+        for i in 0..bytes.len() {
+            let mut corrupted = bytes.clone();
+            corrupted[i] ^= 0xFF;
+            let _ = read_all(corrupted);
+        }
+    }
+
+    #[test]
+    fn nesting_limit() {
+        // Geometry collections sharing one vtable, each holding the next one as its only part
+        let nested = |depth: usize| {
+            let mut buf = vec![24, 0, 0, 0, 20, 0, 12, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 8, 0,
+                               4, 0];
+            for i in 0..depth {
+                let soffset = buf.len() as u32 - 4;
+                let parts = if i + 1 < depth { 1u32 } else { 0 };
+                buf.extend_from_slice(&soffset.to_le_bytes());
+                buf.extend_from_slice(&8u32.to_le_bytes());
+                buf.extend_from_slice(&[geometry_type::GEOMETRY_COLLECTION, 0, 0, 0]);
+                buf.extend_from_slice(&parts.to_le_bytes());
+                if parts == 1 {
+                    buf.extend_from_slice(&4u32.to_le_bytes());
+                }
+            }
+            buf
+        };
+        let decode = |buf: &[u8]| {
+            let mut decoder = GeometryDecoder {
+                budget: buf.len(),
+                depth: 0,
+            };
+            decoder.decode(&TableRef::root(buf).unwrap(), geometry_type::UNKNOWN)
+        };
+        assert!(decode(&nested(MAX_DEPTH)).is_ok());
+        match decode(&nested(20_000)) {
+            Err(Error::MalformedFlatGeobuf(..)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,752 @@
+// Copyright 2015 The GeoRust Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Geobuf encoding and decoding
+//!
+//! [Geobuf](https://github.com/mapbox/geobuf) is a compact Protocol Buffers encoding of
+//! GeoJSON. Coordinates are stored as integers with a fixed number of decimal digits, and
+//! with a fixed number of ordinates per position: extra ordinates are dropped and missing
+//! ones are written as zero. Everything else is stored losslessly; `bbox` and `crs` members,
+//! and `id`s that are neither strings nor integers, are stored as custom properties.
+
+use std::collections::HashMap;
+
+use json::{JsonObject, JsonValue};
+use protobuf::{Reader, Writer, WIRE_FIXED64, WIRE_LENGTH_DELIMITED, WIRE_VARINT, unzigzag,
+               zigzag};
+use serde_json;
+
+use {Bbox, Crs, Error, Feature, FeatureCollection, GeoJson, Geometry, LineStringType,
+     PolygonType, Position, Value, util};
+
+
+/// Default number of decimal digits kept for each ordinate
+pub const DEFAULT_PRECISION: u32 = 6;
+
+/// Default number of ordinates kept for each position
+pub const DEFAULT_DIMENSIONS: u32 = 2;
+
+/// Largest number of decimal digits kept for each ordinate
+pub const MAX_PRECISION: u32 = 16;
+
+/// Largest number of ordinates kept for each position
+pub const MAX_DIMENSIONS: u32 = 16;
+
+/// Encodes a GeoJSON object as Geobuf, keeping `precision` decimal digits and `dimensions`
+/// ordinates of each position.
+///
+/// `precision` is capped at `MAX_PRECISION`, and `dimensions` kept between one and
+/// `MAX_DIMENSIONS`, so that the output can be decoded.
+///
+/// # Examples
+///
+/// ```
+/// use geojson::{Feature, GeoJson, Value};
+/// use geojson::geobuf;
+///
+/// let geojson = GeoJson::from(Feature::builder()
+///     .geometry(Value::Point(vec![-120.66029, 35.2812]))
+///     .property("name", "Firestone Grill")
+///     .build());
+///
+/// let bytes = geobuf::encode(&geojson, geobuf::DEFAULT_PRECISION, geobuf::DEFAULT_DIMENSIONS);
+/// assert!(bytes.len() < geojson.to_string().len());
+/// assert_eq!(geobuf::decode(&bytes).unwrap(), geojson);
+/// ```
+pub fn encode(geojson: &GeoJson, precision: u32, dimensions: u32) -> Vec<u8> {
+    let precision = precision.min(MAX_PRECISION);
+    let dimensions = dimensions.max(1).min(MAX_DIMENSIONS);
+    let mut encoder = Encoder {
+        keys: vec![],
+        key_indexes: HashMap::new(),
+        factor: 10f64.powi(precision as i32),
+        dimensions: dimensions as usize,
+    };
+    let (field, data) = match *geojson {
+        GeoJson::FeatureCollection(ref fc) => (4, encoder.feature_collection(fc)),
+        GeoJson::Feature(ref feature) => (5, encoder.feature(feature)),
+        GeoJson::Geometry(ref geometry) => (6, encoder.geometry(geometry)),
+    };
+
+    let mut writer = Writer::new();
+    for key in &encoder.keys {
+        writer.bytes_field(1, key.as_bytes());
+    }
+    writer.varint_field(2, dimensions as u64);
+    writer.varint_field(3, precision as u64);
+    writer.bytes_field(field, &data);
+    writer.buf
+}
+
+/// Decodes Geobuf bytes into a GeoJSON object.
+///
+/// A precision above `MAX_PRECISION` is read as `MAX_PRECISION`, as `encode` writes it.
+pub fn decode(geobuf: &[u8]) -> Result<GeoJson, Error> {
+    let mut reader = Reader::new(geobuf, Error::MalformedGeobuf);
+    let mut keys = vec![];
+    let mut dimensions = DEFAULT_DIMENSIONS as u64;
+    let mut precision = DEFAULT_PRECISION as u64;
+    let mut data = None;
+    while let Some((field, wire_type)) = try!(reader.key()) {
+        match (field, wire_type) {
+            (1, WIRE_LENGTH_DELIMITED) => keys.push(try!(reader.string())),
+            (2, WIRE_VARINT) => dimensions = try!(reader.varint()),
+            (3, WIRE_VARINT) => precision = try!(reader.varint()),
+            (4..=6, WIRE_LENGTH_DELIMITED) => data = Some((field, try!(reader.bytes()))),
+            _ => try!(reader.skip(wire_type)),
+        }
+    }
+    if dimensions == 0 || dimensions > MAX_DIMENSIONS as u64 {
+        return Err(malformed("unsupported number of dimensions"));
+    }
+    let decoder = Decoder {
+        keys: keys,
+        factor: 10f64.powi(precision.min(MAX_PRECISION as u64) as i32),
+        dimensions: dimensions as usize,
+    };
+    match data {
+        Some((4, data)) => decoder.feature_collection(data).map(GeoJson::FeatureCollection),
+        Some((5, data)) => decoder.feature(data).map(GeoJson::Feature),
+        Some((_, data)) => decoder.geometry(data, 0).map(GeoJson::Geometry),
+        None => Err(malformed("missing data")),
+    }
+}
+
+fn malformed(message: &str) -> Error {
+    Error::MalformedGeobuf(String::from(message))
+}
+
+const POINT: u64 = 0;
+const MULTI_POINT: u64 = 1;
+const LINE_STRING: u64 = 2;
+const MULTI_LINE_STRING: u64 = 3;
+const POLYGON: u64 = 4;
+const MULTI_POLYGON: u64 = 5;
+const GEOMETRY_COLLECTION: u64 = 6;
+
+/// Deepest nesting of geometry collections accepted when decoding
+const MAX_DEPTH: usize = 64;
+
+/// Members common to features, geometries and feature collections that are stored as custom
+/// properties
+fn custom_members(bbox: &Option<Bbox>, crs: &Option<Crs>) -> Vec<(&'static str, JsonValue)> {
+    let mut members = vec![];
+    if let Some(ref bbox) = *bbox {
+        members.push(("bbox", json!(bbox)));
+    }
+    if let Some(ref crs) = *crs {
+        members.push(("crs", JsonValue::Object(JsonObject::from(crs))));
+    }
+    members
+}
+
+struct Encoder {
+    keys: Vec<String>,
+    key_indexes: HashMap<String, u64>,
+    factor: f64,
+    dimensions: usize,
+}
+
+impl Encoder {
+    fn key(&mut self, key: &str) -> u64 {
+        if let Some(&index) = self.key_indexes.get(key) {
+            return index;
+        }
+        let index = self.keys.len() as u64;
+        self.keys.push(String::from(key));
+        self.key_indexes.insert(String::from(key), index);
+        index
+    }
+
+    /// Writes values, followed by the key and value indexes of properties and custom members.
+    fn properties<'a, I, J>(&mut self, writer: &mut Writer, properties: I, custom: J)
+        where I: IntoIterator<Item = (&'a str, &'a JsonValue)>,
+              J: IntoIterator<Item = (&'a str, &'a JsonValue)>
+    {
+        let mut count = 0;
+        let mut write = |encoder: &mut Encoder, (key, value): (&str, &JsonValue)| {
+            writer.bytes_field(13, &encode_value(value));
+            count += 1;
+            vec![encoder.key(key), count - 1]
+        };
+        let properties = properties.into_iter()
+            .flat_map(|property| write(self, property))
+            .collect::<Vec<_>>();
+        let custom = custom.into_iter()
+            .flat_map(|member| write(self, member))
+            .collect::<Vec<_>>();
+        if !properties.is_empty() {
+            writer.packed_field(14, properties);
+        }
+        if !custom.is_empty() {
+            writer.packed_field(15, custom);
+        }
+    }
+
+    fn feature_collection(&mut self, fc: &FeatureCollection) -> Vec<u8> {
+        let mut writer = Writer::new();
+        for feature in &fc.features {
+            let feature = self.feature(feature);
+            writer.bytes_field(1, &feature);
+        }
+        let custom = custom_members(&fc.bbox, &fc.crs);
+        self.properties(&mut writer, vec![], custom.iter().map(|&(k, ref v)| (k, v)));
+        writer.buf
+    }
+
+    fn feature(&mut self, feature: &Feature) -> Vec<u8> {
+        let mut writer = Writer::new();
+        if let Some(ref geometry) = feature.geometry {
+            let geometry = self.geometry(geometry);
+            writer.bytes_field(1, &geometry);
+        }
+        let mut custom = custom_members(&feature.bbox, &feature.crs);
+        match feature.id {
+            Some(JsonValue::String(ref id)) => writer.bytes_field(11, id.as_bytes()),
+            Some(ref id) if id.is_i64() => writer.varint_field(12, zigzag(id.as_i64().unwrap())),
+            Some(ref id) => custom.push(("id", id.clone())),
+            None => {}
+        }
+        let properties = feature.properties
+            .iter()
+            .flat_map(|properties| properties.iter().map(|(k, v)| (k as &str, v)));
+        self.properties(&mut writer, properties, custom.iter().map(|&(k, ref v)| (k, v)));
+        writer.buf
+    }
+
+    fn geometry(&mut self, geometry: &Geometry) -> Vec<u8> {
+        let mut writer = Writer::new();
+        let mut coords = vec![];
+        let (geometry_type, lengths) = match geometry.value {
+            Value::Point(ref position) => {
+                coords.extend(self.position(position).into_iter().map(zigzag));
+                (POINT, vec![])
+            }
+            Value::MultiPoint(ref positions) => {
+                self.line(&mut coords, positions, false);
+                (MULTI_POINT, vec![])
+            }
+            Value::LineString(ref line) => {
+                self.line(&mut coords, line, false);
+                (LINE_STRING, vec![])
+            }
+            Value::MultiLineString(ref lines) => {
+                (MULTI_LINE_STRING, self.lines(&mut coords, lines, false))
+            }
+            Value::Polygon(ref rings) => (POLYGON, self.lines(&mut coords, rings, true)),
+            Value::MultiPolygon(ref polygons) => {
+                let mut lengths = vec![polygons.len() as u64];
+                for polygon in polygons {
+                    lengths.push(polygon.len() as u64);
+                    for ring in polygon {
+                        lengths.push(self.line(&mut coords, ring, true));
+                    }
+                }
+                if polygons.len() == 1 && polygons[0].len() == 1 {
+                    lengths.clear();
+                }
+                (MULTI_POLYGON, lengths)
+            }
+            Value::GeometryCollection(ref geometries) => {
+                for geometry in geometries {
+                    let geometry = self.geometry(geometry);
+                    writer.bytes_field(4, &geometry);
+                }
+                (GEOMETRY_COLLECTION, vec![])
+            }
+        };
+        writer.varint_field(1, geometry_type);
+        if !lengths.is_empty() {
+            writer.packed_field(2, lengths);
+        }
+        if !coords.is_empty() {
+            writer.packed_field(3, coords);
+        }
+        let custom = custom_members(&geometry.bbox, &geometry.crs);
+        self.properties(&mut writer, vec![], custom.iter().map(|&(k, ref v)| (k, v)));
+        writer.buf
+    }
+
+    fn position(&self, position: &Position) -> Vec<i64> {
+        (0..self.dimensions)
+            .map(|i| (position.get(i).cloned().unwrap_or(0.0) * self.factor).round() as i64)
+            .collect()
+    }
+
+    /// Appends delta-encoded zigzag coordinates of a line, without the closing position of a
+    /// ring, and returns the number of positions written.
+    fn line(&self, coords: &mut Vec<u64>, line: &[Position], closed: bool) -> u64 {
+        let count = if closed && !line.is_empty() { line.len() - 1 } else { line.len() };
+        let mut previous = vec![0i64; self.dimensions];
+        for position in &line[..count] {
+            for (i, ordinate) in self.position(position).into_iter().enumerate() {
+                // Wraps like the decoder's sum, so that even saturated ordinates round-trip
+                coords.push(zigzag(ordinate.wrapping_sub(previous[i])));
+                previous[i] = ordinate;
+            }
+        }
+        count as u64
+    }
+
+    /// Appends the coordinates of several lines, returning their lengths unless there is only
+    /// one line.
+    fn lines(&self, coords: &mut Vec<u64>, lines: &[LineStringType], closed: bool) -> Vec<u64> {
+        let lengths = lines.iter()
+            .map(|line| self.line(coords, line, closed))
+            .collect::<Vec<_>>();
+        if lines.len() == 1 { vec![] } else { lengths }
+    }
+}
+
+fn encode_value(value: &JsonValue) -> Vec<u8> {
+    let mut writer = Writer::new();
+    match *value {
+        JsonValue::String(ref s) => writer.bytes_field(1, s.as_bytes()),
+        JsonValue::Bool(b) => writer.varint_field(5, b as u64),
+        JsonValue::Number(ref n) => {
+            if let Some(u) = n.as_u64() {
+                writer.varint_field(3, u);
+            } else if let Some(i) = n.as_i64() {
+                writer.varint_field(4, i.wrapping_neg() as u64);
+            } else {
+                writer.fixed64_field(2, n.as_f64().unwrap_or(0.0).to_bits());
+            }
+        }
+        _ => writer.bytes_field(6, serde_json::to_string(value).unwrap().as_bytes()),
+    }
+    writer.buf
+}
+
+fn decode_value(value: &[u8]) -> Result<JsonValue, Error> {
+    let mut reader = Reader::new(value, Error::MalformedGeobuf);
+    let mut decoded = JsonValue::Null;
+    while let Some((field, wire_type)) = try!(reader.key()) {
+        decoded = match (field, wire_type) {
+            (1, WIRE_LENGTH_DELIMITED) => JsonValue::String(try!(reader.string())),
+            (2, WIRE_FIXED64) => json!(f64::from_bits(try!(reader.fixed(8)))),
+            (3, WIRE_VARINT) => json!(try!(reader.varint())),
+            (4, WIRE_VARINT) => {
+                let magnitude = try!(reader.varint());
+                if magnitude <= 1 << 63 {
+                    json!((magnitude as i64).wrapping_neg())
+                } else {
+                    json!(-(magnitude as f64))
+                }
+            }
+            (5, WIRE_VARINT) => JsonValue::Bool(try!(reader.varint()) != 0),
+            (6, WIRE_LENGTH_DELIMITED) => {
+                match serde_json::from_str(&try!(reader.string())) {
+                    Ok(value) => value,
+                    Err(..) => return Err(malformed("invalid JSON value")),
+                }
+            }
+            _ => {
+                try!(reader.skip(wire_type));
+                continue;
+            }
+        };
+    }
+    Ok(decoded)
+}
+
+struct Decoder {
+    keys: Vec<String>,
+    factor: f64,
+    dimensions: usize,
+}
+
+/// Values and key/value index pairs of a message
+#[derive(Default)]
+struct Properties {
+    values: Vec<JsonValue>,
+    properties: Vec<u64>,
+    custom: Vec<u64>,
+}
+
+impl Properties {
+    /// Reads a properties field, returning `false` for other fields.
+    fn read(&mut self, reader: &mut Reader, field: u64, wire_type: u8) -> Result<bool, Error> {
+        match (field, wire_type) {
+            (13, WIRE_LENGTH_DELIMITED) => {
+                self.values.push(try!(decode_value(try!(reader.bytes()))))
+            }
+            (14, WIRE_LENGTH_DELIMITED) => self.properties = try!(reader.packed()),
+            (15, WIRE_LENGTH_DELIMITED) => self.custom = try!(reader.packed()),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn object(&self, keys: &[String], indexes: &[u64]) -> Result<JsonObject, Error> {
+        if indexes.len() % 2 != 0 {
+            return Err(malformed("odd number of property indexes"));
+        }
+        let mut object = JsonObject::new();
+        for pair in indexes.chunks(2) {
+            match (keys.get(pair[0] as usize), self.values.get(pair[1] as usize)) {
+                (Some(key), Some(value)) => {
+                    object.insert(key.clone(), value.clone());
+                }
+                _ => return Err(malformed("property index out of range")),
+            }
+        }
+        Ok(object)
+    }
+}
+
+impl Decoder {
+    fn feature_collection(&self, data: &[u8]) -> Result<FeatureCollection, Error> {
+        let mut reader = Reader::new(data, Error::MalformedGeobuf);
+        let mut features = vec![];
+        let mut properties = Properties::default();
+        while let Some((field, wire_type)) = try!(reader.key()) {
+            match (field, wire_type) {
+                (1, WIRE_LENGTH_DELIMITED) => {
+                    features.push(try!(self.feature(try!(reader.bytes()))))
+                }
+                _ => {
+                    if !try!(properties.read(&mut reader, field, wire_type)) {
+                        try!(reader.skip(wire_type));
+                    }
+                }
+            }
+        }
+        let custom = try!(properties.object(&self.keys, &properties.custom));
+        Ok(FeatureCollection {
+            bbox: try!(util::get_bbox(&custom)),
+            crs: try!(util::get_crs(&custom)),
+            features: features,
+        })
+    }
+
+    fn feature(&self, data: &[u8]) -> Result<Feature, Error> {
+        let mut reader = Reader::new(data, Error::MalformedGeobuf);
+        let mut geometry = None;
+        let mut id = None;
+        let mut properties = Properties::default();
+        while let Some((field, wire_type)) = try!(reader.key()) {
+            match (field, wire_type) {
+                (1, WIRE_LENGTH_DELIMITED) => {
+                    geometry = Some(try!(self.geometry(try!(reader.bytes()), 0)))
+                }
+                (11, WIRE_LENGTH_DELIMITED) => id = Some(JsonValue::String(try!(reader.string()))),
+                (12, WIRE_VARINT) => id = Some(json!(unzigzag(try!(reader.varint())))),
+                _ => {
+                    if !try!(properties.read(&mut reader, field, wire_type)) {
+                        try!(reader.skip(wire_type));
+                    }
+                }
+            }
+        }
+        let mut custom = try!(properties.object(&self.keys, &properties.custom));
+        let feature_properties = if properties.properties.is_empty() {
+            None
+        } else {
+            Some(try!(properties.object(&self.keys, &properties.properties)))
+        };
+        Ok(Feature {
+            bbox: try!(util::get_bbox(&custom)),
+            crs: try!(util::get_crs(&custom)),
+            geometry: geometry,
+            id: id.or_else(|| custom.remove("id")),
+            properties: feature_properties,
+        })
+    }
+
+    fn geometry(&self, data: &[u8], depth: usize) -> Result<Geometry, Error> {
+        if depth > MAX_DEPTH {
+            return Err(malformed("geometry collections nested too deeply"));
+        }
+        let mut reader = Reader::new(data, Error::MalformedGeobuf);
+        let mut geometry_type = None;
+        let mut lengths = vec![];
+        let mut coords = vec![];
+        let mut geometries = vec![];
+        let mut properties = Properties::default();
+        while let Some((field, wire_type)) = try!(reader.key()) {
+            match (field, wire_type) {
+                (1, WIRE_VARINT) => geometry_type = Some(try!(reader.varint())),
+                (2, WIRE_LENGTH_DELIMITED) => lengths = try!(reader.packed()),
+                (3, WIRE_LENGTH_DELIMITED) => {
+                    coords = try!(reader.packed()).into_iter().map(unzigzag).collect()
+                }
+                (4, WIRE_LENGTH_DELIMITED) => {
+                    geometries.push(try!(self.geometry(try!(reader.bytes()), depth + 1)))
+                }
+                _ => {
+                    if !try!(properties.read(&mut reader, field, wire_type)) {
+                        try!(reader.skip(wire_type));
+                    }
+                }
+            }
+        }
+
+        let mut coords = Coords {
+            coords: &coords,
+            decoder: self,
+        };
+        let value = match geometry_type {
+            Some(POINT) => Value::Point(try!(coords.position(&mut vec![0; self.dimensions]))),
+            Some(MULTI_POINT) => Value::MultiPoint(try!(coords.rest())),
+            Some(LINE_STRING) => Value::LineString(try!(coords.rest())),
+            Some(MULTI_LINE_STRING) => {
+                Value::MultiLineString(try!(coords.lines(&lengths, false)))
+            }
+            Some(POLYGON) => Value::Polygon(try!(coords.lines(&lengths, true))),
+            Some(MULTI_POLYGON) => {
+                if lengths.is_empty() {
+                    Value::MultiPolygon(vec![try!(coords.lines(&[], true))])
+                } else {
+                    let mut lengths = lengths.iter();
+                    let mut polygons = vec![];
+                    for _ in 0..*lengths.next().unwrap() {
+                        let rings = match lengths.next() {
+                            Some(&rings) => rings as usize,
+                            None => return Err(malformed("missing polygon lengths")),
+                        };
+                        let ring_lengths = lengths.as_slice();
+                        if ring_lengths.len() < rings {
+                            return Err(malformed("missing ring lengths"));
+                        }
+                        let polygon: PolygonType = try!(coords.lines(&ring_lengths[..rings],
+                                                                     true));
+                        polygons.push(polygon);
+                        if rings > 0 {
+                            lengths.nth(rings - 1);
+                        }
+                    }
+                    Value::MultiPolygon(polygons)
+                }
+            }
+            Some(GEOMETRY_COLLECTION) => Value::GeometryCollection(geometries),
+            _ => return Err(Error::GeometryUnknownType),
+        };
+        if !coords.coords.is_empty() {
+            return Err(malformed("unused coordinates"));
+        }
+
+        let custom = try!(properties.object(&self.keys, &properties.custom));
+        Ok(Geometry {
+            bbox: try!(util::get_bbox(&custom)),
+            value: value,
+            crs: try!(util::get_crs(&custom)),
+        })
+    }
+}
+
+/// Consumes the coordinates of a geometry.
+struct Coords<'a> {
+    coords: &'a [i64],
+    decoder: &'a Decoder,
+}
+
+impl<'a> Coords<'a> {
+    /// Reads one position, relative to and updating `previous`.
+    fn position(&mut self, previous: &mut Vec<i64>) -> Result<Position, Error> {
+        let dimensions = self.decoder.dimensions;
+        if self.coords.len() < dimensions {
+            return Err(malformed("missing coordinates"));
+        }
+        let position = (0..dimensions)
+            .map(|i| {
+                previous[i] = previous[i].wrapping_add(self.coords[i]);
+                previous[i] as f64 / self.decoder.factor
+            })
+            .collect();
+        self.coords = &self.coords[dimensions..];
+        Ok(position)
+    }
+
+    fn line(&mut self, length: usize, closed: bool) -> Result<LineStringType, Error> {
+        if length > self.coords.len() / self.decoder.dimensions {
+            return Err(malformed("missing coordinates"));
+        }
+        let mut previous = vec![0; self.decoder.dimensions];
+        let mut line = vec![];
+        for _ in 0..length {
+            line.push(try!(self.position(&mut previous)));
+        }
+        if closed && !line.is_empty() {
+            let first = line[0].clone();
+            line.push(first);
+        }
+        Ok(line)
+    }
+
+    fn rest(&mut self) -> Result<LineStringType, Error> {
+        let length = self.coords.len() / self.decoder.dimensions;
+        self.line(length, false)
+    }
+
+    /// Reads lines of the given lengths, or a single line if there are none.
+    fn lines(&mut self, lengths: &[u64], closed: bool) -> Result<Vec<LineStringType>, Error> {
+        if lengths.is_empty() {
+            let length = self.coords.len() / self.decoder.dimensions;
+            return Ok(vec![try!(self.line(length, closed))]);
+        }
+        let mut lines = vec![];
+        for &length in lengths {
+            if length > self.coords.len() as u64 {
+                return Err(malformed("missing coordinates"));
+            }
+            lines.push(try!(self.line(length as usize, closed)));
+        }
+        Ok(lines)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use {Crs, Error, Feature, FeatureCollection, GeoJson, Geometry, Value};
+    use protobuf::Writer;
+    use super::{GEOMETRY_COLLECTION, MAX_DEPTH, decode, encode};
+
+    fn round_trip(geojson: &GeoJson, precision: u32, dimensions: u32) -> GeoJson {
+        decode(&encode(geojson, precision, dimensions)).unwrap()
+    }
+
+    #[test]
+    fn geometry_round_trip() {
+        let polygon = vec![vec![vec![0.0, 0.0], vec![1.0, 0.0], vec![1.0, 1.0], vec![0.0, 0.0]],
+                           vec![vec![0.2, 0.2], vec![0.2, 0.4], vec![0.4, 0.4], vec![0.2, 0.2]]];
+        let values = vec![
+            Value::Point(vec![-120.66029, 35.2812]),
+            Value::MultiPoint(vec![vec![1.0, 2.0], vec![-3.0, -4.0]]),
+            Value::LineString(vec![vec![1.5, 2.5], vec![3.5, 4.5], vec![-5.5, 6.5]]),
+            Value::MultiLineString(vec![vec![vec![1.0, 2.0], vec![3.0, 4.0]]]),
+            Value::MultiLineString(vec![vec![vec![1.0, 2.0], vec![3.0, 4.0]],
+                                        vec![vec![5.0, 6.0], vec![7.0, 8.0], vec![9.0, 1.0]]]),
+            Value::Polygon(vec![polygon[0].clone()]),
+            Value::Polygon(polygon.clone()),
+            Value::MultiPolygon(vec![vec![polygon[0].clone()]]),
+            Value::MultiPolygon(vec![polygon.clone(), vec![polygon[1].clone()]]),
+            Value::GeometryCollection(vec![Geometry::new(Value::Point(vec![1.0, 2.0])),
+                                           Geometry::new(Value::GeometryCollection(vec![]))]),
+        ];
+        for value in values {
+            let geojson = GeoJson::Geometry(Geometry::new(value));
+            assert_eq!(round_trip(&geojson, 6, 2), geojson);
+        }
+    }
+
+    #[test]
+    fn precision_and_dimensions() {
+        let geojson = GeoJson::Geometry(Geometry::new(Value::LineString(vec![
+            vec![1.23456, 2.34567, 100.0],
+            vec![3.45678, 4.56789],
+        ])));
+        assert_eq!(round_trip(&geojson, 2, 3),
+                   GeoJson::Geometry(Geometry::new(Value::LineString(vec![
+                       vec![1.23, 2.35, 100.0],
+                       vec![3.46, 4.57, 0.0],
+                   ]))));
+        assert_eq!(round_trip(&geojson, 6, 2),
+                   GeoJson::Geometry(Geometry::new(Value::LineString(vec![
+                       vec![1.23456, 2.34567],
+                       vec![3.45678, 4.56789],
+                   ]))));
+
+        // Out of range precisions and dimensions are capped as the decoder does
+        let geojson = GeoJson::Geometry(Geometry::new(Value::Point(vec![1.5, 2.5])));
+        assert_eq!(round_trip(&geojson, 40, 0), round_trip(&geojson, 16, 1));
+        assert_eq!(round_trip(&geojson, 40, 2), geojson);
+
+        // Saturated ordinates do not overflow their deltas
+        let geojson = GeoJson::Geometry(Geometry::new(Value::LineString(vec![
+            vec![-1e300, 0.0],
+            vec![1e300, 0.0],
+        ])));
+        match round_trip(&geojson, 6, 2) {
+            GeoJson::Geometry(Geometry { value: Value::LineString(ref line), .. }) => {
+                assert!(line[0][0] < -1e12 && line[1][0] > 1e12);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn feature_collection_round_trip() {
+        let fc = FeatureCollection::builder()
+            .feature(Feature::builder()
+                .geometry(Value::Point(vec![1.0, 2.0]))
+                .id("a")
+                .property("string", "value")
+                .property("integer", 42)
+                .property("negative", -42)
+                .property("min", i64::min_value())
+                .property("float", 1.5)
+                .property("whole float", 2.0)
+                .property("bool", false)
+                .property("null", json!(null))
+                .property("object", json!({"nested": [1, "two"]}))
+                .build())
+            .feature(Feature::builder()
+                .id(-7)
+                .bbox(vec![0.0, 0.0, 1.0, 1.0])
+                .property("string", "other")
+                .build())
+            .feature(Feature::builder()
+                .geometry(Geometry {
+                    bbox: Some(vec![1.0, 2.0, 1.0, 2.0]),
+                    value: Value::Point(vec![1.0, 2.0]),
+                    crs: None,
+                })
+                .id(1.5)
+                .build())
+            .bbox(vec![0.0, 0.0, 1.0, 2.0])
+            .crs(Crs::Named { name: String::from("urn:ogc:def:crs:OGC:1.3:CRS84") })
+            .build();
+        let geojson = GeoJson::FeatureCollection(fc);
+        assert_eq!(round_trip(&geojson, 6, 2), geojson);
+    }
+
+    #[test]
+    fn decode_errors() {
+        let geojson = GeoJson::Geometry(Geometry::new(Value::MultiPolygon(vec![
+            vec![vec![vec![0.0, 0.0], vec![1.0, 0.0], vec![1.0, 1.0], vec![0.0, 0.0]]],
+            vec![vec![vec![5.0, 5.0], vec![6.0, 5.0], vec![6.0, 6.0], vec![5.0, 5.0]]],
+        ])));
+        let bytes = encode(&geojson, 6, 2);
+        for len in 0..bytes.len() {
+            match decode(&bytes[..len]) {
+                Err(Error::MalformedGeobuf(..)) => {}
+                other => panic!("unexpected result for length {}: {:?}", len, other),
+            }
+        }
+        // Zero dimensions
+        assert!(decode(&[0x10, 0x00, 0x32, 0x02, 0x08, 0x00]).is_err());
+    }
+
+    #[test]
+    fn nesting_limit() {
+        let nested = |depth: usize| {
+            let mut geometry = vec![0x08, GEOMETRY_COLLECTION as u8];
+            for _ in 0..depth {
+                let mut writer = Writer::new();
+                writer.varint_field(1, GEOMETRY_COLLECTION);
+                writer.bytes_field(4, &geometry);
+                geometry = writer.buf;
+            }
+            let mut writer = Writer::new();
+            writer.bytes_field(6, &geometry);
+            writer.buf
+        };
+        assert!(decode(&nested(MAX_DEPTH)).is_ok());
+        match decode(&nested(20_000)) {
+            Err(Error::MalformedGeobuf(..)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,529 @@
+// Copyright 2015 The GeoRust Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Geodesic measurements on the WGS84 ellipsoid
+//!
+//! Positions are WGS84 longitudes and latitudes in degrees, and results are in metres and
+//! square metres. Ordinates beyond the first two are ignored.
+//!
+//! Distances and lengths follow geodesics, computed with
+//! [Vincenty's formulae](https://en.wikipedia.org/wiki/Vincenty%27s_formulae), which are
+//! accurate to well under a millimetre. For the nearly antipodal positions where they do not
+//! converge, the great-circle distance on a sphere of the mean radius is used instead.
+//!
+//! Areas are computed on the authalic sphere, which has the surface area of the ellipsoid,
+//! with edges following its great circles. Rings must not enclose a pole.
+//!
+//! # Example
+//!
+//! ```
+//! use geojson::Value;
+//!
+//! let equator = Value::LineString(vec![vec![0.0, 0.0], vec![1.0, 0.0]]);
+//! assert_eq!(equator.length().round(), 111_319.0);
+//!
+//! let point = Value::Point(vec![0.5, 1.0]);
+//! assert_eq!(equator.distance(&point).map(f64::round), Some(110_574.0));
+//! ```
+
+use std::f64::consts::PI;
+
+use rings;
+use iter::SimpleGeometry;
+use {Feature, Geometry, LineStringType, Position, Value};
+
+
+/// Semi-major axis of WGS84, in metres
+const A: f64 = 6378137.0;
+
+/// Flattening of WGS84
+const F: f64 = 1.0 / 298.257223563;
+
+/// Semi-minor axis of WGS84, in metres
+const B: f64 = A * (1.0 - F);
+
+const MAX_ITERATIONS: usize = 200;
+
+/// Difference of longitude from `p1` to `p2`, in radians between -π and π
+fn longitude_difference(p1: &Position, p2: &Position) -> f64 {
+    let difference = (p2[0] - p1[0]).to_radians();
+    if difference > PI {
+        difference - 2.0 * PI
+    } else if difference < -PI {
+        difference + 2.0 * PI
+    } else {
+        difference
+    }
+}
+
+/// Solves the inverse problem, returning the distance between two positions and the initial
+/// azimuth, in radians, or `None` if the iteration does not converge.
+fn vincenty_inverse(p1: &Position, p2: &Position) -> Option<(f64, f64)> {
+    let l = longitude_difference(p1, p2);
+    let u1 = ((1.0 - F) * p1[1].to_radians().tan()).atan();
+    let u2 = ((1.0 - F) * p2[1].to_radians().tan()).atan();
+    let (sin_u1, cos_u1) = u1.sin_cos();
+    let (sin_u2, cos_u2) = u2.sin_cos();
+
+    let mut lambda = l;
+    for _ in 0..MAX_ITERATIONS {
+        let (sin_lambda, cos_lambda) = lambda.sin_cos();
+        let sin_sigma = ((cos_u2 * sin_lambda).powi(2) +
+                         (cos_u1 * sin_u2 - sin_u1 * cos_u2 * cos_lambda).powi(2))
+            .sqrt();
+        if sin_sigma == 0.0 {
+            return Some((0.0, 0.0));
+        }
+        let cos_sigma = sin_u1 * sin_u2 + cos_u1 * cos_u2 * cos_lambda;
+        let sigma = sin_sigma.atan2(cos_sigma);
+        let sin_alpha = cos_u1 * cos_u2 * sin_lambda / sin_sigma;
+        let cos2_alpha = 1.0 - sin_alpha * sin_alpha;
+        let cos_2sigma_m = if cos2_alpha != 0.0 {
+            cos_sigma - 2.0 * sin_u1 * sin_u2 / cos2_alpha
+        } else {
+            0.0
+        };
+        let c = F / 16.0 * cos2_alpha * (4.0 + F * (4.0 - 3.0 * cos2_alpha));
+        let previous = lambda;
+        lambda = l +
+                 (1.0 - c) * F * sin_alpha *
+                 (sigma +
+                  c * sin_sigma *
+                  (cos_2sigma_m + c * cos_sigma * (-1.0 + 2.0 * cos_2sigma_m * cos_2sigma_m)));
+        if lambda.abs() > PI {
+            return None;
+        }
+        if (lambda - previous).abs() < 1e-12 {
+            let u_squared = cos2_alpha * (A * A - B * B) / (B * B);
+            let a = 1.0 +
+                    u_squared / 16384.0 *
+                    (4096.0 + u_squared * (-768.0 + u_squared * (320.0 - 175.0 * u_squared)));
+            let b = u_squared / 1024.0 *
+                    (256.0 + u_squared * (-128.0 + u_squared * (74.0 - 47.0 * u_squared)));
+            let delta_sigma = b * sin_sigma *
+                              (cos_2sigma_m +
+                               b / 4.0 *
+                               (cos_sigma * (-1.0 + 2.0 * cos_2sigma_m * cos_2sigma_m) -
+                                b / 6.0 * cos_2sigma_m * (-3.0 + 4.0 * sin_sigma * sin_sigma) *
+                                (-3.0 + 4.0 * cos_2sigma_m * cos_2sigma_m)));
+            let azimuth = (cos_u2 * sin_lambda)
+                .atan2(cos_u1 * sin_u2 - sin_u1 * cos_u2 * cos_lambda);
+            return Some((B * a * (sigma - delta_sigma), azimuth));
+        }
+    }
+    None
+}
+
+/// Solves the direct problem, returning the position at `distance` from `p1` along the
+/// initial azimuth `azimuth`, in radians.
+fn vincenty_direct(p1: &Position, azimuth: f64, distance: f64) -> Position {
+    let (sin_alpha1, cos_alpha1) = azimuth.sin_cos();
+    let tan_u1 = (1.0 - F) * p1[1].to_radians().tan();
+    let cos_u1 = 1.0 / (1.0 + tan_u1 * tan_u1).sqrt();
+    let sin_u1 = tan_u1 * cos_u1;
+    let sigma1 = tan_u1.atan2(cos_alpha1);
+    let sin_alpha = cos_u1 * sin_alpha1;
+    let cos2_alpha = 1.0 - sin_alpha * sin_alpha;
+    let u_squared = cos2_alpha * (A * A - B * B) / (B * B);
+    let a = 1.0 +
+            u_squared / 16384.0 *
+            (4096.0 + u_squared * (-768.0 + u_squared * (320.0 - 175.0 * u_squared)));
+    let b = u_squared / 1024.0 *
+            (256.0 + u_squared * (-128.0 + u_squared * (74.0 - 47.0 * u_squared)));
+
+    let mut sigma = distance / (B * a);
+    let (mut sin_sigma, mut cos_sigma, mut cos_2sigma_m) = (0.0, 0.0, 0.0);
+    for _ in 0..MAX_ITERATIONS {
+        cos_2sigma_m = (2.0 * sigma1 + sigma).cos();
+        sin_sigma = sigma.sin();
+        cos_sigma = sigma.cos();
+        let delta_sigma = b * sin_sigma *
+                          (cos_2sigma_m +
+                           b / 4.0 *
+                           (cos_sigma * (-1.0 + 2.0 * cos_2sigma_m * cos_2sigma_m) -
+                            b / 6.0 * cos_2sigma_m * (-3.0 + 4.0 * sin_sigma * sin_sigma) *
+                            (-3.0 + 4.0 * cos_2sigma_m * cos_2sigma_m)));
+        let previous = sigma;
+        sigma = distance / (B * a) + delta_sigma;
+        if (sigma - previous).abs() < 1e-12 {
+            break;
+        }
+    }
+
+    let tmp = sin_u1 * sin_sigma - cos_u1 * cos_sigma * cos_alpha1;
+    let latitude = (sin_u1 * cos_sigma + cos_u1 * sin_sigma * cos_alpha1)
+        .atan2((1.0 - F) * (sin_alpha * sin_alpha + tmp * tmp).sqrt());
+    let lambda = (sin_sigma * sin_alpha1)
+        .atan2(cos_u1 * cos_sigma - sin_u1 * sin_sigma * cos_alpha1);
+    let c = F / 16.0 * cos2_alpha * (4.0 + F * (4.0 - 3.0 * cos2_alpha));
+    let l = lambda -
+            (1.0 - c) * F * sin_alpha *
+            (sigma +
+             c * sin_sigma *
+             (cos_2sigma_m + c * cos_sigma * (-1.0 + 2.0 * cos_2sigma_m * cos_2sigma_m)));
+    let longitude = (p1[0].to_radians() + l + 3.0 * PI) % (2.0 * PI) - PI;
+    vec![longitude.to_degrees(), latitude.to_degrees()]
+}
+
+/// Great-circle distance and initial azimuth on a sphere of the mean radius
+fn spherical_inverse(p1: &Position, p2: &Position) -> (f64, f64) {
+    let radius = (2.0 * A + B) / 3.0;
+    let (phi1, phi2) = (p1[1].to_radians(), p2[1].to_radians());
+    let delta_lambda = longitude_difference(p1, p2);
+    let h = ((phi2 - phi1) / 2.0).sin().powi(2) +
+            phi1.cos() * phi2.cos() * (delta_lambda / 2.0).sin().powi(2);
+    let azimuth = (delta_lambda.sin() * phi2.cos())
+        .atan2(phi1.cos() * phi2.sin() - phi1.sin() * phi2.cos() * delta_lambda.cos());
+    (2.0 * radius * h.sqrt().min(1.0).asin(), azimuth)
+}
+
+fn inverse(p1: &Position, p2: &Position) -> (f64, f64) {
+    vincenty_inverse(p1, p2).unwrap_or_else(|| spherical_inverse(p1, p2))
+}
+
+/// Returns the geodesic distance between two positions, in metres.
+///
+/// # Examples
+///
+/// ```
+/// let flinders_peak = vec![144.0 + 25.0 / 60.0 + 29.5244 / 3600.0,
+///                          -(37.0 + 57.0 / 60.0 + 3.7203 / 3600.0)];
+/// let buninyong = vec![143.0 + 55.0 / 60.0 + 35.3839 / 3600.0,
+///                      -(37.0 + 39.0 / 60.0 + 10.1561 / 3600.0)];
+///
+/// let distance = geojson::geodesic::distance(&flinders_peak, &buninyong);
+/// assert!((distance - 54_972.271).abs() < 0.001);
+/// ```
+pub fn distance(p1: &Position, p2: &Position) -> f64 {
+    inverse(p1, p2).0
+}
+
+/// Distance from `p` to the geodesic segment from `a` to `b`
+fn segment_distance(p: &Position, a: &Position, b: &Position) -> f64 {
+    let (length, azimuth) = inverse(a, b);
+    let ends = distance(p, a).min(distance(p, b));
+    if length == 0.0 {
+        return ends;
+    }
+
+    // Golden-section search for the closest position along the segment
+    let ratio = (5.0f64.sqrt() - 1.0) / 2.0;
+    let along = |t: f64| distance(p, &vincenty_direct(a, azimuth, t * length));
+    let (mut low, mut high) = (0.0, 1.0);
+    let mut t1 = high - ratio * (high - low);
+    let mut t2 = low + ratio * (high - low);
+    let (mut d1, mut d2) = (along(t1), along(t2));
+    while (high - low) * length > 1e-4 {
+        if d1 < d2 {
+            high = t2;
+            t2 = t1;
+            d2 = d1;
+            t1 = high - ratio * (high - low);
+            d1 = along(t1);
+        } else {
+            low = t1;
+            t1 = t2;
+            d1 = d2;
+            t2 = low + ratio * (high - low);
+            d2 = along(t2);
+        }
+    }
+    ends.min(d1).min(d2)
+}
+
+/// Length of a line string
+fn line_length(line: &[&Position]) -> f64 {
+    line.windows(2).map(|w| distance(w[0], w[1])).sum()
+}
+
+/// Area of a ring on the authalic sphere, regardless of its orientation
+fn ring_area(ring: &[&Position]) -> f64 {
+    let e2 = F * (2.0 - F);
+    let e = e2.sqrt();
+    let q = |phi: f64| {
+        let sin_phi = phi.sin();
+        (1.0 - e2) *
+        (sin_phi / (1.0 - e2 * sin_phi * sin_phi) -
+         ((1.0 - e * sin_phi) / (1.0 + e * sin_phi)).ln() / (2.0 * e))
+    };
+    let q_pole = q(PI / 2.0);
+    let authalic = |phi: f64| (q(phi) / q_pole).max(-1.0).min(1.0).asin();
+    let radius = A * (q_pole / 2.0).sqrt();
+
+    // Sum of the spherical excesses of the triangles formed by each edge and the pole
+    let excess: f64 = ring.windows(2)
+        .map(|w| {
+            let delta_lambda = longitude_difference(w[0], w[1]);
+            let t1 = (authalic(w[0][1].to_radians()) / 2.0).tan();
+            let t2 = (authalic(w[1][1].to_radians()) / 2.0).tan();
+            2.0 * ((delta_lambda / 2.0).tan() * (t1 + t2)).atan2(1.0 + t1 * t2)
+        })
+        .sum();
+    excess.abs() * radius * radius
+}
+
+fn polygon_contains(polygon: &[LineStringType], point: &Position) -> bool {
+    polygon.first().map_or(false, |exterior| rings::ring_contains(exterior, point)) &&
+    !polygon[1..].iter().any(|hole| rings::ring_contains(hole, point))
+}
+
+/// Positions of a line string or ring with at least two ordinates
+fn valid<'a>(line: &'a [Position]) -> Vec<&'a Position> {
+    line.iter().filter(|position| position.len() >= 2).collect()
+}
+
+/// Chains of segments of a part: its line string or the rings of its polygon
+fn chains<'a>(part: &SimpleGeometry<'a>) -> Vec<Vec<&'a Position>> {
+    match *part {
+        SimpleGeometry::Point(..) => vec![],
+        SimpleGeometry::LineString(line) => vec![valid(line)],
+        SimpleGeometry::Polygon(polygon) => polygon.iter().map(|ring| valid(ring)).collect(),
+    }
+}
+
+/// Positions of a part
+fn vertices<'a>(part: &SimpleGeometry<'a>) -> Vec<&'a Position> {
+    match *part {
+        SimpleGeometry::Point(position) => valid(::std::slice::from_ref(position)),
+        _ => chains(part).into_iter().flatten().collect(),
+    }
+}
+
+/// Segments of a part, a point being a segment of no length
+fn segments<'a>(part: &SimpleGeometry<'a>) -> Vec<(&'a Position, &'a Position)> {
+    match *part {
+        SimpleGeometry::Point(..) => vertices(part).into_iter().map(|p| (p, p)).collect(),
+        _ => {
+            let mut segments = vec![];
+            for chain in chains(part) {
+                segments.extend(chain.windows(2).map(|w| (w[0], w[1])));
+            }
+            segments
+        }
+    }
+}
+
+/// Whether two parts intersect, treating segments as straight in longitude and latitude
+fn parts_intersect(a: &SimpleGeometry, b: &SimpleGeometry) -> bool {
+    let contains = |polygon: &SimpleGeometry, other: &SimpleGeometry| match *polygon {
+        SimpleGeometry::Polygon(..) => {
+            let rings = chains(polygon)
+                .into_iter()
+                .map(|ring| ring.into_iter().cloned().collect())
+                .collect::<Vec<_>>();
+            vertices(other).first().map_or(false, |point| polygon_contains(&rings, point))
+        }
+        _ => false,
+    };
+    if contains(a, b) || contains(b, a) {
+        return true;
+    }
+    let other = segments(b);
+    segments(a).iter().any(|&(p, q)| {
+        other.iter().any(|&(r, s)| rings::segments_intersect(p, q, r, s))
+    })
+}
+
+/// Lowers `best` to the smallest distance from the positions of `a` to the points and
+/// segments of `b`
+fn one_way_distance(a: &SimpleGeometry, b: &SimpleGeometry, best: f64) -> f64 {
+    let mut best = best;
+    let targets = vertices(b);
+    let segments = segments(b);
+    for p in vertices(a) {
+        for target in &targets {
+            best = best.min(distance(p, target));
+        }
+        for &(q, r) in &segments {
+            // Every position of the segment is within half its length of an end
+            let (to_q, to_r) = (distance(p, q), distance(p, r));
+            if to_q.min(to_r) - distance(q, r) / 2.0 < best {
+                best = best.min(segment_distance(p, q, r));
+            }
+        }
+    }
+    best
+}
+
+impl Value {
+    /// Returns the geodesic length of the line strings and polygon rings of the value, in
+    /// metres.
+    pub fn length(&self) -> f64 {
+        self.geometries().flat_map(|part| chains(&part)).map(|line| line_length(&line)).sum()
+    }
+
+    /// Returns the area of the polygons of the value, in square metres, less that of their
+    /// holes.
+    pub fn area(&self) -> f64 {
+        self.geometries()
+            .map(|part| match part {
+                SimpleGeometry::Polygon(polygon) => {
+                    let mut rings = polygon.iter().map(|ring| ring_area(&valid(ring)));
+                    rings.next().map_or(0.0, |exterior| exterior - rings.sum::<f64>())
+                }
+                _ => 0.0,
+            })
+            .sum()
+    }
+
+    /// Returns the geodesic distance between the closest positions of two values, in
+    /// metres, or `None` if either has no positions.
+    ///
+    /// The distance is zero if the values intersect, which is tested with straight segments
+    /// in longitude and latitude.
+    pub fn distance(&self, other: &Value) -> Option<f64> {
+        let a = self.geometries().filter(|part| !vertices(part).is_empty()).collect::<Vec<_>>();
+        let b = other.geometries().filter(|part| !vertices(part).is_empty()).collect::<Vec<_>>();
+        if a.is_empty() || b.is_empty() {
+            return None;
+        }
+        if a.iter().any(|p| b.iter().any(|q| parts_intersect(p, q))) {
+            return Some(0.0);
+        }
+        let mut best = f64::INFINITY;
+        for p in &a {
+            for q in &b {
+                best = one_way_distance(p, q, best);
+                best = one_way_distance(q, p, best);
+            }
+        }
+        Some(best)
+    }
+}
+
+impl Geometry {
+    /// Returns the geodesic length of the geometry, as `Value::length`.
+    pub fn length(&self) -> f64 {
+        self.value.length()
+    }
+
+    /// Returns the area of the geometry, as `Value::area`.
+    pub fn area(&self) -> f64 {
+        self.value.area()
+    }
+
+    /// Returns the geodesic distance between two geometries, as `Value::distance`.
+    pub fn distance(&self, other: &Geometry) -> Option<f64> {
+        self.value.distance(&other.value)
+    }
+}
+
+impl Feature {
+    /// Returns the geodesic length of the feature's geometry, or zero if it has none.
+    pub fn length(&self) -> f64 {
+        self.geometry.as_ref().map_or(0.0, Geometry::length)
+    }
+
+    /// Returns the area of the feature's geometry, or zero if it has none.
+    pub fn area(&self) -> f64 {
+        self.geometry.as_ref().map_or(0.0, Geometry::area)
+    }
+
+    /// Returns the geodesic distance between the geometries of two features, or `None` if
+    /// either has no geometry or an empty one.
+    pub fn distance(&self, other: &Feature) -> Option<f64> {
+        match (self.geometry.as_ref(), other.geometry.as_ref()) {
+            (Some(a), Some(b)) => a.distance(b),
+            _ => None,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use {Feature, Geometry, Value};
+    use super::distance;
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!((actual - expected).abs() <= tolerance,
+                "{} is not within {} of {}",
+                actual,
+                tolerance,
+                expected);
+    }
+
+    #[test]
+    fn distances() {
+        // Quarter of a meridian
+        assert_close(distance(&vec![0.0, 0.0], &vec![0.0, 90.0]), 10_001_965.729, 0.001);
+        assert_eq!(distance(&vec![12.0, 34.0], &vec![12.0, 34.0]), 0.0);
+        // Across the antimeridian
+        assert_close(distance(&vec![179.5, 0.0], &vec![-179.5, 0.0]), 111_319.491, 0.001);
+        // Nearly antipodal positions fall back to a sphere, within half a percent
+        assert_close(distance(&vec![0.0, 0.0], &vec![179.7, 0.5]), 19_980_000.0, 100_000.0);
+    }
+
+    #[test]
+    fn lengths() {
+        let line = vec![vec![0.0, 0.0], vec![1.0, 0.0], vec![1.0, 1.0]];
+        let value = Value::MultiLineString(vec![line.clone(), line.clone()]);
+        assert_close(value.length(), 2.0 * (111_319.491 + 110_574.389), 0.01);
+        assert_eq!(Value::Point(vec![1.0, 2.0]).length(), 0.0);
+
+        let feature = Feature::builder().geometry(Value::LineString(line)).build();
+        assert_close(feature.length(), 111_319.491 + 110_574.389, 0.01);
+        assert_eq!(Feature::builder().build().length(), 0.0);
+    }
+
+    #[test]
+    fn areas() {
+        // An octant of the ellipsoid, bounded by two meridians and the equator
+        let octant = vec![vec![0.0, 0.0], vec![90.0, 0.0], vec![0.0, 90.0], vec![0.0, 0.0]];
+        let octant_area = 510_065_621_724_088.5 / 8.0;
+        assert_close(Value::Polygon(vec![octant.clone()]).area(), octant_area, 1e4);
+
+        // Orientation does not matter, and holes are subtracted
+        let square = |min: f64, max: f64| {
+            vec![vec![min, min], vec![min, max], vec![max, max], vec![max, min], vec![min, min]]
+        };
+        let cell = Value::Polygon(vec![square(0.0, 1.0)]).area();
+        assert_close(cell, 12_308_463_894.0, 1e6);
+        let holed = Value::Polygon(vec![square(0.0, 1.0), square(0.25, 0.75)]).area();
+        assert_close(holed, cell - Value::Polygon(vec![square(0.25, 0.75)]).area(), 1e-3);
+
+        let collection = Value::GeometryCollection(vec![
+            Geometry::new(Value::Polygon(vec![octant])),
+            Geometry::new(Value::Point(vec![0.0, 0.0])),
+        ]);
+        assert_close(collection.area(), octant_area, 1e4);
+    }
+
+    #[test]
+    fn geometry_distances() {
+        let line = Value::LineString(vec![vec![-1.0, 0.0], vec![1.0, 0.0]]);
+        let polygon = Value::Polygon(vec![vec![vec![-1.0, 2.0],
+                                               vec![1.0, 2.0],
+                                               vec![1.0, 3.0],
+                                               vec![-1.0, 2.0]]]);
+        let point = Value::Point(vec![0.0, 1.0]);
+
+        // Closest to the middle of the segment
+        assert_close(line.distance(&point).unwrap(), 110_574.389, 0.01);
+        assert_close(line.distance(&polygon).unwrap(), 221_149.453, 0.01);
+        assert_eq!(line.distance(&Value::Point(vec![0.5, 0.0])), Some(0.0));
+        assert_eq!(polygon.distance(&Value::Point(vec![0.5, 2.2])), Some(0.0));
+        assert_eq!(line.distance(&Value::GeometryCollection(vec![])), None);
+
+        let multi = Value::MultiPoint(vec![vec![50.0, 50.0], vec![0.0, -1.0]]);
+        assert_close(multi.distance(&line).unwrap(), 110_574.389, 0.01);
+
+        // Empty positions are left out
+        assert_eq!(line.distance(&Value::Point(vec![])), None);
+        let multi = Value::MultiPoint(vec![vec![], vec![0.0, -1.0]]);
+        assert_close(multi.distance(&line).unwrap(), 110_574.389, 0.01);
+        let gapped = Value::LineString(vec![vec![-1.0, 0.0], vec![], vec![1.0, 0.0]]);
+        assert_close(gapped.distance(&point).unwrap(), 110_574.389, 0.01);
+    }
+}
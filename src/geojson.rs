@@ -31,6 +31,20 @@ pub enum GeoJson {
     FeatureCollection(FeatureCollection),
 }
 
+impl GeoJson {
+    /// Swaps the first two ordinates of every position and every `bbox` in the object.
+    ///
+    /// GeoJSON positions are always longitude first, but some producers write `EPSG:4326`
+    /// data in that CRS's official latitude-first order. See `CrsId::is_latitude_first`.
+    pub fn swap_axes(&mut self) {
+        match *self {
+            GeoJson::Geometry(ref mut geometry) => geometry.swap_axes(),
+            GeoJson::Feature(ref mut feature) => feature.swap_axes(),
+            GeoJson::FeatureCollection(ref mut fc) => fc.swap_axes(),
+        }
+    }
+}
+
 impl<'a> From<&'a GeoJson> for JsonObject {
     fn from(geojson: &'a GeoJson) -> JsonObject {
         return match *geojson {
@@ -63,6 +63,25 @@ pub enum Value {
     GeometryCollection(Vec<Geometry>),
 }
 
+impl Value {
+    /// Swaps the first two ordinates of every position, e.g. to turn latitude/longitude
+    /// positions read from an `EPSG:4326` source into the longitude/latitude order GeoJSON
+    /// expects.
+    pub fn swap_axes(&mut self) {
+        if let Value::GeometryCollection(ref mut geometries) = *self {
+            for geometry in geometries {
+                geometry.swap_axes();
+            }
+            return;
+        }
+        for position in self.coords_mut() {
+            if position.len() >= 2 {
+                position.swap(0, 1);
+            }
+        }
+    }
+}
+
 impl<'a> From<&'a Value> for JsonValue {
     fn from(value: &'a Value) -> JsonValue {
         match *value {
@@ -107,6 +126,60 @@ impl Geometry {
             crs: None,
         }
     }
+
+    /// Returns a `GeometryBuilder` for a geometry with the specified `value`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geojson::{Crs, Geometry, Value};
+    ///
+    /// let geometry = Geometry::builder(Value::Point(vec![1.0, 2.0]))
+    ///     .bbox(vec![1.0, 2.0, 1.0, 2.0])
+    ///     .crs(Crs::Named { name: String::from("EPSG:4326") })
+    ///     .build();
+    ///
+    /// assert_eq!(geometry.bbox, Some(vec![1.0, 2.0, 1.0, 2.0]));
+    /// ```
+    pub fn builder(value: Value) -> GeometryBuilder {
+        GeometryBuilder { geometry: Geometry::new(value) }
+    }
+
+    /// Swaps the first two ordinates of every position and of the `bbox`, if any.
+    pub fn swap_axes(&mut self) {
+        self.value.swap_axes();
+        if let Some(ref mut bbox) = self.bbox {
+            util::swap_bbox_axes(bbox);
+        }
+    }
+}
+
+impl From<Value> for Geometry {
+    fn from(value: Value) -> Self {
+        Geometry::new(value)
+    }
+}
+
+/// Builder for `Geometry`s, created by `Geometry::builder`
+#[derive(Clone, Debug)]
+pub struct GeometryBuilder {
+    geometry: Geometry,
+}
+
+impl GeometryBuilder {
+    pub fn bbox(mut self, bbox: Bbox) -> Self {
+        self.geometry.bbox = Some(bbox);
+        self
+    }
+
+    pub fn crs(mut self, crs: Crs) -> Self {
+        self.geometry.crs = Some(crs);
+        self
+    }
+
+    pub fn build(self) -> Geometry {
+        self.geometry
+    }
 }
 
 impl<'a> From<&'a Geometry> for JsonObject {
@@ -221,4 +294,32 @@ mod tests {
         };
         assert_eq!(decoded_geometry, geometry);
     }
+
+    #[test]
+    fn swap_geometry_axes() {
+        let mut geometry = Geometry {
+            value: Value::GeometryCollection(vec![
+                Geometry::new(Value::Point(vec![35.2812, -120.66029, 12.0])),
+                Geometry {
+                    value: Value::LineString(vec![vec![1.0, 2.0], vec![3.0, 4.0]]),
+                    crs: None,
+                    bbox: Some(vec![1.0, 2.0, 3.0, 4.0]),
+                },
+            ]),
+            crs: None,
+            bbox: Some(vec![1.0, -120.66029, 35.2812, 4.0]),
+        };
+        geometry.swap_axes();
+
+        assert_eq!(geometry.bbox, Some(vec![-120.66029, 1.0, 4.0, 35.2812]));
+        assert_eq!(geometry.value,
+                   Value::GeometryCollection(vec![
+                       Geometry::new(Value::Point(vec![-120.66029, 35.2812, 12.0])),
+                       Geometry {
+                           value: Value::LineString(vec![vec![2.0, 1.0], vec![4.0, 3.0]]),
+                           crs: None,
+                           bbox: Some(vec![2.0, 1.0, 4.0, 3.0]),
+                       },
+                   ]));
+    }
 }
@@ -0,0 +1,408 @@
+// Copyright 2015 The GeoRust Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! GPX reading and writing
+//!
+//! Waypoints become `Point` features, routes `LineString` features and tracks
+//! `MultiLineString` features with a line per segment. Elevations become the third ordinate of
+//! positions. The `name`, `desc` and `time` of each waypoint, route and track become
+//! properties, and the times of route and track points, if any, are kept in a `times` property:
+//! an array of times for a route, and an array of arrays of times, one per segment, for a track.
+//! Points without a time have a `null` time.
+//!
+//! When writing, `Point` and `MultiPoint` features become waypoints, `LineString` features
+//! routes and `MultiLineString` features tracks, while polygons are left out; the members of
+//! geometry collections are written separately.
+
+use json::{JsonObject, JsonValue};
+use xml;
+
+use {Error, Feature, FeatureCollection, Geometry, LineStringType, Position, Value};
+
+
+const NAMESPACE: &'static str = "http://www.topografix.com/GPX/1/1";
+
+/// Properties read from and written to waypoints, routes and tracks, in schema order
+const PROPERTIES: &'static [&'static str] = &["name", "desc"];
+
+fn malformed(message: &str) -> Error {
+    Error::MalformedGpx(String::from(message))
+}
+
+/// Reads a point, returning its position and time.
+fn read_point(element: &xml::Element) -> Result<(Position, Option<String>), Error> {
+    let ordinate = |name: &str| -> Result<f64, Error> {
+        let value = match element.attribute(name) {
+            Some(value) => value,
+            None => return Err(malformed(&format!("missing '{}' attribute", name))),
+        };
+        match value.trim().parse::<f64>() {
+            Ok(value) if value.is_finite() => Ok(value),
+            _ => Err(malformed(&format!("invalid '{}' attribute '{}'", name, value))),
+        }
+    };
+    let mut position = vec![try!(ordinate("lon")), try!(ordinate("lat"))];
+    if let Some(ele) = element.child_text("ele") {
+        match ele.parse::<f64>() {
+            Ok(ele) if ele.is_finite() => position.push(ele),
+            _ => return Err(malformed(&format!("invalid elevation '{}'", ele))),
+        }
+    }
+    Ok((position, element.child_text("time")))
+}
+
+fn read_points(element: &xml::Element,
+               name: &str)
+               -> Result<(LineStringType, Vec<Option<String>>), Error> {
+    let mut positions = vec![];
+    let mut times = vec![];
+    for point in element.children_named(name) {
+        let (position, time) = try!(read_point(point));
+        positions.push(position);
+        times.push(time);
+    }
+    Ok((positions, times))
+}
+
+fn times_value(times: &[Option<String>]) -> JsonValue {
+    JsonValue::Array(times.iter()
+        .map(|time| time.as_ref().map_or(JsonValue::Null, |t| JsonValue::String(t.clone())))
+        .collect())
+}
+
+fn read_properties(element: &xml::Element) -> JsonObject {
+    let mut properties = JsonObject::new();
+    for name in PROPERTIES.iter().chain(&["time"]) {
+        if let Some(text) = element.child_text(name) {
+            properties.insert(String::from(*name), JsonValue::String(text));
+        }
+    }
+    properties
+}
+
+fn feature(value: Value, properties: JsonObject) -> Feature {
+    Feature {
+        bbox: None,
+        crs: None,
+        geometry: Some(Geometry::new(value)),
+        id: None,
+        properties: Some(properties),
+    }
+}
+
+/// Reads the waypoints, routes and tracks of a GPX document, in that order.
+///
+/// # Examples
+///
+/// ```
+/// use geojson::Value;
+///
+/// let gpx = r#"<?xml version="1.0"?>
+/// <gpx version="1.1" creator="example" xmlns="http://www.topografix.com/GPX/1/1">
+///   <wpt lat="46.5763" lon="7.9904"><ele>2061</ele><name>Camp</name></wpt>
+///   <trk>
+///     <name>Ascent</name>
+///     <trkseg>
+///       <trkpt lat="46.5763" lon="7.9904"><ele>2061</ele></trkpt>
+///       <trkpt lat="46.5580" lon="7.9819"><ele>2962</ele></trkpt>
+///     </trkseg>
+///   </trk>
+/// </gpx>"#;
+///
+/// let fc = geojson::gpx::read(gpx).unwrap();
+/// assert_eq!(fc.features[0].geometry.as_ref().unwrap().value,
+///            Value::Point(vec![7.9904, 46.5763, 2061.0]));
+/// assert_eq!(fc.features[1].property::<String>("name").unwrap(), "Ascent");
+/// assert_eq!(fc.features[1].geometry.as_ref().unwrap().value,
+///            Value::MultiLineString(vec![vec![vec![7.9904, 46.5763, 2061.0],
+///                                             vec![7.9819, 46.5580, 2962.0]]]));
+/// ```
+pub fn read(gpx: &str) -> Result<FeatureCollection, Error> {
+    let root = try!(xml::parse(gpx, Error::MalformedGpx));
+    if root.name != "gpx" {
+        return Err(malformed("expected a 'gpx' element"));
+    }
+
+    let mut features = vec![];
+    for waypoint in root.children_named("wpt") {
+        let (position, _) = try!(read_point(waypoint));
+        features.push(feature(Value::Point(position), read_properties(waypoint)));
+    }
+    for route in root.children_named("rte") {
+        let (line, times) = try!(read_points(route, "rtept"));
+        let mut properties = read_properties(route);
+        if times.iter().any(Option::is_some) {
+            properties.insert(String::from("times"), times_value(&times));
+        }
+        features.push(feature(Value::LineString(line), properties));
+    }
+    for track in root.children_named("trk") {
+        let mut lines = vec![];
+        let mut times = vec![];
+        for segment in track.children_named("trkseg") {
+            let (line, segment_times) = try!(read_points(segment, "trkpt"));
+            lines.push(line);
+            times.push(segment_times);
+        }
+        let mut properties = read_properties(track);
+        if times.iter().flat_map(|t| t).any(Option::is_some) {
+            let times = times.iter().map(|t| times_value(t)).collect();
+            properties.insert(String::from("times"), JsonValue::Array(times));
+        }
+        features.push(feature(Value::MultiLineString(lines), properties));
+    }
+
+    Ok(FeatureCollection {
+        bbox: None,
+        crs: None,
+        features: features,
+    })
+}
+
+/// Writes the features of `fc` as a GPX 1.1 document.
+///
+/// `Point` and `MultiPoint` features become waypoints, `LineString` features routes and
+/// `MultiLineString` features tracks, with the properties and times described in the
+/// [module documentation](index.html).
+pub fn write(fc: &FeatureCollection) -> String {
+    let mut waypoints = vec![];
+    let mut routes = vec![];
+    let mut tracks = vec![];
+    for feature in &fc.features {
+        let properties = feature.properties.as_ref();
+        let mut stack = feature.geometry.iter().map(|g| &g.value).collect::<Vec<_>>();
+        while let Some(value) = stack.pop() {
+            match *value {
+                Value::Point(ref position) => waypoints.push((position, properties)),
+                Value::MultiPoint(ref positions) => {
+                    waypoints.extend(positions.iter().map(|p| (p, properties)))
+                }
+                Value::LineString(ref line) => routes.push((line, properties)),
+                Value::MultiLineString(ref lines) => tracks.push((lines, properties)),
+                Value::Polygon(..) |
+                Value::MultiPolygon(..) => {}
+                Value::GeometryCollection(ref geometries) => {
+                    stack.extend(geometries.iter().rev().map(|g| &g.value))
+                }
+            }
+        }
+    }
+
+    let mut writer = xml::Writer::new();
+    writer.start("gpx",
+                 &[("version", "1.1"), ("creator", "rust-geojson"), ("xmlns", NAMESPACE)]);
+    for (position, properties) in waypoints {
+        if position.len() < 2 {
+            continue;
+        }
+        let time = xml::property(properties, "time");
+        write_point(&mut writer,
+                    "wpt",
+                    position,
+                    time.as_ref().map(|t| t as &str),
+                    properties);
+    }
+    for (line, properties) in routes {
+        writer.start("rte", &[]);
+        write_properties(&mut writer, properties);
+        let times = property_array(properties, "times");
+        write_points(&mut writer, "rtept", line, &times);
+        writer.end("rte");
+    }
+    for (lines, properties) in tracks {
+        writer.start("trk", &[]);
+        write_properties(&mut writer, properties);
+        let times = property_array(properties, "times");
+        for (i, line) in lines.iter().enumerate() {
+            let segment_times = match times.get(i) {
+                Some(&JsonValue::Array(ref times)) => times.clone(),
+                _ => vec![],
+            };
+            writer.start("trkseg", &[]);
+            write_points(&mut writer, "trkpt", line, &segment_times);
+            writer.end("trkseg");
+        }
+        writer.end("trk");
+    }
+    writer.end("gpx");
+    writer.xml
+}
+
+fn property_array(properties: Option<&JsonObject>, name: &str) -> Vec<JsonValue> {
+    match properties.and_then(|p| p.get(name)) {
+        Some(&JsonValue::Array(ref values)) => values.clone(),
+        _ => vec![],
+    }
+}
+
+fn write_properties(writer: &mut xml::Writer, properties: Option<&JsonObject>) {
+    for name in PROPERTIES {
+        if let Some(value) = xml::property(properties, name) {
+            writer.text_element(name, &value);
+        }
+    }
+}
+
+fn write_points(writer: &mut xml::Writer, name: &str, line: &[Position], times: &[JsonValue]) {
+    for (i, position) in line.iter().enumerate().filter(|&(_, p)| p.len() >= 2) {
+        let time = times.get(i).and_then(JsonValue::as_str);
+        write_point(writer, name, position, time, None);
+    }
+}
+
+fn write_point(writer: &mut xml::Writer,
+               name: &str,
+               position: &Position,
+               time: Option<&str>,
+               properties: Option<&JsonObject>) {
+    let (lat, lon) = (position[1].to_string(), position[0].to_string());
+    let attributes = [("lat", &lat as &str), ("lon", &lon as &str)];
+    let has_properties = PROPERTIES.iter().any(|name| xml::property(properties, name).is_some());
+    if position.len() < 3 && time.is_none() && !has_properties {
+        writer.empty(name, &attributes);
+        return;
+    }
+    writer.start(name, &attributes);
+    if let Some(ele) = position.get(2) {
+        writer.text_element("ele", &ele.to_string());
+    }
+    if let Some(time) = time {
+        writer.text_element("time", time);
+    }
+    write_properties(writer, properties);
+    writer.end(name);
+}
+
+
+#[cfg(test)]
+mod tests {
+    use {Error, Feature, FeatureCollection, Geometry, Value};
+    use super::{read, write};
+
+    const GPX: &'static str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!-- Exported from a GPS unit -->
+<gpx version="1.1" creator="test" xmlns="http://www.topografix.com/GPX/1/1"
+     xmlns:gpxx="http://www.garmin.com/xmlschemas/GpxExtensions/v3">
+  <metadata><name>Survey</name></metadata>
+  <wpt lat="46.5" lon="7.9">
+    <ele>2061.5</ele>
+    <time>2017-06-01T08:00:00Z</time>
+    <name>Camp &amp; depot</name>
+    <desc><![CDATA[Tents <b>here</b>]]></desc>
+  </wpt>
+  <rte>
+    <name>Plan</name>
+    <rtept lat="1" lon="2"/>
+    <rtept lat="3" lon="4"><time>2017-06-01T09:00:00Z</time></rtept>
+  </rte>
+  <trk>
+    <name>Walk</name>
+    <desc>Day one</desc>
+    <extensions>
+      <gpxx:TrackExtension><gpxx:DisplayColor>Red</gpxx:DisplayColor></gpxx:TrackExtension>
+    </extensions>
+    <trkseg>
+      <trkpt lat="10" lon="20"><ele>5</ele></trkpt>
+      <trkpt lat="11" lon="21"><ele>6</ele></trkpt>
+    </trkseg>
+    <trkseg>
+      <trkpt lat="12" lon="22"/>
+    </trkseg>
+  </trk>
+</gpx>
+"#;
+
+    #[test]
+    fn read_features() {
+        let fc = read(GPX).unwrap();
+        let values = fc.features
+            .iter()
+            .map(|f| f.geometry.as_ref().unwrap().value.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(values,
+                   vec![Value::Point(vec![7.9, 46.5, 2061.5]),
+                        Value::LineString(vec![vec![2.0, 1.0], vec![4.0, 3.0]]),
+                        Value::MultiLineString(vec![vec![vec![20.0, 10.0, 5.0],
+                                                         vec![21.0, 11.0, 6.0]],
+                                                    vec![vec![22.0, 12.0]]])]);
+        let properties = fc.features
+            .iter()
+            .map(|f| ::serde_json::to_value(f.properties.as_ref().unwrap()).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(properties,
+                   vec![json!({"name": "Camp & depot", "desc": "Tents <b>here</b>",
+                               "time": "2017-06-01T08:00:00Z"}),
+                        json!({"name": "Plan", "times": [null, "2017-06-01T09:00:00Z"]}),
+                        json!({"name": "Walk", "desc": "Day one"})]);
+    }
+
+    #[test]
+    fn round_trip() {
+        let fc = read(GPX).unwrap();
+        let gpx = write(&fc);
+        assert_eq!(read(&gpx).unwrap(), fc);
+    }
+
+    #[test]
+    fn write_features() {
+        let fc = FeatureCollection::builder()
+            .feature(Feature::builder()
+                .geometry(Value::GeometryCollection(vec![
+                    Geometry::new(Value::MultiLineString(vec![vec![vec![1.0, 2.0]]])),
+                    Geometry::new(Value::MultiPoint(vec![vec![3.0, 4.0], vec![5.0, 6.0, 7.0]])),
+                    Geometry::new(Value::Polygon(vec![])),
+                ]))
+                .property("name", "<Mixed>")
+                .property("desc", 42)
+                .build())
+            .build();
+        assert_eq!(write(&fc),
+                   r#"<?xml version="1.0" encoding="UTF-8"?>
+<gpx version="1.1" creator="rust-geojson" xmlns="http://www.topografix.com/GPX/1/1">
+  <wpt lat="4" lon="3">
+    <name>&lt;Mixed&gt;</name>
+    <desc>42</desc>
+  </wpt>
+  <wpt lat="6" lon="5">
+    <ele>7</ele>
+    <name>&lt;Mixed&gt;</name>
+    <desc>42</desc>
+  </wpt>
+  <trk>
+    <name>&lt;Mixed&gt;</name>
+    <desc>42</desc>
+    <trkseg>
+      <trkpt lat="2" lon="1"/>
+    </trkseg>
+  </trk>
+</gpx>
+"#);
+    }
+
+    #[test]
+    fn malformed() {
+        let error = |message: &str| Err(Error::MalformedGpx(String::from(message)));
+        assert_eq!(read("<kml/>"), error("expected a 'gpx' element"));
+        assert_eq!(read("<gpx><wpt lon=\"1\"/></gpx>"), error("missing 'lat' attribute"));
+        assert_eq!(read("<gpx><wpt lat=\"x\" lon=\"1\"/></gpx>"),
+                   error("invalid 'lat' attribute 'x'"));
+        assert_eq!(read("<gpx>\n<wpt lat=\"1\" lon=\"1\"></gpx>"),
+                   error("line 2: expected '</wpt>'"));
+        assert_eq!(read("<gpx><name>&bogus;</name></gpx>"),
+                   error("line 1: unknown entity '&bogus;'"));
+        assert!(read("<gpx><wpt lat=\"1\" lon=\"1\">").is_err());
+        assert!(read("<gpx/><gpx/>").is_err());
+        assert!(read("").is_err());
+    }
+}
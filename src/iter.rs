@@ -0,0 +1,496 @@
+// Copyright 2015 The GeoRust Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Iterators over the positions and geometries of GeoJSON objects
+
+use std::slice;
+
+use {Feature, FeatureCollection, GeoJson, Geometry, LineStringType, PolygonType, Position,
+     Value};
+
+
+/// Iterator over every position of a GeoJSON object, in document order
+///
+/// Created by the `coords` methods of `Value`, `Geometry`, `Feature`, `FeatureCollection`
+/// and `GeoJson`.
+pub struct Coords<'a> {
+    stack: Vec<CoordsFrame<'a>>,
+}
+
+enum CoordsFrame<'a> {
+    Position(Option<&'a Position>),
+    Positions(slice::Iter<'a, Position>),
+    Lines(slice::Iter<'a, LineStringType>),
+    Polygons(slice::Iter<'a, PolygonType>),
+    Geometries(slice::Iter<'a, Geometry>),
+    Features(slice::Iter<'a, Feature>),
+}
+
+impl<'a> Coords<'a> {
+    fn new(frame: CoordsFrame<'a>) -> Self {
+        Coords { stack: vec![frame] }
+    }
+
+    fn empty() -> Self {
+        Coords { stack: vec![] }
+    }
+}
+
+fn coords_frame<'a>(value: &'a Value) -> CoordsFrame<'a> {
+    match *value {
+        Value::Point(ref position) => CoordsFrame::Position(Some(position)),
+        Value::MultiPoint(ref positions) |
+        Value::LineString(ref positions) => CoordsFrame::Positions(positions.iter()),
+        Value::MultiLineString(ref lines) |
+        Value::Polygon(ref lines) => CoordsFrame::Lines(lines.iter()),
+        Value::MultiPolygon(ref polygons) => CoordsFrame::Polygons(polygons.iter()),
+        Value::GeometryCollection(ref geometries) => CoordsFrame::Geometries(geometries.iter()),
+    }
+}
+
+impl<'a> Iterator for Coords<'a> {
+    type Item = &'a Position;
+
+    fn next(&mut self) -> Option<&'a Position> {
+        loop {
+            let next = match self.stack.last_mut() {
+                None => return None,
+                Some(&mut CoordsFrame::Position(ref mut position)) => {
+                    match position.take() {
+                        Some(position) => return Some(position),
+                        None => None,
+                    }
+                }
+                Some(&mut CoordsFrame::Positions(ref mut iter)) => {
+                    match iter.next() {
+                        Some(position) => return Some(position),
+                        None => None,
+                    }
+                }
+                Some(&mut CoordsFrame::Lines(ref mut iter)) => {
+                    iter.next().map(|line| CoordsFrame::Positions(line.iter()))
+                }
+                Some(&mut CoordsFrame::Polygons(ref mut iter)) => {
+                    iter.next().map(|polygon| CoordsFrame::Lines(polygon.iter()))
+                }
+                Some(&mut CoordsFrame::Geometries(ref mut iter)) => {
+                    iter.next().map(|geometry| coords_frame(&geometry.value))
+                }
+                Some(&mut CoordsFrame::Features(ref mut iter)) => {
+                    iter.next().map(|feature| match feature.geometry {
+                        Some(ref geometry) => coords_frame(&geometry.value),
+                        None => CoordsFrame::Position(None),
+                    })
+                }
+            };
+            match next {
+                Some(frame) => self.stack.push(frame),
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+/// Iterator over mutable references to every position of a GeoJSON object, in document order
+///
+/// Created by the `coords_mut` methods of `Value`, `Geometry`, `Feature`, `FeatureCollection`
+/// and `GeoJson`.
+pub struct CoordsMut<'a> {
+    stack: Vec<CoordsMutFrame<'a>>,
+}
+
+enum CoordsMutFrame<'a> {
+    Position(Option<&'a mut Position>),
+    Positions(slice::IterMut<'a, Position>),
+    Lines(slice::IterMut<'a, LineStringType>),
+    Polygons(slice::IterMut<'a, PolygonType>),
+    Geometries(slice::IterMut<'a, Geometry>),
+    Features(slice::IterMut<'a, Feature>),
+}
+
+impl<'a> CoordsMut<'a> {
+    fn new(frame: CoordsMutFrame<'a>) -> Self {
+        CoordsMut { stack: vec![frame] }
+    }
+
+    fn empty() -> Self {
+        CoordsMut { stack: vec![] }
+    }
+}
+
+fn coords_mut_frame<'a>(value: &'a mut Value) -> CoordsMutFrame<'a> {
+    match *value {
+        Value::Point(ref mut position) => CoordsMutFrame::Position(Some(position)),
+        Value::MultiPoint(ref mut positions) |
+        Value::LineString(ref mut positions) => CoordsMutFrame::Positions(positions.iter_mut()),
+        Value::MultiLineString(ref mut lines) |
+        Value::Polygon(ref mut lines) => CoordsMutFrame::Lines(lines.iter_mut()),
+        Value::MultiPolygon(ref mut polygons) => CoordsMutFrame::Polygons(polygons.iter_mut()),
+        Value::GeometryCollection(ref mut geometries) => {
+            CoordsMutFrame::Geometries(geometries.iter_mut())
+        }
+    }
+}
+
+impl<'a> Iterator for CoordsMut<'a> {
+    type Item = &'a mut Position;
+
+    fn next(&mut self) -> Option<&'a mut Position> {
+        loop {
+            let next = match self.stack.last_mut() {
+                None => return None,
+                Some(&mut CoordsMutFrame::Position(ref mut position)) => {
+                    match position.take() {
+                        Some(position) => return Some(position),
+                        None => None,
+                    }
+                }
+                Some(&mut CoordsMutFrame::Positions(ref mut iter)) => {
+                    match iter.next() {
+                        Some(position) => return Some(position),
+                        None => None,
+                    }
+                }
+                Some(&mut CoordsMutFrame::Lines(ref mut iter)) => {
+                    iter.next().map(|line| CoordsMutFrame::Positions(line.iter_mut()))
+                }
+                Some(&mut CoordsMutFrame::Polygons(ref mut iter)) => {
+                    iter.next().map(|polygon| CoordsMutFrame::Lines(polygon.iter_mut()))
+                }
+                Some(&mut CoordsMutFrame::Geometries(ref mut iter)) => {
+                    iter.next().map(|geometry| coords_mut_frame(&mut geometry.value))
+                }
+                Some(&mut CoordsMutFrame::Features(ref mut iter)) => {
+                    iter.next().map(|feature| match feature.geometry {
+                        Some(ref mut geometry) => coords_mut_frame(&mut geometry.value),
+                        None => CoordsMutFrame::Position(None),
+                    })
+                }
+            };
+            match next {
+                Some(frame) => self.stack.push(frame),
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+/// A single-part geometry borrowed from a GeoJSON object
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SimpleGeometry<'a> {
+    Point(&'a Position),
+    LineString(&'a [Position]),
+    Polygon(&'a [LineStringType]),
+}
+
+impl<'a> SimpleGeometry<'a> {
+    /// Returns an owned copy of the part as a `Value`.
+    pub fn to_value(&self) -> Value {
+        match *self {
+            SimpleGeometry::Point(position) => Value::Point(position.clone()),
+            SimpleGeometry::LineString(positions) => Value::LineString(positions.to_vec()),
+            SimpleGeometry::Polygon(rings) => Value::Polygon(rings.to_vec()),
+        }
+    }
+}
+
+/// Iterator over the simple parts of a GeoJSON object, in document order
+///
+/// Geometry collections are flattened, and multi-geometries are split into their points,
+/// line strings and polygons. Created by the `geometries` methods of `Value`, `Geometry`,
+/// `Feature`, `FeatureCollection` and `GeoJson`.
+pub struct Geometries<'a> {
+    stack: Vec<GeometriesFrame<'a>>,
+}
+
+enum GeometriesFrame<'a> {
+    Single(Option<SimpleGeometry<'a>>),
+    Points(slice::Iter<'a, Position>),
+    LineStrings(slice::Iter<'a, LineStringType>),
+    Polygons(slice::Iter<'a, PolygonType>),
+    Geometries(slice::Iter<'a, Geometry>),
+    Features(slice::Iter<'a, Feature>),
+}
+
+impl<'a> Geometries<'a> {
+    fn new(frame: GeometriesFrame<'a>) -> Self {
+        Geometries { stack: vec![frame] }
+    }
+
+    fn empty() -> Self {
+        Geometries { stack: vec![] }
+    }
+}
+
+fn geometries_frame<'a>(value: &'a Value) -> GeometriesFrame<'a> {
+    match *value {
+        Value::Point(ref position) => {
+            GeometriesFrame::Single(Some(SimpleGeometry::Point(position)))
+        }
+        Value::MultiPoint(ref positions) => GeometriesFrame::Points(positions.iter()),
+        Value::LineString(ref positions) => {
+            GeometriesFrame::Single(Some(SimpleGeometry::LineString(positions)))
+        }
+        Value::MultiLineString(ref lines) => GeometriesFrame::LineStrings(lines.iter()),
+        Value::Polygon(ref rings) => GeometriesFrame::Single(Some(SimpleGeometry::Polygon(rings))),
+        Value::MultiPolygon(ref polygons) => GeometriesFrame::Polygons(polygons.iter()),
+        Value::GeometryCollection(ref geometries) => {
+            GeometriesFrame::Geometries(geometries.iter())
+        }
+    }
+}
+
+impl<'a> Iterator for Geometries<'a> {
+    type Item = SimpleGeometry<'a>;
+
+    fn next(&mut self) -> Option<SimpleGeometry<'a>> {
+        loop {
+            let next = match self.stack.last_mut() {
+                None => return None,
+                Some(&mut GeometriesFrame::Single(ref mut part)) => {
+                    match part.take() {
+                        Some(part) => return Some(part),
+                        None => None,
+                    }
+                }
+                Some(&mut GeometriesFrame::Points(ref mut iter)) => {
+                    match iter.next() {
+                        Some(position) => return Some(SimpleGeometry::Point(position)),
+                        None => None,
+                    }
+                }
+                Some(&mut GeometriesFrame::LineStrings(ref mut iter)) => {
+                    match iter.next() {
+                        Some(line) => return Some(SimpleGeometry::LineString(line)),
+                        None => None,
+                    }
+                }
+                Some(&mut GeometriesFrame::Polygons(ref mut iter)) => {
+                    match iter.next() {
+                        Some(polygon) => return Some(SimpleGeometry::Polygon(polygon)),
+                        None => None,
+                    }
+                }
+                Some(&mut GeometriesFrame::Geometries(ref mut iter)) => {
+                    iter.next().map(|geometry| geometries_frame(&geometry.value))
+                }
+                Some(&mut GeometriesFrame::Features(ref mut iter)) => {
+                    iter.next().map(|feature| match feature.geometry {
+                        Some(ref geometry) => geometries_frame(&geometry.value),
+                        None => GeometriesFrame::Single(None),
+                    })
+                }
+            };
+            match next {
+                Some(frame) => self.stack.push(frame),
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+impl Value {
+    /// Returns an iterator over every position of the value.
+    pub fn coords<'a>(&'a self) -> Coords<'a> {
+        Coords::new(coords_frame(self))
+    }
+
+    /// Returns an iterator over mutable references to every position of the value.
+    pub fn coords_mut<'a>(&'a mut self) -> CoordsMut<'a> {
+        CoordsMut::new(coords_mut_frame(self))
+    }
+
+    /// Returns an iterator over the simple parts of the value.
+    pub fn geometries<'a>(&'a self) -> Geometries<'a> {
+        Geometries::new(geometries_frame(self))
+    }
+}
+
+impl Geometry {
+    /// Returns an iterator over every position of the geometry.
+    pub fn coords<'a>(&'a self) -> Coords<'a> {
+        self.value.coords()
+    }
+
+    /// Returns an iterator over mutable references to every position of the geometry.
+    pub fn coords_mut<'a>(&'a mut self) -> CoordsMut<'a> {
+        self.value.coords_mut()
+    }
+
+    /// Returns an iterator over the simple parts of the geometry.
+    pub fn geometries<'a>(&'a self) -> Geometries<'a> {
+        self.value.geometries()
+    }
+}
+
+impl Feature {
+    /// Returns an iterator over every position of the feature's geometry, if any.
+    pub fn coords<'a>(&'a self) -> Coords<'a> {
+        match self.geometry {
+            Some(ref geometry) => geometry.coords(),
+            None => Coords::empty(),
+        }
+    }
+
+    /// Returns an iterator over mutable references to every position of the feature's
+    /// geometry, if any.
+    pub fn coords_mut<'a>(&'a mut self) -> CoordsMut<'a> {
+        match self.geometry {
+            Some(ref mut geometry) => geometry.coords_mut(),
+            None => CoordsMut::empty(),
+        }
+    }
+
+    /// Returns an iterator over the simple parts of the feature's geometry, if any.
+    pub fn geometries<'a>(&'a self) -> Geometries<'a> {
+        match self.geometry {
+            Some(ref geometry) => geometry.geometries(),
+            None => Geometries::empty(),
+        }
+    }
+}
+
+impl FeatureCollection {
+    /// Returns an iterator over every position of every feature.
+    pub fn coords<'a>(&'a self) -> Coords<'a> {
+        Coords::new(CoordsFrame::Features(self.features.iter()))
+    }
+
+    /// Returns an iterator over mutable references to every position of every feature.
+    pub fn coords_mut<'a>(&'a mut self) -> CoordsMut<'a> {
+        CoordsMut::new(CoordsMutFrame::Features(self.features.iter_mut()))
+    }
+
+    /// Returns an iterator over the simple parts of every feature's geometry.
+    pub fn geometries<'a>(&'a self) -> Geometries<'a> {
+        Geometries::new(GeometriesFrame::Features(self.features.iter()))
+    }
+}
+
+impl GeoJson {
+    /// Returns an iterator over every position of the object.
+    pub fn coords<'a>(&'a self) -> Coords<'a> {
+        match *self {
+            GeoJson::Geometry(ref geometry) => geometry.coords(),
+            GeoJson::Feature(ref feature) => feature.coords(),
+            GeoJson::FeatureCollection(ref fc) => fc.coords(),
+        }
+    }
+
+    /// Returns an iterator over mutable references to every position of the object.
+    pub fn coords_mut<'a>(&'a mut self) -> CoordsMut<'a> {
+        match *self {
+            GeoJson::Geometry(ref mut geometry) => geometry.coords_mut(),
+            GeoJson::Feature(ref mut feature) => feature.coords_mut(),
+            GeoJson::FeatureCollection(ref mut fc) => fc.coords_mut(),
+        }
+    }
+
+    /// Returns an iterator over the simple parts of the object.
+    pub fn geometries<'a>(&'a self) -> Geometries<'a> {
+        match *self {
+            GeoJson::Geometry(ref geometry) => geometry.geometries(),
+            GeoJson::Feature(ref feature) => feature.geometries(),
+            GeoJson::FeatureCollection(ref fc) => fc.geometries(),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use {FeatureCollection, GeoJson, Value};
+    use super::SimpleGeometry;
+
+    fn feature_collection() -> FeatureCollection {
+        let geojson = r#"{
+            "type": "FeatureCollection",
+            "features": [
+                {
+                    "type": "Feature",
+                    "properties": {},
+                    "geometry": {
+                        "type": "MultiPolygon",
+                        "coordinates": [
+                            [[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 0.0]]],
+                            [[[2.0, 2.0], [3.0, 2.0], [3.0, 3.0], [2.0, 2.0]]]
+                        ]
+                    }
+                },
+                {"type": "Feature", "properties": {}, "geometry": null},
+                {
+                    "type": "Feature",
+                    "properties": {},
+                    "geometry": {
+                        "type": "GeometryCollection",
+                        "geometries": [
+                            {"type": "MultiPoint", "coordinates": [[4.0, 4.0], [5.0, 5.0]]},
+                            {"type": "GeometryCollection", "geometries": []},
+                            {"type": "LineString", "coordinates": [[6.0, 6.0], [7.0, 7.0]]}
+                        ]
+                    }
+                }
+            ]
+        }"#
+            .parse::<GeoJson>()
+            .unwrap();
+        match geojson {
+            GeoJson::FeatureCollection(fc) => fc,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn coords() {
+        let fc = feature_collection();
+        let xs = fc.coords().map(|p| p[0]).collect::<Vec<_>>();
+        assert_eq!(xs,
+                   vec![0.0, 1.0, 1.0, 0.0, 2.0, 3.0, 3.0, 2.0, 4.0, 5.0, 6.0, 7.0]);
+
+        assert_eq!(fc.features[1].coords().count(), 0);
+        assert_eq!(Value::Point(vec![1.0, 2.0]).coords().collect::<Vec<_>>(),
+                   vec![&vec![1.0, 2.0]]);
+    }
+
+    #[test]
+    fn coords_mut() {
+        let mut geojson = GeoJson::from(feature_collection());
+        for position in geojson.coords_mut() {
+            position[1] = -position[0];
+        }
+        assert_eq!(geojson.coords().count(), 12);
+        assert!(geojson.coords().all(|p| p[1] == -p[0]));
+    }
+
+    #[test]
+    fn geometries() {
+        let fc = feature_collection();
+        let parts = fc.geometries().collect::<Vec<_>>();
+        assert_eq!(parts.len(), 5);
+        match parts[1] {
+            SimpleGeometry::Polygon(rings) => assert_eq!(rings[0][0], vec![2.0, 2.0]),
+            _ => unreachable!(),
+        }
+        assert_eq!(parts[2], SimpleGeometry::Point(&vec![4.0, 4.0]));
+        assert_eq!(parts[3], SimpleGeometry::Point(&vec![5.0, 5.0]));
+        assert_eq!(parts[4].to_value(),
+                   Value::LineString(vec![vec![6.0, 6.0], vec![7.0, 7.0]]));
+    }
+}
@@ -0,0 +1,756 @@
+// Copyright 2015 The GeoRust Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! OGC Features and Geometries JSON (JSON-FG)
+//!
+//! [JSON-FG](https://docs.ogc.org/DRAFTS/21-045.html) extends GeoJSON features with a
+//! `place` in any coordinate reference system, which may also be a solid such as a
+//! `Polyhedron`, a `time`, a `coordRefSys` and a `featureType`. JSON-FG documents remain valid
+//! GeoJSON, so they can also be read as `GeoJson`, which ignores the extra members.
+//!
+//! `JsonFgFeature` and `JsonFgFeatureCollection` hold the plain GeoJSON part of a document
+//! along with the JSON-FG members. When writing, features always have `place` and `time`
+//! members, which are `null` when absent, as JSON-FG requires.
+//!
+//! # Example
+//!
+//! ```
+//! use geojson::GeoJson;
+//! use geojson::jsonfg::{JsonFg, Place};
+//!
+//! let text = r#"{
+//!     "type": "Feature",
+//!     "conformsTo": ["http://www.opengis.net/spec/json-fg-1/0.2/conf/core"],
+//!     "featureType": "Building",
+//!     "time": {"date": "2021-05-01"},
+//!     "coordRefSys": "http://www.opengis.net/def/crs/EPSG/0/25832",
+//!     "place": {"type": "Point", "coordinates": [500000.0, 5500000.0]},
+//!     "geometry": {"type": "Point", "coordinates": [9.0, 49.6]},
+//!     "properties": {"name": "Town hall"}
+//! }"#;
+//!
+//! let feature = match text.parse::<JsonFg>().unwrap() {
+//!     JsonFg::Feature(feature) => feature,
+//!     _ => unreachable!(),
+//! };
+//! assert_eq!(feature.feature_type, vec!["Building"]);
+//! assert_eq!(feature.time.unwrap().date, Some(String::from("2021-05-01")));
+//! assert_eq!(feature.coord_ref_sys.unwrap().id().unwrap().code, "25832");
+//! assert!(match feature.place {
+//!     Some(Place::Geometry(..)) => true,
+//!     _ => false,
+//! });
+//!
+//! // The same document is plain GeoJSON
+//! assert!(text.parse::<GeoJson>().is_ok());
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use json::{Deserialize, Deserializer, JsonObject, JsonValue, Serialize, Serializer};
+use serde_json;
+
+use {Bbox, Crs, CrsId, Error, Feature, FeatureCollection, FromObject, Geometry, PolygonType,
+     util};
+
+
+/// URI of the JSON-FG core conformance class
+pub const CONFORMANCE_CORE: &'static str = "http://www.opengis.net/spec/json-fg-1/0.2/conf/core";
+
+fn malformed(message: &str) -> Error {
+    Error::MalformedJsonFg(String::from(message))
+}
+
+/// Time of a feature, as an instant, an interval, or both
+///
+/// Dates are `YYYY-MM-DD` strings and timestamps RFC 3339 strings. Either bound of an interval
+/// may be `".."`, for an unbounded interval.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Time {
+    pub date: Option<String>,
+    pub timestamp: Option<String>,
+    pub interval: Option<(String, String)>,
+}
+
+/// Coordinate reference system of the `place` of features
+#[derive(Clone, Debug, PartialEq)]
+pub enum CoordRefSys {
+    /// URI or safe CURIE (`[EPSG:4326]`) of a coordinate reference system
+    Uri(String),
+
+    /// Reference to a coordinate reference system, with the epoch of dynamic ones
+    Reference { href: String, epoch: Option<f64> },
+
+    /// Compound coordinate reference system, such as a horizontal one and a vertical one
+    Compound(Vec<CoordRefSys>),
+}
+
+impl CoordRefSys {
+    /// Parses the identifier of a single coordinate reference system.
+    pub fn id(&self) -> Option<CrsId> {
+        let uri = match *self {
+            CoordRefSys::Uri(ref uri) => uri,
+            CoordRefSys::Reference { ref href, .. } => href,
+            CoordRefSys::Compound(..) => return None,
+        };
+        uri.trim_start_matches('[').trim_end_matches(']').parse().ok()
+    }
+}
+
+impl From<CrsId> for CoordRefSys {
+    fn from(id: CrsId) -> CoordRefSys {
+        CoordRefSys::Uri(format!("http://www.opengis.net/def/crs/{}/{}/{}",
+                                 id.authority,
+                                 id.version.as_ref().map_or("0", |v| v as &str),
+                                 id.code))
+    }
+}
+
+/// Extrusion of a base geometry between two heights
+#[derive(Clone, Debug, PartialEq)]
+pub struct Prism {
+    pub base: Geometry,
+    pub lower: Option<f64>,
+    pub upper: f64,
+}
+
+/// Location of a feature in its `coordRefSys`
+#[derive(Clone, Debug, PartialEq)]
+pub enum Place {
+    /// Any GeoJSON geometry
+    Geometry(Geometry),
+
+    /// Solid bounded by shells of polygonal faces, the first shell being the outer one
+    Polyhedron(Vec<Vec<PolygonType>>),
+
+    MultiPolyhedron(Vec<Vec<Vec<PolygonType>>>),
+
+    Prism(Prism),
+
+    MultiPrism(Vec<Prism>),
+}
+
+/// JSON-FG feature
+#[derive(Clone, Debug, PartialEq)]
+pub struct JsonFgFeature {
+    pub feature: Feature,
+    pub conforms_to: Vec<String>,
+    pub feature_type: Vec<String>,
+    pub time: Option<Time>,
+    pub place: Option<Place>,
+    pub coord_ref_sys: Option<CoordRefSys>,
+}
+
+/// JSON-FG feature collection
+///
+/// `feature_type` and `coord_ref_sys` apply to features without their own.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JsonFgFeatureCollection {
+    pub bbox: Option<Bbox>,
+    pub crs: Option<Crs>,
+    pub features: Vec<JsonFgFeature>,
+    pub conforms_to: Vec<String>,
+    pub feature_type: Vec<String>,
+    pub coord_ref_sys: Option<CoordRefSys>,
+}
+
+/// JSON-FG documents
+#[derive(Clone, Debug, PartialEq)]
+pub enum JsonFg {
+    Feature(JsonFgFeature),
+    FeatureCollection(JsonFgFeatureCollection),
+}
+
+
+// Conversion from and to GeoJSON
+
+impl JsonFgFeature {
+    /// Wraps a GeoJSON feature without any JSON-FG members.
+    fn plain(feature: Feature) -> Self {
+        JsonFgFeature {
+            feature: feature,
+            conforms_to: vec![],
+            feature_type: vec![],
+            time: None,
+            place: None,
+            coord_ref_sys: None,
+        }
+    }
+}
+
+/// Wraps a feature as a JSON-FG document conforming to the core conformance class.
+impl From<Feature> for JsonFgFeature {
+    fn from(feature: Feature) -> Self {
+        JsonFgFeature { conforms_to: vec![String::from(CONFORMANCE_CORE)], ..Self::plain(feature) }
+    }
+}
+
+impl From<JsonFgFeature> for Feature {
+    fn from(feature: JsonFgFeature) -> Self {
+        feature.feature
+    }
+}
+
+/// Wraps a collection as a JSON-FG document conforming to the core conformance class.
+impl From<FeatureCollection> for JsonFgFeatureCollection {
+    fn from(fc: FeatureCollection) -> Self {
+        JsonFgFeatureCollection {
+            bbox: fc.bbox,
+            crs: fc.crs,
+            features: fc.features.into_iter().map(JsonFgFeature::plain).collect(),
+            conforms_to: vec![String::from(CONFORMANCE_CORE)],
+            feature_type: vec![],
+            coord_ref_sys: None,
+        }
+    }
+}
+
+impl From<JsonFgFeatureCollection> for FeatureCollection {
+    fn from(fc: JsonFgFeatureCollection) -> Self {
+        FeatureCollection {
+            bbox: fc.bbox,
+            crs: fc.crs,
+            features: fc.features.into_iter().map(Feature::from).collect(),
+        }
+    }
+}
+
+impl From<JsonFgFeature> for JsonFg {
+    fn from(feature: JsonFgFeature) -> Self {
+        JsonFg::Feature(feature)
+    }
+}
+
+impl From<JsonFgFeatureCollection> for JsonFg {
+    fn from(fc: JsonFgFeatureCollection) -> Self {
+        JsonFg::FeatureCollection(fc)
+    }
+}
+
+
+// Reading
+
+fn get_strings(object: &JsonObject, name: &str) -> Result<Vec<String>, Error> {
+    let invalid = || malformed(&format!("expected a string or an array of strings for '{}'", name));
+    match object.get(name) {
+        None | Some(&JsonValue::Null) => Ok(vec![]),
+        Some(&JsonValue::String(ref s)) => Ok(vec![s.clone()]),
+        Some(&JsonValue::Array(ref array)) => {
+            array.iter()
+                .map(|value| value.as_str().map(String::from).ok_or_else(&invalid))
+                .collect()
+        }
+        Some(..) => Err(invalid()),
+    }
+}
+
+fn get_string(object: &JsonObject, name: &str) -> Result<Option<String>, Error> {
+    match object.get(name) {
+        None | Some(&JsonValue::Null) => Ok(None),
+        Some(&JsonValue::String(ref s)) => Ok(Some(s.clone())),
+        Some(..) => Err(malformed(&format!("expected a string for '{}'", name))),
+    }
+}
+
+fn read_time(value: &JsonValue) -> Result<Option<Time>, Error> {
+    let object = match *value {
+        JsonValue::Null => return Ok(None),
+        JsonValue::Object(ref object) => object,
+        _ => return Err(malformed("expected an object for 'time'")),
+    };
+    let interval = match try!(get_strings(object, "interval")).as_slice() {
+        [] => None,
+        [start, end] => Some((start.clone(), end.clone())),
+        _ => return Err(malformed("expected two bounds for 'interval'")),
+    };
+    Ok(Some(Time {
+        date: try!(get_string(object, "date")),
+        timestamp: try!(get_string(object, "timestamp")),
+        interval: interval,
+    }))
+}
+
+fn read_coord_ref_sys(value: &JsonValue) -> Result<CoordRefSys, Error> {
+    match *value {
+        JsonValue::String(ref uri) => Ok(CoordRefSys::Uri(uri.clone())),
+        JsonValue::Object(ref object) => {
+            let href = match try!(get_string(object, "href")) {
+                Some(href) => href,
+                None => return Err(malformed("missing 'href' in 'coordRefSys'")),
+            };
+            let epoch = match object.get("epoch") {
+                Some(epoch) => {
+                    Some(try!(epoch.as_f64()
+                        .ok_or_else(|| malformed("expected a number for 'epoch'"))))
+                }
+                None => None,
+            };
+            Ok(CoordRefSys::Reference {
+                href: href,
+                epoch: epoch,
+            })
+        }
+        JsonValue::Array(ref array) => {
+            array.iter()
+                .map(read_coord_ref_sys)
+                .collect::<Result<_, _>>()
+                .map(CoordRefSys::Compound)
+        }
+        _ => Err(malformed("expected a string, an object or an array for 'coordRefSys'")),
+    }
+}
+
+fn get_coord_ref_sys(object: &JsonObject) -> Result<Option<CoordRefSys>, Error> {
+    match object.get("coordRefSys") {
+        None | Some(&JsonValue::Null) => Ok(None),
+        Some(value) => read_coord_ref_sys(value).map(Some),
+    }
+}
+
+fn read_coordinates<T>(object: &JsonObject) -> Result<T, Error>
+    where T: ::serde::de::DeserializeOwned
+{
+    let coordinates = try!(util::get_coords_value(object));
+    serde_json::from_value(coordinates.clone())
+        .map_err(|_| malformed("expected nested arrays of positions for 'coordinates'"))
+}
+
+fn read_prism(object: &JsonObject) -> Result<Prism, Error> {
+    let base = match object.get("base") {
+        Some(&JsonValue::Object(ref base)) => try!(Geometry::from_object(base)),
+        _ => return Err(malformed("expected a geometry for the 'base' of a prism")),
+    };
+    let height = |name: &str| match object.get(name) {
+        None => Ok(None),
+        Some(height) => {
+            height.as_f64()
+                .map(Some)
+                .ok_or_else(|| malformed(&format!("expected a number for '{}'", name)))
+        }
+    };
+    let lower = try!(height("lower"));
+    let upper = match try!(height("upper")) {
+        Some(upper) => upper,
+        None => return Err(malformed("missing 'upper' in prism")),
+    };
+    Ok(Prism {
+        base: base,
+        lower: lower,
+        upper: upper,
+    })
+}
+
+fn read_place(value: &JsonValue) -> Result<Option<Place>, Error> {
+    let object = match *value {
+        JsonValue::Null => return Ok(None),
+        JsonValue::Object(ref object) => object,
+        _ => return Err(malformed("expected an object for 'place'")),
+    };
+    let place = match expect_type!(object) {
+        "Polyhedron" => Place::Polyhedron(try!(read_coordinates(object))),
+        "MultiPolyhedron" => Place::MultiPolyhedron(try!(read_coordinates(object))),
+        "Prism" => Place::Prism(try!(read_prism(object))),
+        "MultiPrism" => {
+            let prisms = match object.get("prisms") {
+                Some(&JsonValue::Array(ref prisms)) => prisms,
+                _ => return Err(malformed("expected an array for 'prisms'")),
+            };
+            Place::MultiPrism(try!(prisms.iter()
+                .map(|prism| match *prism {
+                    JsonValue::Object(ref prism) => read_prism(prism),
+                    _ => Err(malformed("expected an object for a prism")),
+                })
+                .collect()))
+        }
+        _ => Place::Geometry(try!(Geometry::from_object(object))),
+    };
+    Ok(Some(place))
+}
+
+impl FromObject for JsonFgFeature {
+    fn from_object(object: &JsonObject) -> Result<Self, Error> {
+        Ok(JsonFgFeature {
+            feature: try!(Feature::from_object(object)),
+            conforms_to: try!(get_strings(object, "conformsTo")),
+            feature_type: try!(get_strings(object, "featureType")),
+            time: try!(object.get("time").map_or(Ok(None), read_time)),
+            place: try!(object.get("place").map_or(Ok(None), read_place)),
+            coord_ref_sys: try!(get_coord_ref_sys(object)),
+        })
+    }
+}
+
+impl FromObject for JsonFgFeatureCollection {
+    fn from_object(object: &JsonObject) -> Result<Self, Error> {
+        let features = expect_array!(expect_property!(object, "features", "Missing 'features'"));
+        Ok(JsonFgFeatureCollection {
+            bbox: try!(util::get_bbox(object)),
+            crs: try!(util::get_crs(object)),
+            features: try!(features.iter()
+                .map(|feature| JsonFgFeature::from_object(expect_object!(feature)))
+                .collect()),
+            conforms_to: try!(get_strings(object, "conformsTo")),
+            feature_type: try!(get_strings(object, "featureType")),
+            coord_ref_sys: try!(get_coord_ref_sys(object)),
+        })
+    }
+}
+
+impl FromObject for JsonFg {
+    fn from_object(object: &JsonObject) -> Result<Self, Error> {
+        match expect_type!(object) {
+            "Feature" => JsonFgFeature::from_object(object).map(JsonFg::Feature),
+            "FeatureCollection" => {
+                JsonFgFeatureCollection::from_object(object).map(JsonFg::FeatureCollection)
+            }
+            _ => Err(Error::GeoJsonUnknownType),
+        }
+    }
+}
+
+
+// Writing
+
+fn strings_value(strings: &[String]) -> JsonValue {
+    match strings {
+        [single] => JsonValue::from(single.clone()),
+        _ => JsonValue::from(strings.to_vec()),
+    }
+}
+
+fn insert_common(map: &mut JsonObject,
+                 conforms_to: &[String],
+                 feature_type: &[String],
+                 coord_ref_sys: &Option<CoordRefSys>) {
+    if !conforms_to.is_empty() {
+        map.insert(String::from("conformsTo"), JsonValue::from(conforms_to.to_vec()));
+    }
+    if !feature_type.is_empty() {
+        map.insert(String::from("featureType"), strings_value(feature_type));
+    }
+    if let Some(ref coord_ref_sys) = *coord_ref_sys {
+        map.insert(String::from("coordRefSys"), JsonValue::from(coord_ref_sys));
+    }
+}
+
+impl<'a> From<&'a Time> for JsonValue {
+    fn from(time: &'a Time) -> JsonValue {
+        let mut map = JsonObject::new();
+        if let Some(ref date) = time.date {
+            map.insert(String::from("date"), json!(date));
+        }
+        if let Some(ref timestamp) = time.timestamp {
+            map.insert(String::from("timestamp"), json!(timestamp));
+        }
+        if let Some((ref start, ref end)) = time.interval {
+            map.insert(String::from("interval"), json!([start, end]));
+        }
+        JsonValue::Object(map)
+    }
+}
+
+impl<'a> From<&'a CoordRefSys> for JsonValue {
+    fn from(coord_ref_sys: &'a CoordRefSys) -> JsonValue {
+        match *coord_ref_sys {
+            CoordRefSys::Uri(ref uri) => json!(uri),
+            CoordRefSys::Reference { ref href, epoch } => {
+                let mut map = JsonObject::new();
+                map.insert(String::from("type"), json!("Reference"));
+                map.insert(String::from("href"), json!(href));
+                if let Some(epoch) = epoch {
+                    map.insert(String::from("epoch"), json!(epoch));
+                }
+                JsonValue::Object(map)
+            }
+            CoordRefSys::Compound(ref parts) => {
+                JsonValue::Array(parts.iter().map(JsonValue::from).collect())
+            }
+        }
+    }
+}
+
+impl<'a> From<&'a Prism> for JsonValue {
+    fn from(prism: &'a Prism) -> JsonValue {
+        let mut map = JsonObject::new();
+        map.insert(String::from("type"), json!("Prism"));
+        map.insert(String::from("base"), serde_json::to_value(&prism.base).unwrap());
+        if let Some(lower) = prism.lower {
+            map.insert(String::from("lower"), json!(lower));
+        }
+        map.insert(String::from("upper"), json!(prism.upper));
+        JsonValue::Object(map)
+    }
+}
+
+impl<'a> From<&'a Place> for JsonValue {
+    fn from(place: &'a Place) -> JsonValue {
+        match *place {
+            Place::Geometry(ref geometry) => serde_json::to_value(geometry).unwrap(),
+            Place::Polyhedron(ref shells) => {
+                json!({"type": "Polyhedron", "coordinates": shells})
+            }
+            Place::MultiPolyhedron(ref polyhedra) => {
+                json!({"type": "MultiPolyhedron", "coordinates": polyhedra})
+            }
+            Place::Prism(ref prism) => JsonValue::from(prism),
+            Place::MultiPrism(ref prisms) => {
+                let prisms: Vec<JsonValue> = prisms.iter().map(JsonValue::from).collect();
+                json!({"type": "MultiPrism", "prisms": prisms})
+            }
+        }
+    }
+}
+
+impl<'a> From<&'a JsonFgFeature> for JsonObject {
+    fn from(feature: &'a JsonFgFeature) -> JsonObject {
+        let mut map = JsonObject::from(&feature.feature);
+        map.entry(String::from("properties")).or_insert(JsonValue::Null);
+        insert_common(&mut map,
+                      &feature.conforms_to,
+                      &feature.feature_type,
+                      &feature.coord_ref_sys);
+        map.insert(String::from("time"),
+                   feature.time.as_ref().map_or(JsonValue::Null, JsonValue::from));
+        map.insert(String::from("place"),
+                   feature.place.as_ref().map_or(JsonValue::Null, JsonValue::from));
+        map
+    }
+}
+
+impl<'a> From<&'a JsonFgFeatureCollection> for JsonObject {
+    fn from(fc: &'a JsonFgFeatureCollection) -> JsonObject {
+        let mut map = JsonObject::new();
+        map.insert(String::from("type"), json!("FeatureCollection"));
+        let features: Vec<JsonValue> = fc.features
+            .iter()
+            .map(|feature| JsonValue::Object(JsonObject::from(feature)))
+            .collect();
+        map.insert(String::from("features"), JsonValue::Array(features));
+        if let Some(ref crs) = fc.crs {
+            map.insert(String::from("crs"), serde_json::to_value(crs).unwrap());
+        }
+        if let Some(ref bbox) = fc.bbox {
+            map.insert(String::from("bbox"), serde_json::to_value(bbox).unwrap());
+        }
+        insert_common(&mut map, &fc.conforms_to, &fc.feature_type, &fc.coord_ref_sys);
+        map
+    }
+}
+
+impl<'a> From<&'a JsonFg> for JsonObject {
+    fn from(jsonfg: &'a JsonFg) -> JsonObject {
+        match *jsonfg {
+            JsonFg::Feature(ref feature) => feature.into(),
+            JsonFg::FeatureCollection(ref fc) => fc.into(),
+        }
+    }
+}
+
+impl Serialize for JsonFg {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where S: Serializer
+    {
+        JsonObject::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for JsonFg {
+    fn deserialize<D>(deserializer: D) -> Result<JsonFg, D::Error>
+        where D: Deserializer<'de>
+    {
+        use std::error::Error as StdError;
+        use serde::de::Error as SerdeError;
+
+        let val = try!(JsonObject::deserialize(deserializer));
+
+        JsonFg::from_object(&val).map_err(|e| D::Error::custom(e.description()))
+    }
+}
+
+impl FromStr for JsonFg {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match serde_json::from_str::<JsonValue>(s) {
+            Ok(JsonValue::Object(object)) => JsonFg::from_object(&object),
+            _ => Err(Error::MalformedJson),
+        }
+    }
+}
+
+impl fmt::Display for JsonFg {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        serde_json::to_string(self)
+            .map_err(|_| fmt::Error)
+            .and_then(|s| f.write_str(&s))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CONFORMANCE_CORE, CoordRefSys, JsonFg, JsonFgFeatureCollection, Place, Prism,
+                Time};
+    use {CrsId, Error, Feature, FeatureCollection, GeoJson, Geometry, Value};
+
+    const COLLECTION: &'static str = r#"{
+        "type": "FeatureCollection",
+        "conformsTo": ["http://www.opengis.net/spec/json-fg-1/0.2/conf/core",
+                       "http://www.opengis.net/spec/json-fg-1/0.2/conf/polyhedra"],
+        "featureType": "Building",
+        "coordRefSys": "http://www.opengis.net/def/crs/EPSG/0/5555",
+        "features": [{
+            "type": "Feature",
+            "id": "DENW19AL0000giv5BL",
+            "time": {"interval": ["2014-04-24", ".."]},
+            "place": {
+                "type": "Polyhedron",
+                "coordinates": [[
+                    [[[0, 0, 0], [0, 1, 0], [1, 1, 0], [1, 0, 0], [0, 0, 0]]],
+                    [[[0, 0, 0], [1, 0, 0], [0, 0, 1], [0, 0, 0]]]
+                ]]
+            },
+            "geometry": {"type": "Point", "coordinates": [6.96, 50.94]},
+            "properties": {"height": 1.0}
+        }, {
+            "type": "Feature",
+            "featureType": ["Building", "Monument"],
+            "coordRefSys": [
+                {"type": "Reference", "href": "[EPSG:25832]", "epoch": 2016.47},
+                "http://www.opengis.net/def/crs/EPSG/0/7837"
+            ],
+            "time": null,
+            "place": {
+                "type": "Prism",
+                "base": {"type": "Point", "coordinates": [356000.0, 5645000.0]},
+                "upper": 30.5
+            },
+            "geometry": null,
+            "properties": null
+        }]
+    }"#;
+
+    fn collection() -> JsonFgFeatureCollection {
+        match COLLECTION.parse().unwrap() {
+            JsonFg::FeatureCollection(fc) => fc,
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn read() {
+        let fc = collection();
+        assert_eq!(fc.conforms_to.len(), 2);
+        assert_eq!(fc.feature_type, vec!["Building"]);
+        assert_eq!(fc.coord_ref_sys.as_ref().and_then(CoordRefSys::id),
+                   Some(CrsId::new("EPSG", "5555")));
+
+        let first = &fc.features[0];
+        assert!(first.conforms_to.is_empty());
+        assert!(first.feature_type.is_empty());
+        assert_eq!(first.time,
+                   Some(Time {
+                       interval: Some((String::from("2014-04-24"), String::from(".."))),
+                       ..Time::default()
+                   }));
+        match first.place {
+            Some(Place::Polyhedron(ref shells)) => {
+                assert_eq!(shells.len(), 1);
+                assert_eq!(shells[0].len(), 2);
+                assert_eq!(shells[0][1][0][2], vec![0.0, 0.0, 1.0]);
+            }
+            ref place => panic!("unexpected place {:?}", place),
+        }
+        assert_eq!(first.feature.geometry,
+                   Some(Geometry::new(Value::Point(vec![6.96, 50.94]))));
+
+        let second = &fc.features[1];
+        assert_eq!(second.feature_type, vec!["Building", "Monument"]);
+        assert_eq!(second.time, None);
+        let parts = match second.coord_ref_sys {
+            Some(CoordRefSys::Compound(ref parts)) => parts,
+            ref crs => panic!("unexpected coordRefSys {:?}", crs),
+        };
+        assert_eq!(parts[0],
+                   CoordRefSys::Reference {
+                       href: String::from("[EPSG:25832]"),
+                       epoch: Some(2016.47),
+                   });
+        assert_eq!(parts[0].id(), Some(CrsId::new("EPSG", "25832")));
+        assert_eq!(parts[1].id(), Some(CrsId::new("EPSG", "7837")));
+        assert_eq!(second.place,
+                   Some(Place::Prism(Prism {
+                       base: Geometry::new(Value::Point(vec![356000.0, 5645000.0])),
+                       lower: None,
+                       upper: 30.5,
+                   })));
+    }
+
+    #[test]
+    fn round_trip() {
+        let jsonfg = JsonFg::from(collection());
+        let text = jsonfg.to_string();
+        assert_eq!(text.parse::<JsonFg>().unwrap(), jsonfg);
+
+        // Readable as plain GeoJSON, ignoring the JSON-FG members
+        let fc = match text.parse::<GeoJson>().unwrap() {
+            GeoJson::FeatureCollection(fc) => fc,
+            _ => unreachable!(),
+        };
+        assert_eq!(fc, FeatureCollection::from(collection()));
+    }
+
+    #[test]
+    fn from_geojson() {
+        let feature = Feature {
+            bbox: None,
+            crs: None,
+            geometry: Some(Geometry::new(Value::Point(vec![1.0, 2.0]))),
+            id: None,
+            properties: None,
+        };
+        let jsonfg = JsonFg::Feature(feature.clone().into());
+        let value: ::json::JsonValue = ::serde_json::to_value(&jsonfg).unwrap();
+        assert_eq!(value["conformsTo"], json!([CONFORMANCE_CORE]));
+        assert_eq!(value["time"], json!(null));
+        assert_eq!(value["place"], json!(null));
+        assert!(value.get("featureType").is_none());
+
+        let srid: CoordRefSys = CrsId::new("EPSG", "3857").into();
+        assert_eq!(srid,
+                   CoordRefSys::Uri(String::from("http://www.opengis.net/def/crs/EPSG/0/3857")));
+    }
+
+    #[test]
+    fn malformed() {
+        let invalid = |place: &str| {
+            format!(r#"{{"type": "Feature", "geometry": null, "properties": null,
+                         "time": null, "place": {}}}"#,
+                    place)
+                .parse::<JsonFg>()
+        };
+        match invalid(r#"{"type": "Prism", "base": {"type": "Point", "coordinates": [0, 0]}}"#) {
+            Err(Error::MalformedJsonFg(..)) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        match invalid(r#"{"type": "Polyhedron", "coordinates": [1, 2]}"#) {
+            Err(Error::MalformedJsonFg(..)) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(invalid(r#"{"type": "Sphere", "coordinates": []}"#),
+                   Err(Error::GeometryUnknownType));
+        assert_eq!(r#"{"type": "Point", "coordinates": [0, 0]}"#.parse::<JsonFg>(),
+                   Err(Error::GeoJsonUnknownType));
+    }
+}
@@ -0,0 +1,734 @@
+// Copyright 2015 The GeoRust Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! KML reading and writing
+//!
+//! Each `Placemark` becomes a feature, with its `id` attribute as the feature's id and its
+//! `name`, `description` and `ExtendedData` as properties. `ExtendedData` values are kept as
+//! strings. The name of the innermost `Folder` holding a placemark becomes its `folder`
+//! property, and the writer groups features into folders by that property.
+//!
+//! A `MultiGeometry` holding only points, only line strings or only polygons becomes a
+//! `MultiPoint`, `MultiLineString` or `MultiPolygon`, and any other `MultiGeometry` a
+//! `GeometryCollection`. Positions with three ordinates are written with an altitude.
+//!
+//! Line, polygon and icon colors and line widths are mapped to and from the
+//! [simplestyle](https://github.com/mapbox/simplestyle-spec) properties `stroke`,
+//! `stroke-opacity`, `stroke-width`, `fill`, `fill-opacity` and `marker-color`, both for
+//! styles given in a placemark and for shared styles it refers to by `styleUrl`.
+
+use std::collections::HashMap;
+
+use json::{JsonObject, JsonValue};
+use serde_json;
+use xml;
+
+use {Color, Error, Feature, FeatureCollection, Geometry, LineStringType, PolygonType, Position,
+     Value};
+
+
+const NAMESPACE: &'static str = "http://www.opengis.net/kml/2.2";
+
+/// Properties stored in KML elements rather than in `ExtendedData`
+const RESERVED_PROPERTIES: &'static [&'static str] = &["name",
+                                                        "description",
+                                                        "folder",
+                                                        "stroke",
+                                                        "stroke-opacity",
+                                                        "stroke-width",
+                                                        "fill",
+                                                        "fill-opacity",
+                                                        "marker-color"];
+
+fn malformed(message: &str) -> Error {
+    Error::MalformedKml(String::from(message))
+}
+
+
+// Reading
+
+fn read_coordinates(element: &xml::Element) -> Result<LineStringType, Error> {
+    let text = match element.child("coordinates") {
+        Some(coordinates) => coordinates.text(),
+        None => return Err(malformed(&format!("missing coordinates in '{}'", element.name))),
+    };
+    // Tuples are separated by whitespace, but some writers also put spaces after commas
+    let mut tuples: Vec<String> = vec![];
+    for token in text.split_whitespace() {
+        match tuples.last_mut() {
+            Some(ref mut last) if last.ends_with(',') || token.starts_with(',') => {
+                last.push_str(token)
+            }
+            _ => tuples.push(String::from(token)),
+        }
+    }
+    tuples.iter()
+        .map(|tuple| {
+            let position = tuple.split(',')
+                .map(|ordinate| ordinate.parse::<f64>().ok().filter(|o| o.is_finite()))
+                .collect::<Option<Position>>();
+            match position {
+                Some(ref position) if position.len() >= 2 => Ok(position.clone()),
+                _ => Err(malformed(&format!("invalid coordinates '{}'", tuple))),
+            }
+        })
+        .collect()
+}
+
+fn read_ring(boundary: &xml::Element) -> Result<LineStringType, Error> {
+    match boundary.child("LinearRing") {
+        Some(ring) => read_coordinates(ring),
+        None => Err(malformed("missing LinearRing in polygon boundary")),
+    }
+}
+
+fn read_polygon(element: &xml::Element) -> Result<PolygonType, Error> {
+    let mut rings = vec![];
+    if let Some(outer) = element.child("outerBoundaryIs") {
+        rings.push(try!(read_ring(outer)));
+    }
+    for inner in element.children_named("innerBoundaryIs") {
+        // KML 2.2 allows several rings per innerBoundaryIs, earlier versions only one
+        for ring in inner.children_named("LinearRing") {
+            rings.push(try!(read_coordinates(ring)));
+        }
+    }
+    Ok(rings)
+}
+
+/// Reads a geometry element, or returns `None` for elements that are not geometries.
+fn read_geometry(element: &xml::Element) -> Result<Option<Value>, Error> {
+    Ok(Some(match &element.name as &str {
+        "Point" => {
+            match try!(read_coordinates(element)).into_iter().next() {
+                Some(position) => Value::Point(position),
+                None => return Err(malformed("empty Point")),
+            }
+        }
+        "LineString" | "LinearRing" => Value::LineString(try!(read_coordinates(element))),
+        "Polygon" => Value::Polygon(try!(read_polygon(element))),
+        "MultiGeometry" => {
+            let mut values = vec![];
+            for child in element.elements() {
+                values.extend(try!(read_geometry(child)));
+            }
+            multi_value(values)
+        }
+        _ => return Ok(None),
+    }))
+}
+
+/// Returns the most specific value for the members of a `MultiGeometry`.
+fn multi_value(values: Vec<Value>) -> Value {
+    let all = |f: fn(&Value) -> bool| !values.is_empty() && values.iter().all(f);
+    if all(|v| if let Value::Point(..) = *v { true } else { false }) {
+        Value::MultiPoint(values.into_iter()
+            .filter_map(|v| if let Value::Point(p) = v { Some(p) } else { None })
+            .collect())
+    } else if all(|v| if let Value::LineString(..) = *v { true } else { false }) {
+        Value::MultiLineString(values.into_iter()
+            .filter_map(|v| if let Value::LineString(l) = v { Some(l) } else { None })
+            .collect())
+    } else if all(|v| if let Value::Polygon(..) = *v { true } else { false }) {
+        Value::MultiPolygon(values.into_iter()
+            .filter_map(|v| if let Value::Polygon(p) = v { Some(p) } else { None })
+            .collect())
+    } else {
+        Value::GeometryCollection(values.into_iter().map(Geometry::new).collect())
+    }
+}
+
+/// Parses a KML `aabbggrr` color into a simplestyle `#rrggbb` color and an opacity.
+fn read_color(color: &str) -> Option<(String, f64)> {
+    let color = color.trim().trim_start_matches('#');
+    if color.len() != 8 || !color.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&color[i..i + 2], 16).unwrap();
+    let rgb = Color {
+        red: channel(6),
+        green: channel(4),
+        blue: channel(2),
+    };
+    Some((rgb.to_string(), (channel(0) as f64 / 255.0 * 100.0).round() / 100.0))
+}
+
+/// Adds the simplestyle properties matching a `Style` element.
+fn read_style(style: &xml::Element, properties: &mut JsonObject) {
+    let mut color = |element: Option<&xml::Element>, key: &str, opacity_key: Option<&str>| {
+        let color = element.and_then(|e| e.child_text("color")).and_then(|c| read_color(&c));
+        if let Some((rgb, opacity)) = color {
+            properties.insert(String::from(key), JsonValue::String(rgb));
+            if let Some(opacity_key) = opacity_key {
+                if opacity < 1.0 {
+                    properties.insert(String::from(opacity_key), json!(opacity));
+                }
+            }
+        }
+    };
+    let line = style.child("LineStyle");
+    color(line, "stroke", Some("stroke-opacity"));
+    color(style.child("PolyStyle"), "fill", Some("fill-opacity"));
+    color(style.child("IconStyle"), "marker-color", None);
+    let width = line.and_then(|l| l.child_text("width")).and_then(|w| w.parse::<f64>().ok());
+    if let Some(width) = width.and_then(serde_json::Number::from_f64) {
+        properties.insert(String::from("stroke-width"), JsonValue::Number(width));
+    }
+}
+
+/// Shared styles of a document, by id
+struct Styles<'a> {
+    styles: HashMap<&'a str, &'a xml::Element>,
+    maps: HashMap<&'a str, String>,
+}
+
+impl<'a> Styles<'a> {
+    fn collect(&mut self, element: &'a xml::Element) {
+        for child in element.elements() {
+            match (&child.name as &str, child.attribute("id")) {
+                ("Style", Some(id)) => {
+                    self.styles.insert(id, child);
+                }
+                ("StyleMap", Some(id)) => {
+                    let normal = child.children_named("Pair")
+                        .find(|pair| pair.child_text("key").map_or(false, |k| k == "normal"))
+                        .and_then(|pair| pair.child_text("styleUrl"));
+                    if let Some(normal) = normal {
+                        self.maps.insert(id, normal);
+                    }
+                }
+                ("Placemark", _) => {}
+                _ => self.collect(child),
+            }
+        }
+    }
+
+    /// Resolves a local `#id` style URL, following style maps.
+    fn get(&self, url: &str) -> Option<&'a xml::Element> {
+        let mut url = url.trim();
+        for _ in 0..=self.maps.len() {
+            if !url.starts_with('#') {
+                return None;
+            }
+            let id = &url[1..];
+            if let Some(style) = self.styles.get(id) {
+                return Some(style);
+            }
+            match self.maps.get(id) {
+                Some(normal) => url = normal,
+                None => return None,
+            }
+        }
+        None
+    }
+}
+
+fn read_placemark(placemark: &xml::Element,
+                  folder: Option<&str>,
+                  styles: &Styles)
+                  -> Result<Feature, Error> {
+    let mut properties = JsonObject::new();
+    for name in &["name", "description"] {
+        if let Some(text) = placemark.child_text(name) {
+            properties.insert(String::from(*name), JsonValue::String(text));
+        }
+    }
+    if let Some(folder) = folder {
+        properties.insert(String::from("folder"), JsonValue::String(String::from(folder)));
+    }
+    if let Some(style) = placemark.child_text("styleUrl").and_then(|url| styles.get(&url)) {
+        read_style(style, &mut properties);
+    }
+    if let Some(style) = placemark.child("Style") {
+        read_style(style, &mut properties);
+    }
+    if let Some(data) = placemark.child("ExtendedData") {
+        let simple_data = data.children_named("SchemaData")
+            .flat_map(|schema_data| schema_data.children_named("SimpleData"))
+            .map(|simple_data| (simple_data, simple_data.text()));
+        let data = data.children_named("Data")
+            .map(|data| (data, data.child("value").map(|v| v.text()).unwrap_or_default()));
+        for (element, value) in data.chain(simple_data) {
+            if let Some(name) = element.attribute("name") {
+                properties.insert(String::from(name), JsonValue::String(value));
+            }
+        }
+    }
+
+    let mut value = None;
+    for child in placemark.elements() {
+        value = try!(read_geometry(child));
+        if value.is_some() {
+            break;
+        }
+    }
+    Ok(Feature {
+        bbox: None,
+        crs: None,
+        geometry: value.map(Geometry::new),
+        id: placemark.attribute("id").map(|id| JsonValue::String(String::from(id))),
+        properties: Some(properties),
+    })
+}
+
+fn read_container(container: &xml::Element,
+                  folder: Option<&str>,
+                  styles: &Styles,
+                  features: &mut Vec<Feature>)
+                  -> Result<(), Error> {
+    for child in container.elements() {
+        match &child.name as &str {
+            "Placemark" => features.push(try!(read_placemark(child, folder, styles))),
+            "Folder" => {
+                let name = child.child_text("name");
+                let name = name.as_ref().map(|name| name as &str).or(folder);
+                try!(read_container(child, name, styles, features));
+            }
+            "Document" => try!(read_container(child, folder, styles, features)),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+
+// Writing
+
+fn write_position(kml: &mut String, position: &Position) {
+    let ordinates = position.iter().take(3).map(|o| o.to_string()).collect::<Vec<_>>();
+    kml.push_str(&ordinates.join(","));
+}
+
+fn write_coordinates(writer: &mut xml::Writer, positions: &[Position]) {
+    let mut coordinates = String::new();
+    for (i, position) in positions.iter().filter(|p| p.len() >= 2).enumerate() {
+        if i > 0 {
+            coordinates.push(' ');
+        }
+        write_position(&mut coordinates, position);
+    }
+    writer.text_element("coordinates", &coordinates);
+}
+
+fn write_polygon(writer: &mut xml::Writer, polygon: &PolygonType) {
+    writer.start("Polygon", &[]);
+    for (i, ring) in polygon.iter().enumerate() {
+        let boundary = if i == 0 { "outerBoundaryIs" } else { "innerBoundaryIs" };
+        writer.start(boundary, &[]);
+        writer.start("LinearRing", &[]);
+        write_coordinates(writer, ring);
+        writer.end("LinearRing");
+        writer.end(boundary);
+    }
+    writer.end("Polygon");
+}
+
+/// Writes the geometry element of `value`, omitting points without a position, which KML
+/// cannot represent.
+fn write_value(writer: &mut xml::Writer, value: &Value) {
+    match *value {
+        Value::Point(ref position) if position.len() < 2 => {}
+        Value::Point(ref position) => {
+            writer.start("Point", &[]);
+            write_coordinates(writer, &[position.clone()]);
+            writer.end("Point");
+        }
+        Value::LineString(ref line) => {
+            writer.start("LineString", &[]);
+            write_coordinates(writer, line);
+            writer.end("LineString");
+        }
+        Value::Polygon(ref polygon) => write_polygon(writer, polygon),
+        Value::MultiPoint(ref positions) => {
+            writer.start("MultiGeometry", &[]);
+            for position in positions {
+                write_value(writer, &Value::Point(position.clone()));
+            }
+            writer.end("MultiGeometry");
+        }
+        Value::MultiLineString(ref lines) => {
+            writer.start("MultiGeometry", &[]);
+            for line in lines {
+                write_value(writer, &Value::LineString(line.clone()));
+            }
+            writer.end("MultiGeometry");
+        }
+        Value::MultiPolygon(ref polygons) => {
+            writer.start("MultiGeometry", &[]);
+            for polygon in polygons {
+                write_polygon(writer, polygon);
+            }
+            writer.end("MultiGeometry");
+        }
+        Value::GeometryCollection(ref geometries) => {
+            writer.start("MultiGeometry", &[]);
+            for geometry in geometries {
+                write_value(writer, &geometry.value);
+            }
+            writer.end("MultiGeometry");
+        }
+    }
+}
+
+/// Converts a simplestyle `#rrggbb` or `#rgb` color and an opacity into a KML `aabbggrr` color.
+fn write_color(color: &str, opacity: Option<f64>) -> Option<String> {
+    let color = match color.parse::<Color>() {
+        Ok(color) => color,
+        Err(..) => return None,
+    };
+    let alpha = (opacity.unwrap_or(1.0).max(0.0).min(1.0) * 255.0).round() as u8;
+    Some(format!("{:02x}{:02x}{:02x}{:02x}", alpha, color.blue, color.green, color.red))
+}
+
+fn write_style(writer: &mut xml::Writer, properties: &JsonObject) {
+    let color = |key: &str, opacity_key: Option<&str>| {
+        let opacity = opacity_key.and_then(|k| properties.get(k)).and_then(JsonValue::as_f64);
+        properties.get(key).and_then(JsonValue::as_str).and_then(|c| write_color(c, opacity))
+    };
+    let marker_color = color("marker-color", None);
+    let stroke = color("stroke", Some("stroke-opacity"));
+    let stroke_width = properties.get("stroke-width").and_then(JsonValue::as_f64);
+    let fill = color("fill", Some("fill-opacity"));
+    if marker_color.is_none() && stroke.is_none() && stroke_width.is_none() && fill.is_none() {
+        return;
+    }
+
+    writer.start("Style", &[]);
+    if let Some(color) = marker_color {
+        writer.start("IconStyle", &[]);
+        writer.text_element("color", &color);
+        writer.end("IconStyle");
+    }
+    if stroke.is_some() || stroke_width.is_some() {
+        writer.start("LineStyle", &[]);
+        if let Some(color) = stroke {
+            writer.text_element("color", &color);
+        }
+        if let Some(width) = stroke_width {
+            writer.text_element("width", &width.to_string());
+        }
+        writer.end("LineStyle");
+    }
+    if let Some(color) = fill {
+        writer.start("PolyStyle", &[]);
+        writer.text_element("color", &color);
+        writer.end("PolyStyle");
+    }
+    writer.end("Style");
+}
+
+fn write_placemark(writer: &mut xml::Writer, feature: &Feature) {
+    let id = match feature.id {
+        Some(JsonValue::String(ref id)) => Some(id.clone()),
+        Some(ref id @ JsonValue::Number(..)) => Some(id.to_string()),
+        _ => None,
+    };
+    match id {
+        Some(ref id) => writer.start("Placemark", &[("id", id)]),
+        None => writer.start("Placemark", &[]),
+    }
+    let empty = JsonObject::new();
+    let properties = feature.properties.as_ref().unwrap_or(&empty);
+    for name in &["name", "description"] {
+        if let Some(text) = xml::property(Some(properties), name) {
+            writer.text_element(name, &text);
+        }
+    }
+    write_style(writer, properties);
+
+    let data = properties.keys()
+        .filter(|key| !RESERVED_PROPERTIES.contains(&(key as &str)))
+        .filter_map(|key| xml::property(Some(properties), key).map(|value| (key, value)))
+        .collect::<Vec<_>>();
+    if !data.is_empty() {
+        writer.start("ExtendedData", &[]);
+        for (key, value) in data {
+            writer.start("Data", &[("name", key)]);
+            writer.text_element("value", &value);
+            writer.end("Data");
+        }
+        writer.end("ExtendedData");
+    }
+
+    if let Some(ref geometry) = feature.geometry {
+        write_value(writer, &geometry.value);
+    }
+    writer.end("Placemark");
+}
+
+/// Reads the placemarks of a KML document.
+///
+/// # Examples
+///
+/// ```
+/// use geojson::Value;
+///
+/// let kml = r#"<?xml version="1.0" encoding="UTF-8"?>
+/// <kml xmlns="http://www.opengis.net/kml/2.2">
+///   <Document>
+///     <Folder>
+///       <name>Landmarks</name>
+///       <Placemark id="eiffel">
+///         <name>Eiffel Tower</name>
+///         <ExtendedData>
+///           <Data name="height"><value>300</value></Data>
+///         </ExtendedData>
+///         <Point><coordinates>2.2945,48.8584,0</coordinates></Point>
+///       </Placemark>
+///     </Folder>
+///   </Document>
+/// </kml>"#;
+///
+/// let fc = geojson::kml::read(kml).unwrap();
+/// let feature = &fc.features[0];
+/// assert_eq!(feature.geometry.as_ref().unwrap().value,
+///            Value::Point(vec![2.2945, 48.8584, 0.0]));
+/// assert_eq!(feature.property::<String>("folder").unwrap(), "Landmarks");
+/// assert_eq!(feature.property::<String>("height").unwrap(), "300");
+/// ```
+pub fn read(kml: &str) -> Result<FeatureCollection, Error> {
+    let root = try!(xml::parse(kml, Error::MalformedKml));
+    if root.name != "kml" {
+        return Err(malformed("expected a 'kml' element"));
+    }
+    let mut styles = Styles {
+        styles: HashMap::new(),
+        maps: HashMap::new(),
+    };
+    styles.collect(&root);
+    let mut features = vec![];
+    try!(read_container(&root, None, &styles, &mut features));
+    Ok(FeatureCollection {
+        bbox: None,
+        crs: None,
+        features: features,
+    })
+}
+
+/// Writes the features of `fc` as a KML document.
+///
+/// Features with a `folder` property are written in a `Folder` of that name, in order of
+/// first appearance, after the features without one.
+pub fn write(fc: &FeatureCollection) -> String {
+    let mut folders: Vec<(String, Vec<&Feature>)> = vec![];
+    let mut writer = xml::Writer::new();
+    writer.start("kml", &[("xmlns", NAMESPACE)]);
+    writer.start("Document", &[]);
+    for feature in &fc.features {
+        let folder = xml::property(feature.properties.as_ref(), "folder");
+        match folder {
+            Some(folder) => {
+                match folders.iter().position(|f| f.0 == folder) {
+                    Some(i) => folders[i].1.push(feature),
+                    None => folders.push((folder, vec![feature])),
+                }
+            }
+            None => write_placemark(&mut writer, feature),
+        }
+    }
+    for (name, features) in folders {
+        writer.start("Folder", &[]);
+        writer.text_element("name", &name);
+        for feature in features {
+            write_placemark(&mut writer, feature);
+        }
+        writer.end("Folder");
+    }
+    writer.end("Document");
+    writer.end("kml");
+    writer.xml
+}
+
+
+#[cfg(test)]
+mod tests {
+    use {Error, Feature, FeatureCollection, Geometry, Value};
+    use super::{read, read_color, write, write_color};
+
+    const KML: &'static str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<kml xmlns="http://www.opengis.net/kml/2.2" xmlns:gx="http://www.google.com/kml/ext/2.2">
+  <Document>
+    <name>Survey</name>
+    <Style id="red">
+      <LineStyle><color>ff0000ff</color><width>3</width></LineStyle>
+    </Style>
+    <StyleMap id="red-map">
+      <Pair><key>normal</key><styleUrl>#red</styleUrl></Pair>
+      <Pair><key>highlight</key><styleUrl>#missing</styleUrl></Pair>
+    </StyleMap>
+    <Placemark>
+      <name>Path</name>
+      <styleUrl>#red-map</styleUrl>
+      <LineString>
+        <coordinates>
+          1,2 3,4
+          5, 6
+        </coordinates>
+      </LineString>
+    </Placemark>
+    <Folder>
+      <name>Areas</name>
+      <Folder>
+        <name>Parks</name>
+        <Placemark id="park">
+          <description><![CDATA[<b>Open</b> daily]]></description>
+          <Style><PolyStyle><color>8000ff00</color></PolyStyle></Style>
+          <ExtendedData>
+            <SchemaData schemaUrl="#schema"><SimpleData name="area">12.5</SimpleData></SchemaData>
+          </ExtendedData>
+          <Polygon>
+            <outerBoundaryIs><LinearRing>
+              <coordinates>0,0,1 4,0,1 4,4,1 0,0,1</coordinates>
+            </LinearRing></outerBoundaryIs>
+            <innerBoundaryIs><LinearRing>
+              <coordinates>1,1 2,1 2,2 1,1</coordinates>
+            </LinearRing></innerBoundaryIs>
+          </Polygon>
+        </Placemark>
+      </Folder>
+      <Placemark>
+        <MultiGeometry>
+          <Point><coordinates>1,2</coordinates></Point>
+          <LineString><coordinates>1,2 3,4</coordinates></LineString>
+        </MultiGeometry>
+      </Placemark>
+    </Folder>
+  </Document>
+</kml>
+"##;
+
+    #[test]
+    fn read_features() {
+        let fc = read(KML).unwrap();
+        let values = fc.features
+            .iter()
+            .map(|f| f.geometry.as_ref().unwrap().value.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(values,
+                   vec![Value::LineString(vec![vec![1.0, 2.0], vec![3.0, 4.0],
+                                               vec![5.0, 6.0]]),
+                        Value::Polygon(vec![vec![vec![0.0, 0.0, 1.0], vec![4.0, 0.0, 1.0],
+                                                 vec![4.0, 4.0, 1.0], vec![0.0, 0.0, 1.0]],
+                                            vec![vec![1.0, 1.0], vec![2.0, 1.0],
+                                                 vec![2.0, 2.0], vec![1.0, 1.0]]]),
+                        Value::GeometryCollection(vec![
+                            Geometry::new(Value::Point(vec![1.0, 2.0])),
+                            Geometry::new(Value::LineString(vec![vec![1.0, 2.0],
+                                                                 vec![3.0, 4.0]])),
+                        ])]);
+        let properties = fc.features
+            .iter()
+            .map(|f| ::serde_json::to_value(f.properties.as_ref().unwrap()).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(properties,
+                   vec![json!({"name": "Path", "stroke": "#ff0000", "stroke-width": 3.0}),
+                        json!({"description": "<b>Open</b> daily", "folder": "Parks",
+                               "fill": "#00ff00", "fill-opacity": 0.5, "area": "12.5"}),
+                        json!({"folder": "Areas"})]);
+        assert_eq!(fc.features[1].id, Some(json!("park")));
+    }
+
+    #[test]
+    fn round_trip() {
+        let fc = read(KML).unwrap();
+        let kml = write(&fc);
+        let mut read_back = read(&kml).unwrap().features;
+        // Folders are written after the features outside of them, and not nested
+        read_back.sort_by_key(|f| f.property::<String>("folder"));
+        let mut expected = fc.features.clone();
+        expected.sort_by_key(|f| f.property::<String>("folder"));
+        assert_eq!(read_back, expected);
+    }
+
+    #[test]
+    fn write_features() {
+        let fc = FeatureCollection::builder()
+            .feature(Feature::builder()
+                .geometry(Value::MultiPoint(vec![vec![1.0, 2.0], vec![3.0, 4.0, 5.0]]))
+                .id(7)
+                .property("name", "A & B")
+                .property("marker-color", "#f80")
+                .property("count", 2)
+                .property("missing", json!(null))
+                .build())
+            .build();
+        assert_eq!(write(&fc),
+                   r#"<?xml version="1.0" encoding="UTF-8"?>
+<kml xmlns="http://www.opengis.net/kml/2.2">
+  <Document>
+    <Placemark id="7">
+      <name>A &amp; B</name>
+      <Style>
+        <IconStyle>
+          <color>ff0088ff</color>
+        </IconStyle>
+      </Style>
+      <ExtendedData>
+        <Data name="count">
+          <value>2</value>
+        </Data>
+      </ExtendedData>
+      <MultiGeometry>
+        <Point>
+          <coordinates>1,2</coordinates>
+        </Point>
+        <Point>
+          <coordinates>3,4,5</coordinates>
+        </Point>
+      </MultiGeometry>
+    </Placemark>
+  </Document>
+</kml>
+"#);
+        let read_back = read(&write(&fc)).unwrap();
+        assert_eq!(read_back.features[0].property::<String>("marker-color").unwrap(), "#ff8800");
+    }
+
+    #[test]
+    fn empty_points() {
+        let fc = FeatureCollection::builder()
+            .feature(Feature::builder().geometry(Value::Point(vec![])).build())
+            .feature(Feature::builder()
+                .geometry(Value::MultiPoint(vec![vec![], vec![1.0, 2.0]]))
+                .build())
+            .build();
+        let read_back = read(&write(&fc)).unwrap();
+        assert_eq!(read_back.features[0].geometry, None);
+        assert_eq!(read_back.features[1].geometry,
+                   Some(Geometry::new(Value::MultiPoint(vec![vec![1.0, 2.0]]))));
+    }
+
+    #[test]
+    fn colors() {
+        assert_eq!(write_color("#FF8800", Some(0.5)), Some(String::from("800088ff")));
+        assert_eq!(write_color("red", None), None);
+        assert_eq!(read_color("800088ff"), Some((String::from("#ff8800"), 0.5)));
+        assert_eq!(read_color("ff00"), None);
+    }
+
+    #[test]
+    fn malformed() {
+        let error = |message: &str| Err(Error::MalformedKml(String::from(message)));
+        assert_eq!(read("<gpx/>"), error("expected a 'kml' element"));
+        assert_eq!(read("<kml><Placemark><Point/></Placemark></kml>"),
+                   error("missing coordinates in 'Point'"));
+        assert_eq!(read("<kml><Placemark><LineString><coordinates>1,x</coordinates>\
+                         </LineString></Placemark></kml>"),
+                   error("invalid coordinates '1,x'"));
+        assert_eq!(read("<kml><Placemark><Point><coordinates></coordinates></Point>\
+                         </Placemark></kml>"),
+                   error("empty Point"));
+        assert!(read("<kml><Document></kml>").is_err());
+    }
+}
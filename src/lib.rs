@@ -45,56 +45,27 @@
 //!
 //! ## Writing
 //!
-//! Writing `geojson` depends on the serialization framework because some structs
-//! (like `Feature`) use json values for properties.
+//! `Feature`s, `Geometry`s and `FeatureCollection`s can be put together with their builders.
+//! Property values are `serde_json` values, so anything convertible into one can be used:
 //!
 //! ```
-//! # extern crate geojson;
-//! extern crate serde_json;
+//! use geojson::{Feature, GeoJson, Value};
 //!
-//! # fn main () {
-//! use serde_json::{Map, to_value};
-//!
-//! let mut properties = Map::new();
-//! properties.insert(
-//!     String::from("name"),
-//!     to_value("Firestone Grill").unwrap(),
-//! );
-//! # }
+//! let feature = Feature::builder()
+//!     .geometry(Value::Point(vec![-120.66029, 35.2812]))
+//!     .property("name", "Firestone Grill")
+//!     .build();
 //! ```
 //!
 //! `GeoJson` can then be serialized by calling `to_string`:
 //!
-//! ```rust
-//! # extern crate serde_json;
-//! # extern crate geojson;
-//! use std::collections::BTreeMap;
-//! use geojson::{Feature, GeoJson, Geometry, Value};
-//! # fn properties() -> ::serde_json::Map<String, ::serde_json::Value> {
-//! # let mut properties = ::serde_json::Map::new();
-//! # properties.insert(
-//! #     String::from("name"),
-//! #     ::serde_json::Value::String(String::from("Firestone Grill")),
-//! # );
-//! # properties
-//! # }
-//! # fn main() {
-//! # let properties = properties();
-//!
-//! let geometry = Geometry::new(
-//!     Value::Point(vec![-120.66029,35.2812])
-//! );
-//!
-//! let geojson = GeoJson::Feature(Feature {
-//!     crs: None,
-//!     bbox: None,
-//!     geometry: Some(geometry),
-//!     id: None,
-//!     properties: Some(properties),
-//! });
-//!
-//! let geojson_string = geojson.to_string();
-//! # }
+//! ```
+//! # use geojson::{Feature, GeoJson, Value};
+//! # let feature = Feature::builder()
+//! #     .geometry(Value::Point(vec![-120.66029, 35.2812]))
+//! #     .property("name", "Firestone Grill")
+//! #     .build();
+//! let geojson_string = GeoJson::from(feature).to_string();
 //! ```
 
 extern crate serde;
@@ -104,6 +75,13 @@ extern crate serde_json;
 extern crate geo;
 extern crate num_traits;
 
+#[cfg(feature = "arrow")]
+extern crate arrow_array;
+#[cfg(feature = "arrow")]
+extern crate arrow_buffer;
+#[cfg(feature = "arrow")]
+extern crate arrow_schema;
+
 /// Bounding Boxes
 ///
 /// [GeoJSON Format Specification § 4]
@@ -125,20 +103,74 @@ mod macros;
 
 mod util;
 
+mod protobuf;
+
+mod rings;
+
+mod xml;
+
 mod crs;
-pub use crs::Crs;
+pub use crs::{Crs, CrsEquivalence, CrsId};
 
 mod geojson;
 pub use geojson::GeoJson;
 
 mod geometry;
-pub use geometry::{Geometry, Value};
+pub use geometry::{Geometry, GeometryBuilder, Value};
 
 mod feature;
-pub use feature::Feature;
+pub use feature::{Feature, FeatureBuilder};
 
 mod feature_collection;
-pub use feature_collection::FeatureCollection;
+pub use feature_collection::{FeatureCollection, FeatureCollectionBuilder};
+
+mod projection;
+pub use projection::{Projection, Reproject};
+
+mod style;
+pub use style::{Color, MarkerSize, Style};
+
+#[cfg(feature = "arrow")]
+pub mod arrow;
+
+pub mod csv;
+
+pub mod esri;
+
+pub mod flatgeobuf;
+
+pub mod geobuf;
+
+pub mod geodesic;
+
+pub mod gpx;
+
+pub mod iter;
+
+pub mod jsonfg;
+
+pub mod kml;
+
+pub mod mvt;
+
+pub mod polyline;
+
+pub mod shapefile;
+
+pub mod simplify;
+
+pub mod svg;
+
+pub mod tiling;
+
+pub mod topojson;
+
+pub mod visit;
+pub use visit::{Visitor, VisitorMut, walk, walk_mut};
+
+pub mod wkb;
+
+pub mod wkt;
 
 /// Convert geo::types to geometry::Geometry
 #[doc(hidden)]
@@ -147,15 +179,35 @@ pub mod conversion;
 /// Error when reading a GeoJSON object from a str or Object
 #[derive(Debug, PartialEq, Eq)]
 pub enum Error {
+    ArrowUnsupported(String),
     BboxExpectedArray,
     BboxExpectedNumericValues,
     CrsExpectedObject,
     CrsUnknownType(String),
+    CrsInvalidName(String),
+    EsriUnsupportedGeometry(String),
     GeoJsonExpectedObject,
     GeoJsonUnknownType,
     GeometryUnknownType,
+    Io(String),
+    MalformedArrow(String),
+    MalformedCsv(String),
+    MalformedEsriJson(String),
+    MalformedFlatGeobuf(String),
+    MalformedGeobuf(String),
+    MalformedGpx(String),
     MalformedJson,
+    MalformedJsonFg(String),
+    MalformedKml(String),
+    MalformedMvt(String),
+    MalformedPolyline(String),
+    MalformedShapefile(String),
+    MalformedTopoJson(String),
+    MalformedWkb(String),
+    MalformedWkt(String),
     PropertiesExpectedObjectOrNull,
+    ShapefileUnsupported(String),
+    StyleInvalidProperty(String),
     FeatureInvalidGeometryValue,
 
     // FIXME: make these types more specific
@@ -169,6 +221,8 @@ pub enum Error {
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match *self {
+            Error::ArrowUnsupported(ref e) =>
+                write!(f, "Encountered data not representable in Arrow: {}.", e),
             Error::BboxExpectedArray =>
                 // FIXME: inform what type we actually found
                 write!(f, "Encountered non-array type for a 'bbox' object."),
@@ -180,6 +234,10 @@ impl std::fmt::Display for Error {
                 write!(f, "Encountered non-object type for a 'crs' object."),
             Error::CrsUnknownType(ref t) =>
                 write!(f, "Encountered unknown type '{}' for a 'crs' object.", t),
+            Error::CrsInvalidName(ref n) =>
+                write!(f, "Encountered unrecognised CRS name '{}'.", n),
+            Error::EsriUnsupportedGeometry(ref e) =>
+                write!(f, "Encountered geometry not representable in Esri JSON: {}.", e),
             Error::GeoJsonExpectedObject =>
                 // FIXME: inform what type we actually found
                 write!(f, "Encountered non-object type for GeoJSON."),
@@ -188,13 +246,47 @@ impl std::fmt::Display for Error {
                 write!(f, "Encountered unknown GeoJSON object type."),
             Error::GeometryUnknownType =>
                 write!(f, "Encountered unknown 'geometry' object type."),
+            Error::Io(ref e) =>
+                write!(f, "Encountered an I/O error: {}.", e),
             Error::MalformedJson =>
                 // FIXME: can we report specific serialization error?
                 write!(f, "Encountered malformed JSON."),
+            Error::MalformedArrow(ref e) =>
+                write!(f, "Encountered malformed Arrow data: {}.", e),
+            Error::MalformedCsv(ref e) =>
+                write!(f, "Encountered malformed CSV: {}.", e),
+            Error::MalformedEsriJson(ref e) =>
+                write!(f, "Encountered malformed Esri JSON: {}.", e),
+            Error::MalformedFlatGeobuf(ref e) =>
+                write!(f, "Encountered malformed FlatGeobuf: {}.", e),
+            Error::MalformedGeobuf(ref e) =>
+                write!(f, "Encountered malformed Geobuf: {}.", e),
+            Error::MalformedGpx(ref e) =>
+                write!(f, "Encountered malformed GPX: {}.", e),
+            Error::MalformedJsonFg(ref e) =>
+                write!(f, "Encountered malformed JSON-FG: {}.", e),
+            Error::MalformedKml(ref e) =>
+                write!(f, "Encountered malformed KML: {}.", e),
+            Error::MalformedMvt(ref e) =>
+                write!(f, "Encountered malformed vector tile: {}.", e),
+            Error::MalformedPolyline(ref e) =>
+                write!(f, "Encountered malformed encoded polyline: {}.", e),
+            Error::MalformedShapefile(ref e) =>
+                write!(f, "Encountered malformed Shapefile: {}.", e),
+            Error::MalformedTopoJson(ref e) =>
+                write!(f, "Encountered malformed TopoJSON: {}.", e),
+            Error::MalformedWkb(ref e) =>
+                write!(f, "Encountered malformed WKB: {}.", e),
+            Error::MalformedWkt(ref e) =>
+                write!(f, "Encountered malformed WKT: {}.", e),
             Error::PropertiesExpectedObjectOrNull =>
                 // FIXME: inform what type we actually found
                 write!(f, "Encountered neither object type nor null type for \
                            'properties' object."),
+            Error::ShapefileUnsupported(ref e) =>
+                write!(f, "Encountered data not representable in a Shapefile: {}.", e),
+            Error::StyleInvalidProperty(ref e) =>
+                write!(f, "Encountered invalid simplestyle property {}.", e),
             Error::FeatureInvalidGeometryValue =>
                 // FIXME: inform what type we actually found
                 write!(f, "Encountered neither object type nor null type for \
@@ -216,17 +308,37 @@ impl std::fmt::Display for Error {
 impl std::error::Error for Error {
     fn description(&self) -> &str {
         match *self {
+            Error::ArrowUnsupported(..) => "data not representable in Arrow",
             Error::BboxExpectedArray => "non-array 'bbox' type",
             Error::BboxExpectedNumericValues => "non-numeric 'bbox' array",
             Error::CrsExpectedObject => "non-object 'crs' type",
             Error::CrsUnknownType(..) => "unknown 'crs' type",
+            Error::CrsInvalidName(..) => "unrecognised CRS name",
+            Error::EsriUnsupportedGeometry(..) => "geometry not representable in Esri JSON",
             Error::GeoJsonExpectedObject => "non-object GeoJSON type",
             Error::GeoJsonUnknownType => "unknown GeoJSON object type",
             Error::GeometryUnknownType => "unknown 'geometry' object type",
+            Error::Io(..) => "I/O error",
             Error::MalformedJson => "malformed JSON",
+            Error::MalformedArrow(..) => "malformed Arrow data",
+            Error::MalformedCsv(..) => "malformed CSV",
+            Error::MalformedEsriJson(..) => "malformed Esri JSON",
+            Error::MalformedFlatGeobuf(..) => "malformed FlatGeobuf",
+            Error::MalformedGeobuf(..) => "malformed Geobuf",
+            Error::MalformedGpx(..) => "malformed GPX",
+            Error::MalformedJsonFg(..) => "malformed JSON-FG",
+            Error::MalformedKml(..) => "malformed KML",
+            Error::MalformedMvt(..) => "malformed vector tile",
+            Error::MalformedPolyline(..) => "malformed encoded polyline",
+            Error::MalformedShapefile(..) => "malformed Shapefile",
+            Error::MalformedTopoJson(..) => "malformed TopoJSON",
+            Error::MalformedWkb(..) => "malformed WKB",
+            Error::MalformedWkt(..) => "malformed WKT",
             Error::PropertiesExpectedObjectOrNull => {
                 "neither object type nor null type for properties' object."
             }
+            Error::ShapefileUnsupported(..) => "data not representable in a Shapefile",
+            Error::StyleInvalidProperty(..) => "invalid simplestyle property",
             Error::FeatureInvalidGeometryValue => {
                 "neither object type nor null type for 'geometry' field on 'feature' object."
             }
@@ -239,6 +351,12 @@ impl std::error::Error for Error {
     }
 }
 
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Error {
+        Error::Io(error.to_string())
+    }
+}
+
 mod json {
     pub use serde::{Serialize, Deserialize, Serializer, Deserializer};
     pub use serde_json::{Map, Value as JsonValue};
@@ -62,3 +62,220 @@ macro_rules! expect_property {
         };
     )
 }
+
+/// Builds typed GeoJSON objects from a compact literal syntax
+///
+/// Geometries are written as their GeoJSON type followed by their coordinates, and evaluate
+/// to a `Geometry`. The bracket nesting of the coordinates is checked against the type at
+/// compile time, and coordinates can be any expressions castable to `f64`:
+///
+/// ```
+/// #[macro_use]
+/// extern crate geojson;
+///
+/// use geojson::Value;
+///
+/// # fn main() {
+/// let point = geojson!(Point [-120.66029, 35.2812]);
+/// let polygon = geojson!(Polygon [[[0, 0], [1, 0], [1, 1], [0, 0]]]);
+/// let collection = geojson!(GeometryCollection [
+///     Point [1.0, 2.0],
+///     LineString [[1.0, 2.0], [3.0, 4.0]],
+/// ]);
+///
+/// assert_eq!(point.value, Value::Point(vec![-120.66029, 35.2812]));
+/// # }
+/// ```
+///
+/// Coordinates nested to the wrong depth for their type are rejected:
+///
+/// ```compile_fail
+/// #[macro_use]
+/// extern crate geojson;
+///
+/// # fn main() {
+/// let polygon = geojson!(Polygon [[0, 0], [1, 0], [1, 1], [0, 0]]);
+/// # }
+/// ```
+///
+/// Features take optional `geometry`, `id` and `properties` fields, in that order, and
+/// evaluate to a `Feature`. Property values can be anything convertible into a
+/// `serde_json::Value`. Feature collections are a list of such feature bodies and evaluate to
+/// a `FeatureCollection`:
+///
+/// ```
+/// #[macro_use]
+/// extern crate geojson;
+///
+/// # fn main() {
+/// let feature = geojson!(Feature {
+///     geometry: Point [-120.66029, 35.2812],
+///     id: "firestone",
+///     properties: { "name": "Firestone Grill", "rating": 4.5 },
+/// });
+///
+/// let feature_collection = geojson!(FeatureCollection [
+///     { geometry: LineString [[0, 0], [1, 1]] },
+///     { geometry: null, properties: {} },
+/// ]);
+///
+/// assert_eq!(feature.property::<f64>("rating"), Some(4.5));
+/// assert_eq!(feature_collection.features.len(), 2);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! geojson {
+    (FeatureCollection [$({ $($feature:tt)* }),* $(,)*]) => (
+        $crate::FeatureCollection::builder()
+            .features(vec![$(geojson!(Feature { $($feature)* })),*])
+            .build()
+    );
+
+    (Feature { $($field:tt)* }) => ({
+        #[allow(unused_mut)]
+        let mut feature = $crate::Feature::default();
+        geojson!(@geometry_field feature; $($field)*);
+        feature
+    });
+
+    ($type_:ident $coordinates:tt) => (
+        $crate::Geometry::new(geojson!(@value $type_ $coordinates))
+    );
+
+    // Feature fields are munched in order, each rule handing what follows it on to the next.
+    (@geometry_field $feature:ident; geometry: null, $($rest:tt)*) => (
+        geojson!(@id_field $feature; $($rest)*)
+    );
+    (@geometry_field $feature:ident; geometry: null) => ({});
+    (@geometry_field $feature:ident; geometry: $type_:ident $coordinates:tt, $($rest:tt)*) => ({
+        $feature.geometry = Some(geojson!($type_ $coordinates));
+        geojson!(@id_field $feature; $($rest)*)
+    });
+    (@geometry_field $feature:ident; geometry: $type_:ident $coordinates:tt) => ({
+        $feature.geometry = Some(geojson!($type_ $coordinates));
+    });
+    (@geometry_field $feature:ident; $($rest:tt)*) => (
+        geojson!(@id_field $feature; $($rest)*)
+    );
+
+    (@id_field $feature:ident; id: $id:expr, $($rest:tt)*) => ({
+        $feature.id = Some(::std::convert::From::from($id));
+        geojson!(@properties_field $feature; $($rest)*)
+    });
+    (@id_field $feature:ident; id: $id:expr) => ({
+        $feature.id = Some(::std::convert::From::from($id));
+    });
+    (@id_field $feature:ident; $($rest:tt)*) => (
+        geojson!(@properties_field $feature; $($rest)*)
+    );
+
+    (@properties_field $feature:ident; properties: null $(,)*) => ({});
+    (@properties_field $feature:ident; properties: { $($key:tt : $value:expr),* $(,)* } $(,)*) => ({
+        $feature.properties = Some(::std::default::Default::default());
+        $($feature.set_property($key, $value);)*
+    });
+    (@properties_field $feature:ident;) => ({});
+
+    (@value Point $position:tt) => (
+        $crate::Value::Point(geojson!(@position $position))
+    );
+    (@value MultiPoint [$($position:tt),* $(,)*]) => (
+        $crate::Value::MultiPoint(vec![$(geojson!(@position $position)),*])
+    );
+    (@value LineString $line_string:tt) => (
+        $crate::Value::LineString(geojson!(@line_string $line_string))
+    );
+    (@value MultiLineString [$($line_string:tt),* $(,)*]) => (
+        $crate::Value::MultiLineString(vec![$(geojson!(@line_string $line_string)),*])
+    );
+    (@value Polygon $polygon:tt) => (
+        $crate::Value::Polygon(geojson!(@polygon $polygon))
+    );
+    (@value MultiPolygon [$($polygon:tt),* $(,)*]) => (
+        $crate::Value::MultiPolygon(vec![$(geojson!(@polygon $polygon)),*])
+    );
+    (@value GeometryCollection [$($type_:ident $coordinates:tt),* $(,)*]) => (
+        $crate::Value::GeometryCollection(vec![$(geojson!($type_ $coordinates)),*])
+    );
+
+    (@position [$($ordinate:expr),* $(,)*]) => (
+        vec![$($ordinate as f64),*]
+    );
+    (@line_string [$($position:tt),* $(,)*]) => (
+        vec![$(geojson!(@position $position)),*]
+    );
+    (@polygon [$($ring:tt),* $(,)*]) => (
+        vec![$(geojson!(@line_string $ring)),*]
+    );
+}
+
+
+#[cfg(test)]
+mod tests {
+    use {Feature, FeatureCollection, Geometry, Value};
+
+    #[test]
+    fn geometries() {
+        assert_eq!(geojson!(Point [1, 2.5]), Geometry::new(Value::Point(vec![1.0, 2.5])));
+        assert_eq!(geojson!(MultiPoint [[1, 2], [3, 4],]),
+                   Geometry::new(Value::MultiPoint(vec![vec![1.0, 2.0], vec![3.0, 4.0]])));
+        assert_eq!(geojson!(MultiLineString [[[1, 2], [3, 4]], [[5, 6], [7, -8]]]),
+                   Geometry::new(Value::MultiLineString(vec![
+                       vec![vec![1.0, 2.0], vec![3.0, 4.0]],
+                       vec![vec![5.0, 6.0], vec![7.0, -8.0]],
+                   ])));
+
+        let x = 10.0;
+        assert_eq!(geojson!(MultiPolygon [[[[0, 0], [x, 0], [x, x], [0, 0]]]]),
+                   Geometry::new(Value::MultiPolygon(vec![vec![vec![vec![0.0, 0.0],
+                                                                    vec![10.0, 0.0],
+                                                                    vec![10.0, 10.0],
+                                                                    vec![0.0, 0.0]]]])));
+
+        assert_eq!(geojson!(GeometryCollection [
+                       Point [1, 2],
+                       GeometryCollection [],
+                   ]),
+                   Geometry::new(Value::GeometryCollection(vec![
+                       Geometry::new(Value::Point(vec![1.0, 2.0])),
+                       Geometry::new(Value::GeometryCollection(vec![])),
+                   ])));
+    }
+
+    #[test]
+    fn features() {
+        assert_eq!(geojson!(Feature {}), Feature::default());
+        assert_eq!(geojson!(Feature { geometry: null, properties: null }),
+                   Feature::default());
+
+        let feature = geojson!(Feature {
+            geometry: Point [1, 2],
+            id: 42,
+            properties: { "name": "x", "visible": true, "nested": json!({"a": [1, 2]}) }
+        });
+        assert_eq!(feature,
+                   Feature::builder()
+                       .geometry(Value::Point(vec![1.0, 2.0]))
+                       .id(42)
+                       .property("name", "x")
+                       .property("visible", true)
+                       .property("nested", json!({"a": [1, 2]}))
+                       .build());
+
+        let feature = geojson!(Feature { id: "a", properties: {}, });
+        assert_eq!(feature.id, Some(json!("a")));
+        assert_eq!(feature.properties, Some(::json::JsonObject::new()));
+    }
+
+    #[test]
+    fn feature_collections() {
+        assert_eq!(geojson!(FeatureCollection []), FeatureCollection::builder().build());
+
+        let fc = geojson!(FeatureCollection [
+            { geometry: Point [1, 2] },
+            { properties: { "n": 1 } },
+        ]);
+        assert_eq!(fc.features.len(), 2);
+        assert_eq!(fc.features[1].property::<u8>("n"), Some(1));
+    }
+}
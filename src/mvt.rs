@@ -0,0 +1,840 @@
+// Copyright 2015 The GeoRust Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Mapbox Vector Tile encoding and decoding
+//!
+//! [Mapbox Vector Tile Specification 2.1](https://github.com/mapbox/vector-tile-spec/tree/master/2.1)
+//!
+//! Each layer of a tile corresponds to a `FeatureCollection`. Positions are either tile-local
+//! coordinates, with the origin at the top left corner and `y` growing downwards, or WGS84
+//! longitude and latitude for a tile given by its `TileId`.
+//!
+//! When encoding, positions are rounded to the integer grid and polygon rings are rewound as
+//! the specification requires. Only non-negative integer `id`s can be stored. Properties with
+//! `null` values are dropped, and arrays and objects are stored as JSON strings. Features
+//! without a geometry are skipped, and each member of a `GeometryCollection` becomes a feature
+//! of its own.
+
+use std::collections::{BTreeMap, HashMap};
+use std::f64::consts::PI;
+
+use json::{JsonObject, JsonValue};
+use protobuf::{Reader, Writer, WIRE_FIXED32, WIRE_FIXED64, WIRE_LENGTH_DELIMITED, WIRE_VARINT,
+               unzigzag, zigzag};
+use serde_json;
+
+use {Bbox, Error, Feature, FeatureCollection, Geometry, Position, Value};
+
+
+/// Default number of tile-local units along each side of a tile
+pub const DEFAULT_EXTENT: u32 = 4096;
+
+/// Address of a tile in the Web Mercator tiling scheme, with `y` counted from the north.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TileId {
+    pub z: u8,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl TileId {
+    pub fn new(z: u8, x: u32, y: u32) -> Self {
+        TileId { z: z, x: x, y: y }
+    }
+
+    /// Returns the WGS84 bounding box of the tile, as `[west, south, east, north]`.
+    pub fn bbox(&self) -> Bbox {
+        let north_west = self.to_wgs84(&[0.0, 0.0], 1);
+        let south_east = self.to_wgs84(&[1.0, 1.0], 1);
+        vec![north_west[0], south_east[1], south_east[0], north_west[1]]
+    }
+
+    fn tiles(&self) -> f64 {
+        2f64.powi(self.z as i32)
+    }
+
+    /// Converts a WGS84 position to tile-local coordinates. Latitudes are clamped to the
+    /// Web Mercator range.
+    pub fn to_tile(&self, position: &[f64], extent: u32) -> [f64; 2] {
+        let latitude = position[1].max(-85.0511287798).min(85.0511287798).to_radians();
+        let x = (position[0] + 180.0) / 360.0;
+        let y = (1.0 - (latitude.tan() + 1.0 / latitude.cos()).ln() / PI) / 2.0;
+        [(x * self.tiles() - self.x as f64) * extent as f64,
+         (y * self.tiles() - self.y as f64) * extent as f64]
+    }
+
+    /// Converts tile-local coordinates to a WGS84 position.
+    pub fn to_wgs84(&self, position: &[f64], extent: u32) -> Position {
+        let x = (self.x as f64 + position[0] / extent as f64) / self.tiles();
+        let y = (self.y as f64 + position[1] / extent as f64) / self.tiles();
+        vec![x * 360.0 - 180.0, (PI * (1.0 - 2.0 * y)).sinh().atan().to_degrees()]
+    }
+}
+
+/// Encodes feature collections in tile-local coordinates as the named layers of a tile.
+///
+/// # Examples
+///
+/// ```
+/// use geojson::{Feature, FeatureCollection, Value};
+/// use geojson::mvt;
+///
+/// let fc = FeatureCollection::builder()
+///     .feature(Feature::builder()
+///         .geometry(Value::Point(vec![25.0, 17.0]))
+///         .id(1)
+///         .property("name", "example")
+///         .build())
+///     .build();
+///
+/// let tile = mvt::encode(vec![("points", &fc)], mvt::DEFAULT_EXTENT);
+/// assert_eq!(mvt::decode(&tile).unwrap()["points"], fc);
+/// ```
+pub fn encode<'a, I>(layers: I, extent: u32) -> Vec<u8>
+    where I: IntoIterator<Item = (&'a str, &'a FeatureCollection)>
+{
+    encode_layers(layers, extent, None)
+}
+
+/// Encodes feature collections in WGS84 coordinates as the named layers of tile `tile`.
+pub fn encode_wgs84<'a, I>(layers: I, tile: TileId, extent: u32) -> Vec<u8>
+    where I: IntoIterator<Item = (&'a str, &'a FeatureCollection)>
+{
+    encode_layers(layers, extent, Some(tile))
+}
+
+/// Decodes every layer of a tile, with positions in tile-local coordinates.
+pub fn decode(tile: &[u8]) -> Result<BTreeMap<String, FeatureCollection>, Error> {
+    decode_layers(tile, None)
+}
+
+/// Decodes every layer of tile `tile`, with positions in WGS84 coordinates.
+pub fn decode_wgs84(tile: &[u8],
+                    tile_id: TileId)
+                    -> Result<BTreeMap<String, FeatureCollection>, Error> {
+    decode_layers(tile, Some(tile_id))
+}
+
+fn malformed(message: &str) -> Error {
+    Error::MalformedMvt(String::from(message))
+}
+
+const COMMAND_MOVE_TO: u32 = 1;
+const COMMAND_LINE_TO: u32 = 2;
+const COMMAND_CLOSE_PATH: u32 = 7;
+
+const GEOM_TYPE_POINT: u64 = 1;
+const GEOM_TYPE_LINESTRING: u64 = 2;
+const GEOM_TYPE_POLYGON: u64 = 3;
+
+/// Twice the signed area of a ring, positive for clockwise rings when `y` points down
+fn ring_area(ring: &[[i64; 2]]) -> f64 {
+    let mut area = 0.0;
+    for i in 0..ring.len() {
+        let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+        area += a[0] as f64 * b[1] as f64 - b[0] as f64 * a[1] as f64;
+    }
+    area
+}
+
+
+// Encoding
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum PropertyValue {
+    String(String),
+    Double(u64),
+    Uint(u64),
+    Sint(i64),
+    Bool(bool),
+}
+
+impl PropertyValue {
+    fn from_json(value: &JsonValue) -> Option<Self> {
+        Some(match *value {
+            JsonValue::Null => return None,
+            JsonValue::Bool(b) => PropertyValue::Bool(b),
+            JsonValue::String(ref s) => PropertyValue::String(s.clone()),
+            JsonValue::Number(ref n) => {
+                if let Some(u) = n.as_u64() {
+                    PropertyValue::Uint(u)
+                } else if let Some(i) = n.as_i64() {
+                    PropertyValue::Sint(i)
+                } else {
+                    PropertyValue::Double(n.as_f64().unwrap_or(0.0).to_bits())
+                }
+            }
+            JsonValue::Array(..) | JsonValue::Object(..) => {
+                PropertyValue::String(serde_json::to_string(value).unwrap())
+            }
+        })
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::new();
+        match *self {
+            PropertyValue::String(ref s) => writer.bytes_field(1, s.as_bytes()),
+            PropertyValue::Double(bits) => writer.fixed64_field(3, bits),
+            PropertyValue::Uint(u) => writer.varint_field(5, u),
+            PropertyValue::Sint(i) => writer.varint_field(6, zigzag(i)),
+            PropertyValue::Bool(b) => writer.varint_field(7, b as u64),
+        }
+        writer.buf
+    }
+}
+
+/// Builds the geometry commands of features, tracking the cursor across a feature.
+struct Commands {
+    commands: Vec<u32>,
+    cursor: [i64; 2],
+}
+
+impl Commands {
+    fn new() -> Self {
+        Commands {
+            commands: vec![],
+            cursor: [0, 0],
+        }
+    }
+
+    fn command(&mut self, id: u32, count: usize) {
+        self.commands.push((id & 0x7) | ((count as u32) << 3));
+    }
+
+    fn point(&mut self, point: [i64; 2]) {
+        for i in 0..2 {
+            self.commands.push(zigzag(point[i] - self.cursor[i]) as u32);
+        }
+        self.cursor = point;
+    }
+
+    fn line(&mut self, points: &[[i64; 2]]) {
+        self.command(COMMAND_MOVE_TO, 1);
+        self.point(points[0]);
+        self.command(COMMAND_LINE_TO, points.len() - 1);
+        for &point in &points[1..] {
+            self.point(point);
+        }
+    }
+}
+
+/// Converts positions of a geometry to the integer tile grid.
+struct Grid {
+    extent: u32,
+    tile: Option<TileId>,
+}
+
+impl Grid {
+    fn point(&self, position: &Position) -> [i64; 2] {
+        let point = match self.tile {
+            Some(tile) => tile.to_tile(position, self.extent),
+            None => [position[0], position[1]],
+        };
+        [point[0].round() as i64, point[1].round() as i64]
+    }
+
+    fn points(&self, positions: &[Position]) -> Vec<[i64; 2]> {
+        let mut points = positions.iter()
+            .filter(|p| p.len() >= 2)
+            .map(|p| self.point(p))
+            .collect::<Vec<_>>();
+        points.dedup();
+        points
+    }
+
+    /// Returns the ring's distinct points wound as an exterior or interior ring, or `None` if
+    /// the ring has no area left on the grid.
+    fn ring(&self, positions: &[Position], exterior: bool) -> Option<Vec<[i64; 2]>> {
+        let mut points = self.points(positions);
+        if points.len() > 1 && points[0] == points[points.len() - 1] {
+            points.pop();
+        }
+        let area = ring_area(&points);
+        if points.len() < 3 || area == 0.0 {
+            return None;
+        }
+        if (area > 0.0) != exterior {
+            points[1..].reverse();
+        }
+        Some(points)
+    }
+
+    fn polygon(&self, commands: &mut Commands, polygon: &[Vec<Position>]) {
+        let mut rings = polygon.iter().enumerate();
+        match rings.next().and_then(|(_, ring)| self.ring(ring, true)) {
+            Some(exterior) => commands.line(&exterior),
+            None => return,
+        }
+        commands.command(COMMAND_CLOSE_PATH, 1);
+        for (_, ring) in rings {
+            if let Some(interior) = self.ring(ring, false) {
+                commands.line(&interior);
+                commands.command(COMMAND_CLOSE_PATH, 1);
+            }
+        }
+    }
+
+    /// Returns the geometry type and commands of each feature needed to store `value`.
+    fn geometries(&self, value: &Value) -> Vec<(u64, Vec<u32>)> {
+        let mut commands = Commands::new();
+        let geom_type = match *value {
+            Value::Point(ref position) => {
+                let points = self.points(::std::slice::from_ref(position));
+                if let Some(&point) = points.first() {
+                    commands.command(COMMAND_MOVE_TO, 1);
+                    commands.point(point);
+                }
+                GEOM_TYPE_POINT
+            }
+            Value::MultiPoint(ref positions) => {
+                let points = positions.iter()
+                    .filter(|p| p.len() >= 2)
+                    .map(|p| self.point(p))
+                    .collect::<Vec<_>>();
+                if !points.is_empty() {
+                    commands.command(COMMAND_MOVE_TO, points.len());
+                    for point in points {
+                        commands.point(point);
+                    }
+                }
+                GEOM_TYPE_POINT
+            }
+            Value::LineString(ref line) => {
+                let points = self.points(line);
+                if points.len() >= 2 {
+                    commands.line(&points);
+                }
+                GEOM_TYPE_LINESTRING
+            }
+            Value::MultiLineString(ref lines) => {
+                for line in lines {
+                    let points = self.points(line);
+                    if points.len() >= 2 {
+                        commands.line(&points);
+                    }
+                }
+                GEOM_TYPE_LINESTRING
+            }
+            Value::Polygon(ref polygon) => {
+                self.polygon(&mut commands, polygon);
+                GEOM_TYPE_POLYGON
+            }
+            Value::MultiPolygon(ref polygons) => {
+                for polygon in polygons {
+                    self.polygon(&mut commands, polygon);
+                }
+                GEOM_TYPE_POLYGON
+            }
+            Value::GeometryCollection(ref geometries) => {
+                return geometries.iter().flat_map(|g| self.geometries(&g.value)).collect();
+            }
+        };
+        if commands.commands.is_empty() {
+            vec![]
+        } else {
+            vec![(geom_type, commands.commands)]
+        }
+    }
+}
+
+struct LayerEncoder {
+    keys: Vec<String>,
+    key_indexes: HashMap<String, u32>,
+    values: Vec<PropertyValue>,
+    value_indexes: HashMap<PropertyValue, u32>,
+    layer: Writer,
+}
+
+impl LayerEncoder {
+    fn new(name: &str) -> Self {
+        let mut layer = Writer::new();
+        layer.bytes_field(1, name.as_bytes());
+        LayerEncoder {
+            keys: vec![],
+            key_indexes: HashMap::new(),
+            values: vec![],
+            value_indexes: HashMap::new(),
+            layer: layer,
+        }
+    }
+
+    fn tags(&mut self, properties: &JsonObject) -> Vec<u32> {
+        let mut tags = vec![];
+        for (key, value) in properties {
+            let value = match PropertyValue::from_json(value) {
+                Some(value) => value,
+                None => continue,
+            };
+            let keys = &mut self.keys;
+            tags.push(*self.key_indexes.entry(key.clone()).or_insert_with(|| {
+                keys.push(key.clone());
+                keys.len() as u32 - 1
+            }));
+            let values = &mut self.values;
+            tags.push(*self.value_indexes.entry(value.clone()).or_insert_with(|| {
+                values.push(value);
+                values.len() as u32 - 1
+            }));
+        }
+        tags
+    }
+
+    fn feature(&mut self, grid: &Grid, feature: &Feature) {
+        let geometries = match feature.geometry {
+            Some(ref geometry) => grid.geometries(&geometry.value),
+            None => return,
+        };
+        let tags = feature.properties.as_ref().map(|p| self.tags(p)).unwrap_or_default();
+        for (geom_type, commands) in geometries {
+            let mut writer = Writer::new();
+            if let Some(id) = feature.id.as_ref().and_then(|id| id.as_u64()) {
+                writer.varint_field(1, id);
+            }
+            if !tags.is_empty() {
+                writer.packed_field(2, tags.iter().map(|&tag| tag as u64));
+            }
+            writer.varint_field(3, geom_type);
+            writer.packed_field(4, commands.iter().map(|&command| command as u64));
+            self.layer.bytes_field(2, &writer.buf);
+        }
+    }
+
+    fn finish(mut self, extent: u32) -> Vec<u8> {
+        for key in &self.keys {
+            self.layer.bytes_field(3, key.as_bytes());
+        }
+        for value in &self.values {
+            self.layer.bytes_field(4, &value.encode());
+        }
+        self.layer.varint_field(5, extent as u64);
+        self.layer.varint_field(15, 2);
+        self.layer.buf
+    }
+}
+
+fn encode_layers<'a, I>(layers: I, extent: u32, tile: Option<TileId>) -> Vec<u8>
+    where I: IntoIterator<Item = (&'a str, &'a FeatureCollection)>
+{
+    let grid = Grid {
+        extent: extent,
+        tile: tile,
+    };
+    let mut writer = Writer::new();
+    for (name, fc) in layers {
+        let mut layer = LayerEncoder::new(name);
+        for feature in &fc.features {
+            layer.feature(&grid, feature);
+        }
+        writer.bytes_field(3, &layer.finish(extent));
+    }
+    writer.buf
+}
+
+
+// Decoding
+
+fn decode_layers(tile: &[u8],
+                 tile_id: Option<TileId>)
+                 -> Result<BTreeMap<String, FeatureCollection>, Error> {
+    let mut reader = Reader::new(tile, Error::MalformedMvt);
+    let mut layers = BTreeMap::new();
+    while let Some((field, wire_type)) = try!(reader.key()) {
+        match (field, wire_type) {
+            (3, WIRE_LENGTH_DELIMITED) => {
+                let (name, fc) = try!(decode_layer(try!(reader.bytes()), tile_id));
+                layers.insert(name, fc);
+            }
+            _ => try!(reader.skip(wire_type)),
+        }
+    }
+    Ok(layers)
+}
+
+fn decode_layer(layer: &[u8],
+                tile_id: Option<TileId>)
+                -> Result<(String, FeatureCollection), Error> {
+    let mut reader = Reader::new(layer, Error::MalformedMvt);
+    let mut name = None;
+    let mut features = vec![];
+    let mut keys = vec![];
+    let mut values = vec![];
+    let mut extent = DEFAULT_EXTENT;
+    while let Some((field, wire_type)) = try!(reader.key()) {
+        match (field, wire_type) {
+            (1, WIRE_LENGTH_DELIMITED) => name = Some(try!(reader.string())),
+            (2, WIRE_LENGTH_DELIMITED) => features.push(try!(reader.bytes())),
+            (3, WIRE_LENGTH_DELIMITED) => keys.push(try!(reader.string())),
+            (4, WIRE_LENGTH_DELIMITED) => values.push(try!(decode_value(try!(reader.bytes())))),
+            (5, WIRE_VARINT) => extent = try!(reader.varint()) as u32,
+            _ => try!(reader.skip(wire_type)),
+        }
+    }
+    let name = match name {
+        Some(name) => name,
+        None => return Err(malformed("layer without a name")),
+    };
+    if extent == 0 {
+        return Err(malformed("layer with zero extent"));
+    }
+
+    let mut decoded = vec![];
+    for feature in features {
+        decoded.push(try!(decode_feature(feature, &keys, &values, extent, tile_id)));
+    }
+    Ok((name,
+        FeatureCollection {
+            bbox: None,
+            crs: None,
+            features: decoded,
+        }))
+}
+
+fn decode_value(value: &[u8]) -> Result<JsonValue, Error> {
+    let mut reader = Reader::new(value, Error::MalformedMvt);
+    let mut decoded = JsonValue::Null;
+    while let Some((field, wire_type)) = try!(reader.key()) {
+        decoded = match (field, wire_type) {
+            (1, WIRE_LENGTH_DELIMITED) => JsonValue::String(try!(reader.string())),
+            (2, WIRE_FIXED32) => json!(f32::from_bits(try!(reader.fixed(4)) as u32) as f64),
+            (3, WIRE_FIXED64) => json!(f64::from_bits(try!(reader.fixed(8)))),
+            (4, WIRE_VARINT) => json!(try!(reader.varint()) as i64),
+            (5, WIRE_VARINT) => json!(try!(reader.varint())),
+            (6, WIRE_VARINT) => json!(unzigzag(try!(reader.varint()))),
+            (7, WIRE_VARINT) => JsonValue::Bool(try!(reader.varint()) != 0),
+            _ => {
+                try!(reader.skip(wire_type));
+                continue;
+            }
+        };
+    }
+    Ok(decoded)
+}
+
+fn decode_feature(feature: &[u8],
+                  keys: &[String],
+                  values: &[JsonValue],
+                  extent: u32,
+                  tile_id: Option<TileId>)
+                  -> Result<Feature, Error> {
+    let mut reader = Reader::new(feature, Error::MalformedMvt);
+    let mut id = None;
+    let mut tags = vec![];
+    let mut geom_type = 0;
+    let mut commands = vec![];
+    while let Some((field, wire_type)) = try!(reader.key()) {
+        match (field, wire_type) {
+            (1, WIRE_VARINT) => id = Some(json!(try!(reader.varint()))),
+            (2, WIRE_LENGTH_DELIMITED) => tags = try!(reader.packed()),
+            (3, WIRE_VARINT) => geom_type = try!(reader.varint()),
+            (4, WIRE_LENGTH_DELIMITED) => commands = try!(reader.packed()),
+            _ => try!(reader.skip(wire_type)),
+        }
+    }
+
+    if tags.len() % 2 != 0 {
+        return Err(malformed("odd number of tags"));
+    }
+    let mut properties = JsonObject::new();
+    for tag in tags.chunks(2) {
+        match (keys.get(tag[0] as usize), values.get(tag[1] as usize)) {
+            (Some(key), Some(value)) => {
+                properties.insert(key.clone(), value.clone());
+            }
+            _ => return Err(malformed("tag index out of range")),
+        }
+    }
+
+    let position = |point: [i64; 2]| {
+        let point = [point[0] as f64, point[1] as f64];
+        match tile_id {
+            Some(tile_id) => tile_id.to_wgs84(&point, extent),
+            None => point.to_vec(),
+        }
+    };
+    let value = try!(decode_geometry(geom_type, &commands)).map(|value| match value {
+        DecodedGeometry::Points(points) => {
+            let mut positions = points.into_iter().map(&position).collect::<Vec<_>>();
+            if positions.len() == 1 {
+                Value::Point(positions.pop().unwrap())
+            } else {
+                Value::MultiPoint(positions)
+            }
+        }
+        DecodedGeometry::Lines(lines) => {
+            let mut lines = lines.into_iter()
+                .map(|line| line.into_iter().map(&position).collect::<Vec<_>>())
+                .collect::<Vec<_>>();
+            if lines.len() == 1 {
+                Value::LineString(lines.pop().unwrap())
+            } else {
+                Value::MultiLineString(lines)
+            }
+        }
+        DecodedGeometry::Polygons(polygons) => {
+            let mut polygons = polygons.into_iter()
+                .map(|polygon| {
+                    polygon.into_iter()
+                        .map(|ring| ring.into_iter().map(&position).collect::<Vec<_>>())
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            if polygons.len() == 1 {
+                Value::Polygon(polygons.pop().unwrap())
+            } else {
+                Value::MultiPolygon(polygons)
+            }
+        }
+    });
+
+    Ok(Feature {
+        bbox: None,
+        crs: None,
+        geometry: value.map(Geometry::new),
+        id: id,
+        properties: if properties.is_empty() { None } else { Some(properties) },
+    })
+}
+
+enum DecodedGeometry {
+    Points(Vec<[i64; 2]>),
+    Lines(Vec<Vec<[i64; 2]>>),
+    Polygons(Vec<Vec<Vec<[i64; 2]>>>),
+}
+
+/// Interprets geometry commands, returning `None` for empty or unknown geometries.
+fn decode_geometry(geom_type: u64, commands: &[u64]) -> Result<Option<DecodedGeometry>, Error> {
+    let mut lines: Vec<Vec<[i64; 2]>> = vec![];
+    let mut cursor = [0i64, 0i64];
+    let mut commands = commands.iter();
+    while let Some(&command) = commands.next() {
+        let (id, count) = ((command & 0x7) as u32, command >> 3);
+        match id {
+            COMMAND_MOVE_TO | COMMAND_LINE_TO => {
+                for i in 0..count {
+                    for j in 0..2 {
+                        let param = match commands.next() {
+                            Some(&param) => param,
+                            None => return Err(malformed("missing command parameters")),
+                        };
+                        cursor[j] = match cursor[j].checked_add(unzigzag(param)) {
+                            Some(ordinate) => ordinate,
+                            None => return Err(malformed("coordinate out of range")),
+                        };
+                    }
+                    if id == COMMAND_MOVE_TO && (i == 0 || geom_type == GEOM_TYPE_POINT) {
+                        lines.push(vec![cursor]);
+                    } else {
+                        match lines.last_mut() {
+                            Some(line) => line.push(cursor),
+                            None => return Err(malformed("LineTo without MoveTo")),
+                        }
+                    }
+                }
+            }
+            COMMAND_CLOSE_PATH => {
+                match lines.last_mut() {
+                    Some(line) => {
+                        let first = line[0];
+                        line.push(first);
+                    }
+                    None => return Err(malformed("ClosePath without MoveTo")),
+                }
+            }
+            _ => return Err(malformed("unknown command")),
+        }
+    }
+
+    if lines.is_empty() {
+        return Ok(None);
+    }
+    Ok(match geom_type {
+        GEOM_TYPE_POINT => {
+            Some(DecodedGeometry::Points(lines.into_iter().map(|line| line[0]).collect()))
+        }
+        GEOM_TYPE_LINESTRING => Some(DecodedGeometry::Lines(lines)),
+        GEOM_TYPE_POLYGON => {
+            let mut polygons: Vec<Vec<Vec<[i64; 2]>>> = vec![];
+            for ring in lines {
+                let area = ring_area(&ring);
+                if area > 0.0 || polygons.is_empty() {
+                    polygons.push(vec![ring]);
+                } else if area < 0.0 {
+                    polygons.last_mut().unwrap().push(ring);
+                }
+            }
+            Some(DecodedGeometry::Polygons(polygons))
+        }
+        _ => None,
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use {Error, Feature, FeatureCollection, Geometry, Value};
+    use protobuf::zigzag;
+    use super::{decode, decode_geometry, decode_wgs84, encode, encode_wgs84, Grid, TileId,
+                DEFAULT_EXTENT, GEOM_TYPE_LINESTRING, GEOM_TYPE_POINT, GEOM_TYPE_POLYGON};
+
+    fn commands(value: Value) -> Vec<(u64, Vec<u32>)> {
+        let grid = Grid {
+            extent: DEFAULT_EXTENT,
+            tile: None,
+        };
+        grid.geometries(&value)
+    }
+
+    #[test]
+    fn specification_examples() {
+        assert_eq!(commands(Value::Point(vec![25.0, 17.0])),
+                   vec![(GEOM_TYPE_POINT, vec![9, 50, 34])]);
+        assert_eq!(commands(Value::MultiPoint(vec![vec![5.0, 7.0], vec![3.0, 2.0]])),
+                   vec![(GEOM_TYPE_POINT, vec![17, 10, 14, 3, 9])]);
+        assert_eq!(commands(Value::LineString(vec![vec![2.0, 2.0],
+                                                   vec![2.0, 10.0],
+                                                   vec![10.0, 10.0]])),
+                   vec![(GEOM_TYPE_LINESTRING, vec![9, 4, 4, 18, 0, 16, 16, 0])]);
+        // Wound counter-clockwise on screen, so rewound to the specification's order
+        assert_eq!(commands(Value::Polygon(vec![vec![vec![3.0, 6.0],
+                                                     vec![20.0, 34.0],
+                                                     vec![8.0, 12.0],
+                                                     vec![3.0, 6.0]]])),
+                   vec![(GEOM_TYPE_POLYGON, vec![9, 6, 12, 18, 10, 12, 24, 44, 15])]);
+        assert_eq!(commands(Value::LineString(vec![vec![2.0, 2.0], vec![2.2, 1.9]])), vec![]);
+    }
+
+    #[test]
+    fn round_trip_tile_local() {
+        let exterior = vec![vec![0.0, 0.0], vec![10.0, 0.0], vec![10.0, 10.0], vec![0.0, 10.0],
+                            vec![0.0, 0.0]];
+        let interior = vec![vec![2.0, 2.0], vec![2.0, 4.0], vec![4.0, 4.0], vec![4.0, 2.0],
+                            vec![2.0, 2.0]];
+        let other = vec![vec![20.0, 20.0], vec![30.0, 20.0], vec![30.0, 30.0],
+                         vec![20.0, 20.0]];
+        let fc = FeatureCollection::builder()
+            .feature(Feature::builder()
+                .geometry(Value::MultiPolygon(vec![vec![exterior, interior], vec![other]]))
+                .id(7)
+                .property("name", "polygons")
+                .property("height", 12.5)
+                .property("levels", -3)
+                .property("visible", true)
+                .build())
+            .feature(Feature::builder()
+                .geometry(Value::MultiLineString(vec![vec![vec![1.0, 1.0], vec![2.0, 3.0]],
+                                                      vec![vec![5.0, 5.0], vec![6.0, 8.0]]]))
+                .property("name", "lines")
+                .build())
+            .build();
+
+        let tile = encode(vec![("layer", &fc)], DEFAULT_EXTENT);
+        let layers = decode(&tile).unwrap();
+        assert_eq!(layers.len(), 1);
+        assert_eq!(layers["layer"], fc);
+    }
+
+    #[test]
+    fn encoding_rules() {
+        let fc = FeatureCollection::builder()
+            .feature(Feature::builder()
+                .geometry(Geometry::new(Value::GeometryCollection(vec![
+                    Geometry::new(Value::Point(vec![1.0, 1.0])),
+                    Geometry::new(Value::LineString(vec![vec![1.0, 1.0], vec![2.0, 2.0]])),
+                ])))
+                .id("not a number")
+                .property("nested", json!({"a": [1, 2]}))
+                .property("missing", json!(null))
+                .build())
+            .feature(Feature::builder().property("name", "no geometry").build())
+            .build();
+
+        let layers = decode(&encode(vec![("layer", &fc)], 256)).unwrap();
+        let features = &layers["layer"].features;
+        assert_eq!(features.len(), 2);
+        assert_eq!(features[0].geometry, Some(Geometry::new(Value::Point(vec![1.0, 1.0]))));
+        assert_eq!(features[1].geometry.as_ref().unwrap().value,
+                   Value::LineString(vec![vec![1.0, 1.0], vec![2.0, 2.0]]));
+        for feature in features {
+            assert_eq!(feature.id, None);
+            assert_eq!(feature.property::<String>("nested"),
+                       Some(String::from(r#"{"a":[1,2]}"#)));
+            assert!(!feature.contains_property("missing"));
+        }
+    }
+
+    #[test]
+    fn round_trip_wgs84() {
+        let tile = TileId::new(10, 511, 340);
+        let bbox = tile.bbox();
+        let position = vec![(bbox[0] + bbox[2]) / 2.0, (bbox[1] + bbox[3]) / 2.0];
+        let fc = FeatureCollection::builder()
+            .feature(Feature::builder().geometry(Value::Point(position.clone())).build())
+            .build();
+
+        let encoded = encode_wgs84(vec![("points", &fc)], tile, DEFAULT_EXTENT);
+        let local = decode(&encoded).unwrap();
+        match local["points"].features[0].geometry.as_ref().unwrap().value {
+            Value::Point(ref p) => assert_eq!(p[0], 2048.0),
+            ref other => panic!("unexpected geometry {:?}", other),
+        }
+
+        let decoded = decode_wgs84(&encoded, tile).unwrap();
+        match decoded["points"].features[0].geometry.as_ref().unwrap().value {
+            Value::Point(ref p) => {
+                // Within one tile-local unit
+                assert!((p[0] - position[0]).abs() < (bbox[2] - bbox[0]) / 4096.0);
+                assert!((p[1] - position[1]).abs() < (bbox[3] - bbox[1]) / 4096.0);
+            }
+            ref other => panic!("unexpected geometry {:?}", other),
+        }
+    }
+
+    #[test]
+    fn tile_bbox() {
+        let bbox = TileId::new(0, 0, 0).bbox();
+        assert_eq!(bbox[0], -180.0);
+        assert_eq!(bbox[2], 180.0);
+        assert!((bbox[3] - 85.0511287798).abs() < 1e-9);
+        assert_eq!(TileId::new(1, 1, 0).bbox()[..2], [0.0, 0.0]);
+        assert_eq!(TileId::new(64, 0, 0).bbox()[0], -180.0);
+        assert_eq!(TileId::new(255, 0, 0).bbox()[3], TileId::new(0, 0, 0).bbox()[3]);
+    }
+
+    #[test]
+    fn decode_errors() {
+        let fc = FeatureCollection::builder()
+            .feature(Feature::builder()
+                .geometry(Value::LineString(vec![vec![1.0, 1.0], vec![2.0, 2.0]]))
+                .build())
+            .build();
+        let tile = encode(vec![("layer", &fc)], DEFAULT_EXTENT);
+        for len in 0..tile.len() - 1 {
+            match decode(&tile[..len]) {
+                Ok(..) if len == 0 => {}
+                Err(Error::MalformedMvt(..)) => {}
+                other => panic!("unexpected result for length {}: {:?}", len, other),
+            }
+        }
+        // A LineTo command with a huge count but no parameters
+        assert!(decode(&[0x1a, 0x0c, 0x0a, 0x01, 0x6c, 0x12, 0x07, 0x18, 0x02, 0x22, 0x03, 0x09,
+                         0xff, 0x0f])
+            .is_err());
+        // Two points whose deltas add up beyond the range of the cursor
+        let max = zigzag(i64::max_value());
+        match decode_geometry(GEOM_TYPE_POINT, &[2 << 3 | 1, max, 0, max, 0]) {
+            Err(Error::MalformedMvt(..)) => {}
+            other => panic!("unexpected result: {:?}", other.is_ok()),
+        }
+    }
+}
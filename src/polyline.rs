@@ -0,0 +1,209 @@
+// Copyright 2015 The GeoRust Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Encoded polyline conversion
+//!
+//! [Encoded Polyline Algorithm Format](https://developers.google.com/maps/documentation/utilities/polylinealgorithm)
+//!
+//! Encoded polylines store latitude before longitude, whereas GeoJSON positions store
+//! longitude first; the axes are swapped when encoding and decoding. Google uses a precision
+//! of 5 decimal digits, while OSRM and Valhalla can also produce polylines with 6. Ordinates
+//! beyond the first two are not encoded.
+
+use {Error, LineStringType, Position, Value};
+
+
+impl Value {
+    /// Decodes an encoded polyline into a `Value::LineString`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geojson::Value;
+    ///
+    /// let value = Value::from_polyline("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 5).unwrap();
+    /// assert_eq!(value,
+    ///            Value::LineString(vec![vec![-120.2, 38.5],
+    ///                                   vec![-120.95, 40.7],
+    ///                                   vec![-126.453, 43.252]]));
+    /// assert_eq!(value.to_polylines(5).unwrap(), vec!["_p~iF~ps|U_ulLnnqC_mqNvxq`@"]);
+    /// ```
+    pub fn from_polyline(polyline: &str, precision: u32) -> Result<Value, Error> {
+        decode(polyline, precision).map(Value::LineString)
+    }
+
+    /// Decodes several encoded polylines into a `Value::MultiLineString`.
+    pub fn from_polylines<'a, I>(polylines: I, precision: u32) -> Result<Value, Error>
+        where I: IntoIterator<Item = &'a str>
+    {
+        let mut lines = vec![];
+        for polyline in polylines {
+            lines.push(try!(decode(polyline, precision)));
+        }
+        Ok(Value::MultiLineString(lines))
+    }
+
+    /// Encodes a `LineString` as a single polyline, or each line of a `MultiLineString` as
+    /// its own polyline. Returns `None` for other geometry types.
+    pub fn to_polylines(&self, precision: u32) -> Option<Vec<String>> {
+        match *self {
+            Value::LineString(ref line) => Some(vec![encode(line, precision)]),
+            Value::MultiLineString(ref lines) => {
+                Some(lines.iter().map(|line| encode(line, precision)).collect())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Largest magnitude of an encoded ordinate, so that the difference of two always fits an `i64`
+const MAX_UNITS: f64 = (1u64 << 61) as f64;
+
+/// Encodes positions as a polyline with `precision` decimal digits.
+///
+/// Scaled ordinates are clamped to ±2^61, which only affects precisions far beyond
+/// what longitudes and latitudes need.
+pub fn encode(positions: &[Position], precision: u32) -> String {
+    let factor = 10f64.powi(precision as i32);
+    let units = |ordinate: f64| (ordinate * factor).round().max(-MAX_UNITS).min(MAX_UNITS) as i64;
+    let mut polyline = String::new();
+    let mut previous = [0i64, 0i64];
+    for position in positions.iter().filter(|p| p.len() >= 2) {
+        // Latitude comes first
+        let current = [units(position[1]), units(position[0])];
+        for i in 0..2 {
+            encode_number(&mut polyline, current[i] - previous[i]);
+        }
+        previous = current;
+    }
+    polyline
+}
+
+/// Decodes a polyline with `precision` decimal digits into longitude, latitude positions.
+pub fn decode(polyline: &str, precision: u32) -> Result<LineStringType, Error> {
+    let factor = 10f64.powi(precision as i32);
+    let mut bytes = polyline.bytes();
+    let mut positions = vec![];
+    let mut current = [0i64, 0i64];
+    loop {
+        let latitude = match try!(decode_number(&mut bytes)) {
+            Some(delta) => current[0].checked_add(delta),
+            None => break,
+        };
+        let longitude = match try!(decode_number(&mut bytes)) {
+            Some(delta) => current[1].checked_add(delta),
+            None => return Err(Error::MalformedPolyline(String::from("missing longitude"))),
+        };
+        current = match (latitude, longitude) {
+            (Some(latitude), Some(longitude)) => [latitude, longitude],
+            _ => return Err(Error::MalformedPolyline(String::from("coordinate out of range"))),
+        };
+        positions.push(vec![current[1] as f64 / factor, current[0] as f64 / factor]);
+    }
+    Ok(positions)
+}
+
+fn encode_number(polyline: &mut String, number: i64) {
+    let mut value = ((number << 1) ^ (number >> 63)) as u64;
+    while value >= 0x20 {
+        polyline.push((((value & 0x1f) | 0x20) as u8 + 63) as char);
+        value >>= 5;
+    }
+    polyline.push((value as u8 + 63) as char);
+}
+
+/// Reads one zigzag encoded number, or `None` at the end of the input.
+fn decode_number<I: Iterator<Item = u8>>(bytes: &mut I) -> Result<Option<i64>, Error> {
+    let mut value = 0u64;
+    let mut shift = 0;
+    loop {
+        let byte = match bytes.next() {
+            Some(byte) => byte,
+            None if shift == 0 => return Ok(None),
+            None => return Err(Error::MalformedPolyline(String::from("unexpected end"))),
+        };
+        if byte < 63 || byte > 126 {
+            return Err(Error::MalformedPolyline(format!("invalid character {:?}",
+                                                        byte as char)));
+        }
+        if shift > 60 {
+            return Err(Error::MalformedPolyline(String::from("number too large")));
+        }
+        let chunk = (byte - 63) as u64;
+        value |= (chunk & 0x1f) << shift;
+        shift += 5;
+        if chunk & 0x20 == 0 {
+            break;
+        }
+    }
+    Ok(Some(((value >> 1) as i64) ^ -((value & 1) as i64)))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use {Error, Value};
+    use super::{decode, encode, encode_number};
+
+    #[test]
+    fn encode_decode_precision_6() {
+        let line = vec![vec![13.388860, 52.517037], vec![13.397634, 52.529407, 34.0]];
+        let polyline = encode(&line, 6);
+        assert_eq!(polyline, "yikdcBwbepXcdWkcP");
+        assert_eq!(decode(&polyline, 6).unwrap(),
+                   vec![vec![13.38886, 52.517037], vec![13.397634, 52.529407]]);
+    }
+
+    #[test]
+    fn negative_and_large_values() {
+        let line = vec![vec![-179.98765, -89.5], vec![179.98765, 89.5], vec![0.0, 0.0]];
+        assert_eq!(decode(&encode(&line, 5), 5).unwrap(), line);
+
+        // Ordinates too large for the precision are clamped rather than overflowing
+        let line = vec![vec![0.0, -90.0], vec![0.0, 90.0]];
+        let decoded = decode(&encode(&line, 18), 18).unwrap();
+        assert_eq!(decoded[0][1], -decoded[1][1]);
+        assert!(decoded[1][1] > 2.0 && decoded[1][1] < 90.0);
+    }
+
+    #[test]
+    fn multi_line_string() {
+        let value = Value::MultiLineString(vec![vec![vec![1.0, 2.0], vec![3.0, 4.0]],
+                                                vec![vec![-1.0, -2.0], vec![-3.0, -4.0]]]);
+        let polylines = value.to_polylines(5).unwrap();
+        assert_eq!(polylines.len(), 2);
+        let decoded = Value::from_polylines(polylines.iter().map(|p| p.as_str()), 5).unwrap();
+        assert_eq!(decoded, value);
+        assert_eq!(Value::Point(vec![1.0, 2.0]).to_polylines(5), None);
+    }
+
+    #[test]
+    fn decode_errors() {
+        assert_eq!(decode("", 5).unwrap(), Vec::<Vec<f64>>::new());
+        match decode("_p~iF", 5) {
+            Err(Error::MalformedPolyline(..)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(decode("_p~i", 5).is_err());
+        assert!(decode("_p~iF~ps| ", 5).is_err());
+        assert!(decode("~~~~~~~~~~~~~~~~?", 5).is_err());
+
+        let mut overflowing = String::new();
+        for _ in 0..4 {
+            encode_number(&mut overflowing, 1 << 62);
+        }
+        assert_eq!(decode(&overflowing, 5),
+                   Err(Error::MalformedPolyline(String::from("coordinate out of range"))));
+    }
+}
@@ -0,0 +1,517 @@
+// Copyright 2015 The GeoRust Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::f64::consts::PI;
+
+use {Bbox, Crs, Feature, FeatureCollection, GeoJson, Geometry, Position, Value};
+
+
+/// WGS84 semi-major axis, in metres
+const WGS84_A: f64 = 6378137.0;
+
+/// WGS84 flattening
+const WGS84_F: f64 = 1.0 / 298.257223563;
+
+/// Latitude bounds beyond which Web Mercator is undefined
+const WEB_MERCATOR_MAX_LAT: f64 = 85.0511287798066;
+
+/// UTM scale factor on the central meridian
+const UTM_K0: f64 = 0.9996;
+
+const UTM_FALSE_EASTING: f64 = 500000.0;
+const UTM_FALSE_NORTHING_SOUTH: f64 = 10000000.0;
+
+/// Supported coordinate reference systems
+///
+/// Positions are always handled in GeoJSON axis order, i.e. longitude/easting first and
+/// latitude/northing second. Any ordinates after the first two are left untouched.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+    /// Geographic WGS84 longitude/latitude in degrees (EPSG:4326, CRS84)
+    Wgs84,
+
+    /// Spherical "Web" Mercator in metres (EPSG:3857)
+    WebMercator,
+
+    /// Universal Transverse Mercator on the WGS84 ellipsoid, in metres
+    /// (EPSG:326xx for the northern hemisphere, EPSG:327xx for the southern)
+    Utm { zone: u8, north: bool },
+}
+
+impl Projection {
+    /// Returns the UTM projection whose zone contains the given WGS84 position.
+    pub fn utm_for(lon: f64, lat: f64) -> Projection {
+        let zone = (((lon + 180.0) / 6.0).floor() as i64).max(0).min(59) + 1;
+        Projection::Utm {
+            zone: zone as u8,
+            north: lat >= 0.0,
+        }
+    }
+
+    /// Recognises the projection described by a `Crs`, if it is one of the supported ones.
+    ///
+    /// Any name understood by `CrsId` is accepted. Linked CRSs are never recognised.
+    pub fn from_crs(crs: &Crs) -> Option<Projection> {
+        let id = match crs.id() {
+            Some(id) => id,
+            None => return None,
+        };
+        match (&id.authority as &str, &id.code as &str) {
+            ("OGC", "CRS84") | ("EPSG", "4326") => Some(Projection::Wgs84),
+            ("EPSG", "3857") | ("EPSG", "900913") | ("EPSG", "3785") => {
+                Some(Projection::WebMercator)
+            }
+            ("EPSG", code) => {
+                let code = match code.parse::<u32>() {
+                    Ok(code) => code,
+                    Err(..) => return None,
+                };
+                let (zone, north) = (code % 100, code / 100 == 326);
+                if zone < 1 || zone > 60 || (code / 100 != 326 && code / 100 != 327) {
+                    return None;
+                }
+                Some(Projection::Utm {
+                    zone: zone as u8,
+                    north: north,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the named `Crs` describing this projection.
+    pub fn crs(&self) -> Crs {
+        let name = match *self {
+            Projection::Wgs84 => String::from("urn:ogc:def:crs:OGC:1.3:CRS84"),
+            Projection::WebMercator => String::from("urn:ogc:def:crs:EPSG::3857"),
+            Projection::Utm { zone, north } => {
+                let base = if north { 32600 } else { 32700 };
+                format!("urn:ogc:def:crs:EPSG::{}", base + zone as u32)
+            }
+        };
+        Crs::Named { name: name }
+    }
+
+    /// Converts a WGS84 longitude/latitude pair into this projection.
+    pub fn forward(&self, lon: f64, lat: f64) -> (f64, f64) {
+        match *self {
+            Projection::Wgs84 => (lon, lat),
+            Projection::WebMercator => {
+                let lat = lat.max(-WEB_MERCATOR_MAX_LAT).min(WEB_MERCATOR_MAX_LAT);
+                let x = WGS84_A * lon.to_radians();
+                let y = WGS84_A * (PI / 4.0 + lat.to_radians() / 2.0).tan().ln();
+                (x, y)
+            }
+            Projection::Utm { zone, north } => {
+                let (easting, northing) = TransverseMercator::new(zone).forward(lon, lat);
+                let false_northing = if north { 0.0 } else { UTM_FALSE_NORTHING_SOUTH };
+                (easting + UTM_FALSE_EASTING, northing + false_northing)
+            }
+        }
+    }
+
+    /// Converts a pair of coordinates in this projection back into WGS84 longitude/latitude.
+    pub fn inverse(&self, x: f64, y: f64) -> (f64, f64) {
+        match *self {
+            Projection::Wgs84 => (x, y),
+            Projection::WebMercator => {
+                let lon = (x / WGS84_A).to_degrees();
+                let lat = (2.0 * (y / WGS84_A).exp().atan() - PI / 2.0).to_degrees();
+                (lon, lat)
+            }
+            Projection::Utm { zone, north } => {
+                let false_northing = if north { 0.0 } else { UTM_FALSE_NORTHING_SOUTH };
+                TransverseMercator::new(zone)
+                    .inverse(x - UTM_FALSE_EASTING, y - false_northing)
+            }
+        }
+    }
+
+    /// Converts a single position from this projection into `to`, in place.
+    pub fn transform_position(&self, to: &Projection, position: &mut Position) {
+        if position.len() < 2 || self == to {
+            return;
+        }
+        let (lon, lat) = self.inverse(position[0], position[1]);
+        let (x, y) = to.forward(lon, lat);
+        position[0] = x;
+        position[1] = y;
+    }
+}
+
+/// Transverse Mercator on the WGS84 ellipsoid using Krüger's series to fourth order in `n`,
+/// which is accurate to well under a millimetre within a UTM zone.
+struct TransverseMercator {
+    lon0: f64,
+    n: f64,
+    a: f64,
+    alpha: [f64; 4],
+    beta: [f64; 4],
+    delta: [f64; 4],
+}
+
+impl TransverseMercator {
+    fn new(zone: u8) -> Self {
+        let n = WGS84_F / (2.0 - WGS84_F);
+        let n2 = n * n;
+        let n3 = n2 * n;
+        let n4 = n3 * n;
+        TransverseMercator {
+            lon0: (zone as f64 * 6.0 - 183.0).to_radians(),
+            n: n,
+            a: WGS84_A / (1.0 + n) * (1.0 + n2 / 4.0 + n4 / 64.0),
+            alpha: [n / 2.0 - 2.0 * n2 / 3.0 + 5.0 * n3 / 16.0 + 41.0 * n4 / 180.0,
+                    13.0 * n2 / 48.0 - 3.0 * n3 / 5.0 + 557.0 * n4 / 1440.0,
+                    61.0 * n3 / 240.0 - 103.0 * n4 / 140.0,
+                    49561.0 * n4 / 161280.0],
+            beta: [n / 2.0 - 2.0 * n2 / 3.0 + 37.0 * n3 / 96.0 - n4 / 360.0,
+                   n2 / 48.0 + n3 / 15.0 - 437.0 * n4 / 1440.0,
+                   17.0 * n3 / 480.0 - 37.0 * n4 / 840.0,
+                   4397.0 * n4 / 161280.0],
+            delta: [2.0 * n - 2.0 * n2 / 3.0 - 2.0 * n3 + 116.0 * n4 / 45.0,
+                    7.0 * n2 / 3.0 - 8.0 * n3 / 5.0 - 227.0 * n4 / 45.0,
+                    56.0 * n3 / 15.0 - 136.0 * n4 / 35.0,
+                    4279.0 * n4 / 630.0],
+        }
+    }
+
+    /// Returns easting and northing relative to the central meridian and the equator.
+    fn forward(&self, lon: f64, lat: f64) -> (f64, f64) {
+        let phi = lat.to_radians();
+        let lambda = lon.to_radians() - self.lon0;
+        let k = 2.0 * self.n.sqrt() / (1.0 + self.n);
+        let t = (phi.sin().atanh() - k * (k * phi.sin()).atanh()).sinh();
+        let xi_prime = t.atan2(lambda.cos());
+        let eta_prime = (lambda.sin() / (1.0 + t * t).sqrt()).atanh();
+
+        let mut xi = xi_prime;
+        let mut eta = eta_prime;
+        for (j, alpha) in self.alpha.iter().enumerate() {
+            let j2 = 2.0 * (j + 1) as f64;
+            xi += alpha * (j2 * xi_prime).sin() * (j2 * eta_prime).cosh();
+            eta += alpha * (j2 * xi_prime).cos() * (j2 * eta_prime).sinh();
+        }
+
+        (UTM_K0 * self.a * eta, UTM_K0 * self.a * xi)
+    }
+
+    /// Inverse of `forward`.
+    fn inverse(&self, easting: f64, northing: f64) -> (f64, f64) {
+        let xi = northing / (UTM_K0 * self.a);
+        let eta = easting / (UTM_K0 * self.a);
+
+        let mut xi_prime = xi;
+        let mut eta_prime = eta;
+        for (j, beta) in self.beta.iter().enumerate() {
+            let j2 = 2.0 * (j + 1) as f64;
+            xi_prime -= beta * (j2 * xi).sin() * (j2 * eta).cosh();
+            eta_prime -= beta * (j2 * xi).cos() * (j2 * eta).sinh();
+        }
+
+        let chi = (xi_prime.sin() / eta_prime.cosh()).asin();
+        let mut phi = chi;
+        for (j, delta) in self.delta.iter().enumerate() {
+            phi += delta * (2.0 * (j + 1) as f64 * chi).sin();
+        }
+        let lambda = self.lon0 + eta_prime.sinh().atan2(xi_prime.cos());
+
+        (lambda.to_degrees(), phi.to_degrees())
+    }
+}
+
+/// Reprojection of GeoJSON objects between two `Projection`s
+///
+/// Every position is converted, any `crs` member that is present is replaced with the target
+/// projection's `Crs`, and any `bbox` member that is present is recomputed from the converted
+/// positions. Members that are absent stay absent.
+///
+/// # Examples
+///
+/// ```
+/// use geojson::{Geometry, Projection, Reproject, Value};
+///
+/// let mut geometry = Geometry::new(Value::Point(vec![180.0, 0.0]));
+/// geometry.reproject(&Projection::Wgs84, &Projection::WebMercator);
+///
+/// if let Value::Point(ref position) = geometry.value {
+///     assert!((position[0] - 20037508.34).abs() < 0.01);
+///     assert!(position[1].abs() < 0.01);
+/// }
+/// ```
+pub trait Reproject {
+    fn reproject(&mut self, from: &Projection, to: &Projection);
+}
+
+impl Reproject for Value {
+    fn reproject(&mut self, from: &Projection, to: &Projection) {
+        if let Value::GeometryCollection(ref mut geometries) = *self {
+            for geometry in geometries {
+                geometry.reproject(from, to);
+            }
+            return;
+        }
+        for position in self.coords_mut() {
+            from.transform_position(to, position);
+        }
+    }
+}
+
+impl Reproject for Geometry {
+    fn reproject(&mut self, from: &Projection, to: &Projection) {
+        self.value.reproject(from, to);
+        if self.crs.is_some() {
+            self.crs = Some(to.crs());
+        }
+        if let Some(ref mut bbox) = self.bbox {
+            let mut bounds = Bounds::new(bbox.len() / 2);
+            bounds.extend_value(&self.value);
+            bounds.update(bbox);
+        }
+    }
+}
+
+impl Reproject for Feature {
+    fn reproject(&mut self, from: &Projection, to: &Projection) {
+        if let Some(ref mut geometry) = self.geometry {
+            geometry.reproject(from, to);
+        }
+        if self.crs.is_some() {
+            self.crs = Some(to.crs());
+        }
+        if let Some(ref mut bbox) = self.bbox {
+            let mut bounds = Bounds::new(bbox.len() / 2);
+            if let Some(ref geometry) = self.geometry {
+                bounds.extend_value(&geometry.value);
+            }
+            bounds.update(bbox);
+        }
+    }
+}
+
+impl Reproject for FeatureCollection {
+    fn reproject(&mut self, from: &Projection, to: &Projection) {
+        for feature in &mut self.features {
+            feature.reproject(from, to);
+        }
+        if self.crs.is_some() {
+            self.crs = Some(to.crs());
+        }
+        if let Some(ref mut bbox) = self.bbox {
+            let mut bounds = Bounds::new(bbox.len() / 2);
+            for geometry in self.features.iter().filter_map(|f| f.geometry.as_ref()) {
+                bounds.extend_value(&geometry.value);
+            }
+            bounds.update(bbox);
+        }
+    }
+}
+
+impl Reproject for GeoJson {
+    fn reproject(&mut self, from: &Projection, to: &Projection) {
+        match *self {
+            GeoJson::Geometry(ref mut geometry) => geometry.reproject(from, to),
+            GeoJson::Feature(ref mut feature) => feature.reproject(from, to),
+            GeoJson::FeatureCollection(ref mut fc) => fc.reproject(from, to),
+        }
+    }
+}
+
+/// Running minimum and maximum of the first `dims` ordinates of a set of positions
+struct Bounds {
+    min: Vec<f64>,
+    max: Vec<f64>,
+}
+
+impl Bounds {
+    fn new(dims: usize) -> Self {
+        Bounds {
+            min: vec![::std::f64::INFINITY; dims],
+            max: vec![::std::f64::NEG_INFINITY; dims],
+        }
+    }
+
+    fn extend(&mut self, position: &Position) {
+        for (i, ordinate) in position.iter().take(self.min.len()).enumerate() {
+            self.min[i] = self.min[i].min(*ordinate);
+            self.max[i] = self.max[i].max(*ordinate);
+        }
+    }
+
+    fn extend_value(&mut self, value: &Value) {
+        if let Value::GeometryCollection(ref geometries) = *value {
+            for geometry in geometries {
+                self.extend_value(&geometry.value);
+            }
+            return;
+        }
+        for position in value.coords() {
+            self.extend(position);
+        }
+    }
+
+    /// Overwrites `bbox` with these bounds, leaving it alone if no position was seen.
+    fn update(self, bbox: &mut Bbox) {
+        if self.min.iter().any(|m| m.is_infinite()) {
+            return;
+        }
+        let mut min = self.min;
+        min.extend(self.max);
+        *bbox = min;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use {Crs, Feature, FeatureCollection, GeoJson, Geometry, Value};
+    use super::{Projection, Reproject};
+
+    fn assert_close(a: f64, b: f64, epsilon: f64) {
+        assert!((a - b).abs() <= epsilon, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn web_mercator_round_trip() {
+        let (x, y) = Projection::WebMercator.forward(-120.66029, 35.2812);
+        assert_close(x, -13431842.04, 0.01);
+        assert_close(y, 4202161.07, 0.01);
+
+        let (lon, lat) = Projection::WebMercator.inverse(x, y);
+        assert_close(lon, -120.66029, 1e-9);
+        assert_close(lat, 35.2812, 1e-9);
+    }
+
+    #[test]
+    fn web_mercator_clamps_poles() {
+        let (_, y) = Projection::WebMercator.forward(0.0, 90.0);
+        assert!(y.is_finite());
+        assert_close(y, 20037508.34, 0.01);
+    }
+
+    #[test]
+    fn utm_round_trip() {
+        let utm = Projection::utm_for(-120.66029, 35.2812);
+        assert_eq!(utm, Projection::Utm { zone: 10, north: true });
+
+        let (easting, northing) = utm.forward(-120.66029, 35.2812);
+        assert_close(easting, 712789.28, 0.01);
+        assert_close(northing, 3906738.07, 0.01);
+
+        let (lon, lat) = utm.inverse(easting, northing);
+        assert_close(lon, -120.66029, 1e-9);
+        assert_close(lat, 35.2812, 1e-9);
+
+        let south = Projection::utm_for(151.2093, -33.8688);
+        assert_eq!(south, Projection::Utm { zone: 56, north: false });
+        let (easting, northing) = south.forward(151.2093, -33.8688);
+        assert_close(easting, 334368.63, 0.01);
+        assert_close(northing, 6250948.35, 0.01);
+    }
+
+    #[test]
+    fn crs_names() {
+        let named = |name: &str| Crs::Named { name: String::from(name) };
+        assert_eq!(Projection::from_crs(&named("EPSG:4326")), Some(Projection::Wgs84));
+        assert_eq!(Projection::from_crs(&named("urn:ogc:def:crs:OGC:1.3:CRS84")),
+                   Some(Projection::Wgs84));
+        assert_eq!(Projection::from_crs(&named("urn:ogc:def:crs:EPSG::3857")),
+                   Some(Projection::WebMercator));
+        assert_eq!(Projection::from_crs(&named("EPSG:32733")),
+                   Some(Projection::Utm { zone: 33, north: false }));
+        assert_eq!(Projection::from_crs(&named("EPSG:2193")), None);
+
+        let utm = Projection::Utm { zone: 33, north: false };
+        assert_eq!(Projection::from_crs(&utm.crs()), Some(utm));
+    }
+
+    #[test]
+    fn reproject_feature_collection() {
+        let geojson_str = r#"{
+            "type": "FeatureCollection",
+            "crs": {"type": "name", "properties": {"name": "EPSG:4326"}},
+            "bbox": [-10.0, -10.0, 10.0, 10.0],
+            "features": [{
+                "type": "Feature",
+                "properties": {},
+                "geometry": {
+                    "type": "GeometryCollection",
+                    "geometries": [
+                        {"type": "Point", "coordinates": [10.0, 10.0, 42.0]},
+                        {
+                            "type": "LineString",
+                            "crs": {"type": "name", "properties": {"name": "EPSG:4326"}},
+                            "bbox": [-10.0, -10.0, 0.0, 0.0],
+                            "coordinates": [[-10.0, -10.0], [0.0, 0.0]]
+                        }
+                    ]
+                }
+            }]
+        }"#;
+        let mut geojson = geojson_str.parse::<GeoJson>().unwrap();
+        geojson.reproject(&Projection::Wgs84, &Projection::WebMercator);
+
+        let fc = match geojson {
+            GeoJson::FeatureCollection(fc) => fc,
+            _ => unreachable!(),
+        };
+        assert_eq!(fc.crs, Some(Projection::WebMercator.crs()));
+
+        let bbox = fc.bbox.clone().unwrap();
+        assert_close(bbox[0], -1113194.9, 0.1);
+        assert_close(bbox[1], -1118889.97, 0.1);
+        assert_close(bbox[2], 1113194.9, 0.1);
+        assert_close(bbox[3], 1118889.97, 0.1);
+
+        let geometries = match fc.features[0].geometry {
+            Some(Geometry { value: Value::GeometryCollection(ref g), .. }) => g.clone(),
+            _ => unreachable!(),
+        };
+        match geometries[0].value {
+            Value::Point(ref p) => {
+                assert_close(p[0], bbox[2], 1e-6);
+                assert_close(p[1], bbox[3], 1e-6);
+                assert_eq!(p[2], 42.0);
+            }
+            _ => unreachable!(),
+        }
+        assert_eq!(geometries[1].crs, Some(Projection::WebMercator.crs()));
+        let nested = geometries[1].bbox.clone().unwrap();
+        assert_close(nested[0], bbox[0], 1e-6);
+        assert_close(nested[1], bbox[1], 1e-6);
+        assert_close(nested[2], 0.0, 1e-6);
+        assert_close(nested[3], 0.0, 1e-6);
+
+        let mut back = FeatureCollection { ..fc };
+        back.reproject(&Projection::WebMercator, &Projection::Wgs84);
+        assert_eq!(back.crs, Some(Projection::Wgs84.crs()));
+        let bbox = back.bbox.unwrap();
+        for (actual, expected) in bbox.iter().zip(&[-10.0, -10.0, 10.0, 10.0]) {
+            assert_close(*actual, *expected, 1e-9);
+        }
+    }
+
+    #[test]
+    fn reproject_leaves_absent_members_alone() {
+        let mut feature = Feature {
+            bbox: None,
+            crs: None,
+            geometry: None,
+            id: None,
+            properties: None,
+        };
+        feature.reproject(&Projection::Wgs84, &Projection::WebMercator);
+        assert_eq!(feature.crs, None);
+        assert_eq!(feature.bbox, None);
+    }
+}
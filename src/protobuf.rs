@@ -0,0 +1,171 @@
+// Copyright 2015 The GeoRust Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Minimal Protocol Buffers wire format support, shared by the MVT and Geobuf codecs
+
+use Error;
+
+pub const WIRE_VARINT: u8 = 0;
+pub const WIRE_FIXED64: u8 = 1;
+pub const WIRE_LENGTH_DELIMITED: u8 = 2;
+pub const WIRE_FIXED32: u8 = 5;
+
+pub fn zigzag(n: i64) -> u64 {
+    ((n << 1) ^ (n >> 63)) as u64
+}
+
+pub fn unzigzag(n: u64) -> i64 {
+    ((n >> 1) as i64) ^ -((n & 1) as i64)
+}
+
+pub struct Writer {
+    pub buf: Vec<u8>,
+}
+
+impl Writer {
+    pub fn new() -> Self {
+        Writer { buf: vec![] }
+    }
+
+    pub fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.buf.push((value as u8 & 0x7f) | 0x80);
+            value >>= 7;
+        }
+        self.buf.push(value as u8);
+    }
+
+    pub fn key(&mut self, field: u32, wire_type: u8) {
+        self.varint(((field << 3) | wire_type as u32) as u64);
+    }
+
+    pub fn varint_field(&mut self, field: u32, value: u64) {
+        self.key(field, WIRE_VARINT);
+        self.varint(value);
+    }
+
+    pub fn fixed64_field(&mut self, field: u32, value: u64) {
+        self.key(field, WIRE_FIXED64);
+        self.buf.extend((0..8).map(|i| (value >> (8 * i)) as u8));
+    }
+
+    pub fn bytes_field(&mut self, field: u32, bytes: &[u8]) {
+        self.key(field, WIRE_LENGTH_DELIMITED);
+        self.varint(bytes.len() as u64);
+        self.buf.extend_from_slice(bytes);
+    }
+
+    pub fn packed_field<I: IntoIterator<Item = u64>>(&mut self, field: u32, values: I) {
+        let mut packed = Writer::new();
+        for value in values {
+            packed.varint(value);
+        }
+        self.bytes_field(field, &packed.buf);
+    }
+}
+
+/// Reads a message, reporting malformed input through `error`.
+pub struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    error: fn(String) -> Error,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(buf: &'a [u8], error: fn(String) -> Error) -> Self {
+        Reader {
+            buf: buf,
+            pos: 0,
+            error: error,
+        }
+    }
+
+    fn malformed(&self, message: &str) -> Error {
+        (self.error)(String::from(message))
+    }
+
+    pub fn varint(&mut self) -> Result<u64, Error> {
+        let mut value = 0u64;
+        for shift in 0..10 {
+            let byte = match self.buf.get(self.pos) {
+                Some(&byte) => byte,
+                None => return Err(self.malformed("unexpected end of varint")),
+            };
+            self.pos += 1;
+            value |= ((byte & 0x7f) as u64) << (7 * shift);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(self.malformed("varint too long"))
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.buf.len() - self.pos < len {
+            return Err(self.malformed("unexpected end of field"));
+        }
+        let bytes = &self.buf[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    /// Reads the next field key, or `None` at the end of the message.
+    pub fn key(&mut self) -> Result<Option<(u64, u8)>, Error> {
+        if self.pos == self.buf.len() {
+            return Ok(None);
+        }
+        let key = try!(self.varint());
+        Ok(Some((key >> 3, (key & 0x7) as u8)))
+    }
+
+    pub fn bytes(&mut self) -> Result<&'a [u8], Error> {
+        let len = try!(self.varint());
+        if len > (self.buf.len() - self.pos) as u64 {
+            return Err(self.malformed("unexpected end of field"));
+        }
+        self.take(len as usize)
+    }
+
+    pub fn string(&mut self) -> Result<String, Error> {
+        match String::from_utf8(try!(self.bytes()).to_vec()) {
+            Ok(s) => Ok(s),
+            Err(..) => Err(self.malformed("invalid UTF-8 string")),
+        }
+    }
+
+    pub fn fixed(&mut self, len: usize) -> Result<u64, Error> {
+        let bytes = try!(self.take(len));
+        Ok(bytes.iter().rev().fold(0, |value, &byte| (value << 8) | byte as u64))
+    }
+
+    pub fn packed(&mut self) -> Result<Vec<u64>, Error> {
+        let mut reader = Reader::new(try!(self.bytes()), self.error);
+        let mut values = vec![];
+        while reader.pos < reader.buf.len() {
+            values.push(try!(reader.varint()));
+        }
+        Ok(values)
+    }
+
+    pub fn skip(&mut self, wire_type: u8) -> Result<(), Error> {
+        match wire_type {
+            WIRE_VARINT => try!(self.varint()),
+            WIRE_FIXED64 => try!(self.fixed(8)),
+            WIRE_LENGTH_DELIMITED => try!(self.bytes()).len() as u64,
+            WIRE_FIXED32 => try!(self.fixed(4)),
+            _ => return Err(self.malformed("unsupported wire type")),
+        };
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+// Copyright 2015 The GeoRust Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Ring and segment helpers, shared by the Esri JSON and Shapefile codecs, simplification and
+//! geodesic measurement
+
+use {LineStringType, PolygonType, Position};
+
+/// Twice the signed area of a ring, positive for counter-clockwise rings
+pub fn signed_area(ring: &[Position]) -> f64 {
+    ring.windows(2).map(|w| w[0][0] * w[1][1] - w[1][0] * w[0][1]).sum()
+}
+
+/// Whether `point` is strictly inside `ring`, by ray casting
+pub fn ring_contains(ring: &[Position], point: &Position) -> bool {
+    let mut inside = false;
+    for edge in ring.windows(2) {
+        let (a, b) = (&edge[0], &edge[1]);
+        if (a[1] > point[1]) != (b[1] > point[1]) &&
+           point[0] < (b[0] - a[0]) * (point[1] - a[1]) / (b[1] - a[1]) + a[0] {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+fn cross(a: &Position, b: &Position, c: &Position) -> f64 {
+    (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])
+}
+
+fn within(a: &Position, b: &Position, p: &Position) -> bool {
+    p[0] >= a[0].min(b[0]) && p[0] <= a[0].max(b[0]) && p[1] >= a[1].min(b[1]) &&
+    p[1] <= a[1].max(b[1])
+}
+
+/// Whether segments `ab` and `cd` cross or touch
+pub fn segments_intersect(a: &Position, b: &Position, c: &Position, d: &Position) -> bool {
+    let (d1, d2) = (cross(c, d, a), cross(c, d, b));
+    let (d3, d4) = (cross(a, b, c), cross(a, b, d));
+    if ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0)) &&
+       ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0)) {
+        return true;
+    }
+    (d1 == 0.0 && within(c, d, a)) || (d2 == 0.0 && within(c, d, b)) ||
+    (d3 == 0.0 && within(a, b, c)) || (d4 == 0.0 && within(a, b, d))
+}
+
+/// Rewinds a ring to be counter-clockwise if `ccw`, or clockwise otherwise.
+pub fn wound(ring: &[Position], ccw: bool) -> LineStringType {
+    let mut ring = ring.to_vec();
+    if (signed_area(&ring) > 0.0) != ccw {
+        ring.reverse();
+    }
+    ring
+}
+
+/// Groups rings where clockwise rings are outer rings and counter-clockwise rings are holes,
+/// as in Esri JSON and Shapefiles, into polygons of RFC 7946 orientation.
+pub fn group_clockwise(rings: Vec<LineStringType>) -> Vec<PolygonType> {
+    let mut polygons: Vec<PolygonType> = vec![];
+    let mut holes = vec![];
+    for mut ring in rings {
+        if ring.first() != ring.last() {
+            let first = ring[0].clone();
+            ring.push(first);
+        }
+        if signed_area(&ring) <= 0.0 {
+            ring.reverse();
+            polygons.push(vec![ring]);
+        } else {
+            holes.push(ring);
+        }
+    }
+    for hole in holes {
+        let container = polygons.iter()
+            .enumerate()
+            .filter(|&(_, polygon)| hole.iter().any(|p| ring_contains(&polygon[0], p)))
+            .min_by(|&(_, a), &(_, b)| {
+                signed_area(&a[0]).partial_cmp(&signed_area(&b[0])).unwrap()
+            })
+            .map(|(i, _)| i);
+        match container {
+            Some(i) => polygons[i].push(wound(&hole, false)),
+            // A hole outside of any outer ring is taken to be an outer ring wound the wrong way
+            None => polygons.push(vec![hole]),
+        }
+    }
+    polygons
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::group_clockwise;
+
+    fn square(x: f64, y: f64, size: f64, ccw: bool) -> Vec<Vec<f64>> {
+        let mut ring = vec![vec![x, y], vec![x + size, y], vec![x + size, y + size],
+                            vec![x, y + size], vec![x, y]];
+        if !ccw {
+            ring.reverse();
+        }
+        ring
+    }
+
+    #[test]
+    fn ring_grouping() {
+        let rings = vec![square(0.0, 0.0, 10.0, false),
+                         square(20.0, 0.0, 10.0, false),
+                         square(1.0, 1.0, 8.0, false),
+                         // Hole of the small square inside the large one
+                         square(2.0, 2.0, 1.0, true),
+                         square(21.0, 1.0, 1.0, true),
+                         // Counter-clockwise ring outside of any other
+                         square(50.0, 50.0, 1.0, true)];
+        assert_eq!(group_clockwise(rings),
+                   vec![vec![square(0.0, 0.0, 10.0, true)],
+                        vec![square(20.0, 0.0, 10.0, true), square(21.0, 1.0, 1.0, false)],
+                        vec![square(1.0, 1.0, 8.0, true), square(2.0, 2.0, 1.0, false)],
+                        vec![square(50.0, 50.0, 1.0, true)]]);
+    }
+}
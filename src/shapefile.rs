@@ -0,0 +1,1200 @@
+// Copyright 2015 The GeoRust Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! ESRI Shapefile reading and writing
+//!
+//! A [Shapefile](https://www.esri.com/library/whitepapers/pdfs/shapefile.pdf) is a set of
+//! files sharing a base name: the `.shp` file holds the geometries, the `.shx` file an index
+//! of their offsets, the `.dbf` file a dBase table of their attributes, and the optional
+//! `.prj` file the WKT of their coordinate reference system. Only local files are read and
+//! written; zipped Shapefiles need to be extracted first.
+//!
+//! When reading, DBF fields are typed into the properties of each feature: numbers without
+//! decimals become integers, other numbers floating-point numbers, logical fields booleans and
+//! dates `YYYY-MM-DD` strings. Blank values are read as null, as DBF cannot tell them apart
+//! from empty strings. Text is decoded as UTF-8 when valid, and as Latin-1 otherwise. A
+//! `.prj` naming an EPSG authority, or one of a few well-known Esri names, becomes a
+//! `Crs::Named`. Polygon rings are grouped and rewound as for Esri JSON, and `M` values are
+//! only kept for shapes that also have `Z` values.
+//!
+//! When writing, all geometries must be of the same kind: points, linestrings or polygons,
+//! single or multi. A mix of points and multipoints is written as multipoints. The field types
+//! are inferred from the properties, with mixed or non-scalar values stored as text, and field
+//! names are truncated to the ten bytes DBF allows. Shapefiles have no equivalent of
+//! `Feature::id`, which is not stored.
+//!
+//! # Example
+//!
+//! ```
+//! use geojson::{Feature, FeatureCollection, Value};
+//! use geojson::shapefile;
+//!
+//! let fc = FeatureCollection::builder()
+//!     .feature(Feature::builder()
+//!         .geometry(Value::Point(vec![1.5, 2.5]))
+//!         .property("name", "a")
+//!         .build())
+//!     .build();
+//!
+//! let path = std::env::temp_dir().join("geojson-shapefile-example.shp");
+//! shapefile::write(&path, &fc).unwrap();
+//! let read = shapefile::read(&path).unwrap();
+//! assert_eq!(read.features[0].geometry, fc.features[0].geometry);
+//! assert_eq!(read.features[0].properties, fc.features[0].properties);
+//! ```
+
+use std::cmp;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use json::{JsonObject, JsonValue};
+use rings;
+
+use {Crs, CrsEquivalence, CrsId, Error, Feature, FeatureCollection, Geometry, LineStringType,
+     Position, Value, util};
+
+
+const FILE_CODE: u32 = 9994;
+
+const VERSION: u32 = 1000;
+
+const HEADER_SIZE: usize = 100;
+
+/// Measures below this value denote missing measures
+const NO_DATA_LIMIT: f64 = -1e38;
+
+/// The value written for missing measures
+const NO_DATA: f64 = -1e39;
+
+const MAX_FIELD_LENGTH: usize = 254;
+
+const MAX_FIELD_NAME_LENGTH: usize = 10;
+
+const MAX_FIELDS: usize = 255;
+
+mod shape_type {
+    pub const NULL: u32 = 0;
+    pub const POINT: u32 = 1;
+    pub const POLY_LINE: u32 = 3;
+    pub const POLYGON: u32 = 5;
+    pub const MULTI_POINT: u32 = 8;
+    pub const MULTI_PATCH: u32 = 31;
+
+    /// Added to a base shape type for its `Z` variant
+    pub const Z: u32 = 10;
+
+    /// Added to a base shape type for its `M` variant
+    pub const M: u32 = 20;
+}
+
+/// Well-known coordinate reference systems and the Esri WKT describing them
+const PROJECTIONS: &'static [(&'static str, &'static str)] = &[
+    ("4326",
+     "GEOGCS[\"GCS_WGS_1984\",DATUM[\"D_WGS_1984\",SPHEROID[\"WGS_1984\",6378137.0,\
+      298.257223563]],PRIMEM[\"Greenwich\",0.0],UNIT[\"Degree\",0.0174532925199433]]"),
+    ("3857",
+     "PROJCS[\"WGS_1984_Web_Mercator_Auxiliary_Sphere\",GEOGCS[\"GCS_WGS_1984\",\
+      DATUM[\"D_WGS_1984\",SPHEROID[\"WGS_1984\",6378137.0,298.257223563]],\
+      PRIMEM[\"Greenwich\",0.0],UNIT[\"Degree\",0.0174532925199433]],\
+      PROJECTION[\"Mercator_Auxiliary_Sphere\"],PARAMETER[\"False_Easting\",0.0],\
+      PARAMETER[\"False_Northing\",0.0],PARAMETER[\"Central_Meridian\",0.0],\
+      PARAMETER[\"Standard_Parallel_1\",0.0],PARAMETER[\"Auxiliary_Sphere_Type\",0.0],\
+      UNIT[\"Meter\",1.0]]"),
+];
+
+/// Names of `.prj` coordinate reference systems lacking an `AUTHORITY`, and their EPSG codes
+const PROJECTION_NAMES: &'static [(&'static str, &'static str)] = &[
+    ("GCS_WGS_1984", "4326"),
+    ("WGS 84", "4326"),
+    ("GCS_North_American_1983", "4269"),
+    ("NAD83", "4269"),
+    ("GCS_ETRS_1989", "4258"),
+    ("ETRS89", "4258"),
+    ("WGS_1984_Web_Mercator_Auxiliary_Sphere", "3857"),
+    ("WGS 84 / Pseudo-Mercator", "3857"),
+];
+
+fn malformed(message: &str) -> Error {
+    Error::MalformedShapefile(String::from(message))
+}
+
+fn unsupported(message: &str) -> Error {
+    Error::ShapefileUnsupported(String::from(message))
+}
+
+/// Returns the file with the base name of `path` and `extension`, in lower or upper case.
+fn sibling(path: &Path, extension: &str) -> Option<PathBuf> {
+    [extension.to_lowercase(), extension.to_uppercase()]
+        .iter()
+        .map(|extension| path.with_extension(extension))
+        .find(|path| path.is_file())
+}
+
+
+// Reading
+
+fn read_bytes(buf: &[u8], at: usize, len: usize) -> Result<&[u8], Error> {
+    match at.checked_add(len) {
+        Some(end) if end <= buf.len() => Ok(&buf[at..end]),
+        _ => Err(malformed("offset out of bounds")),
+    }
+}
+
+fn read_u32_be(buf: &[u8], at: usize) -> Result<u32, Error> {
+    let bytes = try!(read_bytes(buf, at, 4));
+    Ok(bytes.iter().fold(0, |value, &byte| (value << 8) | byte as u32))
+}
+
+fn read_u32(buf: &[u8], at: usize) -> Result<u32, Error> {
+    let bytes = try!(read_bytes(buf, at, 4));
+    Ok(bytes.iter().rev().fold(0, |value, &byte| (value << 8) | byte as u32))
+}
+
+fn read_u16(buf: &[u8], at: usize) -> Result<u16, Error> {
+    let bytes = try!(read_bytes(buf, at, 2));
+    Ok(bytes[0] as u16 | (bytes[1] as u16) << 8)
+}
+
+fn f64_from(bytes: &[u8]) -> f64 {
+    let mut array = [0; 8];
+    array.copy_from_slice(&bytes[..8]);
+    f64::from_le_bytes(array)
+}
+
+fn read_f64(buf: &[u8], at: usize) -> Result<f64, Error> {
+    Ok(f64_from(try!(read_bytes(buf, at, 8))))
+}
+
+/// Returns the offset `count` items of `size` bytes past `at`.
+fn skip(at: usize, count: usize, size: usize) -> Result<usize, Error> {
+    count.checked_mul(size)
+        .and_then(|len| at.checked_add(len))
+        .ok_or_else(|| malformed("offset out of bounds"))
+}
+
+/// Reads `count` points from `at`, followed by their `Z` and `M` values if `has_z`.
+fn read_positions(content: &[u8], at: usize, count: usize, has_z: bool)
+                  -> Result<Vec<Position>, Error> {
+    let xy = try!(read_bytes(content, at, try!(skip(0, count, 16))));
+    let mut positions: Vec<Position> =
+        xy.chunks(16).map(|xy| vec![f64_from(xy), f64_from(&xy[8..])]).collect();
+    if has_z {
+        // Each array of values is preceded by its range
+        let z_at = try!(skip(at, count, 16)) + 16;
+        let zs = try!(read_bytes(content, z_at, count * 8));
+        for (position, z) in positions.iter_mut().zip(zs.chunks(8)) {
+            position.push(f64_from(z));
+        }
+        // Measures are optional, and kept only if none are missing
+        let m_at = try!(skip(z_at, count, 8)) + 16;
+        if let Ok(ms) = read_bytes(content, m_at, count * 8) {
+            let ms: Vec<f64> = ms.chunks(8).map(f64_from).collect();
+            if ms.iter().all(|&m| m >= NO_DATA_LIMIT) {
+                for (position, m) in positions.iter_mut().zip(ms) {
+                    position.push(m);
+                }
+            }
+        }
+    }
+    Ok(positions)
+}
+
+fn read_shape(content: &[u8]) -> Result<Option<Value>, Error> {
+    let shape_type = try!(read_u32(content, 0));
+    let (base, has_z) = match shape_type {
+        shape_type::NULL => return Ok(None),
+        shape_type::MULTI_PATCH => return Err(unsupported("MultiPatch shapes")),
+        t if t > shape_type::M => (t - shape_type::M, false),
+        t if t > shape_type::Z => (t - shape_type::Z, true),
+        t => (t, false),
+    };
+
+    if base == shape_type::POINT {
+        let mut position = vec![try!(read_f64(content, 4)), try!(read_f64(content, 12))];
+        if has_z {
+            position.push(try!(read_f64(content, 20)));
+            match read_f64(content, 28) {
+                Ok(m) if m >= NO_DATA_LIMIT => position.push(m),
+                _ => {}
+            }
+        }
+        return Ok(Some(Value::Point(position)));
+    }
+    if base == shape_type::MULTI_POINT {
+        let count = try!(read_u32(content, 36)) as usize;
+        return Ok(Some(Value::MultiPoint(try!(read_positions(content, 40, count, has_z)))));
+    }
+    if base != shape_type::POLY_LINE && base != shape_type::POLYGON {
+        return Err(malformed(&format!("unknown shape type {}", shape_type)));
+    }
+
+    let num_parts = try!(read_u32(content, 36)) as usize;
+    let num_points = try!(read_u32(content, 40)) as usize;
+    let points_at = try!(skip(44, num_parts, 4));
+    let mut positions = try!(read_positions(content, points_at, num_points, has_z));
+    let mut parts: Vec<LineStringType> = vec![];
+    // Split from the last part, so that each part is what remains past its start
+    for i in (0..num_parts).rev() {
+        let start = try!(read_u32(content, 44 + i * 4)) as usize;
+        if start > positions.len() {
+            return Err(malformed("part starts past the end of its points"));
+        }
+        let part = positions.split_off(start);
+        if !part.is_empty() {
+            parts.push(part);
+        }
+    }
+    parts.reverse();
+
+    Ok(Some(if base == shape_type::POLY_LINE {
+        match parts.len() {
+            1 => Value::LineString(parts.pop().unwrap()),
+            _ => Value::MultiLineString(parts),
+        }
+    } else {
+        let mut polygons = rings::group_clockwise(parts);
+        match polygons.len() {
+            1 => Value::Polygon(polygons.pop().unwrap()),
+            _ => Value::MultiPolygon(polygons),
+        }
+    }))
+}
+
+fn read_header(buf: &[u8], name: &str) -> Result<usize, Error> {
+    if buf.len() < HEADER_SIZE || try!(read_u32_be(buf, 0)) != FILE_CODE {
+        return Err(malformed(&format!("missing {} header", name)));
+    }
+    let len = try!(read_u32_be(buf, 24)) as usize * 2;
+    Ok(cmp::min(len, buf.len()))
+}
+
+/// Reads the shapes of a `.shp` file, at the offsets of the `.shx` file if there is one.
+fn read_shapes(shp: &[u8], shx: Option<&[u8]>) -> Result<Vec<Option<Value>>, Error> {
+    let end = try!(read_header(shp, ".shp"));
+    let mut records = vec![];
+    match shx {
+        Some(shx) => {
+            let index_end = try!(read_header(shx, ".shx"));
+            let entries = shx.get(HEADER_SIZE..index_end).unwrap_or(&[]);
+            for entry in entries.chunks(8).filter(|entry| entry.len() == 8) {
+                let offset = try!(read_u32_be(entry, 0)) as usize * 2;
+                let len = try!(read_u32_be(entry, 4)) as usize * 2;
+                records.push(try!(read_bytes(&shp[..end], try!(skip(offset, 1, 8)), len)));
+            }
+        }
+        None => {
+            let mut at = HEADER_SIZE;
+            while at + 8 <= end {
+                let len = try!(read_u32_be(shp, at + 4)) as usize * 2;
+                records.push(try!(read_bytes(&shp[..end], at + 8, len)));
+                at = try!(skip(at + 8, len, 1));
+            }
+        }
+    }
+    records.into_iter()
+        .enumerate()
+        .map(|(i, content)| {
+            read_shape(content).map_err(|e| match e {
+                Error::MalformedShapefile(e) => malformed(&format!("record {}: {}", i + 1, e)),
+                e => e,
+            })
+        })
+        .collect()
+}
+
+/// Decodes text as UTF-8 when valid, and as Latin-1 otherwise.
+fn decode_text(bytes: &[u8]) -> String {
+    match str::from_utf8(bytes) {
+        Ok(text) => String::from(text),
+        Err(..) => bytes.iter().map(|&byte| byte as char).collect(),
+    }
+}
+
+/// A field of a DBF table
+struct Field {
+    name: String,
+    field_type: u8,
+    length: usize,
+    decimals: usize,
+}
+
+impl Field {
+    fn read(&self, bytes: &[u8]) -> Result<JsonValue, Error> {
+        let text = decode_text(bytes);
+        let text = match self.field_type {
+            b'C' => text.trim_end_matches(&[' ', '\0'][..]),
+            _ => text.trim_matches(&[' ', '\0'][..]),
+        };
+        if text.is_empty() {
+            return Ok(JsonValue::Null);
+        }
+        Ok(match self.field_type {
+            // Overflowing numbers are filled with asterisks
+            b'N' | b'F' if text.starts_with('*') => JsonValue::Null,
+            b'N' | b'F' => {
+                match text.parse::<i64>() {
+                    Ok(n) if self.decimals == 0 => JsonValue::from(n),
+                    _ => {
+                        match text.parse::<f64>() {
+                            Ok(n) => JsonValue::from(n),
+                            Err(..) => {
+                                return Err(malformed(&format!("invalid number '{}' in field \
+                                                               '{}'",
+                                                              text,
+                                                              self.name)))
+                            }
+                        }
+                    }
+                }
+            }
+            b'L' => {
+                match text {
+                    "T" | "t" | "Y" | "y" => JsonValue::Bool(true),
+                    "F" | "f" | "N" | "n" => JsonValue::Bool(false),
+                    _ => JsonValue::Null,
+                }
+            }
+            b'D' if text.len() == 8 && text.bytes().all(|b| b.is_ascii_digit()) => {
+                JsonValue::String(format!("{}-{}-{}", &text[..4], &text[4..6], &text[6..]))
+            }
+            // Memo fields point into a separate file, which is not read
+            b'M' => JsonValue::Null,
+            _ => JsonValue::String(String::from(text)),
+        })
+    }
+}
+
+/// Reads the records of a `.dbf` file, with `None` for deleted records.
+fn read_dbf(dbf: &[u8]) -> Result<Vec<Option<JsonObject>>, Error> {
+    let count = try!(read_u32(dbf, 4)) as usize;
+    let header_len = try!(read_u16(dbf, 8)) as usize;
+    let record_len = try!(read_u16(dbf, 10)) as usize;
+
+    let mut fields = vec![];
+    let mut at = 32;
+    let mut fields_len = 1;
+    while at < header_len && try!(read_bytes(dbf, at, 1))[0] != 0x0d {
+        let descriptor = try!(read_bytes(dbf, at, 32));
+        let name = descriptor[..11].split(|&b| b == 0).next().unwrap_or(&[]);
+        let field = Field {
+            name: String::from(decode_text(name).trim()),
+            field_type: descriptor[11],
+            length: descriptor[16] as usize,
+            decimals: descriptor[17] as usize,
+        };
+        fields_len += field.length;
+        fields.push(field);
+        at += 32;
+    }
+    if fields_len > record_len {
+        return Err(malformed("the .dbf fields are longer than its records"));
+    }
+
+    let mut records = Vec::with_capacity(cmp::min(count, dbf.len()));
+    for i in 0..count {
+        let record_at = try!(skip(header_len, i, record_len));
+        let record = try!(read_bytes(dbf, record_at, record_len)
+            .map_err(|_| malformed(&format!("the .dbf file is missing record {}", i + 1))));
+        if record[0] == b'*' {
+            records.push(None);
+            continue;
+        }
+        let mut properties = JsonObject::new();
+        let mut at = 1;
+        for field in &fields {
+            let value = try!(field.read(&record[at..at + field.length])
+                .map_err(|e| match e {
+                    Error::MalformedShapefile(e) => {
+                        malformed(&format!(".dbf record {}: {}", i + 1, e))
+                    }
+                    e => e,
+                }));
+            properties.insert(field.name.clone(), value);
+            at += field.length;
+        }
+        records.push(Some(properties));
+    }
+    Ok(records)
+}
+
+/// Reads the coordinate reference system identified by a `.prj` file.
+fn read_prj(wkt: &str) -> Option<Crs> {
+    let wkt = wkt.trim();
+    // The AUTHORITY of the outermost coordinate reference system is a direct child of it
+    let mut depth = 0;
+    let mut quoted = false;
+    for (i, c) in wkt.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '[' | '(' if !quoted => depth += 1,
+            ']' | ')' if !quoted => depth -= 1,
+            'A' if !quoted && depth == 1 && wkt[i..].starts_with("AUTHORITY[") => {
+                let rest = &wkt[i + "AUTHORITY[".len()..];
+                let arguments: Vec<&str> = rest[..rest.find(']').unwrap_or(0)]
+                    .split(',')
+                    .map(|argument| argument.trim().trim_matches('"'))
+                    .collect();
+                if arguments.len() == 2 {
+                    return Some(Crs::from(CrsId::new(arguments[0], arguments[1])));
+                }
+            }
+            _ => {}
+        }
+    }
+    let name = wkt.split('"').nth(1);
+    PROJECTION_NAMES.iter()
+        .find(|&&(known, _)| Some(known) == name)
+        .map(|&(_, code)| Crs::from(CrsId::new("EPSG", code)))
+}
+
+/// Reads a Shapefile into a `FeatureCollection`.
+///
+/// `path` is the path of the `.shp` file, or its base name. The `.shx`, `.dbf` and `.prj`
+/// files are optional: without a `.shx` file the records are read in sequence, and without a
+/// `.dbf` file the features have no properties.
+pub fn read<P: AsRef<Path>>(path: P) -> Result<FeatureCollection, Error> {
+    let path = path.as_ref();
+    let shp = try!(fs::read(sibling(path, "shp").unwrap_or_else(|| path.with_extension("shp"))));
+    let shx = match sibling(path, "shx") {
+        Some(shx) => Some(try!(fs::read(shx))),
+        None => None,
+    };
+    let shapes = try!(read_shapes(&shp, shx.as_ref().map(|shx| &shx[..])));
+
+    let records = match sibling(path, "dbf") {
+        Some(dbf) => try!(read_dbf(&try!(fs::read(dbf)))),
+        None => shapes.iter().map(|_| Some(JsonObject::new())).collect(),
+    };
+    if records.len() != shapes.len() {
+        return Err(malformed(&format!("the .shp file has {} records but the .dbf file has {}",
+                                      shapes.len(),
+                                      records.len())));
+    }
+    let crs = match sibling(path, "prj") {
+        Some(prj) => read_prj(&String::from_utf8_lossy(&try!(fs::read(prj)))),
+        None => None,
+    };
+
+    let features = shapes.into_iter()
+        .zip(records)
+        .filter_map(|(shape, properties)| {
+            properties.map(|properties| {
+                Feature {
+                    bbox: None,
+                    crs: None,
+                    geometry: shape.map(Geometry::new),
+                    id: None,
+                    properties: Some(properties).filter(|p| !p.is_empty()),
+                }
+            })
+        })
+        .collect();
+    Ok(FeatureCollection {
+        bbox: None,
+        crs: crs,
+        features: features,
+    })
+}
+
+
+// Writing
+
+fn base_shape_type(value: &Value) -> Result<u32, Error> {
+    Ok(match *value {
+        Value::Point(..) => shape_type::POINT,
+        Value::MultiPoint(..) => shape_type::MULTI_POINT,
+        Value::LineString(..) | Value::MultiLineString(..) => shape_type::POLY_LINE,
+        Value::Polygon(..) | Value::MultiPolygon(..) => shape_type::POLYGON,
+        Value::GeometryCollection(..) => return Err(unsupported("GeometryCollection")),
+    })
+}
+
+fn shape_type_name(shape_type: u32) -> &'static str {
+    match shape_type {
+        shape_type::POINT => "point",
+        shape_type::MULTI_POINT => "multipoint",
+        shape_type::POLY_LINE => "linestring",
+        _ => "polygon",
+    }
+}
+
+/// Returns the shape type of the features, and the number of ordinates of their positions.
+fn collection_shape_type(fc: &FeatureCollection) -> Result<(u32, usize), Error> {
+    let mut collection_type = shape_type::NULL;
+    let mut dimensions = 4;
+    for value in fc.features.iter().filter_map(|f| f.geometry.as_ref()).map(|g| &g.value) {
+        let base = try!(base_shape_type(value));
+        collection_type = match (collection_type, base) {
+            (shape_type::NULL, base) => base,
+            (current, base) if current == base => base,
+            (shape_type::POINT, shape_type::MULTI_POINT) |
+            (shape_type::MULTI_POINT, shape_type::POINT) => shape_type::MULTI_POINT,
+            (current, base) => {
+                return Err(unsupported(&format!("mixed {} and {} geometries",
+                                                shape_type_name(current),
+                                                shape_type_name(base))))
+            }
+        };
+        dimensions = cmp::min(dimensions, util::coordinate_dimensions(value));
+    }
+    if collection_type == shape_type::NULL {
+        dimensions = 2;
+    }
+    Ok((collection_type, dimensions))
+}
+
+/// Returns the parts of a shape, with polygon rings wound as Shapefiles expect.
+///
+/// Positions with fewer than two ordinates are left out, as are parts left empty.
+fn shape_parts(value: &Value) -> Vec<LineStringType> {
+    let valid = |line: &[Position]| -> LineStringType {
+        line.iter().filter(|position| position.len() >= 2).cloned().collect()
+    };
+    let wound = |polygon: &Vec<LineStringType>| -> Vec<LineStringType> {
+        polygon.iter().enumerate().map(|(i, ring)| rings::wound(&valid(ring), i > 0)).collect()
+    };
+    let parts = match *value {
+        Value::Point(ref position) => vec![valid(&[position.clone()])],
+        Value::MultiPoint(ref positions) => vec![valid(positions)],
+        Value::LineString(ref line) => vec![valid(line)],
+        Value::MultiLineString(ref lines) => lines.iter().map(|line| valid(line)).collect(),
+        Value::Polygon(ref polygon) => wound(polygon),
+        Value::MultiPolygon(ref polygons) => polygons.iter().flat_map(wound).collect(),
+        Value::GeometryCollection(..) => vec![],
+    };
+    parts.into_iter().filter(|part| !part.is_empty()).collect()
+}
+
+/// Returns the range of the ordinate `i` of `positions`.
+fn range<'a, I>(positions: I, i: usize) -> (f64, f64)
+    where I: Iterator<Item = &'a Position>
+{
+    positions.fold(None, |range, position| {
+            let value = position[i];
+            Some(match range {
+                Some((min, max)) => (f64::min(min, value), f64::max(max, value)),
+                None => (value, value),
+            })
+        })
+        .unwrap_or((0.0, 0.0))
+}
+
+fn push_f64s(buf: &mut Vec<u8>, values: &[f64]) {
+    for value in values {
+        buf.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+/// Returns the measure of a position, or the no data value.
+fn measure(position: &Position, dimensions: usize) -> f64 {
+    if dimensions > 3 { position[3] } else { NO_DATA }
+}
+
+fn encode_shape(value: Option<&Value>, shape_type: u32, dimensions: usize) -> Vec<u8> {
+    let parts = value.map(shape_parts).unwrap_or_default();
+    let positions: Vec<&Position> = parts.iter().flat_map(|part| part.iter()).collect();
+    if positions.is_empty() {
+        return shape_type::NULL.to_le_bytes().to_vec();
+    }
+    let has_z = dimensions > 2;
+
+    let mut buf = vec![];
+    let typed = if has_z { shape_type + shape_type::Z } else { shape_type };
+    buf.extend_from_slice(&typed.to_le_bytes());
+    if shape_type == shape_type::POINT {
+        let position = positions[0];
+        push_f64s(&mut buf, &position[..2]);
+        if has_z {
+            push_f64s(&mut buf, &[position[2], measure(position, dimensions)]);
+        }
+        return buf;
+    }
+
+    let (min_x, max_x) = range(positions.iter().cloned(), 0);
+    let (min_y, max_y) = range(positions.iter().cloned(), 1);
+    push_f64s(&mut buf, &[min_x, min_y, max_x, max_y]);
+    if shape_type != shape_type::MULTI_POINT {
+        buf.extend_from_slice(&(parts.len() as u32).to_le_bytes());
+    }
+    buf.extend_from_slice(&(positions.len() as u32).to_le_bytes());
+    if shape_type != shape_type::MULTI_POINT {
+        let mut start = 0u32;
+        for part in &parts {
+            buf.extend_from_slice(&start.to_le_bytes());
+            start += part.len() as u32;
+        }
+    }
+    for position in &positions {
+        push_f64s(&mut buf, &position[..2]);
+    }
+    if has_z {
+        let (min_z, max_z) = range(positions.iter().cloned(), 2);
+        push_f64s(&mut buf, &[min_z, max_z]);
+        for position in &positions {
+            push_f64s(&mut buf, &[position[2]]);
+        }
+        let ms: Vec<f64> = positions.iter().map(|p| measure(p, dimensions)).collect();
+        let min_m = ms.iter().cloned().fold(ms[0], f64::min);
+        let max_m = ms.iter().cloned().fold(ms[0], f64::max);
+        push_f64s(&mut buf, &[min_m, max_m]);
+        push_f64s(&mut buf, &ms);
+    }
+    buf
+}
+
+fn write_header(buf: &mut Vec<u8>,
+                len: usize,
+                shape_type: u32,
+                positions: &[&Position],
+                dimensions: usize) {
+    buf.extend_from_slice(&FILE_CODE.to_be_bytes());
+    buf.extend_from_slice(&[0; 20]);
+    buf.extend_from_slice(&((len / 2) as u32).to_be_bytes());
+    buf.extend_from_slice(&VERSION.to_le_bytes());
+    buf.extend_from_slice(&shape_type.to_le_bytes());
+    let (min_x, max_x) = range(positions.iter().cloned(), 0);
+    let (min_y, max_y) = range(positions.iter().cloned(), 1);
+    let (min_z, max_z) = if dimensions > 2 {
+        range(positions.iter().cloned(), 2)
+    } else {
+        (0.0, 0.0)
+    };
+    let (min_m, max_m) = if dimensions > 3 {
+        range(positions.iter().cloned(), 3)
+    } else {
+        (0.0, 0.0)
+    };
+    push_f64s(buf, &[min_x, min_y, max_x, max_y, min_z, max_z, min_m, max_m]);
+}
+
+/// Encodes the `.shp` and `.shx` files.
+fn encode_shapes(fc: &FeatureCollection, shape_type: u32, dimensions: usize) -> (Vec<u8>, Vec<u8>) {
+    let contents: Vec<Vec<u8>> = fc.features
+        .iter()
+        .map(|feature| {
+            encode_shape(feature.geometry.as_ref().map(|g| &g.value), shape_type, dimensions)
+        })
+        .collect();
+    let shp_len = HEADER_SIZE + contents.iter().map(|c| 8 + c.len()).sum::<usize>();
+    let shx_len = HEADER_SIZE + contents.len() * 8;
+
+    let parts: Vec<Vec<LineStringType>> = fc.features
+        .iter()
+        .filter_map(|feature| feature.geometry.as_ref())
+        .map(|geometry| shape_parts(&geometry.value))
+        .collect();
+    let positions: Vec<&Position> =
+        parts.iter().flat_map(|parts| parts.iter()).flat_map(|part| part.iter()).collect();
+    let typed = if dimensions > 2 && shape_type != shape_type::NULL {
+        shape_type + shape_type::Z
+    } else {
+        shape_type
+    };
+
+    let mut shp = Vec::with_capacity(shp_len);
+    let mut shx = Vec::with_capacity(shx_len);
+    write_header(&mut shp, shp_len, typed, &positions, dimensions);
+    write_header(&mut shx, shx_len, typed, &positions, dimensions);
+    for (i, content) in contents.iter().enumerate() {
+        shx.extend_from_slice(&((shp.len() / 2) as u32).to_be_bytes());
+        shx.extend_from_slice(&((content.len() / 2) as u32).to_be_bytes());
+        shp.extend_from_slice(&(i as u32 + 1).to_be_bytes());
+        shp.extend_from_slice(&((content.len() / 2) as u32).to_be_bytes());
+        shp.extend_from_slice(content);
+    }
+    (shp, shx)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum FieldType {
+    Logical,
+    Integer,
+    Float,
+    Character,
+}
+
+impl FieldType {
+    fn of(value: &JsonValue) -> Option<FieldType> {
+        Some(match *value {
+            JsonValue::Null => return None,
+            JsonValue::Bool(..) => FieldType::Logical,
+            JsonValue::Number(ref n) if n.is_f64() => FieldType::Float,
+            JsonValue::Number(..) => FieldType::Integer,
+            _ => FieldType::Character,
+        })
+    }
+
+    fn merge(self, other: FieldType) -> FieldType {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (FieldType::Integer, FieldType::Float) |
+            (FieldType::Float, FieldType::Integer) => FieldType::Float,
+            _ => FieldType::Character,
+        }
+    }
+}
+
+/// Returns the text of a value in a character field.
+fn character_text(value: &JsonValue) -> String {
+    match *value {
+        JsonValue::String(ref s) => s.clone(),
+        ref value => value.to_string(),
+    }
+}
+
+/// Returns the text of a number in a float field, and its number of decimals.
+fn float_text(value: &JsonValue) -> (String, usize) {
+    let text = value.as_f64().map(|n| n.to_string()).unwrap_or_default();
+    let decimals = text.find('.').map(|i| text.len() - i - 1).unwrap_or(0);
+    (text, decimals)
+}
+
+/// Truncates `text` to at most `len` bytes, on a character boundary.
+fn truncate(text: &str, len: usize) -> &str {
+    let mut end = cmp::min(len, text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    &text[..end]
+}
+
+/// Returns the fields of the properties, in order of first appearance, and their keys.
+fn infer_fields(fc: &FeatureCollection) -> Result<Vec<(String, Field)>, Error> {
+    let mut columns: Vec<(String, Option<FieldType>)> = vec![];
+    for properties in fc.features.iter().filter_map(|f| f.properties.as_ref()) {
+        for (key, value) in properties {
+            let index = match columns.iter().position(|column| column.0 == *key) {
+                Some(index) => index,
+                None => {
+                    columns.push((key.clone(), None));
+                    columns.len() - 1
+                }
+            };
+            let field_type = &mut columns[index].1;
+            *field_type = match (*field_type, FieldType::of(value)) {
+                (current, None) => current,
+                (None, new) => new,
+                (Some(current), Some(new)) => Some(current.merge(new)),
+            };
+        }
+    }
+    if columns.len() > MAX_FIELDS {
+        return Err(unsupported(&format!("{} fields, more than {}", columns.len(), MAX_FIELDS)));
+    }
+
+    let mut names = HashSet::new();
+    let mut fields = vec![];
+    for (key, field_type) in columns {
+        let values: Vec<&JsonValue> = fc.features
+            .iter()
+            .filter_map(|f| f.properties.as_ref().and_then(|p| p.get(&key)))
+            .filter(|value| !value.is_null())
+            .collect();
+        let mut field_type = field_type.unwrap_or(FieldType::Character);
+        let (mut length, mut decimals) = match field_type {
+            FieldType::Logical => (1, 0),
+            FieldType::Integer => {
+                (values.iter().map(|v| v.to_string().len()).max().unwrap_or(1), 0)
+            }
+            FieldType::Float => {
+                let texts: Vec<(String, usize)> = values.iter().map(|v| float_text(v)).collect();
+                let decimals = texts.iter().map(|t| t.1).max().unwrap_or(0).max(1);
+                let integer_length = texts.iter()
+                    .map(|t| t.0.len() - t.1 - (t.1 > 0) as usize)
+                    .max()
+                    .unwrap_or(1);
+                (integer_length + 1 + decimals, decimals)
+            }
+            FieldType::Character => (0, 0),
+        };
+        if length > MAX_FIELD_LENGTH {
+            field_type = FieldType::Character;
+        }
+        if field_type == FieldType::Character {
+            length = values.iter().map(|v| character_text(v).len()).max().unwrap_or(1);
+            length = cmp::min(length, MAX_FIELD_LENGTH).max(1);
+            decimals = 0;
+        }
+
+        // Field names are case-insensitive, and suffixed with a number to make them unique
+        let mut name = String::from(truncate(&key, MAX_FIELD_NAME_LENGTH));
+        let mut suffix = 1;
+        while name.is_empty() || names.contains(&name.to_uppercase()) {
+            let number = format!("_{}", suffix);
+            name = format!("{}{}",
+                           truncate(&key, MAX_FIELD_NAME_LENGTH - number.len()),
+                           number);
+            suffix += 1;
+        }
+        names.insert(name.to_uppercase());
+
+        fields.push((key,
+                     Field {
+                         name: name,
+                         field_type: match field_type {
+                             FieldType::Logical => b'L',
+                             FieldType::Integer | FieldType::Float => b'N',
+                             FieldType::Character => b'C',
+                         },
+                         length: length,
+                         decimals: decimals,
+                     }));
+    }
+    Ok(fields)
+}
+
+/// Returns the current date as years since 1900, month and day.
+fn today() -> [u8; 3] {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() / 86_400)
+        .unwrap_or(0) as i64;
+    // From Howard Hinnant's `civil_from_days`
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 -
+                       day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    [(year - 1900) as u8, month as u8, day as u8]
+}
+
+fn write_field(buf: &mut Vec<u8>, field: &Field, value: Option<&JsonValue>) {
+    let value = value.filter(|value| !value.is_null());
+    let text = match (field.field_type, value) {
+        (b'L', Some(&JsonValue::Bool(true))) => String::from("T"),
+        (b'L', Some(&JsonValue::Bool(false))) => String::from("F"),
+        (b'L', _) => String::from("?"),
+        (_, None) => String::new(),
+        (b'N', Some(value)) if field.decimals == 0 => {
+            format!("{:>1$}", value.to_string(), field.length)
+        }
+        (b'N', Some(value)) => {
+            format!("{:>1$.2$}", value.as_f64().unwrap_or(0.0), field.length, field.decimals)
+        }
+        (_, Some(value)) => character_text(value),
+    };
+    let text = truncate(&text, field.length);
+    buf.extend_from_slice(text.as_bytes());
+    let len = buf.len() + field.length - text.len();
+    buf.resize(len, b' ');
+}
+
+fn encode_dbf(fc: &FeatureCollection, fields: &[(String, Field)]) -> Result<Vec<u8>, Error> {
+    let record_len = 1 + fields.iter().map(|f| f.1.length).sum::<usize>();
+    if record_len > u16::max_value() as usize {
+        return Err(unsupported(&format!("records of {} bytes, longer than {}",
+                                        record_len,
+                                        u16::max_value())));
+    }
+    let header_len = 32 + fields.len() * 32 + 1;
+
+    let mut buf = Vec::with_capacity(header_len + fc.features.len() * record_len + 1);
+    buf.push(0x03);
+    buf.extend_from_slice(&today());
+    buf.extend_from_slice(&(fc.features.len() as u32).to_le_bytes());
+    buf.extend_from_slice(&(header_len as u16).to_le_bytes());
+    buf.extend_from_slice(&(record_len as u16).to_le_bytes());
+    buf.extend_from_slice(&[0; 20]);
+    for &(_, ref field) in fields {
+        let mut name = [0; 11];
+        name[..field.name.len()].copy_from_slice(field.name.as_bytes());
+        buf.extend_from_slice(&name);
+        buf.push(field.field_type);
+        buf.extend_from_slice(&[0; 4]);
+        buf.push(field.length as u8);
+        buf.push(field.decimals as u8);
+        buf.extend_from_slice(&[0; 14]);
+    }
+    buf.push(0x0d);
+
+    for feature in &fc.features {
+        buf.push(b' ');
+        for &(ref key, ref field) in fields {
+            let value = feature.properties.as_ref().and_then(|p| p.get(key));
+            write_field(&mut buf, field, value);
+        }
+    }
+    buf.push(0x1a);
+    Ok(buf)
+}
+
+/// Returns the `.prj` WKT of a coordinate reference system, if it is a well-known one.
+fn write_prj(crs: &Crs) -> Option<&'static str> {
+    crs.id().and_then(|id| {
+        PROJECTIONS.iter()
+            .find(|&&(code, _)| {
+                // Shapefiles are always longitude first
+                id.equivalence(&CrsId::new("EPSG", code)) != CrsEquivalence::Different
+            })
+            .map(|&(_, wkt)| wkt)
+    })
+}
+
+/// Writes a `FeatureCollection` as a Shapefile.
+///
+/// `path` is the path of the `.shp` file, or its base name. The `.shx` and `.dbf` files are
+/// written alongside it, with a `.cpg` file declaring the `.dbf` file to be UTF-8, and a
+/// `.prj` file if the collection has a CRS known to this module (`EPSG:4326` and
+/// `EPSG:3857`). Features without a geometry are written as null shapes. Text values longer
+/// than the 254 bytes DBF allows are truncated.
+pub fn write<P: AsRef<Path>>(path: P, fc: &FeatureCollection) -> Result<(), Error> {
+    let path = path.as_ref();
+    let (shape_type, dimensions) = try!(collection_shape_type(fc));
+    let fields = try!(infer_fields(fc));
+    let dbf = try!(encode_dbf(fc, &fields));
+    let (shp, shx) = encode_shapes(fc, shape_type, dimensions);
+
+    try!(fs::write(path.with_extension("shp"), shp));
+    try!(fs::write(path.with_extension("shx"), shx));
+    try!(fs::write(path.with_extension("dbf"), dbf));
+    try!(fs::write(path.with_extension("cpg"), "UTF-8"));
+    if let Some(wkt) = fc.crs.as_ref().and_then(write_prj) {
+        try!(fs::write(path.with_extension("prj"), wkt));
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process;
+
+    use {Crs, Error, Feature, FeatureCollection, Value};
+    use super::{read, read_prj, write};
+
+    /// Returns a base name in the temporary directory, unique to this process and `name`.
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("geojson-shapefile-{}-{}", process::id(), name))
+    }
+
+    fn round_trip(fc: &FeatureCollection, name: &str) -> FeatureCollection {
+        let path = temp_path(name);
+        write(&path, fc).unwrap();
+        read(&path).unwrap()
+    }
+
+    #[test]
+    fn points_and_fields() {
+        let fc = FeatureCollection::builder()
+            .crs(Crs::Named { name: String::from("urn:ogc:def:crs:OGC:1.3:CRS84") })
+            .feature(Feature::builder()
+                .geometry(Value::Point(vec![1.5, 2.5]))
+                .id("dropped")
+                .property("name", "Zürich")
+                .property("count", 12)
+                .property("ratio", -0.125)
+                .property("open", true)
+                .property("tags", json!(["a", 1]))
+                .build())
+            .feature(Feature::builder()
+                .geometry(Value::Point(vec![-3.0, 4.0]))
+                .property("name", "Bern")
+                .property("count", -3)
+                .property("ratio", 10)
+                .property("open", false)
+                .property("mixed", "x")
+                .build())
+            .feature(Feature::builder()
+                .property("mixed", 2)
+                .build())
+            .build();
+        let read = round_trip(&fc, "points");
+
+        assert_eq!(read.crs,
+                   Some(Crs::Named { name: String::from("urn:ogc:def:crs:EPSG::4326") }));
+        assert_eq!(read.features.len(), 3);
+        assert_eq!(read.features[0].id, None);
+        assert_eq!(read.features[0].geometry, fc.features[0].geometry);
+        assert_eq!(read.features[2].geometry, None);
+        assert_eq!(read.features[0].properties.as_ref().map(|p| json!(p)),
+                   Some(json!({
+                       "count": 12,
+                       "mixed": null,
+                       "name": "Zürich",
+                       "open": true,
+                       "ratio": -0.125,
+                       "tags": "[\"a\",1]",
+                   })));
+        assert_eq!(read.features[1].properties.as_ref().map(|p| json!(p)),
+                   Some(json!({
+                       "count": -3,
+                       "mixed": "x",
+                       "name": "Bern",
+                       "open": false,
+                       "ratio": 10.0,
+                       "tags": null,
+                   })));
+        assert_eq!(read.features[2].properties.as_ref().map(|p| p["mixed"].clone()),
+                   Some(json!("2")));
+    }
+
+    #[test]
+    fn lines_and_polygons() {
+        let lines = FeatureCollection::builder()
+            .feature(Feature::builder()
+                .geometry(Value::LineString(vec![vec![0.0, 0.0, 1.0], vec![1.0, 1.0, 2.0]]))
+                .build())
+            .feature(Feature::builder()
+                .geometry(Value::MultiLineString(vec![vec![vec![0.0, 0.0, 1.0, 5.0],
+                                                           vec![1.0, 0.0, 1.0, 6.0]],
+                                                      vec![vec![2.0, 2.0, 3.0, 7.0],
+                                                           vec![3.0, 3.0, 3.0, 8.0]]]))
+                .build())
+            .build();
+        // Measures are dropped, as the linestring has none
+        let read = round_trip(&lines, "lines");
+        assert_eq!(read.features[0].geometry, lines.features[0].geometry);
+        assert_eq!(read.features[1].geometry.as_ref().map(|g| g.value.clone()),
+                   Some(Value::MultiLineString(vec![vec![vec![0.0, 0.0, 1.0],
+                                                         vec![1.0, 0.0, 1.0]],
+                                                    vec![vec![2.0, 2.0, 3.0],
+                                                         vec![3.0, 3.0, 3.0]]])));
+
+        // Outer rings are written clockwise and holes counter-clockwise
+        let polygons = FeatureCollection::builder()
+            .feature(Feature::builder()
+                .geometry(Value::Polygon(vec![vec![vec![0.0, 0.0],
+                                                   vec![4.0, 0.0],
+                                                   vec![4.0, 4.0],
+                                                   vec![0.0, 4.0],
+                                                   vec![0.0, 0.0]],
+                                              vec![vec![1.0, 1.0],
+                                                   vec![1.0, 2.0],
+                                                   vec![2.0, 2.0],
+                                                   vec![2.0, 1.0],
+                                                   vec![1.0, 1.0]]]))
+                .build())
+            .build();
+        let path = temp_path("polygons");
+        write(&path, &polygons).unwrap();
+        let shp = fs::read(path.with_extension("shp")).unwrap();
+        assert_eq!(shp[32..36], 5u32.to_le_bytes());
+        let mut record = 5u32.to_le_bytes().to_vec();
+        for x in &[0.0f64, 0.0, 4.0, 4.0] {
+            record.extend_from_slice(&x.to_le_bytes());
+        }
+        for n in &[2u32, 10, 0, 5] {
+            record.extend_from_slice(&n.to_le_bytes());
+        }
+        for x in &[0.0f64, 0.0, 0.0, 4.0, 4.0, 4.0, 4.0, 0.0, 0.0, 0.0, 1.0, 1.0, 2.0, 1.0, 2.0,
+                   2.0, 1.0, 2.0, 1.0, 1.0] {
+            record.extend_from_slice(&x.to_le_bytes());
+        }
+        assert_eq!(shp[108..], record[..]);
+    }
+
+    #[test]
+    fn empty_positions() {
+        let fc = FeatureCollection::builder()
+            .feature(Feature::builder().geometry(Value::from_wkt("POINT EMPTY").unwrap()).build())
+            .feature(Feature::builder()
+                .geometry(Value::from_wkt("MULTIPOINT (EMPTY, 1 2)").unwrap())
+                .build())
+            .build();
+        let read = round_trip(&fc, "empty");
+        assert_eq!(read.features[0].geometry, None);
+        assert_eq!(read.features[1].geometry.as_ref().map(|g| g.value.clone()),
+                   Some(Value::MultiPoint(vec![vec![1.0, 2.0]])));
+    }
+
+    #[test]
+    fn field_names() {
+        let fc = FeatureCollection::builder()
+            .feature(Feature::builder()
+                .geometry(Value::Point(vec![0.0, 0.0]))
+                .property("population_1990", 1)
+                .property("population_2000", 2)
+                .property("", 3)
+                .build())
+            .build();
+        let read = round_trip(&fc, "names");
+        let names: Vec<&String> = read.features[0].properties.as_ref().unwrap().keys().collect();
+        assert_eq!(names, vec!["_1", "populati_1", "population"]);
+    }
+
+    #[test]
+    fn unsupported_geometries() {
+        let mixed = FeatureCollection::builder()
+            .feature(Feature::builder().geometry(Value::Point(vec![0.0, 0.0])).build())
+            .feature(Feature::builder()
+                .geometry(Value::LineString(vec![vec![0.0, 0.0], vec![1.0, 1.0]]))
+                .build())
+            .build();
+        assert_eq!(write(temp_path("mixed"), &mixed),
+                   Err(Error::ShapefileUnsupported(String::from("mixed point and linestring \
+                                                                 geometries"))));
+
+        let collection = FeatureCollection::builder()
+            .feature(Feature::builder().geometry(Value::GeometryCollection(vec![])).build())
+            .build();
+        assert!(write(temp_path("collection"), &collection).is_err());
+    }
+
+    #[test]
+    fn prj() {
+        let wkt = "PROJCS[\"ETRS89 / UTM zone 32N\",GEOGCS[\"ETRS89\",AUTHORITY[\"EPSG\",\
+                   \"4258\"]],UNIT[\"metre\",1],AUTHORITY[\"EPSG\",\"25832\"]]";
+        assert_eq!(read_prj(wkt),
+                   Some(Crs::Named { name: String::from("urn:ogc:def:crs:EPSG::25832") }));
+        assert_eq!(read_prj("GEOGCS[\"GCS_WGS_1984\",DATUM[\"D_WGS_1984\"]]"),
+                   Some(Crs::Named { name: String::from("urn:ogc:def:crs:EPSG::4326") }));
+        assert_eq!(read_prj("LOCAL_CS[\"Unknown\"]"), None);
+    }
+
+    #[test]
+    fn corrupted() {
+        let fc = FeatureCollection::builder()
+            .feature(Feature::builder()
+                .geometry(Value::Polygon(vec![vec![vec![0.0, 0.0],
+                                                   vec![10.0, 0.0],
+                                                   vec![10.0, 10.0],
+                                                   vec![0.0, 0.0]]]))
+                .property("name", "a")
+                .build())
+            .build();
+        let path = temp_path("corrupted");
+        write(&path, &fc).unwrap();
+        let shp = fs::read(path.with_extension("shp")).unwrap();
+        let dbf = fs::read(path.with_extension("dbf")).unwrap();
+
+        for len in 0..shp.len() {
+            fs::write(path.with_extension("shp"), &shp[..len]).unwrap();
+            assert!(read(&path).is_err());
+        }
+        fs::write(path.with_extension("shp"), &shp).unwrap();
+        fs::remove_file(path.with_extension("shx")).unwrap();
+        for i in 100..shp.len() {
+            let mut corrupted = shp.clone();
+            corrupted[i] = 0xff;
+            fs::write(path.with_extension("shp"), &corrupted).unwrap();
+            let _ = read(&path);
+        }
+        fs::write(path.with_extension("shp"), &shp).unwrap();
+        for len in 0..dbf.len() - 1 {
+            fs::write(path.with_extension("dbf"), &dbf[..len]).unwrap();
+            assert!(read(&path).is_err());
+        }
+    }
+}
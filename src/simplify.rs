@@ -0,0 +1,619 @@
+// Copyright 2015 The GeoRust Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Simplification of geometries
+//!
+//! Line strings and polygon rings are simplified by removing positions, so that the positions
+//! kept are unchanged, including any ordinates beyond the first two. Distances and areas are
+//! measured in the plane of the first two ordinates. The first and last positions of line
+//! strings and rings are always kept, and points are left as they are. Positions of line
+//! strings and rings with fewer than two ordinates are dropped.
+//!
+//! Rings with fewer than four positions left are dropped, as are polygons whose exterior ring
+//! is dropped. With `preserve_topology`, positions are restored instead until every ring has
+//! four positions, no two rings of a polygon or multi-polygon cross, and holes stay within
+//! their exterior ring, as long as the input itself has these properties.
+//!
+//! # Example
+//!
+//! ```
+//! use geojson::Value;
+//! use geojson::simplify::{Algorithm, SimplifyOptions};
+//!
+//! let line = Value::LineString(vec![vec![0.0, 0.0, 10.0],
+//!                                   vec![1.0, 0.1, 11.0],
+//!                                   vec![2.0, -0.1, 12.0],
+//!                                   vec![3.0, 5.0, 13.0],
+//!                                   vec![4.0, 6.0, 14.0]]);
+//!
+//! let options = SimplifyOptions {
+//!     algorithm: Algorithm::DouglasPeucker,
+//!     tolerance: 0.5,
+//!     ..SimplifyOptions::default()
+//! };
+//! assert_eq!(line.simplified(&options),
+//!            Some(Value::LineString(vec![vec![0.0, 0.0, 10.0],
+//!                                        vec![2.0, -0.1, 12.0],
+//!                                        vec![3.0, 5.0, 13.0],
+//!                                        vec![4.0, 6.0, 14.0]])));
+//! ```
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashSet};
+
+use rings;
+use {Feature, FeatureCollection, Geometry, LineStringType, PolygonType, Position, Value};
+
+
+/// Simplification algorithm
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    /// Ramer-Douglas-Peucker, keeping positions farther than `tolerance` from the simplified
+    /// line
+    DouglasPeucker,
+    /// Visvalingam-Whyatt, removing positions whose effective area is below `tolerance`
+    Visvalingam,
+}
+
+/// Options for simplifying geometries
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SimplifyOptions {
+    pub algorithm: Algorithm,
+    /// Distance for Douglas-Peucker, or area for Visvalingam-Whyatt; zero disables
+    /// simplification
+    pub tolerance: f64,
+    /// Whether polygon rings are kept valid, see the module documentation
+    pub preserve_topology: bool,
+    /// Area below which rings are dropped before simplification, a polygon being dropped
+    /// with its exterior ring
+    pub min_ring_area: f64,
+}
+
+impl Default for SimplifyOptions {
+    fn default() -> Self {
+        SimplifyOptions {
+            algorithm: Algorithm::DouglasPeucker,
+            tolerance: 0.0,
+            preserve_topology: false,
+            min_ring_area: 0.0,
+        }
+    }
+}
+
+fn square_segment_distance(p: &Position, a: &Position, b: &Position) -> f64 {
+    let (mut x, mut y) = (a[0], a[1]);
+    let (dx, dy) = (b[0] - x, b[1] - y);
+    if dx != 0.0 || dy != 0.0 {
+        let t = ((p[0] - x) * dx + (p[1] - y) * dy) / (dx * dx + dy * dy);
+        if t > 1.0 {
+            x = b[0];
+            y = b[1];
+        } else if t > 0.0 {
+            x += dx * t;
+            y += dy * t;
+        }
+    }
+    (p[0] - x).powi(2) + (p[1] - y).powi(2)
+}
+
+fn triangle_area(a: &Position, b: &Position, c: &Position) -> f64 {
+    ((b[0] - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (b[1] - a[1])).abs() / 2.0
+}
+
+/// Marks the positions kept by Douglas-Peucker simplification.
+fn douglas_peucker(line: &[Position], tolerance: f64) -> Vec<bool> {
+    let square_tolerance = tolerance * tolerance;
+    let mut keep = vec![false; line.len()];
+    keep[0] = true;
+    keep[line.len() - 1] = true;
+    let mut stack = vec![(0, line.len() - 1)];
+    while let Some((first, last)) = stack.pop() {
+        let mut max_distance = square_tolerance;
+        let mut index = None;
+        for i in first + 1..last {
+            let distance = square_segment_distance(&line[i], &line[first], &line[last]);
+            if distance > max_distance {
+                max_distance = distance;
+                index = Some(i);
+            }
+        }
+        if let Some(i) = index {
+            keep[i] = true;
+            stack.push((first, i));
+            stack.push((i, last));
+        }
+    }
+    keep
+}
+
+/// Candidate for removal in Visvalingam-Whyatt simplification, smallest area first
+#[derive(PartialEq)]
+struct Candidate {
+    area: f64,
+    index: usize,
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.area
+            .partial_cmp(&self.area)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Marks the positions kept by Visvalingam-Whyatt simplification.
+///
+/// The effective area of a position never falls below that of a position removed before it.
+fn visvalingam(line: &[Position], tolerance: f64) -> Vec<bool> {
+    let n = line.len();
+    let mut keep = vec![true; n];
+    let mut previous = (0..n).map(|i| i.wrapping_sub(1)).collect::<Vec<_>>();
+    let mut next = (1..n + 1).collect::<Vec<_>>();
+    let mut areas = vec![0.0; n];
+    let mut heap = BinaryHeap::new();
+    for i in 1..n - 1 {
+        areas[i] = triangle_area(&line[i - 1], &line[i], &line[i + 1]);
+        heap.push(Candidate {
+            area: areas[i],
+            index: i,
+        });
+    }
+    let mut max_area = 0.0f64;
+    while let Some(Candidate { area, index }) = heap.pop() {
+        // Skip removed positions and areas that have since been updated
+        if !keep[index] || area != areas[index] {
+            continue;
+        }
+        max_area = max_area.max(area);
+        if max_area >= tolerance {
+            break;
+        }
+        keep[index] = false;
+        let (p, q) = (previous[index], next[index]);
+        next[p] = q;
+        previous[q] = p;
+        for &i in &[p, q] {
+            if i != 0 && i != n - 1 {
+                areas[i] = triangle_area(&line[previous[i]], &line[i], &line[next[i]])
+                    .max(max_area);
+                heap.push(Candidate {
+                    area: areas[i],
+                    index: i,
+                });
+            }
+        }
+    }
+    keep
+}
+
+/// Copies the positions of `line` that have at least two ordinates.
+fn planar(line: &[Position]) -> LineStringType {
+    line.iter().filter(|p| p.len() >= 2).cloned().collect()
+}
+
+fn keep_mask(line: &[Position], options: &SimplifyOptions) -> Vec<bool> {
+    if line.len() <= 2 || options.tolerance <= 0.0 {
+        return vec![true; line.len()];
+    }
+    match options.algorithm {
+        Algorithm::DouglasPeucker => douglas_peucker(line, options.tolerance),
+        Algorithm::Visvalingam => visvalingam(line, options.tolerance),
+    }
+}
+
+fn kept(line: &[Position], keep: &[bool]) -> LineStringType {
+    line.iter().zip(keep).filter(|&(_, &keep)| keep).map(|(p, _)| p.clone()).collect()
+}
+
+/// Ring being simplified without changing the topology of its polygons
+struct Ring<'a> {
+    positions: &'a [Position],
+    keep: Vec<bool>,
+    /// Index of the polygon's exterior ring, or `None` for exterior rings
+    exterior: Option<usize>,
+}
+
+impl<'a> Ring<'a> {
+    /// Pairs of indices of the kept positions joined by a segment
+    fn segments(&self) -> Vec<(usize, usize)> {
+        let indices = (0..self.positions.len()).filter(|&i| self.keep[i]).collect::<Vec<_>>();
+        indices.windows(2).map(|w| (w[0], w[1])).collect()
+    }
+
+    /// Restores the removed position farthest from the segment `(first, last)`.
+    fn refine(&mut self, first: usize, last: usize) -> bool {
+        let (a, b) = (&self.positions[first], &self.positions[last]);
+        let farthest = (first + 1..last).max_by(|&i, &j| {
+            square_segment_distance(&self.positions[i], a, b)
+                .partial_cmp(&square_segment_distance(&self.positions[j], a, b))
+                .unwrap_or(Ordering::Equal)
+        });
+        match farthest {
+            Some(i) => {
+                self.keep[i] = true;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Restores positions of the rings until they are valid, returning whether any was restored.
+///
+/// `checked` holds the segments of the previous pass, pairs of which are known not to cross
+/// unless they could not be refined, and is updated to the segments of this pass.
+fn restore_topology(rings: &mut [Ring], checked: &mut HashSet<(usize, usize, usize)>) -> bool {
+    let mut refine = vec![];
+
+    // Rings too short to be valid
+    for (r, ring) in rings.iter().enumerate() {
+        if ring.positions.len() >= 4 && ring.keep.iter().filter(|&&keep| keep).count() < 4 {
+            let span = |&(first, last): &(usize, usize)| {
+                let (a, b) = (&ring.positions[first], &ring.positions[last]);
+                (first + 1..last)
+                    .map(|i| square_segment_distance(&ring.positions[i], a, b))
+                    .fold(-1.0, f64::max)
+            };
+            let widest = ring.segments()
+                .into_iter()
+                .max_by(|s, t| span(s).partial_cmp(&span(t)).unwrap_or(Ordering::Equal));
+            refine.extend(widest.map(|(first, last)| (r, first, last)));
+        }
+    }
+
+    // Crossing segments, sweeping over the segments sorted by their smallest x so that only
+    // those overlapping along x are compared
+    let mut segments = rings.iter()
+        .enumerate()
+        .flat_map(|(r, ring)| {
+            ring.segments().into_iter().map(move |(first, last)| (r, first, last))
+        })
+        .filter_map(|(r, first, last)| {
+            let (x1, x2) = (rings[r].positions[first][0], rings[r].positions[last][0]);
+            if x1.is_nan() || x2.is_nan() {
+                return None;
+            }
+            Some((x1.min(x2), x1.max(x2), (r, first, last)))
+        })
+        .collect::<Vec<_>>();
+    segments.sort_by(|s, t| s.0.partial_cmp(&t.0).unwrap_or(Ordering::Equal));
+    for (i, &(_, max_x, (r, a, b))) in segments.iter().enumerate() {
+        for &(min_x, _, (s, c, d)) in &segments[i + 1..] {
+            if min_x > max_x {
+                break;
+            }
+            if checked.contains(&(r, a, b)) && checked.contains(&(s, c, d)) {
+                continue;
+            }
+            if r == s {
+                let end = rings[r].positions.len() - 1;
+                if b == c || d == a || (a == 0 && d == end) {
+                    continue;
+                }
+            }
+            let (p, q) = (rings[r].positions, rings[s].positions);
+            if rings::segments_intersect(&p[a], &p[b], &q[c], &q[d]) {
+                refine.push((r, a, b));
+                refine.push((s, c, d));
+            }
+        }
+    }
+    checked.clear();
+    checked.extend(segments.into_iter().map(|(_, _, segment)| segment));
+
+    // Holes outside their exterior ring
+    for r in 0..rings.len() {
+        if let Some(e) = rings[r].exterior {
+            let hole = kept(rings[r].positions, &rings[r].keep);
+            let exterior = kept(rings[e].positions, &rings[e].keep);
+            if !hole.iter().any(|p| rings::ring_contains(&exterior, p)) {
+                refine.extend(rings[e].segments().into_iter().map(|(a, b)| (e, a, b)));
+            }
+        }
+    }
+
+    let mut refined = false;
+    for (r, first, last) in refine {
+        if !rings[r].keep[first + 1..last].iter().any(|&keep| keep) {
+            refined |= rings[r].refine(first, last);
+        }
+    }
+    refined
+}
+
+/// Simplifies the polygons of a polygon or multi-polygon together.
+fn simplify_polygons(polygons: &[PolygonType], options: &SimplifyOptions) -> Vec<PolygonType> {
+    let large = |ring: &LineStringType| {
+        rings::signed_area(ring).abs() / 2.0 >= options.min_ring_area
+    };
+    let polygons = polygons.iter()
+        .map(|polygon| polygon.iter().map(|ring| planar(ring)).collect::<Vec<_>>())
+        .filter(|polygon| polygon.first().map_or(false, &large))
+        .map(|polygon| polygon.into_iter().filter(|ring| large(ring)).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    let mut rings = vec![];
+    for polygon in &polygons {
+        let exterior = rings.len();
+        for (i, ring) in polygon.iter().enumerate() {
+            rings.push(Ring {
+                positions: ring,
+                keep: keep_mask(ring, options),
+                exterior: if i == 0 { None } else { Some(exterior) },
+            });
+        }
+    }
+    if options.preserve_topology {
+        let mut checked = HashSet::new();
+        while restore_topology(&mut rings, &mut checked) {}
+    }
+
+    let mut rings = rings.into_iter()
+        .map(|ring| (ring.exterior.is_none(), kept(ring.positions, &ring.keep)));
+    let mut simplified = vec![];
+    for polygon in &polygons {
+        let polygon = rings.by_ref()
+            .take(polygon.len())
+            .filter(|&(_, ref ring)| ring.len() >= 4)
+            .collect::<Vec<_>>();
+        if polygon.first().map_or(false, |&(exterior, _)| exterior) {
+            simplified.push(polygon.into_iter().map(|(_, ring)| ring).collect());
+        }
+    }
+    simplified
+}
+
+impl Value {
+    /// Returns a simplified copy of the value, or `None` if all of its polygons are dropped.
+    pub fn simplified(&self, options: &SimplifyOptions) -> Option<Value> {
+        let line = |line: &LineStringType| {
+            let line = planar(line);
+            kept(&line, &keep_mask(&line, options))
+        };
+        Some(match *self {
+            Value::Point(..) | Value::MultiPoint(..) => self.clone(),
+            Value::LineString(ref positions) => Value::LineString(line(positions)),
+            Value::MultiLineString(ref lines) => {
+                Value::MultiLineString(lines.iter().map(line).collect())
+            }
+            Value::Polygon(ref polygon) => {
+                match simplify_polygons(::std::slice::from_ref(polygon), options).pop() {
+                    Some(polygon) => Value::Polygon(polygon),
+                    None => return None,
+                }
+            }
+            Value::MultiPolygon(ref polygons) => {
+                let polygons = simplify_polygons(polygons, options);
+                if polygons.is_empty() {
+                    return None;
+                }
+                Value::MultiPolygon(polygons)
+            }
+            Value::GeometryCollection(ref geometries) => {
+                let geometries = geometries.iter()
+                    .filter_map(|geometry| geometry.simplified(options))
+                    .collect::<Vec<_>>();
+                if geometries.is_empty() {
+                    return None;
+                }
+                Value::GeometryCollection(geometries)
+            }
+        })
+    }
+}
+
+impl Geometry {
+    /// Returns a simplified copy of the geometry, with the same `bbox` and `crs`, or `None` if
+    /// all of its polygons are dropped.
+    pub fn simplified(&self, options: &SimplifyOptions) -> Option<Geometry> {
+        self.value.simplified(options).map(|value| {
+            Geometry {
+                bbox: self.bbox.clone(),
+                value: value,
+                crs: self.crs.clone(),
+            }
+        })
+    }
+}
+
+impl Feature {
+    /// Returns a copy of the feature with its geometry simplified, or set to `None` if all of
+    /// its polygons are dropped.
+    pub fn simplified(&self, options: &SimplifyOptions) -> Feature {
+        Feature {
+            bbox: self.bbox.clone(),
+            crs: self.crs.clone(),
+            geometry: self.geometry.as_ref().and_then(|geometry| geometry.simplified(options)),
+            id: self.id.clone(),
+            properties: self.properties.clone(),
+        }
+    }
+}
+
+impl FeatureCollection {
+    /// Returns a copy of the collection with the geometries of its features simplified, as
+    /// `Feature::simplified`.
+    pub fn simplified(&self, options: &SimplifyOptions) -> FeatureCollection {
+        FeatureCollection {
+            bbox: self.bbox.clone(),
+            crs: self.crs.clone(),
+            features: self.features.iter().map(|feature| feature.simplified(options)).collect(),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use {Feature, FeatureCollection, Geometry, Position, Value};
+    use super::{Algorithm, SimplifyOptions};
+
+    fn options(algorithm: Algorithm, tolerance: f64, preserve_topology: bool) -> SimplifyOptions {
+        SimplifyOptions {
+            algorithm: algorithm,
+            tolerance: tolerance,
+            preserve_topology: preserve_topology,
+            min_ring_area: 0.0,
+        }
+    }
+
+    /// Square ring from `min` to `max`, with a position in the middle of every side
+    fn square(min: f64, max: f64) -> Vec<Position> {
+        let mid = (min + max) / 2.0;
+        vec![vec![min, min],
+             vec![mid, min],
+             vec![max, min],
+             vec![max, mid],
+             vec![max, max],
+             vec![mid, max],
+             vec![min, max],
+             vec![min, mid],
+             vec![min, min]]
+    }
+
+    #[test]
+    fn visvalingam() {
+        let line = Value::LineString(vec![vec![0.0, 0.0],
+                                          vec![1.0, 0.1],
+                                          vec![2.0, 0.0],
+                                          vec![3.0, 2.0],
+                                          vec![4.0, 0.0]]);
+        assert_eq!(line.simplified(&options(Algorithm::Visvalingam, 0.5, false)),
+                   Some(Value::LineString(vec![vec![0.0, 0.0],
+                                               vec![2.0, 0.0],
+                                               vec![3.0, 2.0],
+                                               vec![4.0, 0.0]])));
+        assert_eq!(line.simplified(&options(Algorithm::Visvalingam, 0.0, false)),
+                   Some(line.clone()));
+        match line.simplified(&options(Algorithm::Visvalingam, 100.0, false)) {
+            Some(Value::LineString(ref positions)) => assert_eq!(positions.len(), 2),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn collapsed_rings() {
+        let polygon = Value::Polygon(vec![square(0.0, 10.0), square(4.0, 6.0)]);
+        for &algorithm in &[Algorithm::DouglasPeucker, Algorithm::Visvalingam] {
+            // Without topology preservation, the squares collapse
+            assert_eq!(polygon.simplified(&options(algorithm, 1000.0, false)), None);
+
+            // With it, enough positions are restored to keep both rings
+            match polygon.simplified(&options(algorithm, 1000.0, true)) {
+                Some(Value::Polygon(ref rings)) => {
+                    assert_eq!(rings.len(), 2);
+                    assert!(rings.iter()
+                        .all(|ring| ring.len() >= 4 && ring.first() == ring.last()));
+                }
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn holes_stay_inside() {
+        // A small bump of the exterior ring is removed by simplification, along with the hole
+        // in it unless the topology is preserved
+        let exterior = vec![vec![0.0, 0.0],
+                            vec![10.0, 0.0],
+                            vec![10.0, 4.0],
+                            vec![5.2, 4.0],
+                            vec![5.2, 4.5],
+                            vec![4.8, 4.5],
+                            vec![4.8, 4.0],
+                            vec![0.0, 4.0],
+                            vec![0.0, 0.0]];
+        let hole = vec![vec![4.9, 4.1], vec![5.1, 4.1], vec![5.1, 4.2], vec![4.9, 4.1]];
+        let polygon = Value::Polygon(vec![exterior, hole.clone()]);
+
+        let simplified = |preserve_topology| {
+            match polygon.simplified(&options(Algorithm::DouglasPeucker, 0.6, preserve_topology)) {
+                Some(Value::Polygon(rings)) => rings,
+                other => panic!("unexpected {:?}", other),
+            }
+        };
+        let rings = simplified(false);
+        assert_eq!(rings,
+                   vec![vec![vec![0.0, 0.0],
+                             vec![10.0, 0.0],
+                             vec![10.0, 4.0],
+                             vec![0.0, 4.0],
+                             vec![0.0, 0.0]]]);
+
+        let rings = simplified(true);
+        assert_eq!(rings.len(), 2);
+        assert_eq!(rings[1], hole);
+        assert!(hole.iter().all(|p| ::rings::ring_contains(&rings[0], p)));
+    }
+
+    #[test]
+    fn min_ring_area() {
+        let polygons = Value::MultiPolygon(vec![vec![square(0.0, 10.0), square(4.0, 6.0)],
+                                                vec![square(20.0, 21.0)]]);
+        let options = SimplifyOptions { min_ring_area: 5.0, ..SimplifyOptions::default() };
+        assert_eq!(polygons.simplified(&options),
+                   Some(Value::MultiPolygon(vec![vec![square(0.0, 10.0)]])));
+
+        let options = SimplifyOptions { min_ring_area: 500.0, ..options };
+        assert_eq!(polygons.simplified(&options), None);
+    }
+
+    #[test]
+    fn members_are_kept() {
+        let mut geometry = Geometry::new(Value::Polygon(vec![square(0.0, 10.0)]));
+        geometry.bbox = Some(vec![0.0, 0.0, 10.0, 10.0]);
+        let fc = FeatureCollection::builder()
+            .feature(Feature::builder()
+                .geometry(geometry)
+                .property("name", "square")
+                .build())
+            .feature(Feature::builder().geometry(Value::Polygon(vec![square(0.0, 0.1)])).build())
+            .build();
+        let fc = fc.simplified(&options(Algorithm::DouglasPeucker, 1.0, false));
+
+        let simplified = fc.features[0].geometry.as_ref().unwrap();
+        assert_eq!(simplified.bbox, Some(vec![0.0, 0.0, 10.0, 10.0]));
+        assert_eq!(simplified.value,
+                   Value::Polygon(vec![vec![vec![0.0, 0.0],
+                                            vec![10.0, 0.0],
+                                            vec![10.0, 10.0],
+                                            vec![0.0, 10.0],
+                                            vec![0.0, 0.0]]]));
+        assert_eq!(fc.features[0].property::<String>("name"), Some(String::from("square")));
+        assert_eq!(fc.features[1].geometry, None);
+    }
+
+    #[test]
+    fn empty_positions() {
+        let line = Value::LineString(vec![vec![0.0, 0.0], vec![], vec![1.0, 0.1], vec![2.0, 0.0]]);
+        for &algorithm in &[Algorithm::DouglasPeucker, Algorithm::Visvalingam] {
+            assert_eq!(line.simplified(&options(algorithm, 1.0, false)),
+                       Some(Value::LineString(vec![vec![0.0, 0.0], vec![2.0, 0.0]])));
+        }
+
+        let mut ring = square(0.0, 10.0);
+        ring.insert(3, vec![]);
+        let polygon = Value::Polygon(vec![ring]);
+        assert_eq!(polygon.simplified(&options(Algorithm::DouglasPeucker, 0.0, true)),
+                   Some(Value::Polygon(vec![square(0.0, 10.0)])));
+    }
+}
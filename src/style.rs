@@ -0,0 +1,328 @@
+// Copyright 2015 The GeoRust Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+use std::str::FromStr;
+
+use json::{JsonObject, JsonValue};
+
+use {Error, Feature};
+
+
+fn invalid(key: &str, value: &JsonValue) -> Error {
+    Error::StyleInvalidProperty(format!("'{}' = {}", key, value))
+}
+
+/// RGB color, written as `#rrggbb`
+///
+/// Parsed from the `#rrggbb` and `#rgb` forms, with or without the leading `#`.
+///
+/// # Examples
+///
+/// ```
+/// use geojson::Color;
+///
+/// let color = "#f80".parse::<Color>().unwrap();
+/// assert_eq!(color, Color { red: 0xff, green: 0x88, blue: 0x00 });
+/// assert_eq!(color.to_string(), "#ff8800");
+/// assert!("orange".parse::<Color>().is_err());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Color {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl FromStr for Color {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || Error::StyleInvalidProperty(format!("color '{}'", s));
+        let hex = s.trim_start_matches('#');
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(error());
+        }
+        let hex = match hex.len() {
+            3 => hex.chars().flat_map(|c| vec![c, c]).collect::<String>(),
+            6 => String::from(hex),
+            _ => return Err(error()),
+        };
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+        Ok(Color {
+            red: channel(0),
+            green: channel(2),
+            blue: channel(4),
+        })
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+    }
+}
+
+/// Size of a point marker
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MarkerSize {
+    Small,
+    Medium,
+    Large,
+}
+
+impl MarkerSize {
+    fn as_str(&self) -> &'static str {
+        match *self {
+            MarkerSize::Small => "small",
+            MarkerSize::Medium => "medium",
+            MarkerSize::Large => "large",
+        }
+    }
+}
+
+/// Presentation of a feature, following the
+/// [simplestyle specification](https://github.com/mapbox/simplestyle-spec/tree/master/1.1.0)
+///
+/// Each field maps to the feature property of the same name in kebab case, such as
+/// `stroke_width` to `stroke-width`. A `None` field stands for a missing or `null` property,
+/// for which renderers apply the defaults of the specification.
+///
+/// # Examples
+///
+/// ```
+/// use geojson::{Color, Feature, Style};
+///
+/// let mut feature = Feature::builder()
+///     .property("stroke", "#555")
+///     .property("stroke-width", 3)
+///     .build();
+///
+/// let mut style = feature.style().unwrap();
+/// assert_eq!(style.stroke, Some(Color { red: 0x55, green: 0x55, blue: 0x55 }));
+/// assert_eq!(style.stroke_width, Some(3.0));
+///
+/// style.fill_opacity = Some(0.25);
+/// feature.set_style(&style);
+/// assert_eq!(feature.property::<f64>("fill-opacity"), Some(0.25));
+///
+/// feature.set_property("fill-opacity", 2);
+/// assert!(feature.style().is_err());
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Style {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub marker_size: Option<MarkerSize>,
+    /// Name of an icon, or a digit or letter
+    pub marker_symbol: Option<String>,
+    pub marker_color: Option<Color>,
+    pub stroke: Option<Color>,
+    /// Between 0 and 1
+    pub stroke_opacity: Option<f64>,
+    /// Non-negative width, in pixels
+    pub stroke_width: Option<f64>,
+    pub fill: Option<Color>,
+    /// Between 0 and 1
+    pub fill_opacity: Option<f64>,
+}
+
+/// Property keys, in the order of the fields of `Style`
+const KEYS: &'static [&'static str] = &["title",
+                                        "description",
+                                        "marker-size",
+                                        "marker-symbol",
+                                        "marker-color",
+                                        "stroke",
+                                        "stroke-opacity",
+                                        "stroke-width",
+                                        "fill",
+                                        "fill-opacity"];
+
+impl Style {
+    /// Reads the simplestyle properties of a properties object, ignoring any others.
+    ///
+    /// Fails on the first property of the wrong type or out of range.
+    pub fn from_properties(properties: &JsonObject) -> Result<Style, Error> {
+        let get = |key: &str| match properties.get(key) {
+            None | Some(&JsonValue::Null) => None,
+            Some(value) => Some(value),
+        };
+        let string = |key: &str| match get(key) {
+            None => Ok(None),
+            Some(&JsonValue::String(ref s)) => Ok(Some(s.clone())),
+            Some(value) => Err(invalid(key, value)),
+        };
+        let color = |key: &str| match get(key) {
+            None => Ok(None),
+            Some(value) => {
+                value.as_str()
+                    .and_then(|s| s.parse().ok())
+                    .map(Some)
+                    .ok_or_else(|| invalid(key, value))
+            }
+        };
+        let number = |key: &str, max: f64| match get(key) {
+            None => Ok(None),
+            Some(value) => {
+                value.as_f64()
+                    .filter(|&n| n >= 0.0 && n <= max)
+                    .map(Some)
+                    .ok_or_else(|| invalid(key, value))
+            }
+        };
+        let marker_size = match get("marker-size") {
+            None => None,
+            Some(value) => {
+                Some(match value.as_str() {
+                    Some("small") => MarkerSize::Small,
+                    Some("medium") => MarkerSize::Medium,
+                    Some("large") => MarkerSize::Large,
+                    _ => return Err(invalid("marker-size", value)),
+                })
+            }
+        };
+        Ok(Style {
+            title: try!(string("title")),
+            description: try!(string("description")),
+            marker_size: marker_size,
+            marker_symbol: try!(string("marker-symbol")),
+            marker_color: try!(color("marker-color")),
+            stroke: try!(color("stroke")),
+            stroke_opacity: try!(number("stroke-opacity", 1.0)),
+            stroke_width: try!(number("stroke-width", f64::INFINITY)),
+            fill: try!(color("fill")),
+            fill_opacity: try!(number("fill-opacity", 1.0)),
+        })
+    }
+
+    /// Writes the style into a properties object.
+    ///
+    /// Properties for `None` fields are removed, and properties other than simplestyle ones are
+    /// left untouched.
+    pub fn write_properties(&self, properties: &mut JsonObject) {
+        let values = [self.title.as_ref().map(|s| json!(s)),
+                      self.description.as_ref().map(|s| json!(s)),
+                      self.marker_size.map(|size| json!(size.as_str())),
+                      self.marker_symbol.as_ref().map(|s| json!(s)),
+                      self.marker_color.map(|color| json!(color.to_string())),
+                      self.stroke.map(|color| json!(color.to_string())),
+                      self.stroke_opacity.map(|n| json!(n)),
+                      self.stroke_width.map(|n| json!(n)),
+                      self.fill.map(|color| json!(color.to_string())),
+                      self.fill_opacity.map(|n| json!(n))];
+        for (key, value) in KEYS.iter().zip(values.iter()) {
+            match *value {
+                Some(ref value) => {
+                    properties.insert(String::from(*key), value.clone());
+                }
+                None => {
+                    properties.remove(*key);
+                }
+            }
+        }
+    }
+}
+
+impl Feature {
+    /// Returns the simplestyle properties of the feature.
+    ///
+    /// A feature without properties has the default style.
+    pub fn style(&self) -> Result<Style, Error> {
+        match self.properties {
+            Some(ref properties) => Style::from_properties(properties),
+            None => Ok(Style::default()),
+        }
+    }
+
+    /// Replaces the simplestyle properties of the feature with `style`.
+    ///
+    /// The `properties` member is created if it is `None` and the style has any field set.
+    pub fn set_style(&mut self, style: &Style) {
+        if self.properties.is_none() && *style == Style::default() {
+            return;
+        }
+        style.write_properties(self.properties.get_or_insert_with(JsonObject::new));
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use {Error, Feature};
+    use super::{Color, MarkerSize, Style};
+
+    #[test]
+    fn read_and_write() {
+        let mut feature = Feature::builder()
+            .property("name", "kept")
+            .property("title", "Depot")
+            .property("marker-size", "large")
+            .property("marker-symbol", "rail")
+            .property("marker-color", "00FF00")
+            .property("stroke-opacity", 0)
+            .property("fill", ::json::JsonValue::Null)
+            .build();
+        let mut style = feature.style().unwrap();
+        assert_eq!(style,
+                   Style {
+                       title: Some(String::from("Depot")),
+                       marker_size: Some(MarkerSize::Large),
+                       marker_symbol: Some(String::from("rail")),
+                       marker_color: Some(Color {
+                           red: 0,
+                           green: 255,
+                           blue: 0,
+                       }),
+                       stroke_opacity: Some(0.0),
+                       ..Style::default()
+                   });
+
+        style.title = None;
+        style.stroke_width = Some(1.5);
+        feature.set_style(&style);
+        let properties = feature.properties.as_ref().unwrap();
+        assert_eq!(properties.keys().collect::<Vec<_>>(),
+                   vec!["marker-color",
+                        "marker-size",
+                        "marker-symbol",
+                        "name",
+                        "stroke-opacity",
+                        "stroke-width"]);
+        assert_eq!(properties["marker-color"], json!("#00ff00"));
+        assert_eq!(feature.style().unwrap(), style);
+
+        let mut plain = Feature::builder().build();
+        plain.set_style(&Style::default());
+        assert_eq!(plain.properties, None);
+        assert_eq!(plain.style(), Ok(Style::default()));
+    }
+
+    #[test]
+    fn validation() {
+        let style = |key: &str, value: ::json::JsonValue| {
+            Feature::builder().property(key, value).build().style()
+        };
+        assert!(style("fill", json!("#abcd")).is_err());
+        assert!(style("fill", json!("#ggg")).is_err());
+        assert!(style("stroke", json!(255)).is_err());
+        assert!(style("fill-opacity", json!(1.5)).is_err());
+        assert!(style("stroke-opacity", json!("0.5")).is_err());
+        assert!(style("stroke-width", json!(-1)).is_err());
+        assert!(style("marker-size", json!("huge")).is_err());
+        assert_eq!(style("title", json!(1)),
+                   Err(Error::StyleInvalidProperty(String::from("'title' = 1"))));
+        assert_eq!(style("stroke-width", json!(0)).unwrap().stroke_width, Some(0.0));
+    }
+}
@@ -0,0 +1,349 @@
+// Copyright 2015 The GeoRust Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! SVG rendering
+//!
+//! Objects are drawn in their own coordinates, fitted into the viewport with their aspect ratio
+//! kept and the y axis pointing up. The extent is taken from the object's `bbox` member if it
+//! has one and computed from its positions otherwise.
+//!
+//! Points are drawn as circles, line strings as paths, and polygons as paths filled with the
+//! even-odd rule, so that interior rings are holes. Features are styled with their
+//! [simplestyle](https://github.com/mapbox/simplestyle-spec) properties `stroke`,
+//! `stroke-opacity`, `stroke-width`, `fill`, `fill-opacity` and `marker-color`, as read by
+//! `Feature::style`, falling back to the defaults of the specification for those missing. A
+//! feature whose style is invalid is drawn with the defaults.
+//!
+//! # Example
+//!
+//! ```
+//! use geojson::GeoJson;
+//! use geojson::svg::SvgOptions;
+//!
+//! let geojson = r##"{
+//!     "type": "Feature",
+//!     "geometry": {"type": "LineString", "coordinates": [[0.0, 0.0], [10.0, 10.0]]},
+//!     "properties": {"stroke": "#ff0000"}
+//! }"##.parse::<GeoJson>().unwrap();
+//!
+//! let svg = geojson.to_svg(&SvgOptions::default());
+//! assert!(svg.contains(r##"<path d="M4 252L252 4" fill="none" stroke="#ff0000""##));
+//! ```
+
+use std::f64;
+
+use xml;
+
+use {Color, Feature, GeoJson, Position, Style};
+use iter::SimpleGeometry;
+
+
+const NAMESPACE: &'static str = "http://www.w3.org/2000/svg";
+
+// Defaults of the simplestyle specification
+const STROKE: Color = Color { red: 0x55, green: 0x55, blue: 0x55 };
+const STROKE_OPACITY: f64 = 1.0;
+const STROKE_WIDTH: f64 = 2.0;
+const FILL: Color = Color { red: 0x55, green: 0x55, blue: 0x55 };
+const FILL_OPACITY: f64 = 0.6;
+const MARKER_COLOR: Color = Color { red: 0x7e, green: 0x7e, blue: 0x7e };
+
+/// Options for rendering SVG documents
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SvgOptions {
+    /// Width of the viewport, in pixels
+    pub width: f64,
+    /// Height of the viewport, in pixels
+    pub height: f64,
+    /// Margin kept free around the drawing, in pixels
+    pub padding: f64,
+    /// Radius of the circles drawn for points, in pixels
+    pub point_radius: f64,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        SvgOptions {
+            width: 256.0,
+            height: 256.0,
+            padding: 4.0,
+            point_radius: 4.0,
+        }
+    }
+}
+
+/// Mapping from the object's coordinates to the viewport
+struct Transform {
+    scale: f64,
+    x: f64,
+    y: f64,
+}
+
+impl Transform {
+    fn fit(extent: Option<[f64; 4]>, options: &SvgOptions) -> Self {
+        let [min_x, min_y, max_x, max_y] = match extent {
+            Some(extent) => extent,
+            None => {
+                return Transform {
+                    scale: 1.0,
+                    x: 0.0,
+                    y: 0.0,
+                }
+            }
+        };
+        let width = (options.width - 2.0 * options.padding).max(0.0);
+        let height = (options.height - 2.0 * options.padding).max(0.0);
+        let mut scale = f64::INFINITY;
+        if max_x > min_x {
+            scale = scale.min(width / (max_x - min_x));
+        }
+        if max_y > min_y {
+            scale = scale.min(height / (max_y - min_y));
+        }
+        if !scale.is_finite() {
+            scale = 1.0;
+        }
+        Transform {
+            scale: scale,
+            x: options.width / 2.0 - scale * (min_x + max_x) / 2.0,
+            y: options.height / 2.0 + scale * (min_y + max_y) / 2.0,
+        }
+    }
+
+    fn apply(&self, position: &Position) -> (f64, f64) {
+        (self.x + self.scale * position[0], self.y - self.scale * position[1])
+    }
+}
+
+/// Formats a pixel coordinate with two decimals at most.
+fn number(value: f64) -> String {
+    let text = format!("{:.2}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    match text {
+        "-0" => String::from("0"),
+        _ => String::from(text),
+    }
+}
+
+fn extent(geojson: &GeoJson) -> Option<[f64; 4]> {
+    let bbox = match *geojson {
+        GeoJson::Geometry(ref geometry) => geometry.bbox.as_ref(),
+        GeoJson::Feature(ref feature) => feature.bbox.as_ref(),
+        GeoJson::FeatureCollection(ref fc) => fc.bbox.as_ref(),
+    };
+    if let Some(bbox) = bbox {
+        let n = bbox.len() / 2;
+        if n >= 2 && bbox.len() % 2 == 0 {
+            return Some([bbox[0], bbox[1], bbox[n], bbox[n + 1]]);
+        }
+    }
+    geojson.coords().filter(|position| position.len() >= 2).fold(None, |extent, position| {
+        let [x, y] = [position[0], position[1]];
+        Some(match extent {
+            None => [x, y, x, y],
+            Some([min_x, min_y, max_x, max_y]) => {
+                [min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y)]
+            }
+        })
+    })
+}
+
+fn path(transform: &Transform, line: &[Position], closed: bool, d: &mut String) {
+    let mut command = 'M';
+    for position in line.iter().filter(|position| position.len() >= 2) {
+        let (x, y) = transform.apply(position);
+        d.push(command);
+        d.push_str(&number(x));
+        d.push(' ');
+        d.push_str(&number(y));
+        command = 'L';
+    }
+    if closed && command == 'L' {
+        d.push('Z');
+    }
+}
+
+fn write_part(writer: &mut xml::Writer,
+              part: SimpleGeometry,
+              style: &Style,
+              transform: &Transform,
+              options: &SvgOptions) {
+    let stroke = style.stroke.unwrap_or(STROKE).to_string();
+    let stroke_opacity = number(style.stroke_opacity.unwrap_or(STROKE_OPACITY));
+    let stroke_width = number(style.stroke_width.unwrap_or(STROKE_WIDTH));
+    match part {
+        SimpleGeometry::Point(position) => {
+            if position.len() < 2 {
+                return;
+            }
+            let (x, y) = transform.apply(position);
+            writer.empty("circle",
+                         &[("cx", &number(x)),
+                           ("cy", &number(y)),
+                           ("r", &number(options.point_radius)),
+                           ("fill", &style.marker_color.unwrap_or(MARKER_COLOR).to_string())]);
+        }
+        SimpleGeometry::LineString(line) => {
+            let mut d = String::new();
+            path(transform, line, false, &mut d);
+            if d.is_empty() {
+                return;
+            }
+            writer.empty("path",
+                         &[("d", &d),
+                           ("fill", "none"),
+                           ("stroke", &stroke),
+                           ("stroke-opacity", &stroke_opacity),
+                           ("stroke-width", &stroke_width)]);
+        }
+        SimpleGeometry::Polygon(rings) => {
+            let mut d = String::new();
+            for ring in rings {
+                path(transform, ring, true, &mut d);
+            }
+            if d.is_empty() {
+                return;
+            }
+            writer.empty("path",
+                         &[("d", &d),
+                           ("fill", &style.fill.unwrap_or(FILL).to_string()),
+                           ("fill-opacity", &number(style.fill_opacity.unwrap_or(FILL_OPACITY))),
+                           ("fill-rule", "evenodd"),
+                           ("stroke", &stroke),
+                           ("stroke-opacity", &stroke_opacity),
+                           ("stroke-width", &stroke_width)]);
+        }
+    }
+}
+
+fn write_feature(writer: &mut xml::Writer,
+                 feature: &Feature,
+                 transform: &Transform,
+                 options: &SvgOptions) {
+    let style = feature.style().unwrap_or_default();
+    for part in feature.geometries() {
+        write_part(writer, part, &style, transform, options);
+    }
+}
+
+impl GeoJson {
+    /// Renders the object as an SVG document.
+    pub fn to_svg(&self, options: &SvgOptions) -> String {
+        let transform = Transform::fit(extent(self), options);
+        let (width, height) = (number(options.width), number(options.height));
+        let view_box = format!("0 0 {} {}", width, height);
+        let mut writer = xml::Writer::new();
+        writer.start("svg",
+                     &[("xmlns", NAMESPACE),
+                       ("width", &width),
+                       ("height", &height),
+                       ("viewBox", &view_box)]);
+        match *self {
+            GeoJson::Geometry(ref geometry) => {
+                let style = Style::default();
+                for part in geometry.geometries() {
+                    write_part(&mut writer, part, &style, &transform, options);
+                }
+            }
+            GeoJson::Feature(ref feature) => {
+                write_feature(&mut writer, feature, &transform, options);
+            }
+            GeoJson::FeatureCollection(ref fc) => {
+                for feature in &fc.features {
+                    write_feature(&mut writer, feature, &transform, options);
+                }
+            }
+        }
+        writer.end("svg");
+        writer.xml
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use {Feature, FeatureCollection, GeoJson, Geometry, Value};
+    use super::SvgOptions;
+
+    fn options() -> SvgOptions {
+        SvgOptions {
+            width: 100.0,
+            height: 50.0,
+            padding: 0.0,
+            point_radius: 2.5,
+        }
+    }
+
+    #[test]
+    fn fit_and_styles() {
+        let fc = FeatureCollection::builder()
+            .feature(Feature::builder()
+                .geometry(Value::MultiPoint(vec![vec![0.0, 0.0], vec![4.0, 2.0]]))
+                .property("marker-color", "#f80")
+                .build())
+            .feature(Feature::builder()
+                .geometry(Value::LineString(vec![vec![0.0, 2.0], vec![2.0, 1.0]]))
+                .property("stroke", "#f00")
+                .property("stroke-width", 0.5)
+                .build())
+            .build();
+        let svg = GeoJson::from(fc).to_svg(&options());
+        assert_eq!(svg,
+                   r##"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="100" height="50" viewBox="0 0 100 50">
+  <circle cx="0" cy="50" r="2.5" fill="#ff8800"/>
+  <circle cx="100" cy="0" r="2.5" fill="#ff8800"/>
+  <path d="M0 0L50 25" fill="none" stroke="#ff0000" stroke-opacity="1" stroke-width="0.5"/>
+</svg>
+"##);
+
+        // Invalid styles fall back to the defaults
+        let feature = Feature::builder()
+            .geometry(Value::LineString(vec![vec![0.0, 2.0], vec![2.0, 1.0]]))
+            .property("stroke", "red")
+            .property("stroke-width", 0.5)
+            .build();
+        let svg = GeoJson::from(feature).to_svg(&options());
+        assert!(svg.contains(r##"stroke="#555555" stroke-opacity="1" stroke-width="2""##));
+    }
+
+    #[test]
+    fn polygons() {
+        let square = |min: f64, max: f64| {
+            vec![vec![min, min], vec![max, min], vec![max, max], vec![min, max], vec![min, min]]
+        };
+        let geometry = Geometry::new(Value::Polygon(vec![square(0.0, 10.0), square(4.0, 6.0)]));
+        let svg = GeoJson::from(geometry).to_svg(&options());
+        assert!(svg.contains("d=\"M25 50L75 50L75 0L25 0L25 50Z\
+                              M45 30L55 30L55 20L45 20L45 30Z\""));
+        assert!(svg.contains(r##"fill="#555555" fill-opacity="0.6" fill-rule="evenodd""##));
+    }
+
+    #[test]
+    fn extent() {
+        // The bbox member takes precedence over the positions
+        let mut geometry = Geometry::new(Value::Point(vec![1.0, 1.0]));
+        geometry.bbox = Some(vec![0.0, 0.0, -5.0, 4.0, 2.0, 5.0]);
+        let svg = GeoJson::from(geometry).to_svg(&options());
+        assert!(svg.contains(r#"<circle cx="25" cy="25""#));
+
+        // A single point is centered
+        let svg = GeoJson::from(Geometry::new(Value::Point(vec![3.0, -7.0]))).to_svg(&options());
+        assert!(svg.contains(r#"<circle cx="50" cy="25""#));
+
+        // Nothing to draw
+        let empty = GeoJson::from(FeatureCollection::builder().build()).to_svg(&options());
+        assert!(empty.ends_with("viewBox=\"0 0 100 50\">\n</svg>\n"));
+    }
+}
@@ -0,0 +1,554 @@
+// Copyright 2015 The GeoRust Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Slicing of feature collections into tiles
+//!
+//! A `TileIndex` holds WGS84 features projected to Web Mercator. For a requested tile, each
+//! feature is simplified with a tolerance matching the tile's zoom level, clipped to the tile
+//! grown by a buffer, and returned in tile-local coordinates, ready for `mvt::encode`.
+//! Ordinates beyond the first two are interpolated along clipped edges.
+
+use std::collections::BTreeSet;
+
+use mvt::TileId;
+use simplify::SimplifyOptions;
+use {Feature, FeatureCollection, Geometry, LineStringType, PolygonType, Position, Value};
+
+
+/// Options for slicing features into tiles
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TileOptions {
+    /// Number of tile-local units along each side of a tile
+    pub extent: u32,
+    /// Width of the margin around each tile that features are kept in, in tile-local units
+    pub buffer: u32,
+    /// Simplification tolerance, in tile-local units; zero disables simplification
+    pub tolerance: f64,
+}
+
+impl Default for TileOptions {
+    fn default() -> Self {
+        TileOptions {
+            extent: 4096,
+            buffer: 64,
+            tolerance: 3.0,
+        }
+    }
+}
+
+/// Projected features that tiles are cut from.
+///
+/// # Examples
+///
+/// ```
+/// use geojson::{Feature, FeatureCollection, Value};
+/// use geojson::mvt::TileId;
+/// use geojson::tiling::{TileIndex, TileOptions};
+///
+/// let fc = FeatureCollection::builder()
+///     .feature(Feature::builder()
+///         .geometry(Value::LineString(vec![vec![-10.0, 10.0], vec![10.0, 10.0]]))
+///         .id(1)
+///         .build())
+///     .build();
+///
+/// let index = TileIndex::new(&fc, TileOptions::default());
+/// let tiles = index.tiles(1);
+/// assert_eq!(tiles.iter().map(|&(tile, _)| tile).collect::<Vec<_>>(),
+///            vec![TileId::new(1, 0, 0), TileId::new(1, 1, 0)]);
+/// assert_eq!(index.tile(TileId::new(1, 1, 0)).features[0].id, Some(1.into()));
+/// ```
+pub struct TileIndex {
+    features: Vec<(Feature, [f64; 4])>,
+    options: TileOptions,
+}
+
+impl TileIndex {
+    pub fn new(fc: &FeatureCollection, options: TileOptions) -> Self {
+        let world = TileId::new(0, 0, 0);
+        let features = fc.features
+            .iter()
+            .filter(|feature| feature.geometry.is_some())
+            .map(|feature| {
+                let mut feature = feature.clone();
+                drop_short_positions(&mut feature.geometry.as_mut().unwrap().value);
+                let mut bbox = [::std::f64::INFINITY,
+                                ::std::f64::INFINITY,
+                                ::std::f64::NEG_INFINITY,
+                                ::std::f64::NEG_INFINITY];
+                for position in feature.coords_mut().filter(|p| p.len() >= 2) {
+                    let projected = world.to_tile(position, 1);
+                    position[0] = projected[0];
+                    position[1] = projected[1];
+                    for i in 0..2 {
+                        bbox[i] = bbox[i].min(position[i]);
+                        bbox[i + 2] = bbox[i + 2].max(position[i]);
+                    }
+                }
+                (feature, bbox)
+            })
+            .collect();
+        TileIndex {
+            features: features,
+            options: options,
+        }
+    }
+
+    /// Returns the features of `tile`, clipped and simplified, in tile-local coordinates.
+    pub fn tile(&self, tile: TileId) -> FeatureCollection {
+        let bounds = self.bounds(tile);
+        let features = self.features
+            .iter()
+            .filter(|&&(_, bbox)| intersects(&bbox, &bounds))
+            .collect::<Vec<_>>();
+        self.clip(tile, &self.simplify(tile.z, &features))
+    }
+
+    /// Returns every tile at zoom level `z` that contains features.
+    pub fn tiles(&self, z: u8) -> Vec<(TileId, FeatureCollection)> {
+        let tiles_per_side = 2f64.powi(z as i32);
+        let buffer = self.options.buffer as f64 / self.options.extent as f64;
+        let last = tiles_per_side - 1.0;
+        let tile_range = |min: f64, max: f64| {
+            // Features on a tile's edge belong to the tiles on both sides
+            let first = ((min * tiles_per_side - buffer).ceil() - 1.0).max(0.0) as u32;
+            let last = ((max * tiles_per_side + buffer).floor().min(last)) as u32;
+            first..last + 1
+        };
+
+        let mut ids = BTreeSet::new();
+        for &(_, bbox) in &self.features {
+            for y in tile_range(bbox[1], bbox[3]) {
+                for x in tile_range(bbox[0], bbox[2]) {
+                    ids.insert((y, x));
+                }
+            }
+        }
+
+        let features = self.features.iter().collect::<Vec<_>>();
+        let simplified = self.simplify(z, &features);
+        ids.into_iter()
+            .map(|(y, x)| TileId::new(z, x, y))
+            .map(|tile| (tile, self.clip(tile, &simplified)))
+            .filter(|&(_, ref fc)| !fc.features.is_empty())
+            .collect()
+    }
+
+    fn simplify<'a>(&self,
+                    z: u8,
+                    features: &[&'a (Feature, [f64; 4])])
+                    -> Vec<(&'a Feature, Value, [f64; 4])> {
+        let options = SimplifyOptions {
+            tolerance: self.options.tolerance / (2f64.powi(z as i32) * self.options.extent as f64),
+            ..SimplifyOptions::default()
+        };
+        features.iter()
+            .filter_map(|&&(ref feature, bbox)| {
+                let value = &feature.geometry.as_ref().unwrap().value;
+                value.simplified(&options).map(|value| (feature, value, bbox))
+            })
+            .collect()
+    }
+
+    /// Returns the projected bounds of `tile` grown by the buffer.
+    fn bounds(&self, tile: TileId) -> [f64; 4] {
+        let tiles_per_side = 2f64.powi(tile.z as i32);
+        let buffer = self.options.buffer as f64 / self.options.extent as f64;
+        [(tile.x as f64 - buffer) / tiles_per_side,
+         (tile.y as f64 - buffer) / tiles_per_side,
+         (tile.x as f64 + 1.0 + buffer) / tiles_per_side,
+         (tile.y as f64 + 1.0 + buffer) / tiles_per_side]
+    }
+
+    fn clip(&self, tile: TileId, features: &[(&Feature, Value, [f64; 4])]) -> FeatureCollection {
+        let tiles_per_side = 2f64.powi(tile.z as i32);
+        let bounds = self.bounds(tile);
+
+        let mut clipped = vec![];
+        for &(feature, ref value, bbox) in features {
+            if !intersects(&bbox, &bounds) {
+                continue;
+            }
+            let value = clip_value(value, 0, bounds[0], bounds[2])
+                .and_then(|value| clip_value(&value, 1, bounds[1], bounds[3]));
+            let mut value = match value {
+                Some(value) => value,
+                None => continue,
+            };
+            for position in value.coords_mut() {
+                position[0] = (position[0] * tiles_per_side - tile.x as f64) *
+                              self.options.extent as f64;
+                position[1] = (position[1] * tiles_per_side - tile.y as f64) *
+                              self.options.extent as f64;
+            }
+            clipped.push(Feature {
+                bbox: None,
+                crs: None,
+                geometry: Some(Geometry::new(value)),
+                id: feature.id.clone(),
+                properties: feature.properties.clone(),
+            });
+        }
+        FeatureCollection {
+            bbox: None,
+            crs: None,
+            features: clipped,
+        }
+    }
+}
+
+/// Removes the positions without two ordinates from every line, ring and multi-point, so that
+/// simplifying and clipping can index them freely. A lone empty `Point` is kept as is.
+fn drop_short_positions(value: &mut Value) {
+    let retain = |positions: &mut Vec<Position>| positions.retain(|p| p.len() >= 2);
+    match *value {
+        Value::Point(_) => {}
+        Value::MultiPoint(ref mut positions) |
+        Value::LineString(ref mut positions) => retain(positions),
+        Value::MultiLineString(ref mut lines) |
+        Value::Polygon(ref mut lines) => lines.iter_mut().for_each(retain),
+        Value::MultiPolygon(ref mut polygons) => {
+            polygons.iter_mut().flat_map(|polygon| polygon.iter_mut()).for_each(retain)
+        }
+        Value::GeometryCollection(ref mut geometries) => {
+            for geometry in geometries {
+                drop_short_positions(&mut geometry.value);
+            }
+        }
+    }
+}
+
+fn intersects(a: &[f64; 4], b: &[f64; 4]) -> bool {
+    a[0] <= b[2] && a[2] >= b[0] && a[1] <= b[3] && a[3] >= b[1]
+}
+
+/// Interpolates between `a` and `b`, returning the end points exactly at `t` of 0 and 1.
+fn interpolate(a: &Position, b: &Position, t: f64) -> Position {
+    if t <= 0.0 {
+        a.clone()
+    } else if t >= 1.0 {
+        b.clone()
+    } else {
+        a.iter().zip(b).map(|(a, b)| a + (b - a) * t).collect()
+    }
+}
+
+/// Clips a line to `k1 <= position[axis] <= k2`, returning the pieces inside.
+fn clip_line(line: &[Position], axis: usize, k1: f64, k2: f64) -> Vec<LineStringType> {
+    let mut pieces = vec![];
+    let mut piece: LineStringType = vec![];
+    if line.len() == 1 && line[0][axis] >= k1 && line[0][axis] <= k2 {
+        pieces.push(line.to_vec());
+    }
+    for segment in line.windows(2) {
+        let (a, b) = (&segment[0], &segment[1]);
+        let (ak, bk) = (a[axis], b[axis]);
+        let (t0, t1) = if ak == bk {
+            if ak < k1 || ak > k2 {
+                continue;
+            }
+            (0.0, 1.0)
+        } else {
+            let (s1, s2) = ((k1 - ak) / (bk - ak), (k2 - ak) / (bk - ak));
+            (s1.min(s2).max(0.0), s1.max(s2).min(1.0))
+        };
+        if t0 > t1 {
+            continue;
+        }
+        let start = interpolate(a, b, t0);
+        if piece.last() != Some(&start) {
+            if piece.len() >= 2 {
+                pieces.push(piece);
+            }
+            piece = vec![start];
+        }
+        piece.push(interpolate(a, b, t1));
+        if t1 < 1.0 {
+            if piece.len() >= 2 {
+                pieces.push(piece);
+            }
+            piece = vec![];
+        }
+    }
+    if piece.len() >= 2 {
+        pieces.push(piece);
+    }
+    pieces
+}
+
+/// Clips a ring to `k1 <= position[axis] <= k2`, or `None` if nothing remains.
+fn clip_ring(ring: &[Position], axis: usize, k1: f64, k2: f64) -> Option<LineStringType> {
+    let mut points = ring.to_vec();
+    if points.len() > 1 && points[0] == points[points.len() - 1] {
+        points.pop();
+    }
+    // Sutherland-Hodgman, once for each side
+    for &(k, keep_greater) in &[(k1, true), (k2, false)] {
+        let inside = |p: &Position| if keep_greater { p[axis] >= k } else { p[axis] <= k };
+        let mut clipped = vec![];
+        for i in 0..points.len() {
+            let (previous, current) = (&points[(i + points.len() - 1) % points.len()], &points[i]);
+            match (inside(previous), inside(current)) {
+                (true, true) => clipped.push(current.clone()),
+                (true, false) => {
+                    let t = (k - previous[axis]) / (current[axis] - previous[axis]);
+                    clipped.push(interpolate(previous, current, t));
+                }
+                (false, true) => {
+                    let t = (k - previous[axis]) / (current[axis] - previous[axis]);
+                    clipped.push(interpolate(previous, current, t));
+                    clipped.push(current.clone());
+                }
+                (false, false) => {}
+            }
+        }
+        points = clipped;
+    }
+    points.dedup();
+    if points.len() < 3 {
+        return None;
+    }
+    let first = points[0].clone();
+    points.push(first);
+    Some(points)
+}
+
+fn clip_polygon(polygon: &PolygonType, axis: usize, k1: f64, k2: f64) -> Option<PolygonType> {
+    let mut rings = polygon.iter();
+    let exterior = match rings.next().and_then(|ring| clip_ring(ring, axis, k1, k2)) {
+        Some(exterior) => exterior,
+        None => return None,
+    };
+    Some(::std::iter::once(exterior)
+        .chain(rings.filter_map(|ring| clip_ring(ring, axis, k1, k2)))
+        .collect())
+}
+
+/// Clips a geometry to `k1 <= position[axis] <= k2`, or `None` if nothing remains.
+fn clip_value(value: &Value, axis: usize, k1: f64, k2: f64) -> Option<Value> {
+    let inside = |p: &Position| p.len() >= 2 && p[axis] >= k1 && p[axis] <= k2;
+    let value = match *value {
+        Value::Point(ref position) => {
+            if !inside(position) {
+                return None;
+            }
+            value.clone()
+        }
+        Value::MultiPoint(ref positions) => {
+            Value::MultiPoint(positions.iter().filter(|p| inside(p)).cloned().collect())
+        }
+        Value::LineString(ref line) => {
+            let mut pieces = clip_line(line, axis, k1, k2);
+            if pieces.len() == 1 {
+                Value::LineString(pieces.pop().unwrap())
+            } else {
+                Value::MultiLineString(pieces)
+            }
+        }
+        Value::MultiLineString(ref lines) => {
+            Value::MultiLineString(lines.iter()
+                .flat_map(|line| clip_line(line, axis, k1, k2))
+                .collect())
+        }
+        Value::Polygon(ref polygon) => {
+            match clip_polygon(polygon, axis, k1, k2) {
+                Some(polygon) => Value::Polygon(polygon),
+                None => return None,
+            }
+        }
+        Value::MultiPolygon(ref polygons) => {
+            Value::MultiPolygon(polygons.iter()
+                .filter_map(|polygon| clip_polygon(polygon, axis, k1, k2))
+                .collect())
+        }
+        Value::GeometryCollection(ref geometries) => {
+            Value::GeometryCollection(geometries.iter()
+                .filter_map(|g| clip_value(&g.value, axis, k1, k2))
+                .map(Geometry::new)
+                .collect())
+        }
+    };
+    let empty = match value {
+        Value::MultiPoint(ref v) => v.is_empty(),
+        Value::MultiLineString(ref v) => v.is_empty(),
+        Value::MultiPolygon(ref v) => v.is_empty(),
+        Value::GeometryCollection(ref v) => v.is_empty(),
+        _ => false,
+    };
+    if empty { None } else { Some(value) }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use {Feature, FeatureCollection, Geometry, Value};
+    use mvt::TileId;
+    use super::{TileIndex, TileOptions, clip_line, clip_ring};
+
+    fn options(buffer: u32, tolerance: f64) -> TileOptions {
+        TileOptions {
+            extent: 4096,
+            buffer: buffer,
+            tolerance: tolerance,
+        }
+    }
+
+    fn rounded(fc: &FeatureCollection) -> Vec<Value> {
+        fc.features
+            .iter()
+            .map(|feature| {
+                let mut value = feature.geometry.as_ref().unwrap().value.clone();
+                for position in value.coords_mut() {
+                    for ordinate in position.iter_mut() {
+                        *ordinate = ordinate.round();
+                    }
+                }
+                value
+            })
+            .collect()
+    }
+
+    #[test]
+    fn clip_lines() {
+        let line = vec![vec![0.0, 0.0], vec![10.0, 0.0], vec![10.0, 10.0], vec![0.0, 10.0]];
+        assert_eq!(clip_line(&line, 0, 5.0, 20.0),
+                   vec![vec![vec![5.0, 0.0], vec![10.0, 0.0], vec![10.0, 10.0], vec![5.0, 10.0]]]);
+        assert_eq!(clip_line(&line, 1, 2.0, 8.0),
+                   vec![vec![vec![10.0, 2.0], vec![10.0, 8.0]]]);
+        // Leaves and re-enters
+        let line = vec![vec![0.0, 0.0], vec![0.0, 10.0], vec![10.0, 10.0], vec![10.0, 0.0]];
+        assert_eq!(clip_line(&line, 1, -1.0, 5.0),
+                   vec![vec![vec![0.0, 0.0], vec![0.0, 5.0]],
+                        vec![vec![10.0, 5.0], vec![10.0, 0.0]]]);
+        // Interpolates extra ordinates
+        let line = vec![vec![0.0, 0.0, 100.0], vec![10.0, 0.0, 200.0]];
+        assert_eq!(clip_line(&line, 0, -5.0, 5.0),
+                   vec![vec![vec![0.0, 0.0, 100.0], vec![5.0, 0.0, 150.0]]]);
+    }
+
+    #[test]
+    fn clip_rings() {
+        let ring = vec![vec![0.0, 0.0], vec![10.0, 0.0], vec![10.0, 10.0], vec![0.0, 10.0],
+                        vec![0.0, 0.0]];
+        assert_eq!(clip_ring(&ring, 0, 5.0, 20.0),
+                   Some(vec![vec![5.0, 0.0],
+                             vec![10.0, 0.0],
+                             vec![10.0, 10.0],
+                             vec![5.0, 10.0],
+                             vec![5.0, 0.0]]));
+        assert_eq!(clip_ring(&ring, 0, 20.0, 30.0), None);
+        assert_eq!(clip_ring(&ring, 1, -5.0, 15.0), Some(ring));
+    }
+
+    #[test]
+    fn tiles_preserve_ids_and_properties() {
+        let fc = FeatureCollection::builder()
+            .feature(Feature::builder()
+                .geometry(Value::LineString(vec![vec![-90.0, 0.0], vec![90.0, 0.0]]))
+                .id("equator")
+                .property("name", "line")
+                .build())
+            .feature(Feature::builder()
+                .geometry(Value::Point(vec![45.0, 45.0]))
+                .build())
+            .build();
+        let index = TileIndex::new(&fc, options(0, 0.0));
+
+        let tiles = index.tiles(1);
+        assert_eq!(tiles.iter().map(|&(tile, _)| tile).collect::<Vec<_>>(),
+                   vec![TileId::new(1, 0, 0),
+                        TileId::new(1, 1, 0),
+                        TileId::new(1, 0, 1),
+                        TileId::new(1, 1, 1)]);
+
+        let tile = index.tile(TileId::new(1, 1, 0));
+        assert_eq!(tile.features.len(), 2);
+        assert_eq!(tile.features[0].id, Some(json!("equator")));
+        assert_eq!(tile.features[0].property::<String>("name"), Some(String::from("line")));
+        assert_eq!(rounded(&tile),
+                   vec![Value::LineString(vec![vec![0.0, 4096.0], vec![2048.0, 4096.0]]),
+                        Value::Point(vec![1024.0, 2947.0])]);
+
+        // The point lies in a single tile
+        assert_eq!(index.tile(TileId::new(1, 0, 0)).features.len(), 1);
+        assert_eq!(index.tile(TileId::new(1, 0, 1)).features.len(), 1);
+        assert_eq!(index.tile(TileId::new(3, 0, 0)).features.len(), 0);
+    }
+
+    #[test]
+    fn empty_positions() {
+        let fc = FeatureCollection::builder()
+            .feature(Feature::builder().geometry(Value::Point(vec![])).build())
+            .feature(Feature::builder()
+                .geometry(Value::MultiPoint(vec![vec![], vec![45.0, 45.0]]))
+                .build())
+            .build();
+        let index = TileIndex::new(&fc, options(0, 0.0));
+        let tile = index.tile(TileId::new(1, 1, 0));
+        assert_eq!(rounded(&tile), vec![Value::MultiPoint(vec![vec![1024.0, 2947.0]])]);
+
+        let fc = FeatureCollection::builder()
+            .feature(Feature::builder()
+                .geometry(Value::LineString(vec![vec![0.0, 0.0], vec![], vec![10.0, 10.0]]))
+                .build())
+            .build();
+        let index = TileIndex::new(&fc, options(0, 0.0));
+        assert_eq!(rounded(&index.tile(TileId::new(0, 0, 0))),
+                   vec![Value::LineString(vec![vec![2048.0, 2048.0], vec![2162.0, 1934.0]])]);
+        // The line starts at the corner shared by all four tiles
+        assert_eq!(index.tiles(1).len(), 4);
+    }
+
+    #[test]
+    fn buffer() {
+        let fc = FeatureCollection::builder()
+            .feature(Feature::builder()
+                .geometry(Value::Polygon(vec![vec![vec![-90.0, -45.0],
+                                                   vec![90.0, -45.0],
+                                                   vec![90.0, 45.0],
+                                                   vec![-90.0, 45.0],
+                                                   vec![-90.0, -45.0]]]))
+                .build())
+            .build();
+
+        let index = TileIndex::new(&fc, options(64, 0.0));
+        let tile = index.tile(TileId::new(1, 0, 0));
+        let value = &rounded(&tile)[0];
+        assert_eq!(*value,
+                   Value::Polygon(vec![vec![vec![2048.0, 4160.0],
+                                            vec![4160.0, 4160.0],
+                                            vec![4160.0, 2947.0],
+                                            vec![2048.0, 2947.0],
+                                            vec![2048.0, 4160.0]]]));
+    }
+
+    #[test]
+    fn simplification_depends_on_zoom() {
+        // Zigzags about 150 tile-local units wide at zoom level 18
+        let line = (0..11)
+            .map(|i| vec![i as f64 * 0.0001, if i % 2 == 0 { 0.001 } else { 0.00105 }])
+            .collect::<Vec<_>>();
+        let fc = FeatureCollection::builder()
+            .feature(Feature::builder().geometry(Value::LineString(line)).build())
+            .build();
+        let index = TileIndex::new(&fc, TileOptions::default());
+
+        let count = |tile: TileId| match index.tile(tile).features[0].geometry {
+            Some(Geometry { value: Value::LineString(ref line), .. }) => line.len(),
+            ref other => panic!("unexpected geometry {:?}", other),
+        };
+        assert_eq!(count(TileId::new(0, 0, 0)), 2);
+        assert_eq!(count(TileId::new(18, 1 << 17, (1 << 17) - 1)), 11);
+    }
+}
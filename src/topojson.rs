@@ -0,0 +1,837 @@
+// Copyright 2015 The GeoRust Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! TopoJSON reading and writing
+//!
+//! [TopoJSON Format Specification](https://github.com/topojson/topojson-specification)
+//!
+//! Each object of a topology becomes a `FeatureCollection`. The geometries of an object of
+//! type `GeometryCollection` become its features, carrying their `id` and `properties`; an
+//! object of any other type becomes a collection of a single feature.
+//!
+//! When writing, lines and polygon rings are split wherever they meet other lines or rings,
+//! and identical pieces are stored once as shared arcs. Polygon rings may therefore start at
+//! a different position after a round trip. Positions can optionally be quantized, in which
+//! case arcs are delta-encoded as the specification describes.
+
+use std::collections::{BTreeMap, HashMap};
+
+use json::{JsonObject, JsonValue};
+use serde_json;
+
+use {Error, Feature, FeatureCollection, Geometry, LineStringType, PolygonType, Position,
+     Value, util};
+
+
+/// Reads every object of a TopoJSON topology.
+///
+/// # Examples
+///
+/// ```
+/// let topology = r#"{
+///     "type": "Topology",
+///     "objects": {
+///         "example": {
+///             "type": "GeometryCollection",
+///             "geometries": [
+///                 {"type": "Point", "coordinates": [102, 0.5], "properties": {"prop0": "value0"}},
+///                 {"type": "LineString", "arcs": [0]}
+///             ]
+///         }
+///     },
+///     "arcs": [[[102, 0], [103, 1], [104, 0], [105, 1]]]
+/// }"#;
+///
+/// let objects = geojson::topojson::read(topology).unwrap();
+/// assert_eq!(objects["example"].features.len(), 2);
+/// ```
+pub fn read(topology: &str) -> Result<BTreeMap<String, FeatureCollection>, Error> {
+    let topology: JsonValue = match serde_json::from_str(topology) {
+        Ok(topology) => topology,
+        Err(..) => return Err(Error::MalformedJson),
+    };
+    let topology = expect_object!(topology);
+    if expect_type!(topology) != "Topology" {
+        return Err(malformed("expected a Topology"));
+    }
+
+    let transform = match topology.get("transform") {
+        Some(transform) => Some(try!(Transform::from_object(expect_object!(transform)))),
+        None => None,
+    };
+
+    let mut arcs = vec![];
+    for arc in expect_array!(expect_property!(topology, "arcs", "Missing 'arcs' field")) {
+        arcs.push(try!(decode_arc(arc, transform.as_ref())));
+    }
+
+    let reader = Reader {
+        arcs: arcs,
+        transform: transform,
+    };
+
+    let mut objects = BTreeMap::new();
+    let objects_json = expect_object!(expect_property!(topology,
+                                                       "objects",
+                                                       "Missing 'objects' field"));
+    for (name, object) in objects_json {
+        let object = expect_object!(object);
+        let features = if object.get("type").and_then(|t| t.as_str()) ==
+                          Some("GeometryCollection") {
+            let mut features = vec![];
+            for geometry in expect_array!(expect_property!(object,
+                                                           "geometries",
+                                                           "Missing 'geometries' field")) {
+                features.push(try!(reader.feature(expect_object!(geometry))));
+            }
+            features
+        } else {
+            vec![try!(reader.feature(object))]
+        };
+        objects.insert(name.clone(),
+                       FeatureCollection {
+                           bbox: None,
+                           crs: None,
+                           features: features,
+                       });
+    }
+    Ok(objects)
+}
+
+/// Writes feature collections as the named objects of a TopoJSON topology.
+///
+/// If `quantization` is given, positions are snapped to a grid of that many steps along each
+/// axis of the collections' combined bounding box, and arcs are delta-encoded.
+///
+/// # Examples
+///
+/// ```
+/// use geojson::{Feature, FeatureCollection, Value};
+///
+/// let fc = FeatureCollection::builder()
+///     .feature(Feature::builder()
+///         .geometry(Value::LineString(vec![vec![0.0, 0.0], vec![1.0, 1.0]]))
+///         .id(1)
+///         .build())
+///     .build();
+///
+/// let topology = geojson::topojson::write(vec![("lines", &fc)], Some(1000));
+/// assert_eq!(geojson::topojson::read(&topology).unwrap()["lines"], fc);
+/// ```
+pub fn write<'a, I>(objects: I, quantization: Option<u32>) -> String
+    where I: IntoIterator<Item = (&'a str, &'a FeatureCollection)>
+{
+    let objects = objects.into_iter().collect::<Vec<_>>();
+
+    let transform = quantization.and_then(|n| {
+        Transform::fit(objects.iter().flat_map(|&(_, fc)| fc.coords()), n)
+    });
+
+    // Quantize first, so that topology is computed on the positions actually written
+    let objects = objects.into_iter()
+        .map(|(name, fc)| {
+            let mut fc = fc.clone();
+            if let Some(ref transform) = transform {
+                for feature in &mut fc.features {
+                    if let Some(ref mut geometry) = feature.geometry {
+                        transform.quantize_value(&mut geometry.value);
+                    }
+                }
+            }
+            (name, fc)
+        })
+        .collect::<Vec<_>>();
+
+    let mut writer = Writer::new();
+    for &(_, ref fc) in &objects {
+        for geometry in fc.features.iter().filter_map(|f| f.geometry.as_ref()) {
+            writer.find_junctions(&geometry.value);
+        }
+    }
+
+    let mut objects_json = JsonObject::new();
+    for &(name, ref fc) in &objects {
+        let geometries = fc.features
+            .iter()
+            .map(|feature| JsonValue::Object(writer.feature(feature)))
+            .collect();
+        let mut object = JsonObject::new();
+        object.insert(String::from("type"), json!("GeometryCollection"));
+        object.insert(String::from("geometries"), JsonValue::Array(geometries));
+        objects_json.insert(String::from(name), JsonValue::Object(object));
+    }
+
+    let arcs = writer.arcs
+        .iter()
+        .map(|arc| match transform {
+            Some(..) => delta_encode(arc),
+            None => serde_json::to_value(arc).unwrap(),
+        })
+        .collect();
+
+    let mut topology = JsonObject::new();
+    topology.insert(String::from("type"), json!("Topology"));
+    if let Some(ref transform) = transform {
+        topology.insert(String::from("transform"),
+                        json!({
+                            "scale": transform.scale,
+                            "translate": transform.translate,
+                        }));
+    }
+    topology.insert(String::from("objects"), JsonValue::Object(objects_json));
+    topology.insert(String::from("arcs"), JsonValue::Array(arcs));
+    serde_json::to_string(&topology).unwrap()
+}
+
+fn malformed(message: &str) -> Error {
+    Error::MalformedTopoJson(String::from(message))
+}
+
+/// Quantization transform
+struct Transform {
+    scale: [f64; 2],
+    translate: [f64; 2],
+}
+
+impl Transform {
+    fn from_object(object: &JsonObject) -> Result<Self, Error> {
+        let scale = expect_array!(expect_property!(object, "scale", "Missing 'scale' field"));
+        let translate = expect_array!(expect_property!(object,
+                                                       "translate",
+                                                       "Missing 'translate' field"));
+        if scale.len() != 2 || translate.len() != 2 {
+            return Err(malformed("expected two-dimensional transform"));
+        }
+        Ok(Transform {
+            scale: [expect_f64!(scale[0]), expect_f64!(scale[1])],
+            translate: [expect_f64!(translate[0]), expect_f64!(translate[1])],
+        })
+    }
+
+    /// Returns a transform onto an `n` by `n` grid covering `positions`.
+    fn fit<'a, I: Iterator<Item = &'a Position>>(positions: I, n: u32) -> Option<Self> {
+        let mut min = [::std::f64::INFINITY; 2];
+        let mut max = [::std::f64::NEG_INFINITY; 2];
+        for position in positions.filter(|p| p.len() >= 2) {
+            for i in 0..2 {
+                min[i] = min[i].min(position[i]);
+                max[i] = max[i].max(position[i]);
+            }
+        }
+        if min[0].is_infinite() {
+            return None;
+        }
+        let steps = (n.max(2) - 1) as f64;
+        let scale = |i: usize| if max[i] > min[i] { (max[i] - min[i]) / steps } else { 1.0 };
+        Some(Transform {
+            scale: [scale(0), scale(1)],
+            translate: min,
+        })
+    }
+
+    fn apply(&self, position: &mut Position) {
+        for i in 0..2 {
+            position[i] = position[i] * self.scale[i] + self.translate[i];
+        }
+    }
+
+    fn quantize(&self, position: &mut Position) {
+        if position.len() < 2 {
+            return;
+        }
+        for i in 0..2 {
+            position[i] = ((position[i] - self.translate[i]) / self.scale[i]).round();
+        }
+    }
+
+    fn quantize_line(&self, positions: &mut Vec<Position>) {
+        for position in positions.iter_mut() {
+            self.quantize(position);
+        }
+        positions.dedup_by(|a, b| a.get(..2) == b.get(..2));
+    }
+
+    fn quantize_value(&self, value: &mut Value) {
+        match *value {
+            Value::Point(ref mut position) => self.quantize(position),
+            Value::MultiPoint(ref mut positions) => {
+                for position in positions {
+                    self.quantize(position);
+                }
+            }
+            Value::LineString(ref mut line) => self.quantize_line(line),
+            Value::MultiLineString(ref mut lines) |
+            Value::Polygon(ref mut lines) => {
+                for line in lines {
+                    self.quantize_line(line);
+                }
+            }
+            Value::MultiPolygon(ref mut polygons) => {
+                for line in polygons.iter_mut().flat_map(|p| p.iter_mut()) {
+                    self.quantize_line(line);
+                }
+            }
+            Value::GeometryCollection(ref mut geometries) => {
+                for geometry in geometries {
+                    self.quantize_value(&mut geometry.value);
+                }
+            }
+        }
+    }
+}
+
+fn json_to_position(json: &JsonValue) -> Result<Position, Error> {
+    let mut position = vec![];
+    for ordinate in expect_array!(json) {
+        position.push(expect_f64!(ordinate));
+    }
+    if position.len() < 2 {
+        return Err(malformed("expected at least two ordinates"));
+    }
+    Ok(position)
+}
+
+fn decode_arc(json: &JsonValue, transform: Option<&Transform>) -> Result<LineStringType, Error> {
+    let mut arc = vec![];
+    let mut cursor = [0.0, 0.0];
+    for position in expect_array!(json) {
+        let mut position = try!(json_to_position(position));
+        if let Some(transform) = transform {
+            for i in 0..2 {
+                cursor[i] += position[i];
+                position[i] = cursor[i];
+            }
+            transform.apply(&mut position);
+        }
+        arc.push(position);
+    }
+    Ok(arc)
+}
+
+fn delta_encode(arc: &[Position]) -> JsonValue {
+    let mut previous = [0.0, 0.0];
+    let positions = arc.iter()
+        .map(|position| {
+            let mut delta = position.clone();
+            for i in 0..2 {
+                delta[i] = position[i] - previous[i];
+                previous[i] = position[i];
+            }
+            let mut json = delta.iter().take(2).map(|d| json!(*d as i64)).collect::<Vec<_>>();
+            json.extend(delta.iter().skip(2).map(|o| json!(o)));
+            JsonValue::Array(json)
+        })
+        .collect();
+    JsonValue::Array(positions)
+}
+
+struct Reader {
+    arcs: Vec<LineStringType>,
+    transform: Option<Transform>,
+}
+
+impl Reader {
+    fn feature(&self, object: &JsonObject) -> Result<Feature, Error> {
+        let properties = match object.get("properties") {
+            Some(&JsonValue::Object(ref properties)) => Some(properties.clone()),
+            Some(&JsonValue::Null) | None => None,
+            Some(..) => return Err(Error::PropertiesExpectedObjectOrNull),
+        };
+        Ok(Feature {
+            bbox: None,
+            crs: None,
+            geometry: try!(self.geometry(object)),
+            id: try!(util::get_id(object)),
+            properties: properties,
+        })
+    }
+
+    fn geometry(&self, object: &JsonObject) -> Result<Option<Geometry>, Error> {
+        let type_ = match *expect_property!(object, "type", "Missing 'type' field") {
+            JsonValue::Null => return Ok(None),
+            ref type_ => expect_string!(type_),
+        };
+        let arcs = || Ok(expect_property!(object, "arcs", "Missing 'arcs' field"));
+        let value = match type_ {
+            "Point" => Value::Point(try!(self.point(try!(util::get_coords_value(object))))),
+            "MultiPoint" => {
+                let mut positions = vec![];
+                for position in expect_array!(try!(util::get_coords_value(object))) {
+                    positions.push(try!(self.point(position)));
+                }
+                Value::MultiPoint(positions)
+            }
+            "LineString" => Value::LineString(try!(self.line(try!(arcs())))),
+            "MultiLineString" => Value::MultiLineString(try!(self.lines(try!(arcs())))),
+            "Polygon" => Value::Polygon(try!(self.lines(try!(arcs())))),
+            "MultiPolygon" => {
+                let mut polygons = vec![];
+                for polygon in expect_array!(try!(arcs())) {
+                    polygons.push(try!(self.lines(polygon)));
+                }
+                Value::MultiPolygon(polygons)
+            }
+            "GeometryCollection" => {
+                let mut geometries = vec![];
+                for geometry in expect_array!(expect_property!(object,
+                                                               "geometries",
+                                                               "Missing 'geometries' field")) {
+                    if let Some(geometry) = try!(self.geometry(expect_object!(geometry))) {
+                        geometries.push(geometry);
+                    }
+                }
+                Value::GeometryCollection(geometries)
+            }
+            _ => return Err(Error::GeometryUnknownType),
+        };
+        Ok(Some(Geometry::new(value)))
+    }
+
+    fn point(&self, json: &JsonValue) -> Result<Position, Error> {
+        let mut position = try!(json_to_position(json));
+        if let Some(ref transform) = self.transform {
+            transform.apply(&mut position);
+        }
+        Ok(position)
+    }
+
+    /// Stitches a list of arc indexes into a single line.
+    fn line(&self, json: &JsonValue) -> Result<LineStringType, Error> {
+        let mut line: LineStringType = vec![];
+        for index in expect_array!(json) {
+            let index = match index.as_i64() {
+                Some(index) => index,
+                None => return Err(malformed("expected an integer arc index")),
+            };
+            let (arc_index, reversed) = if index < 0 {
+                (!index as usize, true)
+            } else {
+                (index as usize, false)
+            };
+            let arc = match self.arcs.get(arc_index) {
+                Some(arc) => arc,
+                None => return Err(malformed(&format!("arc index {} out of range", index))),
+            };
+            // Consecutive arcs share their end and start positions
+            let skip = if line.is_empty() { 0 } else { 1 };
+            if reversed {
+                line.extend(arc.iter().rev().skip(skip).cloned());
+            } else {
+                line.extend(arc.iter().skip(skip).cloned());
+            }
+        }
+        Ok(line)
+    }
+
+    fn lines(&self, json: &JsonValue) -> Result<Vec<LineStringType>, Error> {
+        let mut lines = vec![];
+        for line in expect_array!(json) {
+            lines.push(try!(self.line(line)));
+        }
+        Ok(lines)
+    }
+}
+
+/// Hashable identity of a position, from its first two ordinates
+type Key = (u64, u64);
+
+fn key(position: &Position) -> Key {
+    (position[0].to_bits(), position[1].to_bits())
+}
+
+enum Neighbours {
+    Seen(Key, Key),
+    Junction,
+}
+
+struct Writer {
+    neighbours: HashMap<Key, Neighbours>,
+    arcs: Vec<LineStringType>,
+    arc_indexes: HashMap<Vec<Key>, i64>,
+}
+
+impl Writer {
+    fn new() -> Self {
+        Writer {
+            neighbours: HashMap::new(),
+            arcs: vec![],
+            arc_indexes: HashMap::new(),
+        }
+    }
+
+    fn is_junction(&self, position: &Position) -> bool {
+        match self.neighbours.get(&key(position)) {
+            Some(&Neighbours::Junction) => true,
+            _ => false,
+        }
+    }
+
+    fn visit(&mut self, position: &Position, neighbours: Option<(&Position, &Position)>) {
+        let state = match neighbours {
+            None => Neighbours::Junction,
+            Some((a, b)) => {
+                let (a, b) = (key(a), key(b));
+                if a < b {
+                    Neighbours::Seen(a, b)
+                } else {
+                    Neighbours::Seen(b, a)
+                }
+            }
+        };
+        let junction = match (self.neighbours.get(&key(position)), &state) {
+            (None, _) => false,
+            (Some(&Neighbours::Seen(a, b)), &Neighbours::Seen(c, d)) => (a, b) != (c, d),
+            _ => true,
+        };
+        self.neighbours.insert(key(position),
+                               if junction { Neighbours::Junction } else { state });
+    }
+
+    /// Marks every position where lines or rings meet, diverge or end.
+    fn find_junctions(&mut self, value: &Value) {
+        match *value {
+            Value::Point(..) | Value::MultiPoint(..) => {}
+            Value::LineString(ref line) => self.find_line_junctions(line),
+            Value::MultiLineString(ref lines) => {
+                for line in lines {
+                    self.find_line_junctions(line);
+                }
+            }
+            Value::Polygon(ref rings) => {
+                for ring in rings {
+                    self.find_ring_junctions(ring);
+                }
+            }
+            Value::MultiPolygon(ref polygons) => {
+                for ring in polygons.iter().flat_map(|p| p.iter()) {
+                    self.find_ring_junctions(ring);
+                }
+            }
+            Value::GeometryCollection(ref geometries) => {
+                for geometry in geometries {
+                    self.find_junctions(&geometry.value);
+                }
+            }
+        }
+    }
+
+    fn find_line_junctions(&mut self, line: &[Position]) {
+        let line = line.iter().filter(|p| p.len() >= 2).collect::<Vec<_>>();
+        for (i, position) in line.iter().enumerate() {
+            if i == 0 || i == line.len() - 1 {
+                self.visit(position, None);
+            } else {
+                self.visit(position, Some((line[i - 1], line[i + 1])));
+            }
+        }
+    }
+
+    fn find_ring_junctions(&mut self, ring: &[Position]) {
+        if !is_closed(ring) {
+            return self.find_line_junctions(ring);
+        }
+        let n = ring.len() - 1;
+        for i in 0..n {
+            self.visit(&ring[i], Some((&ring[(i + n - 1) % n], &ring[i + 1])));
+        }
+    }
+
+    /// Returns the indexes of the arcs making up `line`, adding any new arcs. Positions with
+    /// fewer than two ordinates are skipped, as when finding junctions.
+    fn line_arcs(&mut self, line: &[Position]) -> Vec<i64> {
+        let line = line.iter().filter(|p| p.len() >= 2).cloned().collect::<Vec<_>>();
+        let mut indexes = vec![];
+        let mut start = 0;
+        for i in 1..line.len() {
+            if i == line.len() - 1 || self.is_junction(&line[i]) {
+                indexes.push(self.arc(&line[start..i + 1]));
+                start = i;
+            }
+        }
+        if line.len() == 1 {
+            indexes.push(self.arc(&line));
+        }
+        indexes
+    }
+
+    /// Returns the indexes of the arcs making up `ring`, which is first rotated to start at a
+    /// junction if it has one.
+    fn ring_arcs(&mut self, ring: &[Position]) -> Vec<i64> {
+        if !is_closed(ring) {
+            return self.line_arcs(ring);
+        }
+        let n = ring.len() - 1;
+        match (0..n).find(|&i| self.is_junction(&ring[i])) {
+            Some(0) | None => self.line_arcs(ring),
+            Some(start) => {
+                let mut rotated = ring[start..n].to_vec();
+                rotated.extend_from_slice(&ring[..start + 1]);
+                self.line_arcs(&rotated)
+            }
+        }
+    }
+
+    fn arc(&mut self, positions: &[Position]) -> i64 {
+        let keys = positions.iter().map(key).collect::<Vec<_>>();
+        if let Some(&index) = self.arc_indexes.get(&keys) {
+            return index;
+        }
+        let reversed = keys.iter().rev().cloned().collect::<Vec<_>>();
+        if let Some(&index) = self.arc_indexes.get(&reversed) {
+            return !index;
+        }
+        let index = self.arcs.len() as i64;
+        self.arcs.push(positions.to_vec());
+        self.arc_indexes.insert(keys, index);
+        index
+    }
+
+    fn lines_arcs(&mut self, lines: &[LineStringType], rings: bool) -> JsonValue {
+        let arcs = lines.iter()
+            .map(|line| if rings {
+                self.ring_arcs(line)
+            } else {
+                self.line_arcs(line)
+            })
+            .collect::<Vec<_>>();
+        serde_json::to_value(arcs).unwrap()
+    }
+
+    fn geometry(&mut self, value: &Value) -> JsonObject {
+        let mut object = JsonObject::new();
+        let (type_, member, json) = match *value {
+            Value::Point(ref position) => ("Point", "coordinates", json!(position)),
+            Value::MultiPoint(ref positions) => ("MultiPoint", "coordinates", json!(positions)),
+            Value::LineString(ref line) => ("LineString", "arcs", json!(self.line_arcs(line))),
+            Value::MultiLineString(ref lines) => {
+                ("MultiLineString", "arcs", self.lines_arcs(lines, false))
+            }
+            Value::Polygon(ref rings) => ("Polygon", "arcs", self.lines_arcs(rings, true)),
+            Value::MultiPolygon(ref polygons) => {
+                let arcs = polygons.iter()
+                    .map(|polygon: &PolygonType| self.lines_arcs(polygon, true))
+                    .collect::<Vec<_>>();
+                ("MultiPolygon", "arcs", JsonValue::Array(arcs))
+            }
+            Value::GeometryCollection(ref geometries) => {
+                let geometries = geometries.iter()
+                    .map(|geometry| JsonValue::Object(self.geometry(&geometry.value)))
+                    .collect::<Vec<_>>();
+                ("GeometryCollection", "geometries", JsonValue::Array(geometries))
+            }
+        };
+        object.insert(String::from("type"), json!(type_));
+        object.insert(String::from(member), json);
+        object
+    }
+
+    fn feature(&mut self, feature: &Feature) -> JsonObject {
+        let mut object = match feature.geometry {
+            Some(ref geometry) => self.geometry(&geometry.value),
+            None => {
+                let mut object = JsonObject::new();
+                object.insert(String::from("type"), JsonValue::Null);
+                object
+            }
+        };
+        if let Some(ref id) = feature.id {
+            object.insert(String::from("id"), id.clone());
+        }
+        if let Some(ref properties) = feature.properties {
+            object.insert(String::from("properties"), JsonValue::Object(properties.clone()));
+        }
+        object
+    }
+}
+
+fn is_closed(ring: &[Position]) -> bool {
+    ring.len() >= 4 && ring.iter().all(|p| p.len() >= 2) &&
+    key(&ring[0]) == key(&ring[ring.len() - 1])
+}
+
+
+#[cfg(test)]
+mod tests {
+    use {Feature, FeatureCollection, Geometry, Value};
+    use json::JsonValue;
+    use serde_json;
+    use super::{read, write};
+
+    #[test]
+    fn read_specification_example() {
+        let topology = r#"{
+            "type": "Topology",
+            "transform": {"scale": [0.0005, 0.0001], "translate": [100, 0]},
+            "objects": {
+                "example": {
+                    "type": "GeometryCollection",
+                    "geometries": [
+                        {
+                            "type": "Point",
+                            "properties": {"prop0": "value0"},
+                            "coordinates": [4000, 5000]
+                        },
+                        {
+                            "type": "LineString",
+                            "properties": {"prop0": "value0", "prop1": 0},
+                            "arcs": [0]
+                        },
+                        {
+                            "type": "Polygon",
+                            "id": "poly",
+                            "properties": {"prop0": "value0", "prop1": {"this": "that"}},
+                            "arcs": [[-2]]
+                        },
+                        {"type": null}
+                    ]
+                }
+            },
+            "arcs": [
+                [[4000, 0], [1999, 9999], [2000, -9999], [2000, 9999]],
+                [[0, 0], [0, 9999], [2000, 0], [0, -9999], [-2000, 0]]
+            ]
+        }"#;
+        let objects = read(topology).unwrap();
+        let features = &objects["example"].features;
+        assert_eq!(features.len(), 4);
+
+        let round = |value: &Value| {
+            let mut value = value.clone();
+            for position in value.coords_mut() {
+                for ordinate in position.iter_mut() {
+                    *ordinate = (*ordinate * 1e6).round() / 1e6;
+                }
+            }
+            value
+        };
+
+        assert_eq!(round(&features[0].geometry.as_ref().unwrap().value),
+                   Value::Point(vec![102.0, 0.5]));
+        assert_eq!(round(&features[1].geometry.as_ref().unwrap().value),
+                   Value::LineString(vec![vec![102.0, 0.0],
+                                          vec![102.9995, 0.9999],
+                                          vec![103.9995, 0.0],
+                                          vec![104.9995, 0.9999]]));
+        assert_eq!(round(&features[2].geometry.as_ref().unwrap().value),
+                   Value::Polygon(vec![vec![vec![100.0, 0.0],
+                                            vec![101.0, 0.0],
+                                            vec![101.0, 0.9999],
+                                            vec![100.0, 0.9999],
+                                            vec![100.0, 0.0]]]));
+        assert_eq!(features[2].id, Some(json!("poly")));
+        assert_eq!(features[1].property::<u8>("prop1"), Some(0));
+        assert_eq!(features[3].geometry, None);
+    }
+
+    #[test]
+    fn write_shares_arcs() {
+        // Two squares sharing an edge, with rings starting on that edge's end points
+        let left = vec![vec![1.0, 0.0], vec![1.0, 1.0], vec![0.0, 1.0], vec![0.0, 0.0],
+                        vec![1.0, 0.0]];
+        let right = vec![vec![1.0, 1.0], vec![1.0, 0.0], vec![2.0, 0.0], vec![2.0, 1.0],
+                         vec![1.0, 1.0]];
+
+        let fc = FeatureCollection::builder()
+            .feature(Feature::builder()
+                .geometry(Value::Polygon(vec![left]))
+                .id("left")
+                .property("name", "left")
+                .build())
+            .feature(Feature::builder()
+                .geometry(Value::Polygon(vec![right]))
+                .id("right")
+                .build())
+            .feature(Feature::builder().build())
+            .build();
+
+        let topology = write(vec![("squares", &fc)], None);
+        let json: JsonValue = serde_json::from_str(&topology).unwrap();
+        // The shared edge is stored once, and used in reverse by the second square
+        assert_eq!(json["arcs"].as_array().unwrap().len(), 3);
+        assert_eq!(json["objects"]["squares"]["geometries"][0]["arcs"], json!([[0, 1]]));
+        assert_eq!(json["objects"]["squares"]["geometries"][1]["arcs"], json!([[-1, 2]]));
+        assert_eq!(json["objects"]["squares"]["geometries"][2]["type"], JsonValue::Null);
+
+        assert_eq!(read(&topology).unwrap()["squares"], fc);
+    }
+
+    #[test]
+    fn write_quantized() {
+        let fc = FeatureCollection::builder()
+            .feature(Feature::builder()
+                .geometry(Geometry::new(Value::GeometryCollection(vec![
+                    Geometry::new(Value::Point(vec![10.0, 20.0])),
+                    Geometry::new(Value::MultiLineString(vec![
+                        vec![vec![10.0, 20.0], vec![10.05, 20.0], vec![15.0, 25.0]],
+                        vec![vec![15.0, 25.0], vec![20.0, 30.0]],
+                    ])),
+                ])))
+                .build())
+            .build();
+
+        let topology = write(vec![("collection", &fc)], Some(11));
+        let json: JsonValue = serde_json::from_str(&topology).unwrap();
+        assert_eq!(json["transform"], json!({"scale": [1.0, 1.0], "translate": [10.0, 20.0]}));
+        // The second position collapses onto the first, and arcs are delta-encoded
+        assert_eq!(json["arcs"], json!([[[0, 0], [5, 5]], [[5, 5], [5, 5]]]));
+
+        let read_back = read(&topology).unwrap();
+        assert_eq!(read_back["collection"].features[0].geometry,
+                   Some(Geometry::new(Value::GeometryCollection(vec![
+                       Geometry::new(Value::Point(vec![10.0, 20.0])),
+                       Geometry::new(Value::MultiLineString(vec![
+                           vec![vec![10.0, 20.0], vec![15.0, 25.0]],
+                           vec![vec![15.0, 25.0], vec![20.0, 30.0]],
+                       ])),
+                   ]))));
+    }
+
+    #[test]
+    fn write_empty_positions() {
+        let fc = FeatureCollection::builder()
+            .feature(Feature::builder()
+                .geometry(Value::LineString(vec![vec![0.0, 0.0], vec![], vec![10.0, 10.0]]))
+                .build())
+            .feature(Feature::builder()
+                .geometry(Value::Polygon(vec![vec![vec![0.0, 0.0],
+                                                   vec![1.0, 0.0],
+                                                   vec![1.0],
+                                                   vec![1.0, 1.0],
+                                                   vec![0.0, 0.0]]]))
+                .build())
+            .build();
+
+        let topology = write(vec![("lines", &fc)], None);
+        let read_back = read(&topology).unwrap();
+        assert_eq!(read_back["lines"].features[0].geometry,
+                   Some(Geometry::new(Value::LineString(vec![vec![0.0, 0.0], vec![10.0, 10.0]]))));
+        assert_eq!(read_back["lines"].features[1].geometry,
+                   Some(Geometry::new(Value::Polygon(vec![vec![vec![0.0, 0.0],
+                                                               vec![1.0, 0.0],
+                                                               vec![1.0, 1.0],
+                                                               vec![0.0, 0.0]]]))));
+    }
+
+    #[test]
+    fn read_errors() {
+        assert!(read("{").is_err());
+        assert!(read(r#"{"type": "FeatureCollection", "features": []}"#).is_err());
+        assert!(read(r#"{
+            "type": "Topology",
+            "objects": {"a": {"type": "LineString", "arcs": [1]}},
+            "arcs": [[[0, 0], [1, 1]]]
+        }"#)
+            .is_err());
+    }
+}
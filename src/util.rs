@@ -14,7 +14,7 @@
 
 use json::{JsonValue, JsonObject};
 
-use {Bbox, Crs, Error, Feature, FromObject, Geometry, Position};
+use {Bbox, Crs, Error, Feature, FromObject, Geometry, Position, Value};
 
 
 pub fn get_coords_value<'a>(object: &JsonObject) -> Result<&JsonValue, Error> {
@@ -46,6 +46,24 @@ pub fn get_bbox(object: &JsonObject) -> Result<Option<Bbox>, Error> {
     return Ok(Some(bbox));
 }
 
+/// Number of ordinates, between 2 and 4, that every position of `value` has
+///
+/// Used by WKT and WKB writers
+pub fn coordinate_dimensions(value: &Value) -> usize {
+    value.coords().map(|p| p.len()).min().unwrap_or(2).max(2).min(4)
+}
+
+/// Swaps the first two axes of a bbox in place
+///
+/// Used by FeatureCollection, Feature, Geometry
+pub fn swap_bbox_axes(bbox: &mut Bbox) {
+    let dims = bbox.len() / 2;
+    if dims >= 2 {
+        bbox.swap(0, 1);
+        bbox.swap(dims, dims + 1);
+    }
+}
+
 /// Used by FeatureCollection, Feature, Geometry
 pub fn get_crs(object: &JsonObject) -> Result<Option<Crs>, Error> {
     let crs_json = match object.get("crs") {
@@ -0,0 +1,412 @@
+// Copyright 2015 The GeoRust Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Traversal of GeoJSON trees
+//!
+//! `Visitor` walks a tree by shared reference and `VisitorMut` by mutable reference. Every
+//! method has a default implementation that descends into the node's children through the
+//! matching `walk_*` function, so an implementation only overrides the hooks it is interested
+//! in. An overriding method can call the `walk_*` function itself to keep descending.
+//!
+//! # Examples
+//!
+//! Counting the positions of a document:
+//!
+//! ```
+//! use geojson::{GeoJson, Position, Visitor, walk};
+//!
+//! struct CountPositions(usize);
+//!
+//! impl Visitor for CountPositions {
+//!     fn visit_position(&mut self, _: &Position) {
+//!         self.0 += 1;
+//!     }
+//! }
+//!
+//! let geojson = r#"{"type": "LineString", "coordinates": [[1.0, 2.0], [3.0, 4.0]]}"#
+//!     .parse::<GeoJson>()
+//!     .unwrap();
+//!
+//! let mut counter = CountPositions(0);
+//! walk(&mut counter, &geojson);
+//! assert_eq!(counter.0, 2);
+//! ```
+
+use {Feature, FeatureCollection, GeoJson, Geometry, LineStringType, PolygonType, Position,
+     Value};
+
+
+/// Read-only traversal of a GeoJSON tree
+pub trait Visitor {
+    fn visit_geojson(&mut self, geojson: &GeoJson) {
+        walk_geojson(self, geojson)
+    }
+
+    fn visit_feature_collection(&mut self, feature_collection: &FeatureCollection) {
+        walk_feature_collection(self, feature_collection)
+    }
+
+    fn visit_feature(&mut self, feature: &Feature) {
+        walk_feature(self, feature)
+    }
+
+    fn visit_geometry(&mut self, geometry: &Geometry) {
+        walk_geometry(self, geometry)
+    }
+
+    fn visit_value(&mut self, value: &Value) {
+        walk_value(self, value)
+    }
+
+    fn visit_point(&mut self, position: &Position) {
+        self.visit_position(position)
+    }
+
+    fn visit_multi_point(&mut self, positions: &[Position]) {
+        walk_positions(self, positions)
+    }
+
+    fn visit_line_string(&mut self, positions: &[Position]) {
+        walk_positions(self, positions)
+    }
+
+    fn visit_multi_line_string(&mut self, line_strings: &[LineStringType]) {
+        for line_string in line_strings {
+            self.visit_line_string(line_string);
+        }
+    }
+
+    /// Visits the rings of a polygon, exterior ring first. Rings are visited as line strings.
+    fn visit_polygon(&mut self, rings: &[LineStringType]) {
+        for ring in rings {
+            self.visit_line_string(ring);
+        }
+    }
+
+    fn visit_multi_polygon(&mut self, polygons: &[PolygonType]) {
+        for polygon in polygons {
+            self.visit_polygon(polygon);
+        }
+    }
+
+    fn visit_geometry_collection(&mut self, geometries: &[Geometry]) {
+        for geometry in geometries {
+            self.visit_geometry(geometry);
+        }
+    }
+
+    fn visit_position(&mut self, _position: &Position) {}
+}
+
+/// Visits `geojson` and everything below it.
+pub fn walk<V: Visitor + ?Sized>(visitor: &mut V, geojson: &GeoJson) {
+    visitor.visit_geojson(geojson)
+}
+
+pub fn walk_geojson<V: Visitor + ?Sized>(visitor: &mut V, geojson: &GeoJson) {
+    match *geojson {
+        GeoJson::Geometry(ref geometry) => visitor.visit_geometry(geometry),
+        GeoJson::Feature(ref feature) => visitor.visit_feature(feature),
+        GeoJson::FeatureCollection(ref fc) => visitor.visit_feature_collection(fc),
+    }
+}
+
+pub fn walk_feature_collection<V: Visitor + ?Sized>(visitor: &mut V,
+                                                    feature_collection: &FeatureCollection) {
+    for feature in &feature_collection.features {
+        visitor.visit_feature(feature);
+    }
+}
+
+pub fn walk_feature<V: Visitor + ?Sized>(visitor: &mut V, feature: &Feature) {
+    if let Some(ref geometry) = feature.geometry {
+        visitor.visit_geometry(geometry);
+    }
+}
+
+pub fn walk_geometry<V: Visitor + ?Sized>(visitor: &mut V, geometry: &Geometry) {
+    visitor.visit_value(&geometry.value)
+}
+
+pub fn walk_value<V: Visitor + ?Sized>(visitor: &mut V, value: &Value) {
+    match *value {
+        Value::Point(ref position) => visitor.visit_point(position),
+        Value::MultiPoint(ref positions) => visitor.visit_multi_point(positions),
+        Value::LineString(ref positions) => visitor.visit_line_string(positions),
+        Value::MultiLineString(ref line_strings) => visitor.visit_multi_line_string(line_strings),
+        Value::Polygon(ref rings) => visitor.visit_polygon(rings),
+        Value::MultiPolygon(ref polygons) => visitor.visit_multi_polygon(polygons),
+        Value::GeometryCollection(ref geometries) => {
+            visitor.visit_geometry_collection(geometries)
+        }
+    }
+}
+
+pub fn walk_positions<V: Visitor + ?Sized>(visitor: &mut V, positions: &[Position]) {
+    for position in positions {
+        visitor.visit_position(position);
+    }
+}
+
+/// Mutating traversal of a GeoJSON tree
+///
+/// Multi-part nodes are handed over as `Vec`s so that implementations can add or remove
+/// parts as well as modify them.
+pub trait VisitorMut {
+    fn visit_geojson_mut(&mut self, geojson: &mut GeoJson) {
+        walk_geojson_mut(self, geojson)
+    }
+
+    fn visit_feature_collection_mut(&mut self, feature_collection: &mut FeatureCollection) {
+        walk_feature_collection_mut(self, feature_collection)
+    }
+
+    fn visit_feature_mut(&mut self, feature: &mut Feature) {
+        walk_feature_mut(self, feature)
+    }
+
+    fn visit_geometry_mut(&mut self, geometry: &mut Geometry) {
+        walk_geometry_mut(self, geometry)
+    }
+
+    fn visit_value_mut(&mut self, value: &mut Value) {
+        walk_value_mut(self, value)
+    }
+
+    fn visit_point_mut(&mut self, position: &mut Position) {
+        self.visit_position_mut(position)
+    }
+
+    fn visit_multi_point_mut(&mut self, positions: &mut Vec<Position>) {
+        walk_positions_mut(self, positions)
+    }
+
+    fn visit_line_string_mut(&mut self, positions: &mut LineStringType) {
+        walk_positions_mut(self, positions)
+    }
+
+    fn visit_multi_line_string_mut(&mut self, line_strings: &mut Vec<LineStringType>) {
+        for line_string in line_strings {
+            self.visit_line_string_mut(line_string);
+        }
+    }
+
+    /// Visits the rings of a polygon, exterior ring first. Rings are visited as line strings.
+    fn visit_polygon_mut(&mut self, rings: &mut PolygonType) {
+        for ring in rings {
+            self.visit_line_string_mut(ring);
+        }
+    }
+
+    fn visit_multi_polygon_mut(&mut self, polygons: &mut Vec<PolygonType>) {
+        for polygon in polygons {
+            self.visit_polygon_mut(polygon);
+        }
+    }
+
+    fn visit_geometry_collection_mut(&mut self, geometries: &mut Vec<Geometry>) {
+        for geometry in geometries {
+            self.visit_geometry_mut(geometry);
+        }
+    }
+
+    fn visit_position_mut(&mut self, _position: &mut Position) {}
+}
+
+/// Visits `geojson` and everything below it, allowing modification.
+pub fn walk_mut<V: VisitorMut + ?Sized>(visitor: &mut V, geojson: &mut GeoJson) {
+    visitor.visit_geojson_mut(geojson)
+}
+
+pub fn walk_geojson_mut<V: VisitorMut + ?Sized>(visitor: &mut V, geojson: &mut GeoJson) {
+    match *geojson {
+        GeoJson::Geometry(ref mut geometry) => visitor.visit_geometry_mut(geometry),
+        GeoJson::Feature(ref mut feature) => visitor.visit_feature_mut(feature),
+        GeoJson::FeatureCollection(ref mut fc) => visitor.visit_feature_collection_mut(fc),
+    }
+}
+
+pub fn walk_feature_collection_mut<V>(visitor: &mut V, feature_collection: &mut FeatureCollection)
+    where V: VisitorMut + ?Sized
+{
+    for feature in &mut feature_collection.features {
+        visitor.visit_feature_mut(feature);
+    }
+}
+
+pub fn walk_feature_mut<V: VisitorMut + ?Sized>(visitor: &mut V, feature: &mut Feature) {
+    if let Some(ref mut geometry) = feature.geometry {
+        visitor.visit_geometry_mut(geometry);
+    }
+}
+
+pub fn walk_geometry_mut<V: VisitorMut + ?Sized>(visitor: &mut V, geometry: &mut Geometry) {
+    visitor.visit_value_mut(&mut geometry.value)
+}
+
+pub fn walk_value_mut<V: VisitorMut + ?Sized>(visitor: &mut V, value: &mut Value) {
+    match *value {
+        Value::Point(ref mut position) => visitor.visit_point_mut(position),
+        Value::MultiPoint(ref mut positions) => visitor.visit_multi_point_mut(positions),
+        Value::LineString(ref mut positions) => visitor.visit_line_string_mut(positions),
+        Value::MultiLineString(ref mut line_strings) => {
+            visitor.visit_multi_line_string_mut(line_strings)
+        }
+        Value::Polygon(ref mut rings) => visitor.visit_polygon_mut(rings),
+        Value::MultiPolygon(ref mut polygons) => visitor.visit_multi_polygon_mut(polygons),
+        Value::GeometryCollection(ref mut geometries) => {
+            visitor.visit_geometry_collection_mut(geometries)
+        }
+    }
+}
+
+pub fn walk_positions_mut<V: VisitorMut + ?Sized>(visitor: &mut V, positions: &mut [Position]) {
+    for position in positions {
+        visitor.visit_position_mut(position);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use {Feature, GeoJson, LineStringType, Position, Value};
+    use super::{Visitor, VisitorMut, walk, walk_feature, walk_mut};
+
+    fn geojson() -> GeoJson {
+        r#"{
+            "type": "FeatureCollection",
+            "features": [
+                {
+                    "type": "Feature",
+                    "properties": {"name": "a"},
+                    "geometry": {
+                        "type": "Polygon",
+                        "coordinates": [
+                            [[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 0.0]],
+                            [[1.0, 1.0], [2.0, 1.0], [2.0, 2.0], [1.0, 1.0]]
+                        ]
+                    }
+                },
+                {
+                    "type": "Feature",
+                    "properties": {"name": "b"},
+                    "geometry": {
+                        "type": "GeometryCollection",
+                        "geometries": [
+                            {"type": "Point", "coordinates": [5.0, 5.0]},
+                            {"type": "MultiPoint", "coordinates": [[6.0, 6.0], [7.0, 7.0]]}
+                        ]
+                    }
+                },
+                {"type": "Feature", "properties": null, "geometry": null}
+            ]
+        }"#
+            .parse()
+            .unwrap()
+    }
+
+    #[derive(Default)]
+    struct Counter {
+        features: usize,
+        points: usize,
+        rings: usize,
+        positions: usize,
+    }
+
+    impl Visitor for Counter {
+        fn visit_feature(&mut self, feature: &Feature) {
+            self.features += 1;
+            walk_feature(self, feature);
+        }
+
+        fn visit_point(&mut self, position: &Position) {
+            self.points += 1;
+            self.visit_position(position);
+        }
+
+        fn visit_polygon(&mut self, rings: &[LineStringType]) {
+            self.rings += rings.len();
+        }
+
+        fn visit_position(&mut self, _: &Position) {
+            self.positions += 1;
+        }
+    }
+
+    #[test]
+    fn count_nodes() {
+        let mut counter = Counter::default();
+        walk(&mut counter, &geojson());
+
+        assert_eq!(counter.features, 3);
+        assert_eq!(counter.points, 1);
+        assert_eq!(counter.rings, 2);
+        // The polygon override does not descend, so only the collection's positions count
+        assert_eq!(counter.positions, 3);
+    }
+
+    struct ClosedRings(bool);
+
+    impl Visitor for ClosedRings {
+        fn visit_polygon(&mut self, rings: &[LineStringType]) {
+            for ring in rings {
+                if ring.first() != ring.last() {
+                    self.0 = false;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn validate_rings() {
+        let mut validator = ClosedRings(true);
+        walk(&mut validator, &geojson());
+        assert!(validator.0);
+    }
+
+    struct Translate(f64, f64);
+
+    impl VisitorMut for Translate {
+        fn visit_position_mut(&mut self, position: &mut Position) {
+            position[0] += self.0;
+            position[1] += self.1;
+        }
+    }
+
+    struct DropMultiPoints;
+
+    impl VisitorMut for DropMultiPoints {
+        fn visit_multi_point_mut(&mut self, positions: &mut Vec<Position>) {
+            positions.clear();
+        }
+    }
+
+    #[test]
+    fn transform_positions() {
+        let mut geojson = geojson();
+        walk_mut(&mut Translate(10.0, -10.0), &mut geojson);
+        walk_mut(&mut DropMultiPoints, &mut geojson);
+
+        let fc = match geojson {
+            GeoJson::FeatureCollection(fc) => fc,
+            _ => unreachable!(),
+        };
+        let geometries = match fc.features[1].geometry.as_ref().unwrap().value {
+            Value::GeometryCollection(ref geometries) => geometries.clone(),
+            _ => unreachable!(),
+        };
+        assert_eq!(geometries[0].value, Value::Point(vec![15.0, -5.0]));
+        assert_eq!(geometries[1].value, Value::MultiPoint(vec![]));
+    }
+}
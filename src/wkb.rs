@@ -0,0 +1,565 @@
+// Copyright 2015 The GeoRust Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Well-Known Binary encoding and decoding
+//!
+//! Values are written as ISO WKB, and geometries can additionally be written as PostGIS
+//! extended WKB (EWKB), which carries an SRID taken from an `EPSG` named `crs`. Both flavours
+//! are recognised when reading, in either byte order, and the SRID of EWKB input becomes the
+//! `crs` of the decoded `Geometry`.
+//!
+//! As with WKT, positions with three ordinates are encoded with a Z flag and positions with
+//! four with both Z and M flags. The measure of an M-only geometry is dropped when reading.
+//! An empty point is encoded with NaN ordinates.
+
+use {Crs, CrsId, Error, Geometry, LineStringType, PolygonType, Position, Value, util};
+
+
+/// Byte order of encoded WKB
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ByteOrder {
+    BigEndian,
+    LittleEndian,
+}
+
+const EWKB_Z: u32 = 0x8000_0000;
+const EWKB_M: u32 = 0x4000_0000;
+const EWKB_SRID: u32 = 0x2000_0000;
+
+const POINT: u32 = 1;
+const LINE_STRING: u32 = 2;
+const POLYGON: u32 = 3;
+const MULTI_POINT: u32 = 4;
+const MULTI_LINE_STRING: u32 = 5;
+const MULTI_POLYGON: u32 = 6;
+const GEOMETRY_COLLECTION: u32 = 7;
+
+impl Value {
+    /// Decodes ISO WKB or EWKB. Any SRID is ignored.
+    pub fn from_wkb(wkb: &[u8]) -> Result<Value, Error> {
+        Geometry::from_wkb(wkb).map(|geometry| geometry.value)
+    }
+
+    /// Encodes the value as ISO WKB.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geojson::Value;
+    /// use geojson::wkb::ByteOrder;
+    ///
+    /// let value = Value::Point(vec![1.0, 2.0]);
+    /// let wkb = value.to_wkb(ByteOrder::LittleEndian);
+    ///
+    /// assert_eq!(wkb.len(), 21);
+    /// assert_eq!(Value::from_wkb(&wkb), Ok(value));
+    /// ```
+    pub fn to_wkb(&self, byte_order: ByteOrder) -> Vec<u8> {
+        let mut writer = Writer {
+            out: vec![],
+            byte_order: byte_order,
+            extended: false,
+        };
+        writer.value(self, None);
+        writer.out
+    }
+}
+
+impl Geometry {
+    /// Decodes ISO WKB or EWKB. An EWKB SRID sets `crs` to the matching `EPSG` named CRS.
+    pub fn from_wkb(wkb: &[u8]) -> Result<Geometry, Error> {
+        let mut reader = Reader {
+            data: wkb,
+            offset: 0,
+            depth: 0,
+        };
+        let mut srid = None;
+        let value = try!(reader.value(&mut srid));
+        if reader.offset != wkb.len() {
+            return Err(reader.error("trailing bytes"));
+        }
+
+        Ok(Geometry {
+            bbox: None,
+            value: value,
+            crs: srid.map(|srid| Crs::from(CrsId::new("EPSG", &srid.to_string()))),
+        })
+    }
+
+    /// Encodes the geometry's value as ISO WKB. `bbox` and `crs` are left out.
+    pub fn to_wkb(&self, byte_order: ByteOrder) -> Vec<u8> {
+        self.value.to_wkb(byte_order)
+    }
+
+    /// Encodes the geometry's value as PostGIS EWKB.
+    ///
+    /// The SRID is included if `crs` is an `EPSG` named CRS with a numeric code, or CRS84,
+    /// which is written as SRID 4326.
+    pub fn to_ewkb(&self, byte_order: ByteOrder) -> Vec<u8> {
+        let srid = self.crs.as_ref().and_then(|crs| crs.id()).and_then(|id| {
+            match (&id.authority as &str, &id.code as &str) {
+                ("EPSG", code) => code.parse::<u32>().ok(),
+                ("OGC", "CRS84") => Some(4326),
+                _ => None,
+            }
+        });
+        let mut writer = Writer {
+            out: vec![],
+            byte_order: byte_order,
+            extended: true,
+        };
+        writer.value(&self.value, srid);
+        writer.out
+    }
+}
+
+/// Deepest nesting of geometry collections accepted when reading
+const MAX_DEPTH: usize = 64;
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+    depth: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn error(&self, message: &str) -> Error {
+        Error::MalformedWkb(format!("{} at offset {}", message, self.offset))
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.data.len() - self.offset < len {
+            return Err(self.error("unexpected end of input"));
+        }
+        let bytes = &self.data[self.offset..self.offset + len];
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    fn byte_order(&mut self) -> Result<ByteOrder, Error> {
+        match try!(self.bytes(1))[0] {
+            0 => Ok(ByteOrder::BigEndian),
+            1 => Ok(ByteOrder::LittleEndian),
+            b => Err(self.error(&format!("invalid byte order {}", b))),
+        }
+    }
+
+    fn u32(&mut self, byte_order: ByteOrder) -> Result<u32, Error> {
+        let mut buf = [0; 4];
+        buf.copy_from_slice(try!(self.bytes(4)));
+        Ok(match byte_order {
+            ByteOrder::BigEndian => u32::from_be_bytes(buf),
+            ByteOrder::LittleEndian => u32::from_le_bytes(buf),
+        })
+    }
+
+    fn f64(&mut self, byte_order: ByteOrder) -> Result<f64, Error> {
+        let mut buf = [0; 8];
+        buf.copy_from_slice(try!(self.bytes(8)));
+        Ok(match byte_order {
+            ByteOrder::BigEndian => f64::from_be_bytes(buf),
+            ByteOrder::LittleEndian => f64::from_le_bytes(buf),
+        })
+    }
+
+    /// Reads a count, making sure the input is long enough for that many items of at least
+    /// `min_len` bytes each before anything gets allocated.
+    fn count(&mut self, byte_order: ByteOrder, min_len: usize) -> Result<usize, Error> {
+        let count = try!(self.u32(byte_order)) as usize;
+        if count.saturating_mul(min_len) > self.data.len() - self.offset {
+            return Err(self.error(&format!("count {} exceeds input", count)));
+        }
+        Ok(count)
+    }
+
+    fn value(&mut self, srid: &mut Option<u32>) -> Result<Value, Error> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("geometry collections nested too deeply"));
+        }
+        self.depth += 1;
+        let value = self.value_inner(srid);
+        self.depth -= 1;
+        value
+    }
+
+    fn value_inner(&mut self, srid: &mut Option<u32>) -> Result<Value, Error> {
+        let byte_order = try!(self.byte_order());
+        let type_ = try!(self.u32(byte_order));
+
+        let (has_z, has_m) = if type_ & (EWKB_Z | EWKB_M) != 0 {
+            (type_ & EWKB_Z != 0, type_ & EWKB_M != 0)
+        } else {
+            match (type_ & 0x0fff_ffff) / 1000 {
+                0 => (false, false),
+                1 => (true, false),
+                2 => (false, true),
+                3 => (true, true),
+                _ => return Err(self.error(&format!("unknown geometry type {}", type_))),
+            }
+        };
+        if type_ & EWKB_SRID != 0 {
+            *srid = Some(try!(self.u32(byte_order)));
+        }
+
+        let dims = Dimensions {
+            read: 2 + has_z as usize + has_m as usize,
+            keep: if has_z { 2 + has_z as usize + has_m as usize } else { 2 },
+        };
+
+        Ok(match (type_ & 0x0fff_ffff) % 1000 {
+            POINT => {
+                let position = try!(self.position(byte_order, dims));
+                Value::Point(if position.iter().all(|o| o.is_nan()) {
+                    vec![]
+                } else {
+                    position
+                })
+            }
+            LINE_STRING => Value::LineString(try!(self.line_string(byte_order, dims))),
+            POLYGON => Value::Polygon(try!(self.polygon(byte_order, dims))),
+            MULTI_POINT => {
+                let count = try!(self.count(byte_order, 5));
+                let mut points = Vec::with_capacity(count);
+                for _ in 0..count {
+                    match try!(self.value(&mut None)) {
+                        Value::Point(position) => points.push(position),
+                        _ => return Err(self.error("expected a point")),
+                    }
+                }
+                Value::MultiPoint(points)
+            }
+            MULTI_LINE_STRING => {
+                let count = try!(self.count(byte_order, 9));
+                let mut line_strings = Vec::with_capacity(count);
+                for _ in 0..count {
+                    match try!(self.value(&mut None)) {
+                        Value::LineString(line_string) => line_strings.push(line_string),
+                        _ => return Err(self.error("expected a line string")),
+                    }
+                }
+                Value::MultiLineString(line_strings)
+            }
+            MULTI_POLYGON => {
+                let count = try!(self.count(byte_order, 9));
+                let mut polygons = Vec::with_capacity(count);
+                for _ in 0..count {
+                    match try!(self.value(&mut None)) {
+                        Value::Polygon(polygon) => polygons.push(polygon),
+                        _ => return Err(self.error("expected a polygon")),
+                    }
+                }
+                Value::MultiPolygon(polygons)
+            }
+            GEOMETRY_COLLECTION => {
+                let count = try!(self.count(byte_order, 9));
+                let mut geometries = Vec::with_capacity(count);
+                for _ in 0..count {
+                    geometries.push(Geometry::new(try!(self.value(&mut None))));
+                }
+                Value::GeometryCollection(geometries)
+            }
+            _ => return Err(self.error(&format!("unknown geometry type {}", type_))),
+        })
+    }
+
+    fn position(&mut self, byte_order: ByteOrder, dims: Dimensions) -> Result<Position, Error> {
+        let mut position = Vec::with_capacity(dims.keep);
+        for i in 0..dims.read {
+            let ordinate = try!(self.f64(byte_order));
+            if i < dims.keep {
+                position.push(ordinate);
+            }
+        }
+        Ok(position)
+    }
+
+    fn line_string(&mut self,
+                   byte_order: ByteOrder,
+                   dims: Dimensions)
+                   -> Result<LineStringType, Error> {
+        let count = try!(self.count(byte_order, dims.read * 8));
+        let mut positions = Vec::with_capacity(count);
+        for _ in 0..count {
+            positions.push(try!(self.position(byte_order, dims)));
+        }
+        Ok(positions)
+    }
+
+    fn polygon(&mut self, byte_order: ByteOrder, dims: Dimensions) -> Result<PolygonType, Error> {
+        let count = try!(self.count(byte_order, 4));
+        let mut rings = Vec::with_capacity(count);
+        for _ in 0..count {
+            rings.push(try!(self.line_string(byte_order, dims)));
+        }
+        Ok(rings)
+    }
+}
+
+/// Number of ordinates per encoded position, and how many of those are kept
+#[derive(Clone, Copy)]
+struct Dimensions {
+    read: usize,
+    keep: usize,
+}
+
+struct Writer {
+    out: Vec<u8>,
+    byte_order: ByteOrder,
+    extended: bool,
+}
+
+impl Writer {
+    fn u32(&mut self, n: u32) {
+        let bytes = match self.byte_order {
+            ByteOrder::BigEndian => n.to_be_bytes(),
+            ByteOrder::LittleEndian => n.to_le_bytes(),
+        };
+        self.out.extend_from_slice(&bytes);
+    }
+
+    fn f64(&mut self, n: f64) {
+        let bytes = match self.byte_order {
+            ByteOrder::BigEndian => n.to_be_bytes(),
+            ByteOrder::LittleEndian => n.to_le_bytes(),
+        };
+        self.out.extend_from_slice(&bytes);
+    }
+
+    fn header(&mut self, type_: u32, dims: usize, srid: Option<u32>) {
+        self.out.push(match self.byte_order {
+            ByteOrder::BigEndian => 0,
+            ByteOrder::LittleEndian => 1,
+        });
+        let type_ = if self.extended {
+            let mut type_ = type_;
+            if dims >= 3 {
+                type_ |= EWKB_Z;
+            }
+            if dims >= 4 {
+                type_ |= EWKB_M;
+            }
+            if srid.is_some() {
+                type_ |= EWKB_SRID;
+            }
+            type_
+        } else {
+            match dims {
+                3 => type_ + 1000,
+                4 => type_ + 3000,
+                _ => type_,
+            }
+        };
+        self.u32(type_);
+        if let Some(srid) = srid {
+            self.u32(srid);
+        }
+    }
+
+    /// Writes `dims` ordinates of `position`, NaN standing in for those it lacks.
+    fn position(&mut self, position: &Position, dims: usize) {
+        for i in 0..dims {
+            self.f64(position.get(i).cloned().unwrap_or(::std::f64::NAN));
+        }
+    }
+
+    fn line_string(&mut self, positions: &[Position], dims: usize) {
+        self.u32(positions.len() as u32);
+        for position in positions {
+            self.position(position, dims);
+        }
+    }
+
+    fn polygon(&mut self, rings: &[LineStringType], dims: usize) {
+        self.u32(rings.len() as u32);
+        for ring in rings {
+            self.line_string(ring, dims);
+        }
+    }
+
+    /// Writes `value`, with `srid` if given. Only the outermost geometry of EWKB has an SRID.
+    fn value(&mut self, value: &Value, srid: Option<u32>) {
+        let dims = util::coordinate_dimensions(value);
+        match *value {
+            Value::Point(ref position) => {
+                self.header(POINT, dims, srid);
+                self.position(position, dims);
+            }
+            Value::LineString(ref positions) => {
+                self.header(LINE_STRING, dims, srid);
+                self.line_string(positions, dims);
+            }
+            Value::Polygon(ref rings) => {
+                self.header(POLYGON, dims, srid);
+                self.polygon(rings, dims);
+            }
+            Value::MultiPoint(ref positions) => {
+                self.header(MULTI_POINT, dims, srid);
+                self.u32(positions.len() as u32);
+                for position in positions {
+                    self.header(POINT, dims, None);
+                    self.position(position, dims);
+                }
+            }
+            Value::MultiLineString(ref line_strings) => {
+                self.header(MULTI_LINE_STRING, dims, srid);
+                self.u32(line_strings.len() as u32);
+                for line_string in line_strings {
+                    self.header(LINE_STRING, dims, None);
+                    self.line_string(line_string, dims);
+                }
+            }
+            Value::MultiPolygon(ref polygons) => {
+                self.header(MULTI_POLYGON, dims, srid);
+                self.u32(polygons.len() as u32);
+                for polygon in polygons {
+                    self.header(POLYGON, dims, None);
+                    self.polygon(polygon, dims);
+                }
+            }
+            Value::GeometryCollection(ref geometries) => {
+                self.header(GEOMETRY_COLLECTION, dims, srid);
+                self.u32(geometries.len() as u32);
+                for geometry in geometries {
+                    self.value(&geometry.value, None);
+                }
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use {Crs, Error, Geometry, Value};
+    use super::ByteOrder;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02X}", b)).collect()
+    }
+
+    fn unhex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn values() -> Vec<Value> {
+        vec![Value::Point(vec![1.1, 2.1]),
+             Value::Point(vec![]),
+             Value::MultiPoint(vec![vec![40.02, 116.34, 1.0], vec![13.02, 24.34, 2.0]]),
+             Value::MultiPoint(vec![vec![], vec![1.0, 2.0]]),
+             Value::LineString(vec![vec![40.02, 116.34], vec![13.02, 24.34]]),
+             Value::MultiLineString(vec![vec![vec![40.02, 116.34], vec![13.02, 24.34]],
+                                         vec![vec![46.84, 160.95], vec![42.02, 96.34]]]),
+             Value::Polygon(vec![vec![vec![100.0, 0.0, 1.0, 7.0],
+                                      vec![101.0, 0.0, 1.0, 8.0],
+                                      vec![101.0, 1.0, 1.0, 9.0],
+                                      vec![100.0, 0.0, 1.0, 7.0]]]),
+             Value::MultiPolygon(vec![vec![vec![vec![102.0, 2.0],
+                                                vec![103.0, 2.0],
+                                                vec![103.0, 3.0],
+                                                vec![102.0, 2.0]]],
+                                      vec![]]),
+             Value::GeometryCollection(vec![
+                 Geometry::new(Value::Point(vec![1.0, 2.0])),
+                 Geometry::new(Value::GeometryCollection(vec![
+                     Geometry::new(Value::LineString(vec![vec![1.0, 2.0, 3.0]])),
+                 ])),
+             ])]
+    }
+
+    #[test]
+    fn round_trip() {
+        for value in values() {
+            for byte_order in &[ByteOrder::BigEndian, ByteOrder::LittleEndian] {
+                let wkb = value.to_wkb(*byte_order);
+                assert_eq!(Value::from_wkb(&wkb), Ok(value.clone()));
+
+                let geometry = Geometry {
+                    bbox: None,
+                    value: value.clone(),
+                    crs: Some(Crs::Named { name: String::from("urn:ogc:def:crs:EPSG::3857") }),
+                };
+                let ewkb = geometry.to_ewkb(*byte_order);
+                assert_eq!(Geometry::from_wkb(&ewkb), Ok(geometry));
+            }
+        }
+
+        // Missing ordinates of line positions are written as NaN
+        let line = Value::LineString(vec![vec![1.0, 2.0], vec![], vec![3.0, 4.0]]);
+        match Value::from_wkb(&line.to_wkb(ByteOrder::LittleEndian)) {
+            Ok(Value::LineString(ref positions)) => {
+                assert_eq!(positions.len(), 3);
+                assert!(positions[1].iter().all(|o| o.is_nan()));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn encode() {
+        let point = Geometry::new(Value::Point(vec![1.0, 2.0]));
+        assert_eq!(hex(&point.to_wkb(ByteOrder::LittleEndian)),
+                   "0101000000000000000000F03F0000000000000040");
+        assert_eq!(hex(&point.to_wkb(ByteOrder::BigEndian)),
+                   "00000000013FF00000000000004000000000000000");
+
+        let point = Geometry {
+            crs: Some(Crs::Named { name: String::from("EPSG:4326") }),
+            ..point
+        };
+        assert_eq!(hex(&point.to_ewkb(ByteOrder::LittleEndian)),
+                   "0101000020E6100000000000000000F03F0000000000000040");
+
+        let point_z = Value::Point(vec![1.0, 2.0, 3.0]);
+        assert_eq!(hex(&point_z.to_wkb(ByteOrder::LittleEndian)),
+                   "01E9030000000000000000F03F00000000000000400000000000000840");
+        assert_eq!(hex(&Geometry::new(point_z).to_ewkb(ByteOrder::LittleEndian)),
+                   "0101000080000000000000F03F00000000000000400000000000000840");
+    }
+
+    #[test]
+    fn decode_measures() {
+        // ISO POINT M (1 2 3) and EWKB POINTM(1 2 3)
+        let expected = Ok(Value::Point(vec![1.0, 2.0]));
+        let iso = unhex("01D1070000000000000000F03F00000000000000400000000000000840");
+        let ewkb = unhex("0101000040000000000000F03F00000000000000400000000000000840");
+        assert_eq!(Value::from_wkb(&iso), expected);
+        assert_eq!(Value::from_wkb(&ewkb), expected);
+    }
+
+    #[test]
+    fn decode_errors() {
+        let point = Value::Point(vec![1.0, 2.0]).to_wkb(ByteOrder::LittleEndian);
+        for wkb in &[&point[..20], &[2, 1, 0, 0, 0][..], &unhex("0109000000"),
+                     &unhex("0102000000FFFFFFFF")] {
+            match Value::from_wkb(wkb) {
+                Err(Error::MalformedWkb(..)) => {}
+                result => panic!("{} decoded as {:?}", hex(wkb), result),
+            }
+        }
+
+        let mut nested = vec![];
+        for _ in 0..100 {
+            nested.extend(unhex("010700000001000000"));
+        }
+        nested.extend(&point);
+        assert!(Value::from_wkb(&nested).is_err());
+
+        let mut trailing = point.clone();
+        trailing.push(0);
+        assert!(Value::from_wkb(&trailing).is_err());
+    }
+}
@@ -0,0 +1,649 @@
+// Copyright 2015 The GeoRust Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Well-Known Text reading and writing
+//!
+//! All seven geometry types are supported, along with `EMPTY` geometries and the `Z`, `M`
+//! and `ZM` variants. Since GeoJSON positions have no notion of a measure, `M` ordinates are
+//! only kept for `ZM` geometries, where they become the fourth ordinate; the measure of an
+//! `M` geometry is dropped. Positions with three ordinates are written as `Z`, and positions
+//! with four as `ZM`.
+//!
+//! PostGIS extended WKT is accepted as well: dimensions may be given by the number of
+//! ordinates alone, and a leading `SRID=<srid>;` becomes an `EPSG` `Crs` when read with
+//! `Geometry::from_wkt`.
+
+use std::fmt::Write;
+
+use {Crs, CrsId, Error, Geometry, LineStringType, PolygonType, Position, Value, util};
+
+
+impl Value {
+    /// Parses a Well-Known Text geometry. Any `SRID=<srid>;` prefix is ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use geojson::Value;
+    ///
+    /// let value = Value::from_wkt("LINESTRING Z (30 10 1, 10 30 2)").unwrap();
+    /// assert_eq!(value, Value::LineString(vec![vec![30.0, 10.0, 1.0], vec![10.0, 30.0, 2.0]]));
+    /// assert_eq!(value.to_wkt(), "LINESTRING Z (30 10 1, 10 30 2)");
+    /// ```
+    pub fn from_wkt(wkt: &str) -> Result<Value, Error> {
+        Geometry::from_wkt(wkt).map(|geometry| geometry.value)
+    }
+
+    /// Returns the Well-Known Text representation of the value.
+    pub fn to_wkt(&self) -> String {
+        let mut wkt = String::new();
+        write_value(&mut wkt, self);
+        wkt
+    }
+}
+
+impl Geometry {
+    /// Parses a Well-Known Text geometry.
+    ///
+    /// An extended WKT `SRID=<srid>;` prefix sets `crs` to the matching `EPSG` named CRS.
+    pub fn from_wkt(wkt: &str) -> Result<Geometry, Error> {
+        let (crs, wkt) = match split_srid(wkt) {
+            Some((srid, rest)) => {
+                let crs = Crs::from(CrsId::new("EPSG", srid.trim()));
+                (Some(crs), rest)
+            }
+            None => (None, wkt),
+        };
+
+        let mut parser = Parser::new(wkt);
+        let value = try!(parser.geometry());
+        if let Some(token) = parser.next() {
+            return Err(parser.unexpected(Some(token)));
+        }
+
+        Ok(Geometry {
+            bbox: None,
+            value: value,
+            crs: crs,
+        })
+    }
+
+    /// Returns the Well-Known Text representation of the geometry's value.
+    ///
+    /// `bbox` and `crs` are not part of WKT and are left out.
+    pub fn to_wkt(&self) -> String {
+        self.value.to_wkt()
+    }
+}
+
+fn split_srid(wkt: &str) -> Option<(&str, &str)> {
+    let trimmed = wkt.trim_start();
+    if !trimmed.get(..5).map_or(false, |s| s.eq_ignore_ascii_case("SRID=")) {
+        return None;
+    }
+    trimmed[5..].find(';').map(|i| (&trimmed[5..5 + i], &trimmed[5 + i + 1..]))
+}
+
+const GEOMETRY_TYPES: &'static [&'static str] = &["POINT",
+                                                   "MULTIPOINT",
+                                                   "LINESTRING",
+                                                   "MULTILINESTRING",
+                                                   "POLYGON",
+                                                   "MULTIPOLYGON",
+                                                   "GEOMETRYCOLLECTION"];
+
+/// Splits an upper case geometry type such as `POINTZM` into the type and its dimensions.
+fn split_type(word: &str) -> Option<(&'static str, Dimensions)> {
+    for type_ in GEOMETRY_TYPES {
+        if !word.starts_with(type_) {
+            continue;
+        }
+        let dims = match &word[type_.len()..] {
+            "" => Dimensions::Inferred,
+            "Z" => Dimensions::Xyz,
+            "M" => Dimensions::Xym,
+            "ZM" => Dimensions::Xyzm,
+            _ => continue,
+        };
+        return Some((type_, dims));
+    }
+    None
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Number(f64),
+    Open,
+    Close,
+    Comma,
+}
+
+/// Number of ordinates per position, and whether the last one is a measure
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Dimensions {
+    Inferred,
+    Xyz,
+    Xym,
+    Xyzm,
+}
+
+/// Deepest nesting of geometry collections accepted when parsing
+const MAX_DEPTH: usize = 64;
+
+struct Parser<'a> {
+    input: &'a str,
+    offset: usize,
+    peeked: Option<Token>,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Parser {
+            input: input,
+            offset: 0,
+            peeked: None,
+            depth: 0,
+        }
+    }
+
+    fn error(&self, message: &str) -> Error {
+        Error::MalformedWkt(format!("{} at offset {}", message, self.offset))
+    }
+
+    fn unexpected(&self, token: Option<Token>) -> Error {
+        match token {
+            Some(token) => self.error(&format!("unexpected {:?}", token)),
+            None => self.error("unexpected end of input"),
+        }
+    }
+
+    fn lex(&mut self) -> Option<Token> {
+        let rest = &self.input[self.offset..];
+        let start = match rest.find(|c: char| !c.is_whitespace()) {
+            Some(start) => start,
+            None => {
+                self.offset = self.input.len();
+                return None;
+            }
+        };
+        let rest = &rest[start..];
+        self.offset += start;
+
+        let c = rest.chars().next().unwrap();
+        let (token, len) = match c {
+            '(' => (Token::Open, 1),
+            ')' => (Token::Close, 1),
+            ',' => (Token::Comma, 1),
+            _ if c.is_alphabetic() => {
+                let len = rest.find(|c: char| !c.is_alphanumeric()).unwrap_or(rest.len());
+                (Token::Word(rest[..len].to_uppercase()), len)
+            }
+            _ => {
+                let len = rest.find(|c: char| c.is_whitespace() || "(),".contains(c))
+                    .unwrap_or(rest.len());
+                match rest[..len].parse::<f64>() {
+                    Ok(number) => (Token::Number(number), len),
+                    Err(..) => (Token::Word(String::from(&rest[..len])), len),
+                }
+            }
+        };
+        self.offset += len;
+        Some(token)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        match self.peeked.take() {
+            Some(token) => Some(token),
+            None => self.lex(),
+        }
+    }
+
+    fn peek(&mut self) -> Option<&Token> {
+        if self.peeked.is_none() {
+            self.peeked = self.lex();
+        }
+        self.peeked.as_ref()
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), Error> {
+        match self.next() {
+            Some(ref token) if *token == expected => Ok(()),
+            token => Err(self.unexpected(token)),
+        }
+    }
+
+    fn word(&mut self) -> Result<String, Error> {
+        match self.next() {
+            Some(Token::Word(word)) => Ok(word),
+            token => Err(self.unexpected(token)),
+        }
+    }
+
+    /// Consumes an `EMPTY` keyword if it is next, returning whether it was.
+    fn empty(&mut self) -> bool {
+        if let Some(&Token::Word(ref word)) = self.peek() {
+            if word == "EMPTY" {
+                self.peeked = None;
+                return true;
+            }
+        }
+        false
+    }
+
+    fn geometry(&mut self) -> Result<Value, Error> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("geometry collections nested too deeply"));
+        }
+        self.depth += 1;
+        let value = self.geometry_inner();
+        self.depth -= 1;
+        value
+    }
+
+    fn geometry_inner(&mut self) -> Result<Value, Error> {
+        let word = try!(self.word());
+
+        // Extended WKT spells e.g. POINTM without a space
+        let (type_, mut dims) = match split_type(&word) {
+            Some(split) => split,
+            None => return Err(self.error(&format!("unknown geometry type '{}'", word))),
+        };
+
+        if dims == Dimensions::Inferred {
+            let tag = match self.peek() {
+                Some(&Token::Word(ref word)) if word == "Z" || word == "M" || word == "ZM" => {
+                    Some(word.clone())
+                }
+                _ => None,
+            };
+            if let Some(tag) = tag {
+                self.peeked = None;
+                dims = match &tag as &str {
+                    "Z" => Dimensions::Xyz,
+                    "M" => Dimensions::Xym,
+                    _ => Dimensions::Xyzm,
+                };
+            }
+        }
+
+        let empty = self.empty();
+        Ok(match type_ {
+            "POINT" => {
+                Value::Point(if empty {
+                    vec![]
+                } else {
+                    try!(self.expect(Token::Open));
+                    let position = try!(self.position(dims));
+                    try!(self.expect(Token::Close));
+                    position
+                })
+            }
+            "MULTIPOINT" => {
+                Value::MultiPoint(if empty {
+                    vec![]
+                } else {
+                    try!(self.multi_point(dims))
+                })
+            }
+            "LINESTRING" => {
+                Value::LineString(if empty {
+                    vec![]
+                } else {
+                    try!(self.line_string(dims))
+                })
+            }
+            "MULTILINESTRING" => {
+                Value::MultiLineString(if empty {
+                    vec![]
+                } else {
+                    try!(self.list(|p| p.line_string_or_empty(dims)))
+                })
+            }
+            "POLYGON" => {
+                Value::Polygon(if empty {
+                    vec![]
+                } else {
+                    try!(self.polygon(dims))
+                })
+            }
+            "MULTIPOLYGON" => {
+                Value::MultiPolygon(if empty {
+                    vec![]
+                } else {
+                    try!(self.list(|p| if p.empty() { Ok(vec![]) } else { p.polygon(dims) }))
+                })
+            }
+            "GEOMETRYCOLLECTION" => {
+                Value::GeometryCollection(if empty {
+                    vec![]
+                } else {
+                    try!(self.list(|p| p.geometry().map(Geometry::new)))
+                })
+            }
+            _ => unreachable!(),
+        })
+    }
+
+    /// Parses a parenthesized, comma separated list of items.
+    fn list<T, F>(&mut self, mut item: F) -> Result<Vec<T>, Error>
+        where F: FnMut(&mut Self) -> Result<T, Error>
+    {
+        try!(self.expect(Token::Open));
+        let mut items = vec![try!(item(self))];
+        loop {
+            match self.next() {
+                Some(Token::Comma) => items.push(try!(item(self))),
+                Some(Token::Close) => return Ok(items),
+                token => return Err(self.unexpected(token)),
+            }
+        }
+    }
+
+    fn position(&mut self, dims: Dimensions) -> Result<Position, Error> {
+        let mut position = vec![];
+        while let Some(&Token::Number(n)) = self.peek() {
+            self.peeked = None;
+            position.push(n);
+        }
+        let expected = match dims {
+            Dimensions::Inferred => {
+                if position.len() >= 2 && position.len() <= 4 {
+                    position.len()
+                } else {
+                    2
+                }
+            }
+            Dimensions::Xyz | Dimensions::Xym => 3,
+            Dimensions::Xyzm => 4,
+        };
+        if position.len() != expected {
+            return Err(self.error(&format!("expected {} ordinates, found {}",
+                                           expected,
+                                           position.len())));
+        }
+        if dims == Dimensions::Xym {
+            position.truncate(2);
+        }
+        Ok(position)
+    }
+
+    fn multi_point(&mut self, dims: Dimensions) -> Result<Vec<Position>, Error> {
+        // Both MULTIPOINT ((1 2), (3 4)) and MULTIPOINT (1 2, 3 4) are in common use
+        self.list(|p| {
+            if p.empty() {
+                return Ok(vec![]);
+            }
+            if p.peek() == Some(&Token::Open) {
+                try!(p.expect(Token::Open));
+                let position = try!(p.position(dims));
+                try!(p.expect(Token::Close));
+                Ok(position)
+            } else {
+                p.position(dims)
+            }
+        })
+    }
+
+    fn line_string(&mut self, dims: Dimensions) -> Result<LineStringType, Error> {
+        self.list(|p| p.position(dims))
+    }
+
+    fn line_string_or_empty(&mut self, dims: Dimensions) -> Result<LineStringType, Error> {
+        if self.empty() {
+            Ok(vec![])
+        } else {
+            self.line_string(dims)
+        }
+    }
+
+    fn polygon(&mut self, dims: Dimensions) -> Result<PolygonType, Error> {
+        self.list(|p| p.line_string_or_empty(dims))
+    }
+}
+
+fn write_value(wkt: &mut String, value: &Value) {
+    let dims = util::coordinate_dimensions(value);
+    let (type_, empty) = match *value {
+        Value::Point(ref position) => ("POINT", position.is_empty()),
+        Value::MultiPoint(ref positions) => ("MULTIPOINT", positions.is_empty()),
+        Value::LineString(ref positions) => ("LINESTRING", positions.is_empty()),
+        Value::MultiLineString(ref lines) => ("MULTILINESTRING", lines.is_empty()),
+        Value::Polygon(ref rings) => ("POLYGON", rings.is_empty()),
+        Value::MultiPolygon(ref polygons) => ("MULTIPOLYGON", polygons.is_empty()),
+        Value::GeometryCollection(ref geometries) => ("GEOMETRYCOLLECTION", geometries.is_empty()),
+    };
+
+    wkt.push_str(type_);
+    if empty {
+        wkt.push_str(" EMPTY");
+        return;
+    }
+    match *value {
+        Value::GeometryCollection(..) => {}
+        _ if dims == 3 => wkt.push_str(" Z"),
+        _ if dims == 4 => wkt.push_str(" ZM"),
+        _ => {}
+    }
+    wkt.push(' ');
+
+    match *value {
+        Value::Point(ref position) => {
+            wkt.push('(');
+            write_position(wkt, position, dims);
+            wkt.push(')');
+        }
+        Value::MultiPoint(ref positions) => {
+            write_list(wkt, positions, |wkt, position| {
+                if position.is_empty() {
+                    wkt.push_str("EMPTY");
+                } else {
+                    wkt.push('(');
+                    write_position(wkt, position, dims);
+                    wkt.push(')');
+                }
+            })
+        }
+        Value::LineString(ref positions) => write_line_string(wkt, positions, dims),
+        Value::MultiLineString(ref lines) => {
+            write_list(wkt, lines, |wkt, line| write_line_string(wkt, line, dims))
+        }
+        Value::Polygon(ref rings) => write_polygon(wkt, rings, dims),
+        Value::MultiPolygon(ref polygons) => {
+            write_list(wkt, polygons, |wkt, polygon| write_polygon(wkt, polygon, dims))
+        }
+        Value::GeometryCollection(ref geometries) => {
+            write_list(wkt, geometries, |wkt, geometry| write_value(wkt, &geometry.value))
+        }
+    }
+}
+
+fn write_list<T, F>(wkt: &mut String, items: &[T], mut write_item: F)
+    where F: FnMut(&mut String, &T)
+{
+    wkt.push('(');
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            wkt.push_str(", ");
+        }
+        write_item(wkt, item);
+    }
+    wkt.push(')');
+}
+
+fn write_position(wkt: &mut String, position: &Position, dims: usize) {
+    for (i, ordinate) in position.iter().take(dims).enumerate() {
+        if i > 0 {
+            wkt.push(' ');
+        }
+        write!(wkt, "{}", ordinate).unwrap();
+    }
+}
+
+fn write_line_string(wkt: &mut String, positions: &[Position], dims: usize) {
+    if positions.is_empty() {
+        wkt.push_str("EMPTY");
+    } else {
+        write_list(wkt,
+                   positions,
+                   |wkt, position| write_position(wkt, position, dims))
+    }
+}
+
+fn write_polygon(wkt: &mut String, rings: &[LineStringType], dims: usize) {
+    if rings.is_empty() {
+        wkt.push_str("EMPTY");
+    } else {
+        write_list(wkt, rings, |wkt, ring| write_line_string(wkt, ring, dims))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use {Crs, Error, Geometry, Value};
+
+    fn values() -> Vec<Value> {
+        vec![Value::Point(vec![1.1, 2.1]),
+             Value::Point(vec![40.02, 116.34, 12.5]),
+             Value::MultiPoint(vec![vec![40.02, 116.34], vec![13.02, 24.34]]),
+             Value::LineString(vec![vec![40.02, 116.34], vec![13.02, 24.34]]),
+             Value::MultiLineString(vec![vec![vec![40.02, 116.34], vec![13.02, 24.34]],
+                                         vec![vec![46.84, 160.95], vec![42.02, 96.34]]]),
+             Value::Polygon(vec![vec![vec![100.0, 0.0, 1.0, 7.0],
+                                      vec![101.0, 0.0, 1.0, 8.0],
+                                      vec![101.0, 1.0, 1.0, 9.0],
+                                      vec![100.0, 0.0, 1.0, 7.0]]]),
+             Value::MultiPolygon(vec![vec![vec![vec![102.0, 2.0],
+                                                vec![103.0, 2.0],
+                                                vec![103.0, 3.0],
+                                                vec![102.0, 2.0]]],
+                                      vec![vec![vec![100.0, 0.0],
+                                                vec![101.0, 0.0],
+                                                vec![101.0, 1.0],
+                                                vec![100.0, 0.0]],
+                                           vec![vec![100.2, 0.2],
+                                                vec![100.8, 0.2],
+                                                vec![100.8, 0.8],
+                                                vec![100.2, 0.2]]]]),
+             Value::GeometryCollection(vec![
+                 Geometry::new(Value::Point(vec![1.0, 2.0])),
+                 Geometry::new(Value::LineString(vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]])),
+                 Geometry::new(Value::GeometryCollection(vec![])),
+             ]),
+             Value::Point(vec![]),
+             Value::MultiPolygon(vec![])]
+    }
+
+    #[test]
+    fn round_trip() {
+        for value in values() {
+            let wkt = value.to_wkt();
+            assert_eq!(Value::from_wkt(&wkt), Ok(value.clone()), "{}", wkt);
+        }
+    }
+
+    #[test]
+    fn round_trip_fixtures() {
+        // Geometries of the GeoJSON, geometry and conversion tests
+        let fixtures = [
+            r#"{"type": "Point", "coordinates": [-120.66029, 35.2812]}"#,
+            r#"{"type": "Point", "coordinates": [1.1, 2.1]}"#,
+            r#"{"type": "MultiPoint", "coordinates": [[100.0, 0.2], [101.0, 1.0]]}"#,
+            r#"{"type": "LineString", "coordinates": [[100.0, 0.2], [101.0, 1.0]]}"#,
+            r#"{"type": "MultiLineString",
+                "coordinates": [[[100.0, 0.2], [101.0, 1.0]], [[101.0, 1.0], [102.0, 0.8]]]}"#,
+            r#"{"type": "Polygon",
+                "coordinates": [[[100.0, 0.0], [101.0, 1.0], [101.0, 1.0], [100.0, 0.0]],
+                                [[104.0, 0.2], [100.9, 0.2], [100.9, 0.7], [104.0, 0.2]]]}"#,
+            r#"{"type": "Polygon", "coordinates": []}"#,
+            r#"{"type": "MultiPolygon",
+                "coordinates": [[[[100.0, 0.0], [101.0, 1.0], [101.0, 1.0], [100.0, 0.0]]],
+                                [[[104.0, 0.2], [100.9, 0.2], [100.9, 0.7], [104.0, 0.2]]]]}"#,
+            r#"{"type": "GeometryCollection", "geometries": [
+                {"type": "MultiPoint", "coordinates": [[100.0, 0.0], [100.0, 1.0]]},
+                {"type": "MultiLineString",
+                 "coordinates": [[[100.0, 0.0], [100.0, 1.0]], [[100.0, 1.0], [101.0, 1.0]]]},
+                {"type": "MultiPolygon",
+                 "coordinates": [[[[101.0, 1.0], [102.0, 0.0], [101.0, 0.0], [101.0, 1.0]]],
+                                 [[[100.0, 0.0], [101.0, 0.0], [101.0, 1.0], [100.0, 0.0]]]]}
+            ]}"#,
+        ];
+        for fixture in &fixtures {
+            let value = ::serde_json::from_str::<Geometry>(fixture).unwrap().value;
+            let wkt = value.to_wkt();
+            assert_eq!(Value::from_wkt(&wkt), Ok(value.clone()), "{}", wkt);
+        }
+    }
+
+    #[test]
+    fn write() {
+        let wkt = values().iter().map(|v| v.to_wkt()).collect::<Vec<_>>();
+        assert_eq!(wkt[0], "POINT (1.1 2.1)");
+        assert_eq!(wkt[1], "POINT Z (40.02 116.34 12.5)");
+        assert_eq!(wkt[2], "MULTIPOINT ((40.02 116.34), (13.02 24.34))");
+        assert_eq!(wkt[5], "POLYGON ZM ((100 0 1 7, 101 0 1 8, 101 1 1 9, 100 0 1 7))");
+        assert_eq!(wkt[7],
+                   "GEOMETRYCOLLECTION (POINT (1 2), LINESTRING Z (1 2 3, 4 5 6), \
+                    GEOMETRYCOLLECTION EMPTY)");
+        assert_eq!(wkt[8], "POINT EMPTY");
+        assert_eq!(wkt[9], "MULTIPOLYGON EMPTY");
+    }
+
+    #[test]
+    fn read_variants() {
+        assert_eq!(Value::from_wkt("point(1 2)"), Ok(Value::Point(vec![1.0, 2.0])));
+        assert_eq!(Value::from_wkt("MULTIPOINT (1 2, 3 4)"),
+                   Ok(Value::MultiPoint(vec![vec![1.0, 2.0], vec![3.0, 4.0]])));
+        assert_eq!(Value::from_wkt("POINT M (1 2 3)"), Ok(Value::Point(vec![1.0, 2.0])));
+        assert_eq!(Value::from_wkt("POINTM(1 2 3)"), Ok(Value::Point(vec![1.0, 2.0])));
+        assert_eq!(Value::from_wkt("POINT(1 2 3 4)"),
+                   Ok(Value::Point(vec![1.0, 2.0, 3.0, 4.0])));
+        assert_eq!(Value::from_wkt("LINESTRINGZ(1 2 3, 4 5 6)"),
+                   Ok(Value::LineString(vec![vec![1.0, 2.0, 3.0], vec![4.0, 5.0, 6.0]])));
+        assert_eq!(Value::from_wkt("POLYGON ((0 0, 1e1 0, -1.5 2, 0 0))"),
+                   Ok(Value::Polygon(vec![vec![vec![0.0, 0.0],
+                                               vec![10.0, 0.0],
+                                               vec![-1.5, 2.0],
+                                               vec![0.0, 0.0]]])));
+    }
+
+    #[test]
+    fn read_srid() {
+        let geometry = Geometry::from_wkt("SRID=4326;POINT(1 2)").unwrap();
+        assert_eq!(geometry.value, Value::Point(vec![1.0, 2.0]));
+        assert_eq!(geometry.crs,
+                   Some(Crs::Named { name: String::from("urn:ogc:def:crs:EPSG::4326") }));
+    }
+
+    #[test]
+    fn read_errors() {
+        for wkt in &["", "POINT", "POINT (1)", "POINT Z (1 2)", "CIRCLE (1 2)",
+                     "LINESTRING (1 2, 3 4", "POINT (1 2) trailing", "POINT (1 x)"] {
+            match Value::from_wkt(wkt) {
+                Err(Error::MalformedWkt(..)) => {}
+                result => panic!("{:?} parsed as {:?}", wkt, result),
+            }
+        }
+        assert!(Value::from_wkt("abcd\u{20ac}").is_err());
+        assert!(Geometry::from_wkt("SRID\u{20ac};POINT (1 2)").is_err());
+
+        let nested = "GEOMETRYCOLLECTION (".repeat(200_000);
+        match Value::from_wkt(&nested) {
+            Err(Error::MalformedWkt(ref message)) => assert!(message.contains("nested")),
+            result => panic!("nested collections parsed as {:?}", result),
+        }
+    }
+}
@@ -0,0 +1,354 @@
+// Copyright 2015 The GeoRust Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Minimal XML support, shared by the GPX and KML codecs
+//!
+//! Documents are parsed into a tree of elements. Namespace prefixes are dropped from element
+//! and attribute names, and DTDs are skipped without being processed.
+
+use std::fmt::Write;
+
+use json::{JsonObject, JsonValue};
+use serde_json;
+
+use Error;
+
+const MAX_DEPTH: usize = 256;
+
+pub struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Node>,
+}
+
+pub enum Node {
+    Element(Element),
+    Text(String),
+}
+
+impl Element {
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.iter().find(|a| a.0 == name).map(|a| &a.1 as &str)
+    }
+
+    pub fn elements<'a>(&'a self) -> Box<dyn Iterator<Item = &'a Element> + 'a> {
+        Box::new(self.children.iter().filter_map(|node| match *node {
+            Node::Element(ref element) => Some(element),
+            Node::Text(..) => None,
+        }))
+    }
+
+    pub fn children_named<'a>(&'a self,
+                              name: &'a str)
+                              -> Box<dyn Iterator<Item = &'a Element> + 'a> {
+        Box::new(self.elements().filter(move |element| element.name == name))
+    }
+
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.elements().find(|element| element.name == name)
+    }
+
+    /// Returns the text content of the element and its descendants.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for node in &self.children {
+            match *node {
+                Node::Element(ref element) => text.push_str(&element.text()),
+                Node::Text(ref t) => text.push_str(t),
+            }
+        }
+        text
+    }
+
+    /// Returns the trimmed text content of the named child, if any.
+    pub fn child_text(&self, name: &str) -> Option<String> {
+        self.child(name).map(|child| String::from(child.text().trim()))
+    }
+}
+
+fn local_name(name: &str) -> String {
+    String::from(name.rsplit(':').next().unwrap_or(name))
+}
+
+/// Returns a property as text, writing values other than strings as JSON.
+pub fn property(properties: Option<&JsonObject>, name: &str) -> Option<String> {
+    match properties.and_then(|p| p.get(name)) {
+        None | Some(&JsonValue::Null) => None,
+        Some(&JsonValue::String(ref s)) => Some(s.clone()),
+        Some(value) => Some(serde_json::to_string(value).unwrap()),
+    }
+}
+
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Writes indented elements.
+pub struct Writer {
+    pub xml: String,
+    depth: usize,
+}
+
+impl Writer {
+    pub fn new() -> Self {
+        Writer {
+            xml: String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"),
+            depth: 0,
+        }
+    }
+
+    fn indent(&mut self) {
+        for _ in 0..self.depth {
+            self.xml.push_str("  ");
+        }
+    }
+
+    fn tag(&mut self, name: &str, attributes: &[(&str, &str)]) {
+        self.indent();
+        self.xml.push('<');
+        self.xml.push_str(name);
+        for &(key, value) in attributes {
+            write!(self.xml, " {}=\"{}\"", key, escape(value)).unwrap();
+        }
+    }
+
+    pub fn start(&mut self, name: &str, attributes: &[(&str, &str)]) {
+        self.tag(name, attributes);
+        self.xml.push_str(">\n");
+        self.depth += 1;
+    }
+
+    pub fn empty(&mut self, name: &str, attributes: &[(&str, &str)]) {
+        self.tag(name, attributes);
+        self.xml.push_str("/>\n");
+    }
+
+    pub fn end(&mut self, name: &str) {
+        self.depth -= 1;
+        self.indent();
+        write!(self.xml, "</{}>\n", name).unwrap();
+    }
+
+    pub fn text_element(&mut self, name: &str, text: &str) {
+        self.tag(name, &[]);
+        write!(self.xml, ">{}</{}>\n", escape(text), name).unwrap();
+    }
+}
+
+/// Parses a document, reporting malformed input through `error`.
+pub fn parse(xml: &str, error: fn(String) -> Error) -> Result<Element, Error> {
+    let mut parser = Parser {
+        xml: xml,
+        pos: 0,
+        error: error,
+    };
+    try!(parser.skip_misc());
+    if !parser.rest().starts_with('<') {
+        return Err(parser.malformed("expected a root element"));
+    }
+    let root = try!(parser.element(0));
+    try!(parser.skip_misc());
+    if parser.pos != xml.len() {
+        return Err(parser.malformed("unexpected content after the root element"));
+    }
+    Ok(root)
+}
+
+struct Parser<'a> {
+    xml: &'a str,
+    pos: usize,
+    error: fn(String) -> Error,
+}
+
+impl<'a> Parser<'a> {
+    fn malformed(&self, message: &str) -> Error {
+        let line = self.xml[..self.pos].matches('\n').count() + 1;
+        (self.error)(format!("line {}: {}", line, message))
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.xml[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Skips past the next occurrence of `end`.
+    fn skip_past(&mut self, end: &str) -> Result<&'a str, Error> {
+        match self.rest().find(end) {
+            Some(i) => {
+                let skipped = &self.rest()[..i];
+                self.pos += i + end.len();
+                Ok(skipped)
+            }
+            None => Err(self.malformed(&format!("missing '{}'", end))),
+        }
+    }
+
+    /// Skips whitespace, comments, processing instructions and document type declarations.
+    fn skip_misc(&mut self) -> Result<(), Error> {
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("\u{feff}") {
+                self.pos += "\u{feff}".len();
+            } else if rest.starts_with("<?") {
+                try!(self.skip_past("?>"));
+            } else if rest.starts_with("<!--") {
+                try!(self.skip_past("-->"));
+            } else if rest.starts_with("<!DOCTYPE") {
+                // Internal subsets are enclosed in brackets
+                let end = match (rest.find('['), rest.find('>')) {
+                    (Some(bracket), Some(close)) if bracket < close => "]>",
+                    _ => ">",
+                };
+                try!(self.skip_past(end));
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<&'a str, Error> {
+        let rest = self.rest();
+        let len = rest.find(|c: char| c.is_whitespace() || "/>=".contains(c))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.malformed("expected a name"));
+        }
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    fn unescape(&self, text: &str) -> Result<String, Error> {
+        let mut unescaped = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(i) = rest.find('&') {
+            unescaped.push_str(&rest[..i]);
+            rest = &rest[i + 1..];
+            let end = match rest.find(';') {
+                Some(end) => end,
+                None => return Err(self.malformed("unterminated entity")),
+            };
+            let entity = &rest[..end];
+            let c = match entity {
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "amp" => Some('&'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ if entity.starts_with("#x") => {
+                    u32::from_str_radix(&entity[2..], 16).ok().and_then(::std::char::from_u32)
+                }
+                _ if entity.starts_with('#') => {
+                    entity[1..].parse().ok().and_then(::std::char::from_u32)
+                }
+                _ => None,
+            };
+            match c {
+                Some(c) => unescaped.push(c),
+                None => return Err(self.malformed(&format!("unknown entity '&{};'", entity))),
+            }
+            rest = &rest[end + 1..];
+        }
+        unescaped.push_str(rest);
+        Ok(unescaped)
+    }
+
+    fn element(&mut self, depth: usize) -> Result<Element, Error> {
+        if depth > MAX_DEPTH {
+            return Err(self.malformed("elements nested too deeply"));
+        }
+        self.pos += 1;
+        let name = try!(self.name());
+        let mut element = Element {
+            name: local_name(name),
+            attributes: vec![],
+            children: vec![],
+        };
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("/>") {
+                self.pos += 2;
+                return Ok(element);
+            } else if rest.starts_with('>') {
+                self.pos += 1;
+                break;
+            }
+            let key = try!(self.name());
+            self.skip_whitespace();
+            if !self.rest().starts_with('=') {
+                return Err(self.malformed("expected '='"));
+            }
+            self.pos += 1;
+            self.skip_whitespace();
+            let quote = match self.rest().chars().next() {
+                Some(quote) if quote == '"' || quote == '\'' => quote,
+                _ => return Err(self.malformed("expected a quoted attribute value")),
+            };
+            self.pos += 1;
+            let value = try!(self.skip_past(&quote.to_string()));
+            let value = try!(self.unescape(value));
+            element.attributes.push((local_name(key), value));
+        }
+
+        loop {
+            let rest = self.rest();
+            if rest.starts_with("</") {
+                self.pos += 2;
+                let end = try!(self.name());
+                if end != name {
+                    return Err(self.malformed(&format!("expected '</{}>'", name)));
+                }
+                self.skip_whitespace();
+                if !self.rest().starts_with('>') {
+                    return Err(self.malformed("expected '>'"));
+                }
+                self.pos += 1;
+                return Ok(element);
+            } else if rest.starts_with("<!--") {
+                try!(self.skip_past("-->"));
+            } else if rest.starts_with("<![CDATA[") {
+                self.pos += "<![CDATA[".len();
+                let text = try!(self.skip_past("]]>"));
+                element.children.push(Node::Text(String::from(text)));
+            } else if rest.starts_with("<?") {
+                try!(self.skip_past("?>"));
+            } else if rest.starts_with('<') {
+                element.children.push(Node::Element(try!(self.element(depth + 1))));
+            } else if rest.is_empty() {
+                return Err(self.malformed(&format!("missing '</{}>'", name)));
+            } else {
+                let len = rest.find('<').unwrap_or(rest.len());
+                self.pos += len;
+                let text = try!(self.unescape(&rest[..len]));
+                element.children.push(Node::Text(text));
+            }
+        }
+    }
+}